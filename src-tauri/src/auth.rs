@@ -1,11 +1,18 @@
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use eyre::{eyre, Result};
 use crate::error::AppError;
-use crate::helper;
+use crate::{crypto, helper, logger, profiles};
+
+// Kept to log in again when the session expires, only stored when the user asks for it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AuthState {
@@ -19,6 +26,12 @@ pub struct AuthState {
     pub region: String,
     pub role: String,
     pub status: Option<String>,
+    // Never sent to the window, saved encrypted in credentials.json
+    #[serde(skip)]
+    pub credentials: Option<Credentials>,
+    // Profile the state was read from, a background scraper saves to its own profile
+    #[serde(skip)]
+    pub profile: Option<String>,
 }
 // Allow us to run AuthState::default()
 impl Default for AuthState {
@@ -34,40 +47,133 @@ impl Default for AuthState {
             region: "".to_string(),
             role: "".to_string(),
             status: Some("invisible".to_string()),
+            credentials: None,
+            profile: None,
         }
     }
 }
 impl AuthState {
-    fn get_file_path() -> PathBuf {
-        let app_path = helper::get_app_roaming_path();
-        let auth_path = app_path.join("auth.json");
-        auth_path
+    fn get_profile_path(&self) -> Result<PathBuf, AppError> {
+        match &self.profile {
+            Some(profile) => profiles::get_data_path(profile),
+            None => profiles::get_active_path(),
+        }
     }
     pub fn setup() -> Result<Self, AppError> {
-        let path_ref = Self::get_file_path();
+        Self::setup_profile(&profiles::get_active_id())
+    }
+    // The profile doesn't have to be the active one
+    pub fn setup_profile(profile: &str) -> Result<Self, AppError> {
+        let path_ref = profiles::get_data_path(profile)?.join("auth.json");
         if path_ref.exists() {
-            Self::read_from_file()
+            let (auth, plain) = Self::read(profile)?;
+            // Tokens saved by older versions are encrypted right away
+            if plain {
+                auth.save_to_file()?;
+            }
+            Ok(auth)
         } else {
-            let default_auth = AuthState::default();
+            let mut default_auth = AuthState::default();
+            default_auth.profile = Some(profile.to_string());
             default_auth.save_to_file()?;
             Ok(default_auth)
         }
     }
     pub fn save_to_file(&self) -> Result<(), AppError> {
-        let json = serde_json::to_string_pretty(self).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
-        let mut file = File::create(Self::get_file_path()).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+        let path = self.get_profile_path()?;
+        let mut stored = self.clone();
+        stored.access_token = match &self.access_token {
+            Some(token) => Some(crypto::encrypt(token)?),
+            None => None,
+        };
+        let json = serde_json::to_string_pretty(&stored).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+        let mut file = File::create(path.join("auth.json")).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+        restrict_permissions(&path.join("auth.json"))?;
         file.write_all(json.as_bytes()).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+
+        let credentials_path = path.join("credentials.json");
+        match &self.credentials {
+            Some(credentials) => {
+                let json = serde_json::to_string(credentials).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+                let mut file = File::create(&credentials_path).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+                restrict_permissions(&credentials_path)?;
+                file.write_all(crypto::encrypt(&json)?.as_bytes()).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+            }
+            None if credentials_path.exists() => {
+                fs::remove_file(credentials_path).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+            }
+            None => {}
+        }
         Ok(())
     }
 
-    pub fn read_from_file() -> Result<Self, AppError> {
-        let mut file = File::open(Self::get_file_path()).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+    // Auth of a profile that may not be the active one
+    pub fn read_from_profile(profile: &str) -> Result<Self, AppError> {
+        Ok(Self::read(profile)?.0)
+    }
+    // Also tells if the token was stored in plain text
+    fn read(profile: &str) -> Result<(Self, bool), AppError> {
+        let profile_path = profiles::get_data_path(profile)?;
+        let mut file = File::open(profile_path.join("auth.json")).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
         let mut content = String::new();
         file.read_to_string(&mut content).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
-        let auth = serde_json::from_str(&content).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
-        Ok(auth)
+        let mut auth: AuthState = serde_json::from_str(&content).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
+        auth.profile = Some(profile.to_string());
+
+        let plain = auth
+            .access_token
+            .as_deref()
+            .map(|t| !crypto::is_encrypted(t))
+            .unwrap_or(false);
+        // Without the key the user has to log in again
+        auth.access_token = match auth.access_token.as_deref().map(crypto::decrypt) {
+            Some(Ok(token)) => Some(token),
+            Some(Err(e)) => {
+                logger::warning_con("AuthState", e.cause().as_str());
+                None
+            }
+            None => None,
+        };
+        let credentials_path = profile_path.join("credentials.json");
+        if credentials_path.exists() {
+            auth.credentials = fs::read_to_string(credentials_path)
+                .ok()
+                .and_then(|content| crypto::decrypt(&content).ok())
+                .and_then(|json| serde_json::from_str(&json).ok());
+        }
+        Ok((auth, plain))
+    }
+    // Takes the user from a login, the status and the stored credentials are kept
+    pub fn set_user(&mut self, user: AuthState) {
+        self.banned = user.banned;
+        self.id = user.id;
+        self.access_token = user.access_token;
+        self.avatar = user.avatar;
+        self.ingame_name = user.ingame_name;
+        self.locale = user.locale;
+        self.platform = user.platform;
+        self.region = user.region;
+        self.role = user.role;
+    }
+    pub fn is_active_profile(&self) -> bool {
+        match &self.profile {
+            Some(profile) => *profile == profiles::get_active_id(),
+            None => true,
+        }
     }
     pub fn send_to_window(&self) {
         helper::emit_update("user","SET", Some(json!(self.clone())));
     }
 }
+
+// Only the user running the app can read the token and the credentials
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn restrict_permissions(path: &Path) -> Result<(), AppError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| AppError::new("AuthState", eyre!(e.to_string())))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,471 @@
+// Headless entry point, runs the same backend as the app without a window.
+use eyre::eyre;
+use quantframe_lib::{
+    auth::{AuthState, Credentials},
+    cache::client::CacheClient,
+    crypto,
+    database::client::DBClient,
+    enums::OrderType,
+    error::{self, AppError},
+    handler::MonitorHandler,
+    live_scraper::{client::LiveScraperClient, grading},
+    logger,
+    metrics::server::MetricsServer,
+    price_scraper::PriceScraper,
+    profiles::{self, ProfileStore},
+    settings::SettingsState,
+    wfm_client::client::WFMClient,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{env, fs, process};
+use tauri::async_runtime::block_on;
+
+static COMPONENT: &str = "Cli";
+static LOG_FILE: &str = "cli.log";
+// Read by login when --password is left out, keeps the password out of the shell history
+static PASSWORD_ENV: &str = "QUANTFRAME_PASSWORD";
+
+const USAGE: &str = "Usage: quantframe-cli <command> [options] [--profile <id|name>]
+
+Commands:
+  profile list
+  profile create --name <name>
+  login --email <email> [--password <password>] [--remember]
+  price-data [--days <days>]
+  stock list [--rivens]
+  stock add --url <url_name> --quantity <quantity> --price <price> [--rank <rank>] [--sub-type <sub_type>] [--minium-price <price>]
+  stock sell --id <id> --quantity <quantity> --price <price>
+  stock delete --id <id> [--rivens]
+  stock grade
+  scraper run [--dry-run]
+  report [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--out <path>]
+  export --table <stock_item|stock_riven|transaction> --format <csv|json> --out <path>
+
+Without --password, login reads the password from QUANTFRAME_PASSWORD or else from the first line of stdin.";
+
+// Positional words and --key value options, a key without a value is a flag
+struct Args {
+    commands: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(raw: Vec<String>) -> Self {
+        let mut commands = vec![];
+        let mut options = HashMap::new();
+        let mut raw = raw.into_iter().peekable();
+        while let Some(arg) = raw.next() {
+            match arg.strip_prefix("--") {
+                Some(key) => {
+                    let value = match raw.peek() {
+                        Some(next) if !next.starts_with("--") => raw.next().unwrap(),
+                        _ => "true".to_string(),
+                    };
+                    options.insert(key.to_string(), value);
+                }
+                None => commands.push(arg),
+            }
+        }
+        Args { commands, options }
+    }
+    fn command(&self, index: usize) -> &str {
+        self.commands.get(index).map(|c| c.as_str()).unwrap_or("")
+    }
+    fn flag(&self, key: &str) -> bool {
+        self.options.contains_key(key)
+    }
+    fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>, AppError> {
+        match self.options.get(key) {
+            Some(value) => value.parse::<T>().map(Some).map_err(|_| {
+                AppError::new(COMPONENT, eyre!("Invalid value for --{}: {}", key, value))
+            }),
+            None => Ok(None),
+        }
+    }
+    fn require<T: FromStr>(&self, key: &str) -> Result<T, AppError> {
+        self.get(key)?
+            .ok_or_else(|| AppError::new(COMPONENT, eyre!("Missing --{}\n\n{}", key, USAGE)))
+    }
+}
+
+// The same clients the app creates in setup_async, without the window
+struct Backend {
+    settings: Arc<Mutex<SettingsState>>,
+    auth: Arc<Mutex<AuthState>>,
+    wfm: Arc<Mutex<WFMClient>>,
+    db: Arc<Mutex<DBClient>>,
+    price_scraper: Arc<Mutex<PriceScraper>>,
+    live_scraper: Arc<Mutex<LiveScraperClient>>,
+}
+
+async fn setup() -> Result<Backend, AppError> {
+    // Before anything reads or writes a secret
+    crypto::setup()?;
+    let monitor_handler = Arc::new(Mutex::new(MonitorHandler::headless()));
+    let settings = Arc::new(Mutex::new(SettingsState::setup()?));
+    let auth = Arc::new(Mutex::new(AuthState::setup()?));
+
+    #[cfg_attr(not(feature = "mock-server"), allow(unused_mut))]
+    let mut wfm = WFMClient::new(Arc::clone(&auth), Arc::clone(&settings));
+
+    // Point the client at the in-process mock server, it keeps running until the cli exits
+    #[cfg(feature = "mock-server")]
+    {
+        let mock_server = quantframe_lib::wfm_client::mock_server::MockServer::start().await?;
+        wfm.set_endpoint(&mock_server.endpoint());
+    }
+    let wfm = Arc::new(Mutex::new(wfm));
+
+    let cache = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm))));
+    let db = Arc::new(Mutex::new(
+        DBClient::new(Arc::clone(&cache), Arc::clone(&wfm)).await?,
+    ));
+    let db_client = db.lock()?.clone();
+    db_client.initialize().await?;
+    let cache_client = cache.lock()?.clone();
+    cache_client.load().await?;
+
+    // Keeps serving until the cli exits
+    let metrics_settings = settings.lock()?.metrics.clone();
+    if metrics_settings.enabled {
+        MetricsServer::start(&metrics_settings).await?;
+    }
+
+    let price_scraper = Arc::new(Mutex::new(PriceScraper::new(
+        Arc::clone(&wfm),
+        Arc::clone(&auth),
+        Arc::clone(&db),
+    )));
+    let live_scraper = Arc::new(Mutex::new(LiveScraperClient::new(
+        Arc::clone(&settings),
+        Arc::clone(&price_scraper),
+        Arc::clone(&wfm),
+        Arc::clone(&auth),
+        Arc::clone(&db),
+        Arc::clone(&monitor_handler),
+    )));
+    Ok(Backend {
+        settings,
+        auth,
+        wfm,
+        db,
+        price_scraper,
+        live_scraper,
+    })
+}
+
+async fn login(backend: &Backend, args: &Args) -> Result<Value, AppError> {
+    let email: String = args.require("email")?;
+    let password = get_password(args, env::var(PASSWORD_ENV).ok(), io::stdin().lock())?;
+    let wfm = backend.wfm.lock()?.clone();
+    let user = wfm.auth().login(email.clone(), password.clone()).await?;
+    if user.access_token.is_none() {
+        return Err(AppError::new(
+            "WarframeMarket",
+            eyre!("No access token found for user"),
+        ));
+    }
+    let mut auth = backend.auth.lock()?;
+    auth.set_user(user);
+    auth.credentials = match args.flag("remember") {
+        true => Some(Credentials { email, password }),
+        false => None,
+    };
+    auth.save_to_file()?;
+    Ok(json!({ "ingame_name": auth.ingame_name }))
+}
+
+// --password, then the environment variable, then the first line of stdin
+fn get_password(
+    args: &Args,
+    env_password: Option<String>,
+    mut stdin: impl BufRead,
+) -> Result<String, AppError> {
+    if let Some(password) = args.get::<String>("password")? {
+        return Ok(password);
+    }
+    if let Some(password) = env_password.filter(|p| !p.is_empty()) {
+        return Ok(password);
+    }
+    // On stderr so it doesn't end up in the json output
+    eprint!("Password: ");
+    let mut line = String::new();
+    stdin
+        .read_line(&mut line)
+        .map_err(|e| AppError::new(COMPONENT, eyre!(e.to_string())))?;
+    let password = line.trim_end_matches(&['\r', '\n'][..]).to_string();
+    if password.is_empty() {
+        return Err(AppError::new(
+            COMPONENT,
+            eyre!(
+                "Missing password, use --password, {} or stdin",
+                PASSWORD_ENV
+            ),
+        ));
+    }
+    Ok(password)
+}
+
+async fn price_data(backend: &Backend, args: &Args) -> Result<Value, AppError> {
+    let days = args.get::<i64>("days")?.unwrap_or(7);
+    let price_scraper = backend.price_scraper.lock()?.clone();
+    let rows = price_scraper.generate(days).await?;
+    Ok(json!({ "rows": rows }))
+}
+
+async fn stock(backend: &Backend, args: &Args) -> Result<Value, AppError> {
+    let db = backend.db.lock()?.clone();
+    let wfm = backend.wfm.lock()?.clone();
+    let settings = backend.settings.lock()?.clone();
+    let report_to_wfm = settings.live_scraper.stock_item.report_to_wfm;
+    match args.command(1) {
+        "list" if args.flag("rivens") => Ok(json!(db.stock_riven().get_rivens().await?)),
+        "list" => Ok(json!(db.stock_item().get_items().await?)),
+        "add" => {
+            let url_name: String = args.require("url")?;
+            let quantity: i32 = args.require("quantity")?;
+            let price: f64 = args.require("price")?;
+            let rank = args.get::<i32>("rank")?.unwrap_or(0);
+            let sub_type: Option<String> = args.get("sub-type")?;
+            let minium_price: Option<i32> = args.get("minium-price")?;
+            let stock_item = db
+                .stock_item()
+                .create(
+                    &url_name,
+                    quantity,
+                    price,
+                    minium_price,
+                    rank,
+                    sub_type.as_deref(),
+                )
+                .await?;
+            // Same as adding it in the app, a bought item is a buy transaction
+            if price > 0.0 {
+                db.transaction()
+                    .create(&url_name, "item", "buy", quantity, price as i32, rank, None)
+                    .await?;
+                if report_to_wfm {
+                    wfm.orders().close(&url_name, OrderType::Buy).await?;
+                }
+            }
+            Ok(json!(stock_item))
+        }
+        "sell" => {
+            let id: i64 = args.require("id")?;
+            let quantity: i32 = args.require("quantity")?;
+            let price: i32 = args.require("price")?;
+            let stock_item = db.stock_item().sell_item(id, quantity).await?;
+            db.transaction()
+                .create(
+                    &stock_item.url,
+                    "item",
+                    "sell",
+                    quantity,
+                    price,
+                    stock_item.rank,
+                    None,
+                )
+                .await?;
+            if report_to_wfm {
+                wfm.orders().close(&stock_item.url, OrderType::Sell).await?;
+            }
+            Ok(json!(stock_item))
+        }
+        // Listed orders are left to the live scraper, it removes orders without stock
+        "delete" if args.flag("rivens") => {
+            let id: i64 = args.require("id")?;
+            Ok(json!(db.stock_riven().delete(id).await?))
+        }
+        "delete" => {
+            let id: i64 = args.require("id")?;
+            Ok(json!(db.stock_item().delete(id).await?))
+        }
+        "grade" => Ok(json!(grading::grade_stock(&db).await?)),
+        _ => Err(AppError::new(COMPONENT, eyre!("{}", USAGE))),
+    }
+}
+
+// Runs the live scraper until ctrl-c or until it stops on an error
+async fn scraper(backend: &Backend, args: &Args) -> Result<Value, AppError> {
+    if args.command(1) != "run" {
+        return Err(AppError::new(COMPONENT, eyre!("{}", USAGE)));
+    }
+    if args.flag("dry-run") {
+        backend.settings.lock()?.live_scraper.dry_run = true;
+    }
+    backend.live_scraper.lock()?.start_loop()?;
+    logger::info_con(
+        COMPONENT,
+        "Live scraper is running, press ctrl-c to stop it",
+    );
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                backend.live_scraper.lock()?.stop_loop();
+                break;
+            }
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {
+                if !backend.live_scraper.lock()?.is_running() {
+                    return Err(AppError::new(
+                        COMPONENT,
+                        eyre!("Live scraper stopped, see live_scraper.log"),
+                    ));
+                }
+            }
+        }
+    }
+    let live_scraper = backend.live_scraper.lock()?.clone();
+    if live_scraper.is_dry_run() {
+        return Ok(json!({ "dry_run": live_scraper.dry_run().get_diff()? }));
+    }
+    Ok(json!({ "running": false }))
+}
+
+async fn report(backend: &Backend, args: &Args) -> Result<Value, AppError> {
+    let db = backend.db.lock()?.clone();
+    let report = db
+        .profit_report()
+        .get_report(args.get("from")?, args.get("to")?)
+        .await?;
+    let report = json!(report);
+    match args.get::<String>("out")? {
+        Some(path) => {
+            let content = serde_json::to_string_pretty(&report)
+                .map_err(|e| AppError::new(COMPONENT, eyre!(e.to_string())))?;
+            fs::write(&path, content)
+                .map_err(|e| AppError::new(COMPONENT, eyre!(e.to_string())))?;
+            Ok(json!({ "path": path }))
+        }
+        None => Ok(report),
+    }
+}
+
+async fn export(backend: &Backend, args: &Args) -> Result<Value, AppError> {
+    let table: String = args.require("table")?;
+    let format: String = args.require("format")?;
+    let path: String = args.require("out")?;
+    let db = backend.db.lock()?.clone();
+    let rows = db.data_transfer().export(&table, &format, &path).await?;
+    Ok(json!({ "rows": rows, "path": path }))
+}
+
+// The profile only applies to this run, the app keeps its own active profile
+fn select_profile(args: &Args) -> Result<ProfileStore, AppError> {
+    let store = ProfileStore::setup()?;
+    if let Some(id_or_name) = args.get::<String>("profile")? {
+        let profile = store
+            .find(&id_or_name)
+            .ok_or_else(|| AppError::new(COMPONENT, eyre!("Profile {} not found", id_or_name)))?;
+        profiles::set_active(&profile.id);
+    }
+    Ok(store)
+}
+
+fn profile(store: &mut ProfileStore, args: &Args) -> Result<Value, AppError> {
+    match args.command(1) {
+        "list" => Ok(json!({
+            "active": profiles::get_active_id(),
+            "profiles": store.profiles,
+        })),
+        "create" => {
+            let name: String = args.require("name")?;
+            let profile = store.create(&name)?;
+            store.save_to_file()?;
+            Ok(json!(profile))
+        }
+        _ => Err(AppError::new(COMPONENT, eyre!("{}", USAGE))),
+    }
+}
+
+async fn run(args: Args) -> Result<Value, AppError> {
+    let mut store = select_profile(&args)?;
+    if args.command(0) == "profile" {
+        return profile(&mut store, &args);
+    }
+    let backend = setup().await?;
+    match args.command(0) {
+        "login" => login(&backend, &args).await,
+        "price-data" => price_data(&backend, &args).await,
+        "stock" => stock(&backend, &args).await,
+        "scraper" => scraper(&backend, &args).await,
+        "report" => report(&backend, &args).await,
+        "export" => export(&backend, &args).await,
+        _ => Err(AppError::new(COMPONENT, eyre!("{}", USAGE))),
+    }
+}
+
+fn main() {
+    let args = Args::parse(env::args().skip(1).collect());
+    if args.commands.is_empty() || args.flag("help") {
+        println!("{}", USAGE);
+        return;
+    }
+    match block_on(run(args)) {
+        Ok(output) => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.to_string(), &e);
+            eprintln!("{}", e.cause());
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Args {
+        Args::parse(raw.split_whitespace().map(|a| a.to_string()).collect())
+    }
+
+    #[test]
+    fn commands_and_options_are_split() {
+        let args = parse("report --from 2024-01-01 --out report.csv");
+        assert_eq!(args.command(0), "report");
+        assert_eq!(args.command(1), "");
+        assert_eq!(args.options.get("from").unwrap(), "2024-01-01");
+        assert_eq!(args.options.get("out").unwrap(), "report.csv");
+    }
+
+    #[test]
+    fn options_without_a_value_are_flags() {
+        let args = parse("scraper run --dry-run");
+        assert_eq!(args.command(1), "run");
+        assert!(args.flag("dry-run"));
+        let args = parse("login --remember --email a@b.c");
+        assert!(args.flag("remember"));
+        assert_eq!(args.options.get("email").unwrap(), "a@b.c");
+    }
+
+    #[test]
+    fn values_are_parsed_and_checked() {
+        let args = parse("stock add --quantity 3 --rank x");
+        assert_eq!(args.require::<i64>("quantity").unwrap(), 3);
+        assert!(args.get::<i64>("rank").is_err());
+        assert_eq!(args.get::<i64>("price").unwrap(), None);
+        assert!(args.require::<i64>("price").is_err());
+    }
+
+    #[test]
+    fn the_password_flag_comes_first() {
+        let args = parse("login --email a@b.c --password flag");
+        let password = get_password(&args, Some("env".to_string()), "stdin\n".as_bytes());
+        assert_eq!(password.unwrap(), "flag");
+    }
+
+    #[test]
+    fn the_password_falls_back_to_the_env_and_stdin() {
+        let args = parse("login --email a@b.c");
+        let password = get_password(&args, Some("env".to_string()), "stdin\n".as_bytes());
+        assert_eq!(password.unwrap(), "env");
+        let password = get_password(&args, Some("".to_string()), "stdin pass\r\n".as_bytes());
+        assert_eq!(password.unwrap(), "stdin pass");
+        assert!(get_password(&args, None, "\n".as_bytes()).is_err());
+        assert!(get_password(&args, None, "".as_bytes()).is_err());
+    }
+}
@@ -5,9 +5,10 @@ use serde_json::{json, Value};
 use eyre::eyre;
 
 use crate::{
-    auth::AuthState,
+    auth::{AuthState, Credentials},
     error::{self, AppError},
     logger,
+    profiles::client::ProfileClient,
     wfm_client::client::WFMClient,
 };
 
@@ -18,11 +19,13 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_auth.log
 pub async fn login(
     email: String,
     password: String,
+    remember: Option<bool>,
     auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
 ) -> Result<AuthState, AppError> {
     let wfm = wfm.lock().expect("Could not lock wfm").clone();
-    match wfm.auth().login(email, password).await {
+    match wfm.auth().login(email.clone(), password.clone()).await {
         Ok(user) => {
             if user.access_token.is_none() {
                 logger::critical(
@@ -39,18 +42,17 @@ pub async fn login(
 
             let arced_mutex = Arc::clone(&auth);
             let mut auth = arced_mutex.lock().expect("Could not lock auth");
-            auth.banned = user.banned;
-            auth.id = user.id;
-            auth.access_token = user.access_token;
-            auth.avatar = user.avatar;
-            auth.ingame_name = user.ingame_name;
-            auth.locale = user.locale;
-            auth.platform = user.platform;
-            auth.region = user.region;
-            auth.role = user.role;
+            auth.set_user(user);
+            // Lets the client log in again when the session expires
+            auth.credentials = match remember.unwrap_or(false) {
+                true => Some(Credentials { email, password }),
+                false => None,
+            };
             auth.save_to_file()?;
             auth.send_to_window();
-            return Ok(auth.clone());
+            let auth = auth.clone();
+            profiles.lock()?.sync_active()?;
+            return Ok(auth);
         }
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -62,25 +64,34 @@ pub async fn login(
 pub async fn update_user_status(
     status: String,
     auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
 ) -> Result<(), AppError> {
-    let arced_mutex = Arc::clone(&auth);
-    let mut auth = arced_mutex.lock().expect("Could not lock auth");
-    auth.status = Some(status);
-    auth.save_to_file()?;
-    auth.send_to_window();
+    {
+        let arced_mutex = Arc::clone(&auth);
+        let mut auth = arced_mutex.lock().expect("Could not lock auth");
+        auth.status = Some(status);
+        auth.save_to_file()?;
+        auth.send_to_window();
+    }
+    profiles.lock()?.sync_active()?;
     Ok(())
 }
 #[tauri::command]
 pub async fn logout(
     auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
 ) -> Result<(), AppError> {
-    let arced_mutex = Arc::clone(&auth);
-    let mut auth = arced_mutex.lock().expect("Could not lock auth");
-    auth.access_token = None;
-    auth.avatar = None;
-    auth.ingame_name = "".to_string();
-    auth.id = "".to_string();
-    auth.save_to_file()?;
-    auth.send_to_window();
+    {
+        let arced_mutex = Arc::clone(&auth);
+        let mut auth = arced_mutex.lock().expect("Could not lock auth");
+        auth.access_token = None;
+        auth.avatar = None;
+        auth.ingame_name = "".to_string();
+        auth.id = "".to_string();
+        auth.credentials = None;
+        auth.save_to_file()?;
+        auth.send_to_window();
+    }
+    profiles.lock()?.sync_active()?;
     Ok(())
 }
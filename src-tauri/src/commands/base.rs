@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs::{self, File},
     io::{self, Read, Write},
     path::{Path, PathBuf},
@@ -22,8 +23,11 @@ use crate::{
     enums::LogLevel,
     error::{self, AppError},
     handler::MonitorHandler,
-    helper, logger,
+    helper,
+    live_scraper::client::LiveScraperClient,
+    logger,
     price_scraper::PriceScraper,
+    profiles::client::ProfileClient,
     settings::SettingsState,
     wf_ee_log_parser::client::EELogParser,
     wfm_client::client::WFMClient,
@@ -41,6 +45,7 @@ pub async fn init(
     price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
     ee_log: tauri::State<'_, Arc<std::sync::Mutex<EELogParser>>>,
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    live_scraper: tauri::State<'_, Arc<Mutex<LiveScraperClient>>>,
 ) -> Result<Value, AppError> {
     let db = db.lock()?.clone();
     let mut ee_log = ee_log.lock()?.clone();
@@ -50,7 +55,7 @@ pub async fn init(
     let cache = cache.lock()?.clone();
     let price_scraper = price_scraper.lock()?.clone();
     let mut response = json!({
-        "settings": &settings.clone(),
+        "settings": &settings.masked(),
         "user": &auth.clone(),
         "price_scraper_last_run": price_scraper.get_status(),
     });
@@ -169,17 +174,37 @@ pub async fn init(
         ee_log.start_loop();
     }
 
+    // Resume the live scraper if the app was closed while it was running
+    let mut live_scraper = live_scraper.lock()?.clone();
+    if is_validate
+        && settings.live_scraper.resume_on_start
+        && !live_scraper.is_running()
+        && live_scraper.should_resume()
+    {
+        match live_scraper.start_loop() {
+            Ok(_) => helper::send_message_to_window("LiveScraper:Toggle", None),
+            Err(e) => error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e),
+        }
+    } else if live_scraper.is_running() {
+        // The profile was switched away from and back while its scraper kept running
+        helper::send_message_to_window("LiveScraper:Toggle", None);
+    }
+
     Ok(response)
 }
 
 #[tauri::command]
 pub async fn update_settings(
-    settings: SettingsState,
+    mut settings: SettingsState,
     settings_state: tauri::State<'_, Arc<std::sync::Mutex<SettingsState>>>,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
 ) -> Result<(), AppError> {
     let arced_mutex = Arc::clone(&settings_state);
     let mut my_lock = arced_mutex.lock()?;
 
+    // The window only has the masked secrets
+    settings.restore_secrets(&my_lock);
+
     // Set Loggin Settings
     my_lock.debug = settings.debug;
 
@@ -188,8 +213,18 @@ pub async fn update_settings(
 
     // Set Whisper Scraper Settings
     my_lock.notifications = settings.notifications;
+    my_lock.notification_channels = settings.notification_channels;
+
+    // Set EE.log Settings
+    my_lock.ee_log = settings.ee_log;
+
+    // The local api is only read on start
+    my_lock.local_api = settings.local_api;
+    my_lock.metrics = settings.metrics;
 
     my_lock.save_to_file().expect("Could not save settings");
+    drop(my_lock);
+    profiles.lock()?.sync_active()?;
     Ok(())
 }
 
@@ -227,30 +262,23 @@ pub fn on_new_wfm_message(
 ) {
     let mh = mh.lock().unwrap();
     let auth = auth.lock().unwrap().clone();
-    let settings = settings.lock().unwrap().clone().notifications.on_wfm_chat_message;
+    let settings = settings.lock().unwrap().clone();
 
     if auth.id == message.message_from {
         return;
     }
 
-    let content = settings.content.replace("<WFM_MESSAGE>", &message.raw_message.unwrap_or("".to_string()));
-    if settings.system_notify {
-        mh.show_notification(
-            &settings.title,
-            &content,
-            Some("https://i.imgur.com/UggEVVI.jpeg"),
-            Some("Default"),
-        );
-    }
-
-    if settings.discord_notify  && settings.webhook.is_some() {
-        crate::helper::send_message_to_discord(
-            settings.webhook.unwrap_or("".to_string()),
-            settings.title,
-            content,
-            settings.user_ids,
-        );
-    }
+    let raw_message = message.raw_message.unwrap_or("".to_string());
+    let values = HashMap::from([("WFM_MESSAGE", raw_message.clone())]);
+    crate::notification::notify(
+        &mh,
+        &settings,
+        &settings.notifications.on_wfm_chat_message,
+        crate::notification::NotificationKind::WfmChatMessage,
+        &values,
+        vec![crate::notification::EmbedField::new("Message", &raw_message, false)],
+        None,
+    );
 }
 
 #[tauri::command]
@@ -0,0 +1,51 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{client::DBClient, modules::data_transfer::ImportReport},
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_data_transfer.log".to_string()));
+
+#[tauri::command(async)]
+pub async fn export_data(
+    table: String,
+    format: String,
+    path: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<i64, AppError> {
+    let db = db.lock()?.clone();
+    match db.data_transfer().export(&table, &format, &path).await {
+        Ok(rows) => Ok(rows),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command(async)]
+pub async fn import_data(
+    table: String,
+    format: String,
+    path: String,
+    replace: bool,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<ImportReport, AppError> {
+    let db = db.lock()?.clone();
+    match db
+        .data_transfer()
+        .import(&table, &format, &path, replace)
+        .await
+    {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{self, AppError},
+    wf_ee_log_parser::{client::EELogParser, replay::ReplayReport},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_ee_log.log".to_string()));
+
+#[tauri::command(async)]
+pub async fn replay_ee_log(
+    path: String,
+    speed: Option<f64>,
+    reconcile: Option<bool>,
+    ee_log: tauri::State<'_, Arc<Mutex<EELogParser>>>,
+) -> Result<ReplayReport, AppError> {
+    let ee_log = ee_log.lock()?.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        ee_log.replay(
+            PathBuf::from(path),
+            speed.unwrap_or(0.0),
+            reconcile.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| AppError::new("EELogReplay", eyre!(e.to_string())))
+    .and_then(|result| result);
+    match result {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
@@ -1,8 +1,14 @@
 use std::sync::{Arc, Mutex};
 
 use once_cell::sync::Lazy;
+use serde_json::json;
 
-use crate::{error, live_scraper::client::LiveScraperClient, logger::error};
+use crate::{
+    error::{self, AppError},
+    live_scraper::{client::LiveScraperClient, modules::backtest::BacktestReport},
+    logger::error,
+    settings::StockItemSettings,
+};
 
 // Create a static variable to store the log file name
 static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_live_scraper.log".to_string()));
@@ -23,3 +29,49 @@ pub fn toggle_live_scraper(
         }
     }
 }
+
+#[tauri::command]
+pub fn get_dry_run_journal(
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let live_scraper = live_scraper.lock()?;
+    let dry_run = live_scraper.dry_run();
+    Ok(json!({
+        "entries": dry_run.get_entries()?,
+        "diff": dry_run.get_diff()?,
+    }))
+}
+
+#[tauri::command]
+pub fn clear_dry_run_journal(
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<(), AppError> {
+    let live_scraper = live_scraper.lock()?;
+    match live_scraper.dry_run().clear() {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn run_backtest(
+    stock_item: Option<StockItemSettings>,
+    from: Option<String>,
+    to: Option<String>,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<BacktestReport, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    let settings = live_scraper.settings.lock()?.clone();
+    // Use the current settings when none are given
+    let stock_item = stock_item.unwrap_or(settings.live_scraper.stock_item);
+    match live_scraper.backtest().run(&stock_item, from, to).await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
@@ -2,9 +2,13 @@ pub mod auctions;
 pub mod auth;
 pub mod base;
 pub mod debug;
+pub mod ee_log;
 pub mod live_scraper;
 pub mod orders;
 pub mod price_scraper;
+pub mod profiles;
+pub mod profit_report;
 pub mod stock;
 pub mod chat;
+pub mod data_transfer;
 pub mod transaction;
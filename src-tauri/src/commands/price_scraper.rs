@@ -3,6 +3,7 @@ use std::sync::{Arc, Mutex};
 use once_cell::sync::Lazy;
 
 use crate::{
+    database::{client::DBClient, modules::price_history::PriceHistoryStruct},
     error::{self, AppError},
     price_scraper::PriceScraper,
 };
@@ -24,3 +25,19 @@ pub async fn generate_price_history(
         }
     }
 }
+
+#[tauri::command(async)]
+pub async fn get_price_history(
+    from: Option<String>,
+    to: Option<String>,
+    db: tauri::State<'_, Arc<std::sync::Mutex<DBClient>>>,
+) -> Result<Vec<PriceHistoryStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.price_history().get_history(from, to).await {
+        Ok(rows) => Ok(rows),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{self, AppError},
+    profiles::{
+        client::{ProfileClient, ProfileInfo, ProfilesReport},
+        Profile,
+    },
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_profiles.log".to_string()));
+
+#[tauri::command]
+pub fn get_profiles(
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
+) -> Result<Vec<ProfileInfo>, AppError> {
+    let profiles = profiles.lock()?.clone();
+    match profiles.get_profiles() {
+        Ok(list) => Ok(list),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn create_profile(
+    name: String,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
+) -> Result<Profile, AppError> {
+    let profiles = profiles.lock()?.clone();
+    match profiles.create(&name) {
+        Ok(profile) => Ok(profile),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command(async)]
+pub async fn switch_profile(
+    id: String,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
+) -> Result<Profile, AppError> {
+    let profiles = profiles.lock()?.clone();
+    match profiles.switch(&id).await {
+        Ok(profile) => Ok(profile),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn delete_profile(
+    id: String,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
+) -> Result<Profile, AppError> {
+    let profiles = profiles.lock()?.clone();
+    match profiles.delete(&id) {
+        Ok(profile) => Ok(profile),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command(async)]
+pub async fn get_profiles_report(
+    from: Option<String>,
+    to: Option<String>,
+    profiles: tauri::State<'_, Arc<Mutex<ProfileClient>>>,
+) -> Result<ProfilesReport, AppError> {
+    let profiles = profiles.lock()?.clone();
+    match profiles.get_report(from, to).await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
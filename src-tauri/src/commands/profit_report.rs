@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::profit_report::{HoldingProfit, ProfitReport},
+    },
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_profit_report.log".to_string()));
+
+#[tauri::command(async)]
+pub async fn get_profit_report(
+    from: Option<String>,
+    to: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<ProfitReport, AppError> {
+    let db = db.lock()?.clone();
+    match db.profit_report().get_report(from, to).await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command(async)]
+pub async fn get_profit_holdings(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<HoldingProfit>, AppError> {
+    let db = db.lock()?.clone();
+    match db.profit_report().get_holdings().await {
+        Ok(holdings) => Ok(holdings),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -12,6 +12,7 @@ use crate::{
     },
     enums::{LogLevel, OrderType},
     error::{self, AppError},
+    live_scraper::grading,
     logger,
     structs::{Order, RivenAttribute},
     wfm_client::client::WFMClient,
@@ -589,4 +590,13 @@ pub async fn sell_riven_stock(
     Ok(json_stock)
 }
 
+#[tauri::command]
+pub async fn get_riven_grades(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    let grades = grading::grade_stock(&db).await?;
+    Ok(json!(grades))
+}
+
 // -----------------------------------------------------------------------------------------------
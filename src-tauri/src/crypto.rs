@@ -0,0 +1,109 @@
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use eyre::eyre;
+use once_cell::sync::OnceCell;
+
+use crate::{error::AppError, helper};
+
+// Marks a value that was encrypted, anything else is read as plain text from older versions
+const PREFIX: &str = "enc:";
+const NONCE_LEN: usize = 12;
+
+// Loaded once per process, see setup
+static KEY: OnceCell<Key> = OnceCell::new();
+
+// The key sits in the app folder, next to the auth.json and settings.json of the default
+// profile, and is shared by every profile. It keeps the secrets out of the files as plain
+// text, anyone who can read the app folder can also read the key.
+fn get_key_path() -> PathBuf {
+    helper::get_app_roaming_path().join("secret.key")
+}
+
+// Creates the key on first start. Called once from the app and cli setup so two
+// threads can't each write a different key.
+pub fn setup() -> Result<(), AppError> {
+    load_key().map(|_| ())
+}
+
+fn load_key() -> Result<Key, AppError> {
+    KEY.get_or_try_init(read_or_create_key).copied()
+}
+
+fn read_or_create_key() -> Result<Key, AppError> {
+    let path = get_key_path();
+    // create_new so a key written by another process in the meantime is never replaced
+    let mut file = match File::options().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return read_key(&path),
+        Err(e) => return Err(AppError::new("Crypto", eyre!(e.to_string()))),
+    };
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    file.write_all(key.as_slice())
+        .map_err(|e| AppError::new("Crypto", eyre!(e.to_string())))?;
+    // Only the user running the app can read it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| AppError::new("Crypto", eyre!(e.to_string())))?;
+    }
+    Ok(key)
+}
+
+fn read_key(path: &Path) -> Result<Key, AppError> {
+    let bytes = fs::read(path).map_err(|e| AppError::new("Crypto", eyre!(e.to_string())))?;
+    if bytes.len() != 32 {
+        return Err(AppError::new(
+            "Crypto",
+            eyre!("Key file {} is not valid", path.display()),
+        ));
+    }
+    Ok(*Key::from_slice(&bytes))
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+pub fn encrypt(plain: &str) -> Result<String, AppError> {
+    let cipher = ChaCha20Poly1305::new(&load_key()?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let encrypted = cipher
+        .encrypt(&nonce, plain.as_bytes())
+        .map_err(|e| AppError::new("Crypto", eyre!(e.to_string())))?;
+    let mut data = nonce.to_vec();
+    data.extend(encrypted);
+    Ok(format!("{}{}", PREFIX, STANDARD.encode(data)))
+}
+
+// Plain values are returned as they are, so files from older versions keep working
+pub fn decrypt(value: &str) -> Result<String, AppError> {
+    let encoded = match value.strip_prefix(PREFIX) {
+        Some(encoded) => encoded,
+        None => return Ok(value.to_string()),
+    };
+    let data = STANDARD
+        .decode(encoded)
+        .map_err(|e| AppError::new("Crypto", eyre!(e.to_string())))?;
+    if data.len() <= NONCE_LEN {
+        return Err(AppError::new(
+            "Crypto",
+            eyre!("Encrypted value is too short"),
+        ));
+    }
+    let (nonce, encrypted) = data.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(&load_key()?);
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), encrypted)
+        .map_err(|_| AppError::new("Crypto", eyre!("Could not decrypt, the key file changed")))?;
+    String::from_utf8(plain).map_err(|e| AppError::new("Crypto", eyre!(e.to_string())))
+}
@@ -1,4 +1,7 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use eyre::eyre;
 use polars::{
@@ -6,35 +9,76 @@ use polars::{
     series::Series,
 };
 use reqwest::{header::HeaderMap, Client, Method, Url};
+use sea_query::{ColumnDef, Expr, Func, Iden, InsertStatement, Query, SqliteQueryBuilder, Table};
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
-use sqlx::{migrate::MigrateDatabase, Pool, Sqlite, SqlitePool};
+use sqlx::{migrate::MigrateDatabase, Pool, Row, Sqlite, SqlitePool};
 
 use crate::{
     auth::AuthState,
     cache::client::CacheClient,
+    enums::LogLevel,
     error::AppError,
-    helper,
     logger::{self},
+    profiles,
     wfm_client::client::WFMClient,
 };
 
-use super::modules::{ transaction::TransactionModule, stock_item::StockItemModule, stock_riven::StockRivenModule};
+use super::migrations;
+use super::modules::{
+    data_transfer::DataTransferModule, price_history::PriceHistoryModule,
+    profit_report::ProfitReportModule, riven_snapshot::RivenSnapshotModule,
+    stock_item::StockItemModule, stock_riven::StockRivenModule, transaction::TransactionModule,
+};
+#[derive(Iden)]
+pub enum SchemaVersion {
+    Table,
+    Version,
+    Name,
+    Applied,
+}
+
+pub enum MigrationStep {
+    Sql(String),
+    // Skipped when the table already has the column
+    AddColumn {
+        table: String,
+        column: String,
+        sql: String,
+    },
+}
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub steps: Vec<MigrationStep>,
+}
+
 #[derive(Clone, Debug)]
 pub struct DBClient {
     pub log_file: String,
     pub connection: Arc<Mutex<Pool<Sqlite>>>,
     pub cache: Arc<Mutex<CacheClient>>,
     pub wfm: Arc<Mutex<WFMClient>>,
+    // Folder of the profile the database belongs to
+    pub data_path: PathBuf,
 }
 
 impl DBClient {
+    // Database of the active profile
     pub async fn new(
         cache: Arc<Mutex<CacheClient>>,
         wfm: Arc<Mutex<WFMClient>>,
+    ) -> Result<Self, AppError> {
+        Self::open(cache, wfm, profiles::get_active_path()?).await
+    }
+    pub async fn open(
+        cache: Arc<Mutex<CacheClient>>,
+        wfm: Arc<Mutex<WFMClient>>,
+        data_path: PathBuf,
     ) -> Result<Self, AppError> {
         let log_file = "db.log";
-        let mut db_url = helper::get_app_roaming_path();
+        let mut db_url = data_path.clone();
         db_url.push("quantframe.sqlite");
         let db_url: &str = db_url.to_str().unwrap();
         if !Sqlite::database_exists(db_url).await.unwrap_or(false) {
@@ -56,14 +100,170 @@ impl DBClient {
             connection: Arc::new(Mutex::new(SqlitePool::connect(db_url).await.unwrap())),
             cache,
             wfm,
+            data_path,
         })
     }
     pub async fn initialize(&self) -> Result<bool, AppError> {
-        self.stock_item().initialize().await?;
-        self.stock_riven().initialize().await?;
-        self.transaction().initialize().await?;
+        self.migrate().await?;
         Ok(true)
     }
+
+    // Brings the schema up to date, returns the schema version.
+    pub async fn migrate(&self) -> Result<i64, AppError> {
+        let connection = self.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(SchemaVersion::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(SchemaVersion::Version)
+                    .integer()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(SchemaVersion::Name).string().not_null())
+            .col(
+                ColumnDef::new(SchemaVersion::Applied)
+                    .date_time()
+                    .not_null(),
+            )
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let current = self.get_schema_version().await?;
+        let migrations = migrations::get_migrations();
+        let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+        if current > latest {
+            return Err(AppError::new_with_level(
+                "Database",
+                eyre!(
+                    "The database schema version {} is newer than this version of the app supports ({}), please update the app",
+                    current,
+                    latest
+                ),
+                LogLevel::Critical,
+            ));
+        }
+
+        let pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > current).collect();
+        if pending.is_empty() {
+            return Ok(current);
+        }
+        self.backup(current).await?;
+
+        for migration in pending {
+            logger::info_con(
+                "Database",
+                format!(
+                    "Running migration {}: {}",
+                    migration.version, migration.name
+                )
+                .as_str(),
+            );
+            let mut tx = connection
+                .begin()
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            for step in migration.steps.iter() {
+                let sql = match step {
+                    MigrationStep::Sql(sql) => sql,
+                    MigrationStep::AddColumn { table, column, sql } => {
+                        let columns =
+                            sqlx::query(format!("PRAGMA table_info(\"{}\")", table).as_str())
+                                .fetch_all(&mut *tx)
+                                .await
+                                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+                        if columns
+                            .iter()
+                            .any(|row| row.get::<String, _>("name") == *column)
+                        {
+                            continue;
+                        }
+                        sql
+                    }
+                };
+                sqlx::query(sql).execute(&mut *tx).await.map_err(|e| {
+                    AppError::new(
+                        "Database",
+                        eyre!("Migration {} failed: {}", migration.version, e),
+                    )
+                })?;
+            }
+            let sql = InsertStatement::default()
+                .into_table(SchemaVersion::Table)
+                .columns([
+                    SchemaVersion::Version,
+                    SchemaVersion::Name,
+                    SchemaVersion::Applied,
+                ])
+                .values_panic([
+                    migration.version.into(),
+                    migration.name.into(),
+                    chrono::Local::now().naive_local().to_string().into(),
+                ])
+                .to_string(SqliteQueryBuilder);
+            sqlx::query(&sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            tx.commit()
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        Ok(latest)
+    }
+
+    pub async fn get_schema_version(&self) -> Result<i64, AppError> {
+        let connection = self.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .expr(Func::max(Expr::col(SchemaVersion::Version)))
+            .from(SchemaVersion::Table)
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql)
+            .fetch_one(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let version: Option<i64> = row.get(0);
+        Ok(version.unwrap_or(0))
+    }
+
+    // Copies the database to the backups folder before migrating it.
+    // Uses VACUUM INTO so pages still in the WAL file are included.
+    async fn backup(&self, version: i64) -> Result<(), AppError> {
+        let connection = self.connection.lock().unwrap().clone();
+        // Nothing to back up for a new database
+        let tables = sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' AND name != 'schema_version' AND name NOT LIKE 'sqlite_%'")
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        if tables.is_empty() {
+            return Ok(());
+        }
+
+        let mut path = self.data_path.clone();
+        path.push("backups");
+        std::fs::create_dir_all(&path)
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        path.push(format!(
+            "quantframe_v{}_{}.sqlite",
+            version,
+            chrono::Local::now().format("%Y%m%d%H%M%S")
+        ));
+        let path = path.to_str().unwrap().replace("'", "''");
+        sqlx::query(format!("VACUUM INTO '{}'", path).as_str())
+            .execute(&connection)
+            .await
+            .map_err(|e| {
+                AppError::new("Database", eyre!("Could not back up the database: {}", e))
+            })?;
+        logger::info_con(
+            "Database",
+            format!("Database backed up to {}", path).as_str(),
+        );
+        Ok(())
+    }
     pub fn get_connection(&self) -> Arc<Mutex<Pool<Sqlite>>> {
         self.connection.clone()
     }
@@ -79,4 +279,182 @@ impl DBClient {
     pub fn stock_riven(&self) -> StockRivenModule {
         StockRivenModule { client: self }
     }
+
+    pub fn price_history(&self) -> PriceHistoryModule {
+        PriceHistoryModule { client: self }
+    }
+
+    pub fn riven_snapshot(&self) -> RivenSnapshotModule {
+        RivenSnapshotModule { client: self }
+    }
+
+    pub fn profit_report(&self) -> ProfitReportModule {
+        ProfitReportModule { client: self }
+    }
+
+    pub fn data_transfer(&self) -> DataTransferModule {
+        DataTransferModule { client: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use sqlx::Row;
+
+    use super::{migrations, DBClient};
+    use crate::{
+        auth::AuthState, cache::client::CacheClient, settings::SettingsState,
+        wfm_client::client::WFMClient,
+    };
+
+    fn latest() -> i64 {
+        migrations::get_migrations().last().unwrap().version
+    }
+
+    // A new database file, nothing is migrated yet
+    fn open_db(name: &str) -> DBClient {
+        let wfm = Arc::new(Mutex::new(WFMClient::new(
+            Arc::new(Mutex::new(AuthState::default())),
+            Arc::new(Mutex::new(SettingsState::default())),
+        )));
+        let cache = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm))));
+        let path = std::env::temp_dir().join(format!(
+            "quantframe_migrate_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        tauri::async_runtime::block_on(DBClient::open(cache, wfm, path)).unwrap()
+    }
+
+    fn execute(db: &DBClient, sql: &str) {
+        let connection = db.connection.lock().unwrap().clone();
+        tauri::async_runtime::block_on(sqlx::query(sql).execute(&connection)).unwrap();
+    }
+
+    fn columns(db: &DBClient, table: &str) -> Vec<String> {
+        let connection = db.connection.lock().unwrap().clone();
+        tauri::async_runtime::block_on(
+            sqlx::query(format!("PRAGMA table_info(\"{}\")", table).as_str())
+                .fetch_all(&connection),
+        )
+        .unwrap()
+        .iter()
+        .map(|row| row.get::<String, _>("name"))
+        .collect()
+    }
+
+    fn backups(db: &DBClient) -> Vec<std::path::PathBuf> {
+        match std::fs::read_dir(db.data_path.join("backups")) {
+            Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    #[test]
+    fn a_new_database_gets_every_migration() {
+        let db = open_db("new");
+        assert_eq!(
+            tauri::async_runtime::block_on(db.migrate()).unwrap(),
+            latest()
+        );
+        assert_eq!(
+            tauri::async_runtime::block_on(db.get_schema_version()).unwrap(),
+            latest()
+        );
+        assert!(columns(&db, "stock_item").contains(&"status".to_string()));
+        assert!(columns(&db, "stock_riven").contains(&"fair_value".to_string()));
+        assert!(!columns(&db, "price_history").is_empty());
+        // Nothing worth keeping yet
+        assert!(backups(&db).is_empty());
+        // Running it again changes nothing
+        assert_eq!(
+            tauri::async_runtime::block_on(db.migrate()).unwrap(),
+            latest()
+        );
+    }
+
+    #[test]
+    fn a_database_from_before_the_migrations_is_upgraded() {
+        let db = open_db("old");
+        // stock_item as it was, minium_price and hidden are there but status is not
+        execute(
+            &db,
+            "CREATE TABLE stock_item (id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT, wfm_id TEXT NOT NULL, url TEXT NOT NULL, name TEXT NOT NULL, tags TEXT NOT NULL, rank INTEGER NOT NULL DEFAULT 0, sub_type TEXT, price REAL NOT NULL DEFAULT 0, minium_price INTEGER, listed_price INTEGER, owned INTEGER NOT NULL DEFAULT 1, hidden BOOLEAN NOT NULL DEFAULT false, created TEXT NOT NULL)",
+        );
+        execute(
+            &db,
+            "INSERT INTO stock_item (wfm_id, url, name, tags, price, minium_price, created) VALUES ('nova_id', 'nova_prime_set', 'Nova Prime Set', 'prime,set', 45, 40, '2024-01-01')",
+        );
+
+        assert_eq!(
+            tauri::async_runtime::block_on(db.migrate()).unwrap(),
+            latest()
+        );
+        let columns = columns(&db, "stock_item");
+        assert_eq!(columns.iter().filter(|c| c.as_str() == "hidden").count(), 1);
+        assert!(columns.contains(&"status".to_string()));
+        let items = tauri::async_runtime::block_on(db.stock_item().get_items()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].minium_price, Some(40));
+        assert_eq!(items[0].status, "pending");
+    }
+
+    #[test]
+    fn the_database_is_backed_up_before_it_is_migrated() {
+        let db = open_db("backup");
+        execute(
+            &db,
+            "CREATE TABLE stock_item (id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT, wfm_id TEXT NOT NULL, url TEXT NOT NULL, name TEXT NOT NULL, tags TEXT NOT NULL, rank INTEGER NOT NULL DEFAULT 0, sub_type TEXT, price REAL NOT NULL DEFAULT 0, minium_price INTEGER, listed_price INTEGER, owned INTEGER NOT NULL DEFAULT 1, hidden BOOLEAN NOT NULL DEFAULT false, created TEXT NOT NULL)",
+        );
+        execute(
+            &db,
+            "INSERT INTO stock_item (wfm_id, url, name, tags, created) VALUES ('nova_id', 'nova_prime_set', 'Nova Prime Set', 'prime,set', '2024-01-01')",
+        );
+        tauri::async_runtime::block_on(db.migrate()).unwrap();
+
+        let backups = backups(&db);
+        assert_eq!(backups.len(), 1);
+        let file_name = backups[0].file_name().unwrap().to_str().unwrap();
+        assert!(file_name.starts_with("quantframe_v0_"));
+        // The copy holds the data as it was before the migration
+        let copy =
+            tauri::async_runtime::block_on(sqlx::SqlitePool::connect(backups[0].to_str().unwrap()))
+                .unwrap();
+        let rows = tauri::async_runtime::block_on(
+            sqlx::query("SELECT url FROM stock_item").fetch_all(&copy),
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get::<String, _>("url"), "nova_prime_set");
+        let status = tauri::async_runtime::block_on(
+            sqlx::query("SELECT status FROM stock_item").fetch_all(&copy),
+        );
+        assert!(status.is_err());
+    }
+
+    #[test]
+    fn a_schema_newer_than_the_app_is_refused() {
+        let db = open_db("newer");
+        tauri::async_runtime::block_on(db.migrate()).unwrap();
+        execute(
+            &db,
+            format!(
+                "INSERT INTO schema_version (version, name, applied) VALUES ({}, 'from_the_future', '2024-01-01')",
+                latest() + 1
+            )
+            .as_str(),
+        );
+
+        let error = tauri::async_runtime::block_on(db.migrate()).unwrap_err();
+        assert!(error.cause().contains("newer than this version of the app"));
+        // Left as it was
+        assert_eq!(
+            tauri::async_runtime::block_on(db.get_schema_version()).unwrap(),
+            latest() + 1
+        );
+    }
 }
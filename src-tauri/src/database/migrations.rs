@@ -0,0 +1,409 @@
+use sea_query::{ColumnDef, Iden, SqliteQueryBuilder, Table, Value};
+use serde_json::json;
+
+use super::{
+    client::{Migration, MigrationStep},
+    modules::{
+        price_history::PriceHistory, riven_snapshot::RivenSnapshot, stock_item::StockItem,
+        stock_riven::StockRiven, transaction::Transaction,
+    },
+};
+
+// Ordered list of every schema change. Never edit a migration that has shipped, add a new one.
+pub fn get_migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            name: "initial_schema",
+            steps: vec![
+                MigrationStep::Sql(create_stock_item()),
+                MigrationStep::Sql(create_stock_riven()),
+                MigrationStep::Sql(create_transaction()),
+            ],
+        },
+        Migration {
+            version: 2,
+            name: "stock_item_status",
+            steps: vec![
+                add_column(
+                    StockItem::Table,
+                    "stock_item",
+                    "minium_price",
+                    ColumnDef::new(StockItem::MiniumPrice)
+                        .integer()
+                        .default(Value::Int(None))
+                ),
+                add_column(
+                    StockItem::Table,
+                    "stock_item",
+                    "hidden",
+                    ColumnDef::new(StockItem::Hidden)
+                        .boolean()
+                        .not_null()
+                        .default(Value::Bool(Some(false)))
+                ),
+                add_column(
+                    StockItem::Table,
+                    "stock_item",
+                    "status",
+                    ColumnDef::new(StockItem::Status)
+                        .string()
+                        .not_null()
+                        .default("pending")
+                ),
+            ],
+        },
+        Migration {
+            version: 3,
+            name: "stock_riven_status",
+            steps: vec![
+                add_column(
+                    StockRiven::Table,
+                    "stock_riven",
+                    "match_riven",
+                    ColumnDef::new(StockRiven::MatchRiven)
+                        .json()
+                        .not_null()
+                        .default(json!({}))
+                ),
+                add_column(
+                    StockRiven::Table,
+                    "stock_riven",
+                    "minium_price",
+                    ColumnDef::new(StockRiven::MiniumPrice)
+                        .integer()
+                        .default(Value::Int(None))
+                ),
+                add_column(
+                    StockRiven::Table,
+                    "stock_riven",
+                    "status",
+                    ColumnDef::new(StockRiven::Status)
+                        .string()
+                        .not_null()
+                        .default("pending")
+                ),
+            ],
+        },
+        Migration {
+            version: 4,
+            name: "price_history",
+            steps: vec![
+                MigrationStep::Sql(create_price_history()),
+                MigrationStep::Sql(
+                    "CREATE UNIQUE INDEX IF NOT EXISTS idx_price_history_unique ON price_history (name, datetime, order_type, mod_rank)".to_string(),
+                ),
+            ],
+        },
+        Migration {
+            version: 5,
+            name: "riven_valuation",
+            steps: vec![
+                MigrationStep::Sql(create_riven_snapshot()),
+                MigrationStep::Sql(
+                    "CREATE UNIQUE INDEX IF NOT EXISTS idx_riven_snapshot_auction ON riven_snapshot (auction_id)".to_string(),
+                ),
+                MigrationStep::Sql(
+                    "CREATE INDEX IF NOT EXISTS idx_riven_snapshot_weapon_type ON riven_snapshot (weapon_type, last_seen)".to_string(),
+                ),
+                add_column(
+                    StockRiven::Table,
+                    "stock_riven",
+                    "lowest_price",
+                    ColumnDef::new(StockRiven::LowestPrice)
+                        .integer()
+                        .default(Value::Int(None))
+                ),
+                add_column(
+                    StockRiven::Table,
+                    "stock_riven",
+                    "fair_value",
+                    ColumnDef::new(StockRiven::FairValue)
+                        .integer()
+                        .default(Value::Int(None))
+                ),
+                add_column(
+                    StockRiven::Table,
+                    "stock_riven",
+                    "fair_value_confidence",
+                    ColumnDef::new(StockRiven::FairValueConfidence)
+                        .float()
+                        .default(Value::Double(None))
+                ),
+            ],
+        },
+    ]
+}
+
+// Databases from before the migrations already have some of these columns, they are skipped.
+fn add_column<T: Iden + 'static>(
+    table: T,
+    table_name: &str,
+    column_name: &str,
+    def: &mut ColumnDef,
+) -> MigrationStep {
+    MigrationStep::AddColumn {
+        table: table_name.to_string(),
+        column: column_name.to_string(),
+        sql: Table::alter()
+            .table(table)
+            .add_column(def)
+            .to_string(SqliteQueryBuilder),
+    }
+}
+
+fn create_stock_item() -> String {
+    Table::create()
+        .table(StockItem::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(StockItem::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(StockItem::WFMId).uuid().not_null())
+        .col(ColumnDef::new(StockItem::Url).string().not_null())
+        .col(ColumnDef::new(StockItem::Name).string().not_null())
+        .col(ColumnDef::new(StockItem::Tags).string().not_null())
+        .col(
+            ColumnDef::new(StockItem::Rank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(ColumnDef::new(StockItem::SubType).string())
+        .col(
+            ColumnDef::new(StockItem::Price)
+                .float()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(StockItem::MiniumPrice)
+                .integer()
+                .default(Value::Int(None)),
+        )
+        .col(
+            ColumnDef::new(StockItem::ListedPrice)
+                .integer()
+                .default(Value::Int(None)),
+        )
+        .col(
+            ColumnDef::new(StockItem::Owned)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(1))),
+        )
+        .col(
+            ColumnDef::new(StockItem::Hidden)
+                .boolean()
+                .not_null()
+                .default(Value::Bool(Some(false))),
+        )
+        .col(ColumnDef::new(StockItem::Created).date_time().not_null())
+        .build(SqliteQueryBuilder)
+}
+
+fn create_stock_riven() -> String {
+    Table::create()
+        .table(StockRiven::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(StockRiven::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(StockRiven::OrderId).uuid())
+        .col(ColumnDef::new(StockRiven::WeaponId).uuid().not_null())
+        .col(ColumnDef::new(StockRiven::WeaponUrl).string().not_null())
+        .col(ColumnDef::new(StockRiven::WeaponName).string().not_null())
+        .col(ColumnDef::new(StockRiven::WeaponType).string().not_null())
+        .col(ColumnDef::new(StockRiven::ModName).string().not_null())
+        .col(
+            ColumnDef::new(StockRiven::Rank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(StockRiven::Attributes)
+                .json()
+                .not_null()
+                .default(json!([])),
+        )
+        .col(
+            ColumnDef::new(StockRiven::MatchRiven)
+                .json()
+                .not_null()
+                .default(json!({})),
+        )
+        .col(
+            ColumnDef::new(StockRiven::MasteryRank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(StockRiven::ReRolls)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(ColumnDef::new(StockRiven::Polarity).string().not_null())
+        .col(
+            ColumnDef::new(StockRiven::Price)
+                .float()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(StockRiven::MiniumPrice)
+                .integer()
+                .default(Value::Int(None)),
+        )
+        .col(
+            ColumnDef::new(StockRiven::ListedPrice)
+                .integer()
+                .default(Value::Int(None)),
+        )
+        .col(
+            ColumnDef::new(StockRiven::Private)
+                .boolean()
+                .default(Value::Bool(Some(false))),
+        )
+        .col(
+            ColumnDef::new(StockRiven::Status)
+                .string()
+                .not_null()
+                .default("pending"),
+        )
+        .col(ColumnDef::new(StockRiven::Created).date_time().not_null())
+        .build(SqliteQueryBuilder)
+}
+
+fn create_transaction() -> String {
+    Table::create()
+        .table(Transaction::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Transaction::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Transaction::WFMId).uuid().not_null())
+        .col(ColumnDef::new(Transaction::Url).string().not_null())
+        .col(ColumnDef::new(Transaction::Name).string().not_null())
+        .col(ColumnDef::new(Transaction::ItemType).string().not_null())
+        .col(ColumnDef::new(Transaction::Tags).string().not_null())
+        .col(
+            ColumnDef::new(Transaction::TransactionType)
+                .string()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(Transaction::Quantity)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(1))),
+        )
+        .col(
+            ColumnDef::new(Transaction::Rank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(Transaction::Price)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(ColumnDef::new(Transaction::Properties).json())
+        .col(ColumnDef::new(Transaction::Created).date_time().not_null())
+        .build(SqliteQueryBuilder)
+}
+
+fn create_price_history() -> String {
+    Table::create()
+        .table(PriceHistory::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(PriceHistory::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(PriceHistory::ItemId).string().not_null())
+        .col(ColumnDef::new(PriceHistory::Name).string().not_null())
+        .col(ColumnDef::new(PriceHistory::Datetime).string().not_null())
+        .col(ColumnDef::new(PriceHistory::OrderType).string().not_null())
+        .col(
+            ColumnDef::new(PriceHistory::ModRank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(-1))),
+        )
+        .col(ColumnDef::new(PriceHistory::Volume).integer())
+        .col(ColumnDef::new(PriceHistory::MinPrice).float())
+        .col(ColumnDef::new(PriceHistory::MaxPrice).float())
+        .col(ColumnDef::new(PriceHistory::AvgPrice).float())
+        .col(ColumnDef::new(PriceHistory::Median).float())
+        .build(SqliteQueryBuilder)
+}
+
+fn create_riven_snapshot() -> String {
+    Table::create()
+        .table(RivenSnapshot::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(RivenSnapshot::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(RivenSnapshot::AuctionId).string().not_null())
+        .col(ColumnDef::new(RivenSnapshot::WeaponUrl).string().not_null())
+        .col(
+            ColumnDef::new(RivenSnapshot::WeaponType)
+                .string()
+                .not_null(),
+        )
+        .col(ColumnDef::new(RivenSnapshot::ModName).string().not_null())
+        .col(
+            ColumnDef::new(RivenSnapshot::Attributes)
+                .json()
+                .not_null()
+                .default(json!([])),
+        )
+        .col(
+            ColumnDef::new(RivenSnapshot::MasteryRank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(RivenSnapshot::ReRolls)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(
+            ColumnDef::new(RivenSnapshot::ModRank)
+                .integer()
+                .not_null()
+                .default(Value::Int(Some(0))),
+        )
+        .col(ColumnDef::new(RivenSnapshot::Polarity).string().not_null())
+        .col(ColumnDef::new(RivenSnapshot::Price).integer().not_null())
+        .col(ColumnDef::new(RivenSnapshot::FirstSeen).string().not_null())
+        .col(ColumnDef::new(RivenSnapshot::LastSeen).string().not_null())
+        .build(SqliteQueryBuilder)
+}
@@ -1,3 +1,4 @@
 pub mod client;
+pub mod migrations;
 pub mod modules;
 pub mod types;
\ No newline at end of file
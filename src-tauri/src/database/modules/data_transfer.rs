@@ -0,0 +1,1057 @@
+use std::{collections::HashSet, fs};
+
+use eyre::eyre;
+use sea_query::{DeleteStatement, InsertStatement, Query, SqliteQueryBuilder, Value, Values};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{query::Query as SqlxQuery, sqlite::SqliteArguments, Sqlite};
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::{
+            stock_item::{StockItem, StockItemStruct},
+            stock_riven::{MatchRivenStruct, StockRiven, StockRivenStruct},
+            transaction::{Transaction, TransactionStruct},
+        },
+    },
+    error::AppError,
+    logger,
+    structs::RivenAttribute,
+};
+
+// A row that was not imported and why.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportConflict {
+    // 1 based, the header of a csv file is not counted
+    pub row: usize,
+    pub key: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub table: String,
+    pub imported: i64,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+// Csv can't hold the nested fields, they are stored as json strings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StockRivenCsvRecord {
+    pub id: i64,
+    pub order_id: Option<String>,
+    pub weapon_id: String,
+    pub weapon_url: String,
+    pub weapon_name: String,
+    pub weapon_type: String,
+    pub mod_name: String,
+    pub rank: i32,
+    pub attributes: String,
+    pub mastery_rank: i32,
+    pub re_rolls: i32,
+    pub polarity: String,
+    pub price: f64,
+    pub minium_price: Option<i32>,
+    pub listed_price: Option<i32>,
+    pub match_riven: String,
+    pub private: bool,
+    pub status: String,
+    pub created: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionCsvRecord {
+    pub id: i64,
+    pub wfm_id: String,
+    pub url: String,
+    pub name: String,
+    pub item_type: String,
+    pub tags: String,
+    pub transaction_type: String,
+    pub quantity: i32,
+    pub rank: i32,
+    pub price: i32,
+    pub created: String,
+    pub properties: Option<String>,
+}
+
+impl From<StockRivenStruct> for StockRivenCsvRecord {
+    fn from(riven: StockRivenStruct) -> Self {
+        StockRivenCsvRecord {
+            id: riven.id,
+            order_id: riven.order_id,
+            weapon_id: riven.weapon_id,
+            weapon_url: riven.weapon_url,
+            weapon_name: riven.weapon_name,
+            weapon_type: riven.weapon_type,
+            mod_name: riven.mod_name,
+            rank: riven.rank,
+            attributes: serde_json::to_string(&riven.attributes.0).unwrap_or_default(),
+            mastery_rank: riven.mastery_rank,
+            re_rolls: riven.re_rolls,
+            polarity: riven.polarity,
+            price: riven.price,
+            minium_price: riven.minium_price,
+            listed_price: riven.listed_price,
+            match_riven: serde_json::to_string(&riven.match_riven.0).unwrap_or_default(),
+            private: riven.private,
+            status: riven.status,
+            created: riven.created,
+        }
+    }
+}
+
+impl TryFrom<StockRivenCsvRecord> for StockRivenStruct {
+    type Error = String;
+    fn try_from(record: StockRivenCsvRecord) -> Result<Self, Self::Error> {
+        let attributes: Vec<RivenAttribute> = serde_json::from_str(&record.attributes)
+            .map_err(|e| format!("Invalid attributes: {}", e))?;
+        let match_riven: MatchRivenStruct = serde_json::from_str(&record.match_riven)
+            .map_err(|e| format!("Invalid match_riven: {}", e))?;
+        Ok(StockRivenStruct {
+            id: record.id,
+            order_id: record.order_id,
+            weapon_id: record.weapon_id,
+            weapon_url: record.weapon_url,
+            weapon_name: record.weapon_name,
+            weapon_type: record.weapon_type,
+            mod_name: record.mod_name,
+            rank: record.rank,
+            attributes: sqlx::types::Json(attributes),
+            mastery_rank: record.mastery_rank,
+            re_rolls: record.re_rolls,
+            polarity: record.polarity,
+            price: record.price,
+            minium_price: record.minium_price,
+            listed_price: record.listed_price,
+            match_riven: sqlx::types::Json(match_riven),
+            private: record.private,
+            status: record.status,
+            created: record.created,
+            // Recalculated by the live scraper
+            lowest_price: None,
+            fair_value: None,
+            fair_value_confidence: None,
+        })
+    }
+}
+
+impl From<TransactionStruct> for TransactionCsvRecord {
+    fn from(transaction: TransactionStruct) -> Self {
+        TransactionCsvRecord {
+            id: transaction.id,
+            wfm_id: transaction.wfm_id,
+            url: transaction.url,
+            name: transaction.name,
+            item_type: transaction.item_type,
+            tags: transaction.tags,
+            transaction_type: transaction.transaction_type,
+            quantity: transaction.quantity,
+            rank: transaction.rank,
+            price: transaction.price,
+            created: transaction.created,
+            properties: transaction
+                .properties
+                .and_then(|p| p.0)
+                .map(|p| p.to_string()),
+        }
+    }
+}
+
+impl TryFrom<TransactionCsvRecord> for TransactionStruct {
+    type Error = String;
+    fn try_from(record: TransactionCsvRecord) -> Result<Self, Self::Error> {
+        let properties = match record.properties.filter(|p| !p.is_empty()) {
+            Some(p) => Some(
+                serde_json::from_str::<serde_json::Value>(&p)
+                    .map_err(|e| format!("Invalid properties: {}", e))?,
+            ),
+            None => None,
+        };
+        Ok(TransactionStruct {
+            id: record.id,
+            wfm_id: record.wfm_id,
+            url: record.url,
+            name: record.name,
+            item_type: record.item_type,
+            tags: record.tags,
+            transaction_type: record.transaction_type,
+            quantity: record.quantity,
+            rank: record.rank,
+            price: record.price,
+            created: record.created,
+            properties: Some(sqlx::types::Json(properties)),
+        })
+    }
+}
+
+pub struct DataTransferModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> DataTransferModule<'a> {
+    // Writes a table to a csv or json file, returns the number of rows written.
+    pub async fn export(&self, table: &str, format: &str, path: &str) -> Result<i64, AppError> {
+        let rows = match table {
+            "stock_item" => {
+                let items = self.client.stock_item().get_items().await?;
+                write_rows(format, path, &items, &items)?
+            }
+            "stock_riven" => {
+                let rivens = self.client.stock_riven().get_rivens().await?;
+                let records: Vec<StockRivenCsvRecord> =
+                    rivens.iter().cloned().map(|r| r.into()).collect();
+                write_rows(format, path, &rivens, &records)?
+            }
+            "transaction" => {
+                let transactions = self.client.transaction().get_items().await?;
+                let records: Vec<TransactionCsvRecord> =
+                    transactions.iter().cloned().map(|t| t.into()).collect();
+                write_rows(format, path, &transactions, &records)?
+            }
+            _ => {
+                return Err(AppError::new(
+                    "DataTransfer",
+                    eyre!("Unknown table: {}", table),
+                ))
+            }
+        };
+        logger::info_con(
+            "DataTransfer",
+            format!("Exported {} rows from {} to {}", rows, table, path).as_str(),
+        );
+        Ok(rows)
+    }
+
+    // Reads a csv or json file into a table.
+    // Rows with an unknown url_name, invalid fields or that already exist are skipped and reported.
+    // When replace is set the table is emptied in the same transaction the rows are written in.
+    pub async fn import(
+        &self,
+        table: &str,
+        format: &str,
+        path: &str,
+        replace: bool,
+    ) -> Result<ImportReport, AppError> {
+        let report = match table {
+            "stock_item" => {
+                let rows = read_rows::<StockItemStruct, StockItemStruct>(format, path)?;
+                self.import_stock_items(rows, replace).await?
+            }
+            "stock_riven" => {
+                let rows = read_rows::<StockRivenStruct, StockRivenCsvRecord>(format, path)?;
+                self.import_stock_rivens(rows, replace).await?
+            }
+            "transaction" => {
+                let rows = read_rows::<TransactionStruct, TransactionCsvRecord>(format, path)?;
+                self.import_transactions(rows, replace).await?
+            }
+            _ => {
+                return Err(AppError::new(
+                    "DataTransfer",
+                    eyre!("Unknown table: {}", table),
+                ))
+            }
+        };
+        logger::info_con(
+            "DataTransfer",
+            format!(
+                "Imported {} rows into {}, {} conflicts",
+                report.imported,
+                table,
+                report.conflicts.len()
+            )
+            .as_str(),
+        );
+        Ok(report)
+    }
+
+    async fn import_stock_items(
+        &self,
+        rows: Vec<Result<StockItemStruct, String>>,
+        replace: bool,
+    ) -> Result<ImportReport, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let mut report = ImportReport {
+            table: "stock_item".to_string(),
+            ..Default::default()
+        };
+        // The rows in the table don't count when they are replaced
+        let mut existing: HashSet<String> = if replace {
+            HashSet::new()
+        } else {
+            self.client
+                .stock_item()
+                .get_items()
+                .await?
+                .iter()
+                .map(|i| format!("{}:{}:{:?}", i.url, i.rank, i.sub_type))
+                .collect()
+        };
+        let mut statements = vec![];
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let item = match row {
+                Ok(item) => item,
+                Err(e) => {
+                    report.conflicts.push(conflict(i, "", &e));
+                    continue;
+                }
+            };
+            let key = format!("{}:{}:{:?}", item.url, item.rank, item.sub_type);
+            let cached = match cache.items().find_type(&item.url)? {
+                Some(cached) => cached,
+                None => {
+                    report
+                        .conflicts
+                        .push(conflict(i, &item.url, "Unknown item url_name"));
+                    continue;
+                }
+            };
+            if !existing.insert(key) {
+                report
+                    .conflicts
+                    .push(conflict(i, &item.url, "Item is already in stock"));
+                continue;
+            }
+
+            let statement = InsertStatement::default()
+                .into_table(StockItem::Table)
+                .columns([
+                    StockItem::WFMId,
+                    StockItem::Url,
+                    StockItem::Name,
+                    StockItem::Tags,
+                    StockItem::Rank,
+                    StockItem::SubType,
+                    StockItem::Price,
+                    StockItem::MiniumPrice,
+                    StockItem::ListedPrice,
+                    StockItem::Owned,
+                    StockItem::Hidden,
+                    StockItem::Status,
+                    StockItem::Created,
+                ])
+                .values_panic([
+                    cached.id.clone().into(),
+                    cached.url_name.clone().into(),
+                    cached.item_name.replace("\'", "").into(),
+                    cached.tags.clone().unwrap_or_default().join(",").into(),
+                    item.rank.into(),
+                    item.sub_type.clone().into(),
+                    item.price.into(),
+                    item.minium_price.into(),
+                    item.listed_price.into(),
+                    item.owned.into(),
+                    item.hidden.into(),
+                    item.status.clone().into(),
+                    item.created.clone().into(),
+                ])
+                .build(SqliteQueryBuilder);
+            statements.push(statement);
+        }
+        report.imported = self
+            .write(
+                replace.then(|| Query::delete().from_table(StockItem::Table).to_owned()),
+                statements,
+            )
+            .await?;
+        self.client
+            .stock_item()
+            .emit("SET", json!(self.client.stock_item().get_items().await?));
+        Ok(report)
+    }
+
+    async fn import_stock_rivens(
+        &self,
+        rows: Vec<Result<StockRivenStruct, String>>,
+        replace: bool,
+    ) -> Result<ImportReport, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let mut report = ImportReport {
+            table: "stock_riven".to_string(),
+            ..Default::default()
+        };
+        let riven_key = |r: &StockRivenStruct| {
+            format!(
+                "{}:{}:{}:{}",
+                r.weapon_url, r.mod_name, r.mastery_rank, r.re_rolls
+            )
+        };
+        let mut existing: HashSet<String> = if replace {
+            HashSet::new()
+        } else {
+            self.client
+                .stock_riven()
+                .get_rivens()
+                .await?
+                .iter()
+                .map(riven_key)
+                .collect()
+        };
+        let mut statements = vec![];
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let riven = match row {
+                Ok(riven) => riven,
+                Err(e) => {
+                    report.conflicts.push(conflict(i, "", &e));
+                    continue;
+                }
+            };
+            let cached = match cache.riven().find_type(&riven.weapon_url)? {
+                Some(cached) => cached,
+                None => {
+                    report.conflicts.push(conflict(
+                        i,
+                        &riven.weapon_url,
+                        "Unknown weapon url_name",
+                    ));
+                    continue;
+                }
+            };
+            let mut unknown_attributes = vec![];
+            for attribute in riven.attributes.0.iter() {
+                if cache.riven().find_attribute(&attribute.url_name)?.is_none() {
+                    unknown_attributes.push(attribute.url_name.clone());
+                }
+            }
+            if !unknown_attributes.is_empty() {
+                report.conflicts.push(conflict(
+                    i,
+                    &riven.weapon_url,
+                    format!("Unknown attributes: {}", unknown_attributes.join(", ")).as_str(),
+                ));
+                continue;
+            }
+            if !existing.insert(riven_key(&riven)) {
+                report
+                    .conflicts
+                    .push(conflict(i, &riven.weapon_url, "Riven is already in stock"));
+                continue;
+            }
+
+            let statement = InsertStatement::default()
+                .into_table(StockRiven::Table)
+                .columns([
+                    StockRiven::OrderId,
+                    StockRiven::WeaponId,
+                    StockRiven::WeaponUrl,
+                    StockRiven::WeaponName,
+                    StockRiven::WeaponType,
+                    StockRiven::ModName,
+                    StockRiven::Rank,
+                    StockRiven::Attributes,
+                    StockRiven::MatchRiven,
+                    StockRiven::MasteryRank,
+                    StockRiven::ReRolls,
+                    StockRiven::Price,
+                    StockRiven::MiniumPrice,
+                    StockRiven::ListedPrice,
+                    StockRiven::Polarity,
+                    StockRiven::Private,
+                    StockRiven::Status,
+                    StockRiven::Created,
+                ])
+                .values_panic([
+                    riven.order_id.clone().into(),
+                    cached.id.clone().into(),
+                    cached.url_name.clone().into(),
+                    cached.item_name.clone().into(),
+                    cached
+                        .riven_type
+                        .clone()
+                        .unwrap_or("Unknown".to_string())
+                        .into(),
+                    riven.mod_name.clone().into(),
+                    riven.rank.into(),
+                    serde_json::to_value(&riven.attributes).unwrap().into(),
+                    serde_json::to_value(&riven.match_riven).unwrap().into(),
+                    riven.mastery_rank.into(),
+                    riven.re_rolls.into(),
+                    riven.price.into(),
+                    riven.minium_price.into(),
+                    riven.listed_price.into(),
+                    riven.polarity.clone().into(),
+                    riven.private.into(),
+                    riven.status.clone().into(),
+                    riven.created.clone().into(),
+                ])
+                .build(SqliteQueryBuilder);
+            statements.push(statement);
+        }
+        report.imported = self
+            .write(
+                replace.then(|| Query::delete().from_table(StockRiven::Table).to_owned()),
+                statements,
+            )
+            .await?;
+        self.client
+            .stock_riven()
+            .emit("SET", json!(self.client.stock_riven().get_rivens().await?));
+        Ok(report)
+    }
+
+    async fn import_transactions(
+        &self,
+        rows: Vec<Result<TransactionStruct, String>>,
+        replace: bool,
+    ) -> Result<ImportReport, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let mut report = ImportReport {
+            table: "transaction".to_string(),
+            ..Default::default()
+        };
+        let transaction_key = |t: &TransactionStruct| {
+            format!("{}:{}:{}:{}", t.url, t.transaction_type, t.price, t.created)
+        };
+        let mut existing: HashSet<String> = if replace {
+            HashSet::new()
+        } else {
+            self.client
+                .transaction()
+                .get_items()
+                .await?
+                .iter()
+                .map(transaction_key)
+                .collect()
+        };
+        let mut statements = vec![];
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let transaction = match row {
+                Ok(transaction) => transaction,
+                Err(e) => {
+                    report.conflicts.push(conflict(i, "", &e));
+                    continue;
+                }
+            };
+            // Look the item up again so the ids and names match this machine's cache
+            let (wfm_id, name, tags) = match transaction.item_type.as_str() {
+                "item" => match cache.items().find_type(&transaction.url)? {
+                    Some(item) => (
+                        item.id,
+                        item.item_name.replace("\'", ""),
+                        item.tags.unwrap_or_default().join(","),
+                    ),
+                    None => {
+                        report.conflicts.push(conflict(
+                            i,
+                            &transaction.url,
+                            "Unknown item url_name",
+                        ));
+                        continue;
+                    }
+                },
+                "riven" => match cache.riven().find_type(&transaction.url)? {
+                    Some(riven) => (
+                        riven.id,
+                        riven.item_name,
+                        riven.riven_type.unwrap_or("Unknown".to_string()),
+                    ),
+                    None => {
+                        report.conflicts.push(conflict(
+                            i,
+                            &transaction.url,
+                            "Unknown weapon url_name",
+                        ));
+                        continue;
+                    }
+                },
+                item_type => {
+                    report.conflicts.push(conflict(
+                        i,
+                        &transaction.url,
+                        format!("Unknown item type: {}", item_type).as_str(),
+                    ));
+                    continue;
+                }
+            };
+            if transaction.transaction_type != "buy" && transaction.transaction_type != "sell" {
+                report.conflicts.push(conflict(
+                    i,
+                    &transaction.url,
+                    format!("Unknown transaction type: {}", transaction.transaction_type).as_str(),
+                ));
+                continue;
+            }
+            if !existing.insert(transaction_key(&transaction)) {
+                report
+                    .conflicts
+                    .push(conflict(i, &transaction.url, "Transaction already exists"));
+                continue;
+            }
+
+            let properties = transaction.properties.clone().and_then(|p| p.0);
+            let statement = InsertStatement::default()
+                .into_table(Transaction::Table)
+                .columns([
+                    Transaction::WFMId,
+                    Transaction::Url,
+                    Transaction::Name,
+                    Transaction::ItemType,
+                    Transaction::Tags,
+                    Transaction::Rank,
+                    Transaction::Properties,
+                    Transaction::Price,
+                    Transaction::TransactionType,
+                    Transaction::Quantity,
+                    Transaction::Created,
+                ])
+                .values_panic([
+                    wfm_id.into(),
+                    transaction.url.clone().into(),
+                    name.into(),
+                    transaction.item_type.clone().into(),
+                    tags.into(),
+                    transaction.rank.into(),
+                    properties.into(),
+                    transaction.price.into(),
+                    transaction.transaction_type.clone().into(),
+                    transaction.quantity.into(),
+                    transaction.created.clone().into(),
+                ])
+                .build(SqliteQueryBuilder);
+            statements.push(statement);
+        }
+        report.imported = self
+            .write(
+                replace.then(|| Query::delete().from_table(Transaction::Table).to_owned()),
+                statements,
+            )
+            .await?;
+        self.client
+            .transaction()
+            .emit("SET", json!(self.client.transaction().get_items().await?));
+        Ok(report)
+    }
+
+    // Runs after every row was checked, so a bad file leaves the table as it was.
+    async fn write(
+        &self,
+        clear: Option<DeleteStatement>,
+        statements: Vec<(String, Values)>,
+    ) -> Result<i64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut tx = connection
+            .begin()
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        if let Some(clear) = clear {
+            sqlx::query(&clear.to_string(SqliteQueryBuilder))
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        for (sql, values) in statements.iter() {
+            bind_values(sqlx::query(sql), values)?
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(statements.len() as i64)
+    }
+}
+
+// Values are bound instead of written into the sql, so text is stored as it was exported
+fn bind_values<'q>(
+    mut query: SqlxQuery<'q, Sqlite, SqliteArguments<'q>>,
+    values: &Values,
+) -> Result<SqlxQuery<'q, Sqlite, SqliteArguments<'q>>, AppError> {
+    for value in values.0.iter() {
+        query = match value.clone() {
+            Value::Bool(v) => query.bind(v),
+            Value::Int(v) => query.bind(v),
+            Value::BigInt(v) => query.bind(v),
+            Value::Double(v) => query.bind(v),
+            Value::String(v) => query.bind(v.map(|v| *v)),
+            Value::Json(v) => query.bind(v.map(|v| v.to_string())),
+            value => {
+                return Err(AppError::new(
+                    "DataTransfer",
+                    eyre!("Unsupported value: {:?}", value),
+                ))
+            }
+        };
+    }
+    Ok(query)
+}
+
+fn conflict(index: usize, key: &str, reason: &str) -> ImportConflict {
+    ImportConflict {
+        row: index + 1,
+        key: key.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+// Json files hold the table rows as is, csv files hold the flattened record.
+fn write_rows<J: Serialize, C: Serialize>(
+    format: &str,
+    path: &str,
+    json_rows: &[J],
+    csv_rows: &[C],
+) -> Result<i64, AppError> {
+    match format {
+        "json" => {
+            let content = serde_json::to_string_pretty(json_rows)
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            fs::write(path, content)
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            Ok(json_rows.len() as i64)
+        }
+        "csv" => {
+            let mut writer = csv::Writer::from_path(path)
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            for row in csv_rows {
+                writer
+                    .serialize(row)
+                    .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            Ok(csv_rows.len() as i64)
+        }
+        _ => Err(AppError::new(
+            "DataTransfer",
+            eyre!("Unknown format: {}", format),
+        )),
+    }
+}
+
+// Rows that can't be parsed are returned as errors so they can be reported as conflicts.
+fn read_rows<J, C>(format: &str, path: &str) -> Result<Vec<Result<J, String>>, AppError>
+where
+    J: DeserializeOwned,
+    C: DeserializeOwned + TryInto<J>,
+    <C as TryInto<J>>::Error: ToString,
+{
+    match format {
+        "json" => {
+            let content = fs::read_to_string(path)
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            let rows: Vec<serde_json::Value> = serde_json::from_str(&content)
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            Ok(rows
+                .into_iter()
+                .map(|row| serde_json::from_value::<J>(row).map_err(|e| e.to_string()))
+                .collect())
+        }
+        "csv" => {
+            let mut reader = csv::Reader::from_path(path)
+                .map_err(|e| AppError::new("DataTransfer", eyre!(e.to_string())))?;
+            Ok(reader
+                .deserialize::<C>()
+                .map(|row| match row {
+                    Ok(record) => record.try_into().map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                })
+                .collect())
+        }
+        _ => Err(AppError::new(
+            "DataTransfer",
+            eyre!("Unknown format: {}", format),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::AuthState, cache::client::CacheClient, settings::SettingsState, structs::Item,
+        wfm_client::client::WFMClient,
+    };
+
+    const TABLES: [&str; 3] = ["stock_item", "stock_riven", "transaction"];
+
+    fn dir(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "quantframe_data_transfer_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    // Knows nova_prime_set, the soma riven and critical_chance
+    fn open_db(name: &str) -> DBClient {
+        let settings = Arc::new(Mutex::new(SettingsState::default()));
+        let wfm = Arc::new(Mutex::new(WFMClient::new(
+            Arc::new(Mutex::new(AuthState::default())),
+            settings,
+        )));
+        let cache = CacheClient::new(Arc::clone(&wfm));
+        {
+            let mut data = cache.cache_data.lock().unwrap();
+            data.item.items.push(Item {
+                item_name: "Nova Prime Set".to_string(),
+                id: "nova_prime_set_id".to_string(),
+                url_name: "nova_prime_set".to_string(),
+                thumb: "".to_string(),
+                wikia_url: None,
+                trade_tax: None,
+                mr_requirement: None,
+                set_items: None,
+                tags: Some(vec!["prime".to_string(), "set".to_string()]),
+                mod_max_rank: None,
+                subtypes: None,
+            });
+            data.riven.items.push(
+                serde_json::from_value(json!({
+                    "thumb": "",
+                    "url_name": "soma",
+                    "mastery_level": 6,
+                    "group": "primary",
+                    "id": "soma_id",
+                    "icon": "",
+                    "item_name": "Soma",
+                    "icon_format": null,
+                    "riven_type": "rifle"
+                }))
+                .unwrap(),
+            );
+            data.riven.attributes.push(
+                serde_json::from_value(json!({
+                    "negative_only": false,
+                    "effect": "Critical Chance",
+                    "id": "critical_chance_id",
+                    "exclusive_to": null,
+                    "group": "default",
+                    "units": "percent",
+                    "search_only": false,
+                    "url_name": "critical_chance",
+                    "suffix": "tis",
+                    "positive_is_negative": false,
+                    "prefix": "crita"
+                }))
+                .unwrap(),
+            );
+        }
+        tauri::async_runtime::block_on(async {
+            let db = DBClient::open(Arc::new(Mutex::new(cache)), wfm, dir(name))
+                .await
+                .unwrap();
+            db.initialize().await.unwrap();
+            db
+        })
+    }
+
+    // One row of every kind, the transactions are one item and one riven
+    fn seed(db: &DBClient) {
+        tauri::async_runtime::block_on(async {
+            db.stock_item()
+                .create("nova_prime_set", 2, 45.0, Some(40), 0, None)
+                .await
+                .unwrap();
+            db.stock_riven()
+                .create(
+                    None,
+                    "soma",
+                    "crita-satiatis",
+                    250.0,
+                    8,
+                    vec![RivenAttribute {
+                        positive: true,
+                        value: 120.5,
+                        url_name: "critical_chance".to_string(),
+                        match_type: None,
+                    }],
+                    None,
+                    10,
+                    3,
+                    "madurai",
+                    None,
+                )
+                .await
+                .unwrap();
+            db.transaction()
+                .create(
+                    "nova_prime_set",
+                    "item",
+                    "buy",
+                    1,
+                    45,
+                    0,
+                    Some(json!({ "user_name": "Trader" })),
+                )
+                .await
+                .unwrap();
+            db.transaction()
+                .create(
+                    "soma",
+                    "riven",
+                    "sell",
+                    1,
+                    300,
+                    8,
+                    Some(json!({ "mod_name": "crita-satiatis" })),
+                )
+                .await
+                .unwrap();
+        });
+    }
+
+    fn rows(db: &DBClient, table: &str) -> serde_json::Value {
+        tauri::async_runtime::block_on(async {
+            match table {
+                "stock_item" => json!(db.stock_item().get_items().await.unwrap()),
+                "stock_riven" => json!(db.stock_riven().get_rivens().await.unwrap()),
+                _ => json!(db.transaction().get_items().await.unwrap()),
+            }
+        })
+    }
+
+    fn round_trip(format: &str) {
+        let source = open_db(&format!("{}_source", format));
+        seed(&source);
+        let target = open_db(&format!("{}_target", format));
+        let files = dir(&format!("{}_files", format));
+        for table in TABLES {
+            let path = files.join(format!("{}.{}", table, format));
+            let path = path.to_str().unwrap();
+            let (exported, report) = tauri::async_runtime::block_on(async {
+                let exported = source
+                    .data_transfer()
+                    .export(table, format, path)
+                    .await
+                    .unwrap();
+                let report = target
+                    .data_transfer()
+                    .import(table, format, path, false)
+                    .await
+                    .unwrap();
+                (exported, report)
+            });
+            assert_eq!(report.imported, exported, "{}", table);
+            assert!(report.conflicts.is_empty(), "{}", table);
+            assert_eq!(rows(&target, table), rows(&source, table), "{}", table);
+        }
+    }
+
+    #[test]
+    fn every_table_round_trips_through_csv() {
+        round_trip("csv");
+    }
+
+    #[test]
+    fn every_table_round_trips_through_json() {
+        round_trip("json");
+    }
+
+    #[test]
+    fn unknown_url_names_are_reported() {
+        let db = open_db("unknown");
+        let path = dir("unknown_files").join("stock_item.json");
+        fs::write(
+            &path,
+            json!([{
+                "id": 1,
+                "wfm_id": "old_id",
+                "url": "not_an_item",
+                "name": "Not An Item",
+                "tags": "",
+                "rank": 0,
+                "sub_type": null,
+                "price": 10.0,
+                "minium_price": null,
+                "listed_price": null,
+                "owned": 1,
+                "hidden": false,
+                "status": "pending",
+                "created": "2024-01-01"
+            }])
+            .to_string(),
+        )
+        .unwrap();
+
+        let report = tauri::async_runtime::block_on(db.data_transfer().import(
+            "stock_item",
+            "json",
+            path.to_str().unwrap(),
+            false,
+        ))
+        .unwrap();
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].row, 1);
+        assert_eq!(report.conflicts[0].key, "not_an_item");
+        assert_eq!(report.conflicts[0].reason, "Unknown item url_name");
+    }
+
+    #[test]
+    fn rows_already_in_the_table_are_reported() {
+        let db = open_db("duplicate");
+        seed(&db);
+        let files = dir("duplicate_files");
+        let reasons = [
+            ("stock_item", "nova_prime_set", "Item is already in stock"),
+            ("stock_riven", "soma", "Riven is already in stock"),
+            (
+                "transaction",
+                "nova_prime_set",
+                "Transaction already exists",
+            ),
+        ];
+        for (table, key, reason) in reasons {
+            let path = files.join(format!("{}.json", table));
+            let path = path.to_str().unwrap();
+            let report = tauri::async_runtime::block_on(async {
+                db.data_transfer()
+                    .export(table, "json", path)
+                    .await
+                    .unwrap();
+                db.data_transfer()
+                    .import(table, "json", path, false)
+                    .await
+                    .unwrap()
+            });
+            assert_eq!(report.imported, 0, "{}", table);
+            assert_eq!(report.conflicts[0].row, 1, "{}", table);
+            assert_eq!(report.conflicts[0].key, key, "{}", table);
+            assert_eq!(report.conflicts[0].reason, reason, "{}", table);
+        }
+    }
+
+    #[test]
+    fn unparseable_csv_rows_are_reported() {
+        let source = open_db("unparseable_source");
+        seed(&source);
+        let target = open_db("unparseable_target");
+        let path = dir("unparseable_files").join("transaction.csv");
+        let path = path.to_str().unwrap();
+        tauri::async_runtime::block_on(source.data_transfer().export("transaction", "csv", path))
+            .unwrap();
+        // A third row with text where the quantity goes
+        let mut content = fs::read_to_string(path).unwrap();
+        content.push_str(
+            "3,nova_prime_set_id,nova_prime_set,Nova Prime Set,item,\"prime,set\",buy,two,0,45,2024-01-01,\n",
+        );
+        fs::write(path, content).unwrap();
+
+        let report = tauri::async_runtime::block_on(target.data_transfer().import(
+            "transaction",
+            "csv",
+            path,
+            false,
+        ))
+        .unwrap();
+        // The good rows still go in
+        assert_eq!(report.imported, 2);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].row, 3);
+        assert_eq!(report.conflicts[0].key, "");
+        assert!(report.conflicts[0].reason.contains("invalid digit"));
+    }
+}
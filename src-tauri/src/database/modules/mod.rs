@@ -1,3 +1,7 @@
+pub mod data_transfer;
+pub mod price_history;
+pub mod profit_report;
+pub mod riven_snapshot;
 pub mod stock_item;
 pub mod stock_riven;
 pub mod transaction;
@@ -0,0 +1,314 @@
+use crate::{database::client::DBClient, error::AppError};
+use eyre::eyre;
+use polars::prelude::*;
+use sea_query::{Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Iden)]
+pub enum PriceHistory {
+    Table,
+    Id,
+    ItemId,
+    Name,
+    Datetime,
+    OrderType,
+    ModRank,
+    Volume,
+    MinPrice,
+    MaxPrice,
+    AvgPrice,
+    Median,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct PriceHistoryStruct {
+    pub id: i64,
+    pub item_id: String,
+    pub name: String,
+    pub datetime: String,
+    pub order_type: String,
+    // -1 for items without a rank, so the unique index also works for them
+    pub mod_rank: i64,
+    pub volume: Option<i64>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub avg_price: Option<f64>,
+    pub median: Option<f64>,
+}
+
+// Rows per insert statement, keeps us well below the SQLite variable limit
+const UPSERT_CHUNK_SIZE: usize = 500;
+
+pub struct PriceHistoryModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> PriceHistoryModule<'a> {
+    // Inserts the rows of a price scraper DataFrame, rows that already exist are updated.
+    pub async fn upsert_dataframe(&self, df: &DataFrame) -> Result<i64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let df = df
+            .clone()
+            .lazy()
+            .select([
+                col("name").cast(DataType::Utf8),
+                col("item_id").cast(DataType::Utf8),
+                col("datetime").cast(DataType::Utf8),
+                col("order_type").cast(DataType::Utf8),
+                col("mod_rank").cast(DataType::Int64),
+                col("volume").cast(DataType::Int64),
+                col("min_price").cast(DataType::Float64),
+                col("max_price").cast(DataType::Float64),
+                col("avg_price").cast(DataType::Float64),
+                col("median").cast(DataType::Float64),
+            ])
+            .collect()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let names = self.get_utf8(&df, "name")?;
+        let item_ids = self.get_utf8(&df, "item_id")?;
+        let datetimes = self.get_utf8(&df, "datetime")?;
+        let order_types = self.get_utf8(&df, "order_type")?;
+        let mod_ranks = self.get_i64(&df, "mod_rank")?;
+        let volumes = self.get_i64(&df, "volume")?;
+        let min_prices = self.get_f64(&df, "min_price")?;
+        let max_prices = self.get_f64(&df, "max_price")?;
+        let avg_prices = self.get_f64(&df, "avg_price")?;
+        let medians = self.get_f64(&df, "median")?;
+
+        let mut total = 0;
+        let rows: Vec<usize> = (0..df.height()).collect();
+        for chunk in rows.chunks(UPSERT_CHUNK_SIZE) {
+            let mut statement = InsertStatement::default();
+            statement.into_table(PriceHistory::Table).columns([
+                PriceHistory::Name,
+                PriceHistory::ItemId,
+                PriceHistory::Datetime,
+                PriceHistory::OrderType,
+                PriceHistory::ModRank,
+                PriceHistory::Volume,
+                PriceHistory::MinPrice,
+                PriceHistory::MaxPrice,
+                PriceHistory::AvgPrice,
+                PriceHistory::Median,
+            ]);
+            let mut values = 0;
+            for &i in chunk {
+                // Skip rows we can't key on
+                let (name, datetime, order_type) = match (&names[i], &datetimes[i], &order_types[i])
+                {
+                    (Some(name), Some(datetime), Some(order_type)) => (name, datetime, order_type),
+                    _ => continue,
+                };
+                statement.values_panic([
+                    name.clone().into(),
+                    item_ids[i].clone().unwrap_or_default().into(),
+                    datetime.clone().into(),
+                    order_type.clone().into(),
+                    mod_ranks[i].unwrap_or(-1).into(),
+                    volumes[i].into(),
+                    min_prices[i].into(),
+                    max_prices[i].into(),
+                    avg_prices[i].into(),
+                    medians[i].into(),
+                ]);
+                values += 1;
+            }
+            if values == 0 {
+                continue;
+            }
+            let sql = format!(
+                "{} ON CONFLICT (name, datetime, order_type, mod_rank) DO UPDATE SET item_id = excluded.item_id, volume = excluded.volume, min_price = excluded.min_price, max_price = excluded.max_price, avg_price = excluded.avg_price, median = excluded.median",
+                statement.to_string(SqliteQueryBuilder)
+            );
+            sqlx::query(&sql)
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            total += values;
+        }
+        Ok(total)
+    }
+
+    // Returns the rows between from and to (inclusive, compared on the datetime string).
+    pub async fn get_history(
+        &self,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<Vec<PriceHistoryStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut query = Query::select();
+        query
+            .columns([
+                PriceHistory::Id,
+                PriceHistory::ItemId,
+                PriceHistory::Name,
+                PriceHistory::Datetime,
+                PriceHistory::OrderType,
+                PriceHistory::ModRank,
+                PriceHistory::Volume,
+                PriceHistory::MinPrice,
+                PriceHistory::MaxPrice,
+                PriceHistory::AvgPrice,
+                PriceHistory::Median,
+            ])
+            .from(PriceHistory::Table);
+        if let Some(from) = from {
+            query.and_where(Expr::col(PriceHistory::Datetime).gte(from));
+        }
+        if let Some(to) = to {
+            // Dates are stored with a time part, so compare against the end of the day
+            query.and_where(Expr::col(PriceHistory::Datetime).lte(format!("{}~", to)));
+        }
+        let sql = query
+            .order_by(PriceHistory::Name, Order::Asc)
+            .order_by(PriceHistory::Datetime, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, PriceHistoryStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    // Same columns as the old price_data.csv so the live scraper can use it as is.
+    pub async fn get_history_dataframe(
+        &self,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<DataFrame, AppError> {
+        let rows = self.get_history(from, to).await?;
+        let min_price: Vec<Option<f64>> = rows.iter().map(|r| r.min_price).collect();
+        let max_price: Vec<Option<f64>> = rows.iter().map(|r| r.max_price).collect();
+        // Null like in the csv when a day has no min or max
+        let range: Vec<Option<f64>> = max_price
+            .iter()
+            .zip(min_price.iter())
+            .map(|(max, min)| Some((*max)? - (*min)?))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new(
+                "name",
+                rows.iter().map(|r| r.name.clone()).collect::<Vec<String>>(),
+            ),
+            Series::new(
+                "datetime",
+                rows.iter()
+                    .map(|r| r.datetime.clone())
+                    .collect::<Vec<String>>(),
+            ),
+            Series::new(
+                "order_type",
+                rows.iter()
+                    .map(|r| r.order_type.clone())
+                    .collect::<Vec<String>>(),
+            ),
+            Series::new(
+                "volume",
+                rows.iter().map(|r| r.volume).collect::<Vec<Option<i64>>>(),
+            ),
+            Series::new("min_price", min_price),
+            Series::new("max_price", max_price),
+            Series::new(
+                "avg_price",
+                rows.iter()
+                    .map(|r| r.avg_price)
+                    .collect::<Vec<Option<f64>>>(),
+            ),
+            Series::new(
+                "mod_rank",
+                rows.iter()
+                    .map(|r| {
+                        if r.mod_rank < 0 {
+                            None
+                        } else {
+                            Some(r.mod_rank as f64)
+                        }
+                    })
+                    .collect::<Vec<Option<f64>>>(),
+            ),
+            Series::new(
+                "median",
+                rows.iter().map(|r| r.median).collect::<Vec<Option<f64>>>(),
+            ),
+            Series::new(
+                "item_id",
+                rows.iter()
+                    .map(|r| r.item_id.clone())
+                    .collect::<Vec<String>>(),
+            ),
+            Series::new("range", range),
+        ])
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(df)
+    }
+
+    // Newest row per item and rank for the order type
+    pub async fn get_latest(&self, order_type: &str) -> Result<Vec<PriceHistoryStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let rows = sqlx::query_as::<_, PriceHistoryStruct>(
+            "SELECT p.* FROM price_history p JOIN (SELECT name, mod_rank, MAX(datetime) AS datetime FROM price_history WHERE order_type = ? GROUP BY name, mod_rank) l ON p.name = l.name AND p.mod_rank = l.mod_rank AND p.datetime = l.datetime WHERE p.order_type = ?",
+        )
+        .bind(order_type)
+        .bind(order_type)
+        .fetch_all(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    // First day of the last `days` days we have data for, None if we have fewer days than that.
+    // Relative to the stored data and not to today, so a stale history still gives a full window.
+    pub async fn get_window_start(&self, days: i64) -> Result<Option<String>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT DISTINCT substr(datetime, 1, 10) AS day FROM price_history ORDER BY day DESC LIMIT 1 OFFSET ?",
+        )
+        .bind((days - 1).max(0))
+        .fetch_optional(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(row.map(|row| row.0))
+    }
+
+    pub async fn count(&self) -> Result<i64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM price_history")
+            .fetch_one(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(row.0)
+    }
+
+    fn get_utf8(&self, df: &DataFrame, column: &str) -> Result<Vec<Option<String>>, AppError> {
+        Ok(df
+            .column(column)
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .utf8()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .into_iter()
+            .map(|v| v.map(|v| v.to_string()))
+            .collect())
+    }
+
+    fn get_i64(&self, df: &DataFrame, column: &str) -> Result<Vec<Option<i64>>, AppError> {
+        Ok(df
+            .column(column)
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .i64()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .into_iter()
+            .collect())
+    }
+
+    fn get_f64(&self, df: &DataFrame, column: &str) -> Result<Vec<Option<f64>>, AppError> {
+        Ok(df
+            .column(column)
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .f64()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .into_iter()
+            .collect())
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{client::DBClient, modules::transaction::TransactionStruct},
+    error::AppError,
+};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ItemProfit {
+    pub url: String,
+    pub name: String,
+    pub item_type: String,
+    pub rank: i32,
+    pub quantity_sold: i64,
+    pub revenue: i64,
+    // FIFO cost of the sold quantity
+    pub cost: i64,
+    pub profit: i64,
+    // Sold quantity without a matching buy, counted with a cost of 0
+    pub unmatched_quantity: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HoldingProfit {
+    pub url: String,
+    pub name: String,
+    pub rank: i32,
+    pub owned: i32,
+    pub cost: f64,
+    // Latest closed average from the price history, None if we have no data for the item
+    pub market_price: Option<f64>,
+    pub value: f64,
+    pub profit: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PeriodProfit {
+    // "2023-10-01", "2023-W39" or "2023-10"
+    pub period: String,
+    pub revenue: i64,
+    pub cost: i64,
+    pub profit: i64,
+    pub quantity_sold: i64,
+    // Plat spent on buys in the period
+    pub expense: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProfitReport {
+    pub revenue: i64,
+    pub cost: i64,
+    pub realised_profit: i64,
+    pub unmatched_quantity: i64,
+    pub unrealised_value: f64,
+    pub unrealised_profit: f64,
+    pub items: Vec<ItemProfit>,
+    pub holdings: Vec<HoldingProfit>,
+    pub daily: Vec<PeriodProfit>,
+    pub weekly: Vec<PeriodProfit>,
+    pub monthly: Vec<PeriodProfit>,
+}
+
+// A bought quantity that has not been sold yet
+struct Lot {
+    quantity: i64,
+    unit_cost: f64,
+}
+
+pub struct ProfitReportModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> ProfitReportModule<'a> {
+    // Matches sells against buys per item and rank, first in first out.
+    // Only sells between from and to ("YYYY-MM-DD", inclusive) are reported, older buys are still used as cost.
+    pub async fn get_report(
+        &self,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<ProfitReport, AppError> {
+        let from = from.and_then(|d| parse_day(&d));
+        let to = to.and_then(|d| parse_day(&d));
+        let in_range = |day: &NaiveDate| {
+            from.map(|f| *day >= f).unwrap_or(true) && to.map(|t| *day <= t).unwrap_or(true)
+        };
+
+        let mut transactions = self.client.transaction().get_items().await?;
+        transactions.sort_by(|a, b| a.created.cmp(&b.created).then(a.id.cmp(&b.id)));
+
+        let mut report = ProfitReport::default();
+        let mut lots: HashMap<(String, String, i32), VecDeque<Lot>> = HashMap::new();
+        let mut items: HashMap<(String, String, i32), ItemProfit> = HashMap::new();
+        let mut daily: BTreeMap<String, PeriodProfit> = BTreeMap::new();
+        let mut weekly: BTreeMap<String, PeriodProfit> = BTreeMap::new();
+        let mut monthly: BTreeMap<String, PeriodProfit> = BTreeMap::new();
+
+        for transaction in transactions.iter() {
+            let key = (
+                transaction.item_type.clone(),
+                transaction.url.clone(),
+                transaction.rank,
+            );
+            let quantity = transaction.quantity.max(1) as i64;
+            let day = parse_day(&transaction.created);
+            let reported = day.as_ref().map(|d| in_range(d)).unwrap_or(false);
+
+            if transaction.transaction_type == "buy" {
+                lots.entry(key).or_default().push_back(Lot {
+                    quantity,
+                    unit_cost: transaction.price as f64 / quantity as f64,
+                });
+                if reported {
+                    for period in get_periods(&day.unwrap(), &mut daily, &mut weekly, &mut monthly)
+                    {
+                        period.expense += transaction.price as i64;
+                    }
+                }
+                continue;
+            }
+            if transaction.transaction_type != "sell" {
+                continue;
+            }
+
+            let (cost, unmatched) = consume_lots(lots.entry(key.clone()).or_default(), quantity);
+            if !reported {
+                continue;
+            }
+            let revenue = transaction.price as i64;
+            let item = items
+                .entry(key)
+                .or_insert_with(|| new_item_profit(transaction));
+            item.quantity_sold += quantity;
+            item.revenue += revenue;
+            item.cost += cost;
+            item.profit += revenue - cost;
+            item.unmatched_quantity += unmatched;
+
+            for period in get_periods(&day.unwrap(), &mut daily, &mut weekly, &mut monthly) {
+                period.revenue += revenue;
+                period.cost += cost;
+                period.profit += revenue - cost;
+                period.quantity_sold += quantity;
+            }
+            report.revenue += revenue;
+            report.cost += cost;
+            report.unmatched_quantity += unmatched;
+        }
+        report.realised_profit = report.revenue - report.cost;
+
+        let mut items: Vec<ItemProfit> = items.into_values().collect();
+        items.sort_by(|a, b| b.profit.cmp(&a.profit));
+        report.items = items;
+        report.daily = daily.into_values().collect();
+        report.weekly = weekly.into_values().collect();
+        report.monthly = monthly.into_values().collect();
+
+        report.holdings = self.get_holdings().await?;
+        for holding in report.holdings.iter() {
+            report.unrealised_value += holding.value;
+            report.unrealised_profit += holding.profit;
+        }
+        Ok(report)
+    }
+
+    // Values the current stock at the latest closed average of the price history.
+    pub async fn get_holdings(&self) -> Result<Vec<HoldingProfit>, AppError> {
+        let stock = self.client.stock_item().get_items().await?;
+        let latest = self.client.price_history().get_latest("closed").await?;
+
+        // Unranked items are stored with a mod_rank of -1
+        let mut prices: HashMap<(String, i64), f64> = HashMap::new();
+        let mut any_rank: HashMap<String, f64> = HashMap::new();
+        for row in latest {
+            if let Some(avg_price) = row.avg_price {
+                any_rank.entry(row.name.clone()).or_insert(avg_price);
+                prices.insert((row.name, row.mod_rank), avg_price);
+            }
+        }
+
+        let mut holdings = vec![];
+        for item in stock.iter().filter(|item| item.owned > 0) {
+            let rank = if item.rank > 0 { item.rank as i64 } else { -1 };
+            let market_price = prices
+                .get(&(item.url.clone(), rank))
+                .or_else(|| any_rank.get(&item.url))
+                .cloned();
+            let cost = item.price * item.owned as f64;
+            let value = market_price.unwrap_or(0.0) * item.owned as f64;
+            holdings.push(HoldingProfit {
+                url: item.url.clone(),
+                name: item.name.clone(),
+                rank: item.rank,
+                owned: item.owned,
+                cost,
+                market_price,
+                value,
+                // Without a market price we can't say anything about the profit
+                profit: market_price.map(|_| value - cost).unwrap_or(0.0),
+            });
+        }
+        Ok(holdings)
+    }
+}
+
+// Takes the quantity from the oldest lots, returns the cost and the quantity that had no lot.
+fn consume_lots(lots: &mut VecDeque<Lot>, mut quantity: i64) -> (i64, i64) {
+    let mut cost = 0.0;
+    while quantity > 0 {
+        let lot = match lots.front_mut() {
+            Some(lot) => lot,
+            None => break,
+        };
+        let take = lot.quantity.min(quantity);
+        cost += lot.unit_cost * take as f64;
+        lot.quantity -= take;
+        quantity -= take;
+        if lot.quantity == 0 {
+            lots.pop_front();
+        }
+    }
+    (cost.round() as i64, quantity)
+}
+
+fn new_item_profit(transaction: &TransactionStruct) -> ItemProfit {
+    ItemProfit {
+        url: transaction.url.clone(),
+        name: transaction.name.clone(),
+        item_type: transaction.item_type.clone(),
+        rank: transaction.rank,
+        ..Default::default()
+    }
+}
+
+// Transactions are stored as rfc3339, only the date part is used.
+fn parse_day(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.get(0..10)?, "%Y-%m-%d").ok()
+}
+
+fn get_periods<'b>(
+    day: &NaiveDate,
+    daily: &'b mut BTreeMap<String, PeriodProfit>,
+    weekly: &'b mut BTreeMap<String, PeriodProfit>,
+    monthly: &'b mut BTreeMap<String, PeriodProfit>,
+) -> [&'b mut PeriodProfit; 3] {
+    let keys = [
+        (daily, day.format("%Y-%m-%d").to_string()),
+        (weekly, day.format("%G-W%V").to_string()),
+        (monthly, day.format("%Y-%m").to_string()),
+    ];
+    keys.map(|(map, period)| {
+        map.entry(period.clone()).or_insert_with(|| PeriodProfit {
+            period,
+            ..Default::default()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lots(lots: &[(i64, f64)]) -> VecDeque<Lot> {
+        lots.iter()
+            .map(|(quantity, unit_cost)| Lot {
+                quantity: *quantity,
+                unit_cost: *unit_cost,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sells_take_the_oldest_lots_first() {
+        let mut queue = lots(&[(2, 10.0), (3, 20.0)]);
+        assert_eq!(consume_lots(&mut queue, 3), (40, 0));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].quantity, 2);
+        assert_eq!(queue[0].unit_cost, 20.0);
+
+        assert_eq!(consume_lots(&mut queue, 2), (40, 0));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn sells_without_a_lot_are_unmatched() {
+        let mut queue = lots(&[(1, 15.0)]);
+        assert_eq!(consume_lots(&mut queue, 4), (15, 3));
+        assert!(queue.is_empty());
+
+        let mut queue = VecDeque::new();
+        assert_eq!(consume_lots(&mut queue, 2), (0, 2));
+    }
+
+    #[test]
+    fn lot_costs_are_rounded_once() {
+        // 10 plat for 3 is 3.33 each, two of them cost 7 and not 6
+        let mut queue = lots(&[(3, 10.0 / 3.0)]);
+        assert_eq!(consume_lots(&mut queue, 2), (7, 0));
+    }
+
+    #[test]
+    fn days_are_read_from_rfc3339() {
+        assert_eq!(
+            parse_day("2023-10-02T18:30:00+00:00"),
+            NaiveDate::from_ymd_opt(2023, 10, 2)
+        );
+        assert_eq!(parse_day("2023-10"), None);
+        assert_eq!(parse_day("not a date"), None);
+    }
+
+    #[test]
+    fn periods_use_the_iso_week() {
+        let mut daily = BTreeMap::new();
+        let mut weekly = BTreeMap::new();
+        let mut monthly = BTreeMap::new();
+        // 1 January 2023 is a Sunday and still in week 52 of 2022
+        let day = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let periods: Vec<String> = get_periods(&day, &mut daily, &mut weekly, &mut monthly)
+            .iter()
+            .map(|p| p.period.clone())
+            .collect();
+        assert_eq!(periods, vec!["2023-01-01", "2022-W52", "2023-01"]);
+    }
+}
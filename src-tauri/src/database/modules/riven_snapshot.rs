@@ -0,0 +1,165 @@
+use crate::{
+    database::client::DBClient,
+    error::AppError,
+    structs::{Auction, AuctionOwner, RivenAttribute},
+};
+use eyre::eyre;
+use sea_query::{Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Iden)]
+pub enum RivenSnapshot {
+    Table,
+    Id,
+    AuctionId,
+    WeaponUrl,
+    WeaponType,
+    ModName,
+    Attributes,
+    MasteryRank,
+    ReRolls,
+    ModRank,
+    Polarity,
+    Price,
+    FirstSeen,
+    LastSeen,
+}
+
+// A riven auction as it was seen on warframe.market, kept to value our own rivens
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct RivenSnapshotStruct {
+    pub id: i64,
+    pub auction_id: String,
+    pub weapon_url: String,
+    pub weapon_type: String,
+    pub mod_name: String,
+    pub attributes: sqlx::types::Json<Vec<RivenAttribute>>,
+    pub mastery_rank: i64,
+    pub re_rolls: i64,
+    pub mod_rank: i64,
+    pub polarity: String,
+    pub price: i64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+pub struct RivenSnapshotModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> RivenSnapshotModule<'a> {
+    // Stores the riven auctions of a search, auctions we already have get the new price and last seen.
+    pub async fn upsert_auctions(
+        &self,
+        weapon_type: &str,
+        auctions: &[Auction<AuctionOwner>],
+    ) -> Result<i64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let now = chrono::Local::now().naive_local().to_string();
+        let mut statement = InsertStatement::default();
+        statement.into_table(RivenSnapshot::Table).columns([
+            RivenSnapshot::AuctionId,
+            RivenSnapshot::WeaponUrl,
+            RivenSnapshot::WeaponType,
+            RivenSnapshot::ModName,
+            RivenSnapshot::Attributes,
+            RivenSnapshot::MasteryRank,
+            RivenSnapshot::ReRolls,
+            RivenSnapshot::ModRank,
+            RivenSnapshot::Polarity,
+            RivenSnapshot::Price,
+            RivenSnapshot::FirstSeen,
+            RivenSnapshot::LastSeen,
+        ]);
+        let mut total = 0;
+        for auction in auctions {
+            let item = &auction.item;
+            // Only rivens with known stats are useful for the valuation
+            let (weapon_url, attributes) = match (&item.weapon_url_name, &item.attributes) {
+                (Some(weapon_url), Some(attributes)) if item.item_type == "riven" => {
+                    (weapon_url, attributes)
+                }
+                _ => continue,
+            };
+            statement.values_panic([
+                auction.id.clone().into(),
+                weapon_url.clone().into(),
+                weapon_type.into(),
+                item.name.clone().unwrap_or_default().into(),
+                serde_json::to_value(attributes).unwrap().into(),
+                item.mastery_level.unwrap_or(0).into(),
+                item.re_rolls.unwrap_or(0).into(),
+                item.mod_rank.unwrap_or(0).into(),
+                item.polarity.clone().unwrap_or_default().into(),
+                auction
+                    .buyout_price
+                    .unwrap_or(auction.starting_price)
+                    .into(),
+                now.clone().into(),
+                now.clone().into(),
+            ]);
+            total += 1;
+        }
+        if total == 0 {
+            return Ok(0);
+        }
+        let sql = format!(
+            "{} ON CONFLICT (auction_id) DO UPDATE SET price = excluded.price, attributes = excluded.attributes, re_rolls = excluded.re_rolls, last_seen = excluded.last_seen",
+            statement.to_string(SqliteQueryBuilder)
+        );
+        sqlx::query(&sql.replace("\\", ""))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(total)
+    }
+
+    // Snapshots of every weapon of a riven type seen since the given time, newest first
+    pub async fn get_by_weapon_type(
+        &self,
+        weapon_type: &str,
+        since: &str,
+    ) -> Result<Vec<RivenSnapshotStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                RivenSnapshot::Id,
+                RivenSnapshot::AuctionId,
+                RivenSnapshot::WeaponUrl,
+                RivenSnapshot::WeaponType,
+                RivenSnapshot::ModName,
+                RivenSnapshot::Attributes,
+                RivenSnapshot::MasteryRank,
+                RivenSnapshot::ReRolls,
+                RivenSnapshot::ModRank,
+                RivenSnapshot::Polarity,
+                RivenSnapshot::Price,
+                RivenSnapshot::FirstSeen,
+                RivenSnapshot::LastSeen,
+            ])
+            .from(RivenSnapshot::Table)
+            .and_where(Expr::col(RivenSnapshot::WeaponType).eq(weapon_type))
+            .and_where(Expr::col(RivenSnapshot::LastSeen).gte(since))
+            .order_by(RivenSnapshot::LastSeen, Order::Desc)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, RivenSnapshotStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    // Removes auctions not seen since the given time
+    pub async fn prune(&self, before: &str) -> Result<u64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(RivenSnapshot::Table)
+            .and_where(Expr::col(RivenSnapshot::LastSeen).lt(before))
+            .to_string(SqliteQueryBuilder);
+        let result = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(result.rows_affected())
+    }
+}
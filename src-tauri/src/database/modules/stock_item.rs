@@ -13,7 +13,7 @@ use polars::{
     series::Series,
 };
 use reqwest::header::HeaderMap;
-use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table, Value};
+use sea_query::{Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Value};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::Row;
@@ -63,102 +63,6 @@ pub struct StockItemModule<'a> {
 impl<'a> StockItemModule<'a> {
     // Methods sea-query
 
-    // Initialize the database
-    pub async fn initialize(&self) -> Result<bool, AppError> {
-        let connection = self.client.connection.lock().unwrap().clone();
-        let sql = Table::create()
-            .table(StockItem::Table)
-            .if_not_exists()
-            .col(
-                ColumnDef::new(StockItem::Id)
-                    .integer()
-                    .not_null()
-                    .auto_increment()
-                    .primary_key(),
-            )
-            .col(ColumnDef::new(StockItem::WFMId).uuid().not_null())
-            .col(ColumnDef::new(StockItem::Url).string().not_null())
-            .col(ColumnDef::new(StockItem::Name).string().not_null())
-            .col(ColumnDef::new(StockItem::Tags).string().not_null())
-            .col(
-                ColumnDef::new(StockItem::Rank)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(ColumnDef::new(StockItem::SubType).string())
-            .col(
-                ColumnDef::new(StockItem::Price)
-                    .float()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(
-                ColumnDef::new(StockItem::MiniumPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .col(
-                ColumnDef::new(StockItem::ListedPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .col(
-                ColumnDef::new(StockItem::Owned)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(1))),
-            )
-            .col(
-                ColumnDef::new(StockItem::Hidden)
-                    .boolean()
-                    .not_null()
-                    .default(Value::Bool(Some(false))),
-            )
-            .col(ColumnDef::new(StockItem::Created).date_time().not_null())
-            .build(SqliteQueryBuilder);
-
-        sqlx::query(&sql)
-            .execute(&connection)
-            .await
-            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
-
-        let mut table = Table::alter()
-            .table(StockItem::Table)
-            .add_column(
-                ColumnDef::new(StockItem::MiniumPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockItem::Table)
-            .add_column(
-                ColumnDef::new(StockItem::Hidden)
-                    .boolean()
-                    .not_null()
-                    .default(Value::Bool(Some(false))),
-            )
-            .to_string(SqliteQueryBuilder);
-
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockItem::Table)
-            .add_column(
-                ColumnDef::new(StockItem::Status)
-                    .string()
-                    .not_null()
-                    .default("pending"),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        Ok(true)
-    }
-
     pub async fn get_items(&self) -> Result<Vec<StockItemStruct>, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         // Read
@@ -14,7 +14,7 @@ use polars::{
 };
 use reqwest::header::HeaderMap;
 use sea_query::{
-    Alias, ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table, Value,
+    Alias, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Value,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -44,6 +44,9 @@ pub enum StockRiven {
     Private,
     Status,
     Created,
+    LowestPrice,
+    FairValue,
+    FairValueConfidence,
 }
 
 #[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
@@ -68,6 +71,10 @@ pub struct StockRivenStruct {
     pub private: bool,
     pub status: String,
     pub created: String,
+    // Cheapest matching live auction and the estimated value, set by the live scraper
+    pub lowest_price: Option<i32>,
+    pub fair_value: Option<i32>,
+    pub fair_value_confidence: Option<f64>,
 }
 #[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
@@ -92,127 +99,6 @@ pub struct StockRivenModule<'a> {
 impl<'a> StockRivenModule<'a> {
     // Methods sea-query
 
-    // Initialize the database
-    pub async fn initialize(&self) -> Result<bool, AppError> {
-        let connection = self.client.connection.lock().unwrap().clone();
-        let sql = Table::create()
-            .table(StockRiven::Table)
-            .if_not_exists()
-            .col(
-                ColumnDef::new(StockRiven::Id)
-                    .integer()
-                    .not_null()
-                    .auto_increment()
-                    .primary_key(),
-            )
-            .col(ColumnDef::new(StockRiven::OrderId).uuid())
-            .col(ColumnDef::new(StockRiven::WeaponId).uuid().not_null())
-            .col(ColumnDef::new(StockRiven::WeaponUrl).string().not_null())
-            .col(ColumnDef::new(StockRiven::WeaponName).string().not_null())
-            .col(ColumnDef::new(StockRiven::WeaponType).string().not_null())
-            .col(ColumnDef::new(StockRiven::ModName).string().not_null())
-            .col(
-                ColumnDef::new(StockRiven::Rank)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(
-                ColumnDef::new(StockRiven::Attributes)
-                    .json()
-                    .not_null()
-                    .default(json!([])),
-            )
-            .col(
-                ColumnDef::new(StockRiven::MatchRiven)
-                    .json()
-                    .not_null()
-                    .default(json!({})),
-            )
-            .col(
-                ColumnDef::new(StockRiven::MasteryRank)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(
-                ColumnDef::new(StockRiven::ReRolls)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(ColumnDef::new(StockRiven::Polarity).string().not_null())
-            .col(
-                ColumnDef::new(StockRiven::Price)
-                    .float()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(
-                ColumnDef::new(StockRiven::MiniumPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .col(
-                ColumnDef::new(StockRiven::ListedPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .col(
-                ColumnDef::new(StockRiven::Private)
-                    .boolean()
-                    .default(Value::Bool(Some(false))),
-            )
-            .col(
-                ColumnDef::new(StockRiven::Status)
-                    .string()
-                    .not_null()
-                    .default("pending"),
-            )
-            .col(ColumnDef::new(StockRiven::Created).date_time().not_null())
-            .build(SqliteQueryBuilder);
-
-        sqlx::query(&sql)
-            .execute(&connection)
-            .await
-            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
-
-        // Alter Table
-        let mut table = Table::alter()
-            .table(StockRiven::Table)
-            .add_column(
-                ColumnDef::new(StockRiven::MatchRiven)
-                    .json()
-                    .not_null()
-                    .default(json!({})),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockRiven::Table)
-            .add_column(
-                ColumnDef::new(StockRiven::MiniumPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockRiven::Table)
-            .add_column(
-                ColumnDef::new(StockRiven::Status)
-                    .string()
-                    .not_null()
-                    .default("pending"),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        Ok(true)
-    }
-
     pub async fn get_rivens(&self) -> Result<Vec<StockRivenStruct>, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         // Read
@@ -237,6 +123,9 @@ impl<'a> StockRivenModule<'a> {
                 StockRiven::Private,
                 StockRiven::Status,
                 StockRiven::Created,
+                StockRiven::LowestPrice,
+                StockRiven::FairValue,
+                StockRiven::FairValueConfidence,
             ])
             .from(StockRiven::Table)
             .to_string(SqliteQueryBuilder);
@@ -309,6 +198,9 @@ impl<'a> StockRivenModule<'a> {
             private: false,
             status: "pending".to_string(),
             created: chrono::Local::now().naive_local().to_string(),
+            lowest_price: None,
+            fair_value: None,
+            fair_value_confidence: None,
         };
 
         let sql = InsertStatement::default()
@@ -518,6 +410,51 @@ impl<'a> StockRivenModule<'a> {
         );
         Ok(stock_riven.clone())
     }
+    pub async fn update_valuation(
+        &self,
+        id: i64,
+        lowest_price: Option<i32>,
+        fair_value: Option<i32>,
+        fair_value_confidence: Option<f64>,
+    ) -> Result<StockRivenStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut stock_riven = match self.get_by_id(id).await? {
+            Some(stock_riven) => stock_riven,
+            None => {
+                return Err(AppError::new_with_level(
+                    "Database",
+                    eyre!("Riven not found in database"),
+                    LogLevel::Error,
+                ))
+            }
+        };
+        stock_riven.lowest_price = lowest_price;
+        stock_riven.fair_value = fair_value;
+        stock_riven.fair_value_confidence = fair_value_confidence;
+
+        let sql = Query::update()
+            .table(StockRiven::Table)
+            .values([
+                (StockRiven::LowestPrice, lowest_price.into()),
+                (StockRiven::FairValue, fair_value.into()),
+                (
+                    StockRiven::FairValueConfidence,
+                    fair_value_confidence.into(),
+                ),
+            ])
+            .and_where(Expr::col(StockRiven::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(stock_riven.clone()).unwrap(),
+        );
+        Ok(stock_riven)
+    }
     pub async fn delete(&self, id: i64) -> Result<StockRivenStruct, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         let items = self.get_rivens().await?;
@@ -1,6 +1,6 @@
 use crate::{database::client::DBClient, error::AppError, helper, structs::RivenAttribute, logger::{self}, enums::LogLevel};
 use eyre::eyre;
-use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table, Value};
+use sea_query::{Expr, Iden, InsertStatement, Query, SqliteQueryBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -41,57 +41,6 @@ pub struct TransactionStruct {
     pub properties: Option<sqlx::types::Json<Option<serde_json::Value>>>,
 }
 impl<'a> TransactionModule<'a> {
-    pub async fn initialize(&self) -> Result<bool, AppError> {
-        let connection = self.client.connection.lock().unwrap().clone();
-        let sql = Table::create()
-            .table(Transaction::Table)
-            .if_not_exists()
-            .col(
-                ColumnDef::new(Transaction::Id)
-                    .integer()
-                    .not_null()
-                    .auto_increment()
-                    .primary_key(),
-            )
-            .col(ColumnDef::new(Transaction::WFMId).uuid().not_null())
-            .col(ColumnDef::new(Transaction::Url).string().not_null())
-            .col(ColumnDef::new(Transaction::Name).string().not_null())
-            .col(ColumnDef::new(Transaction::ItemType).string().not_null())
-            .col(ColumnDef::new(Transaction::Tags).string().not_null())
-            .col(
-                ColumnDef::new(Transaction::TransactionType)
-                    .string()
-                    .not_null(),
-            )
-            .col(
-                ColumnDef::new(Transaction::Quantity)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(1))),
-            )
-            .col(
-                ColumnDef::new(Transaction::Rank)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(
-                ColumnDef::new(Transaction::Price)
-                    .integer()
-                    .not_null()
-                    .default(Value::Int(Some(0))),
-            )
-            .col(ColumnDef::new(Transaction::Properties).json())
-            .col(ColumnDef::new(Transaction::Created).date_time().not_null())
-            .build(SqliteQueryBuilder);
-
-        sqlx::query(&sql)
-            .execute(&connection)
-            .await
-            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
-        Ok(true)
-    }
-
     pub async fn get_items(&self) -> Result<Vec<TransactionStruct>, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         // Read
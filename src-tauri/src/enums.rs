@@ -124,6 +124,8 @@ pub enum StockMode {
     All,
     Riven,
     Item,
+    // Nothing is bought or sold, only the riven watchlist is searched
+    Sniper,
     Unknown(String),
 }
 impl StockMode {
@@ -133,6 +135,7 @@ impl StockMode {
             StockMode::All => "all",
             StockMode::Item => "item",
             StockMode::Riven => "riven",
+            StockMode::Sniper => "sniper",
             StockMode::Unknown(ref i) => i,
         }
     }
@@ -146,6 +149,7 @@ impl Serialize for StockMode {
             StockMode::All => "all",
             StockMode::Item => "item",
             StockMode::Riven => "riven",
+            StockMode::Sniper => "sniper",
             StockMode::Unknown(i) => {
                 logger::critical_file(
                     "OrderMode",
@@ -169,6 +173,7 @@ impl<'de> Deserialize<'de> for StockMode {
             "all" => StockMode::All,
             "item" => StockMode::Item,
             "riven" => StockMode::Riven,
+            "sniper" => StockMode::Sniper,
             s => StockMode::Unknown(s.parse().map_err(|_| {
                 serde::de::Error::custom(format!(
                     "invalid value for Color, must be an string: {}",
@@ -178,6 +183,112 @@ impl<'de> Deserialize<'de> for StockMode {
         })
     }
 }
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PricingStrategyType {
+    Default,
+    Undercut,
+    MedianMargin,
+    Unknown(String),
+}
+impl PricingStrategyType {
+    // Create method to convert `PricingStrategyType` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            PricingStrategyType::Default => "default",
+            PricingStrategyType::Undercut => "undercut",
+            PricingStrategyType::MedianMargin => "median_margin",
+            PricingStrategyType::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for PricingStrategyType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            PricingStrategyType::Default => "default",
+            PricingStrategyType::Undercut => "undercut",
+            PricingStrategyType::MedianMargin => "median_margin",
+            PricingStrategyType::Unknown(i) => {
+                logger::critical_file(
+                    "PricingStrategyType",
+                    format!("Unknown PricingStrategyType: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for PricingStrategyType {
+    fn deserialize<D>(deserializer: D) -> Result<PricingStrategyType, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "default" => PricingStrategyType::Default,
+            "undercut" => PricingStrategyType::Undercut,
+            "median_margin" => PricingStrategyType::MedianMargin,
+            s => PricingStrategyType::Unknown(s.to_string()),
+        })
+    }
+}
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnipeTargetType {
+    // Buy below a fixed price
+    Fixed,
+    // Buy below a percentage of the recent price of the roll
+    Relative,
+    Unknown(String),
+}
+impl SnipeTargetType {
+    // Create method to convert `SnipeTargetType` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            SnipeTargetType::Fixed => "fixed",
+            SnipeTargetType::Relative => "relative",
+            SnipeTargetType::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for SnipeTargetType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            SnipeTargetType::Fixed => "fixed",
+            SnipeTargetType::Relative => "relative",
+            SnipeTargetType::Unknown(i) => {
+                logger::critical_file(
+                    "SnipeTargetType",
+                    format!("Unknown SnipeTargetType: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for SnipeTargetType {
+    fn deserialize<D>(deserializer: D) -> Result<SnipeTargetType, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "fixed" => SnipeTargetType::Fixed,
+            "relative" => SnipeTargetType::Relative,
+            s => SnipeTargetType::Unknown(s.to_string()),
+        })
+    }
+}
 #[derive(PartialEq, Debug, Clone)]
 pub enum LogLevel {
     Info,
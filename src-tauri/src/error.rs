@@ -3,7 +3,7 @@ use regex::Regex;
 use reqwest::header::HeaderMap;
 use serde_json::{json, Value};
 
-use crate::{enums::LogLevel, logger};
+use crate::{enums::LogLevel, logger, metrics};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct ErrorApiResponse {
@@ -209,6 +209,7 @@ impl<T> From<std::sync::PoisonError<T>> for AppError {
 }
 
 pub fn create_log_file(file: String, e: &AppError) {
+    metrics::record_error(e);
     let component = e.component();
     let cause = e.cause();
     let backtrace = e.backtrace();
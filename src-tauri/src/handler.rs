@@ -2,25 +2,48 @@ use std::{env, fs};
 
 use tauri::{api::notification::Notification, AppHandle, Window};
 
-#[derive(Clone,Debug)]
+use crate::logger;
+
+#[derive(Clone, Debug)]
 pub struct MonitorHandler {
-    pub main_window: Window,
-    pub app_handler: AppHandle,
+    // None when running without a window, like the cli
+    pub main_window: Option<Window>,
+    pub app_handler: Option<AppHandle>,
 }
 
 impl MonitorHandler {
     pub fn new(window: Window, app_handler: AppHandle) -> Self {
         MonitorHandler {
-            main_window: window,
-            app_handler,
+            main_window: Some(window),
+            app_handler: Some(app_handler),
         }
     }
-    pub fn show_notification(&self, title: &str, body: &str,icon: Option<&str>, sound: Option<&str>) {
+    pub fn headless() -> Self {
+        MonitorHandler {
+            main_window: None,
+            app_handler: None,
+        }
+    }
+    pub fn show_notification(
+        &self,
+        title: &str,
+        body: &str,
+        icon: Option<&str>,
+        sound: Option<&str>,
+    ) {
+        // Without a desktop session the notification ends up in the console
+        let app_handler = match &self.app_handler {
+            Some(app_handler) => app_handler,
+            None => {
+                logger::info_con("Notification", format!("{}: {}", title, body).as_str());
+                return;
+            }
+        };
         let sound = match sound {
             Some(s) => s,
             None => "Default",
         };
-        let notification = Notification::new(&self.app_handler.config().tauri.bundle.identifier)
+        let notification = Notification::new(&app_handler.config().tauri.bundle.identifier)
             .title(title)
             .body(body)
             .icon(icon.unwrap_or("assets/icons/icon.png"))
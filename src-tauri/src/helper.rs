@@ -19,6 +19,7 @@ use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
 use crate::{
     error::AppError,
+    local_api,
     logger::{self},
     structs::WarframeLanguage,
     PACKAGEINFO,
@@ -112,19 +113,16 @@ pub async fn get_app_info() -> Result<serde_json::Value, AppError> {
 }
 
 pub fn emit_progress(id: &str, i18n_key: &str, values: Option<Value>, is_completed: bool) {
-    send_message_to_window(
-        "Client:Update:Progress",
-        Some(
-            json!({ "id": id, "i18n_key": i18n_key,"values": values, "isCompleted": is_completed}),
-        ),
-    );
+    let data =
+        json!({ "id": id, "i18n_key": i18n_key,"values": values, "isCompleted": is_completed});
+    local_api::publish("Client:Update:Progress", &data);
+    send_message_to_window("Client:Update:Progress", Some(data));
 }
 
 pub fn emit_update(update_type: &str, operation: &str, data: Option<Value>) {
-    send_message_to_window(
-        "Client:Update",
-        Some(json!({ "type": update_type, "operation": operation, "data": data})),
-    );
+    let data = json!({ "type": update_type, "operation": operation, "data": data});
+    local_api::publish("Client:Update", &data);
+    send_message_to_window("Client:Update", Some(data));
 }
 
 pub fn emit_undate_initializ_status(status: &str, data: Option<Value>) {
@@ -530,112 +528,6 @@ pub fn last_x_days(x: i64) -> Vec<String> {
         .rev()
         .collect()
 }
-pub fn send_message_to_discord(
-    webhook: String,
-    title: String,
-    content: String,
-    user_ids: Option<Vec<String>>,
-) {
-    // Check if the webhook is empty
-    if webhook.is_empty() {
-        logger::warning_con("Helper", "Discord webhook is empty");
-        return;
-    }
-    tauri::async_runtime::spawn(async move {
-        let client = reqwest::Client::new();
-
-        let mut body = json!({
-            "username": "Quantframe",
-            "avatar_url": "https://i.imgur.com/bgR6vAd.png",
-            "embeds": [
-                {
-                    "title": title,
-                    "description": content,
-                    "color": 5814783,
-                    "footer": {
-                        "text": format!("Quantframe v{}", PACKAGEINFO.lock().unwrap().clone().unwrap().version.to_string()),
-                        "timestamp": chrono::Local::now()
-                        .naive_utc()
-                        .to_string()
-                    }
-                }
-            ]
-        });
-
-        let mut pings: Vec<String> = Vec::new();
-        if let Some(user_ids) = user_ids {
-            for user_id in user_ids {
-                pings.push(format!("<@{}>", user_id));
-            }
-        }
-        if pings.len() > 0 {
-            body["content"] = json!(format!("{}", pings.join(" ")).replace("\"", ""));
-        } else {
-            body["content"] = json!("");
-        }
-
-        let res = client.post(webhook).json(&body).send().await;
-        match res {
-            Ok(_) => {
-                logger::info_con("Helper", "Message sent to discord");
-            }
-            Err(e) => {
-                println!("Error while sending message to discord {:?}", e);
-            }
-        }
-    });
-}
-
-pub async fn alter_table(
-    connection: sqlx::Pool<sqlx::Sqlite>,
-    alter_sql: &str,
-) -> Result<bool, AppError> {
-    let re = regex::Regex::new(r#"ALTER TABLE "(?P<table>[^"]+)" ADD COLUMN "(?P<column>[^"]+)"#)
-        .unwrap();
-    if let Some(captures) = re.captures(alter_sql) {
-        let table_name = captures.name("table").map_or("", |m| m.as_str());
-        let column_name = captures.name("column").map_or("", |m| m.as_str());
-        if table_name != "" && column_name != "" {
-            let rep = sqlx::query(format!("PRAGMA table_info(\"{}\")", table_name).as_str())
-                .fetch_all(&connection)
-                .await
-                .map_err(|e| AppError::new("Database", eyre!(e.to_string())));
-            match rep {
-                Ok(r) => {
-                    for row in r {
-                        let name: String = sqlx::Row::get(&row, "name");
-                        if name == column_name {
-                            return Ok(true);
-                        }
-                    }
-                    sqlx::query(&alter_sql)
-                        .execute(&connection)
-                        .await
-                        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
-                    return Ok(false);
-                }
-                Err(_) => {
-                    return Err(AppError::new(
-                        "Database",
-                        eyre!("Could not find table in database."),
-                    ));
-                }
-            }
-        } else {
-            logger::warning_con(
-                "Helper",
-                "Could not find table name or column name in the SQL.",
-            );
-        }
-    } else {
-        logger::warning_con(
-            "Helper",
-            "Could not find table name or column name in the SQL.",
-        );
-    }
-    Ok(false)
-}
-
 pub fn calculate_trade_tax(item_tags: Vec<String>, rank: Option<i64>) -> i64 {
     // If tags contains "arcane_upgrade" then it is an arcane
     if item_tags.contains(&"arcane_enhancement".to_string()) {
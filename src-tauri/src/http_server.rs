@@ -0,0 +1,186 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::{Error, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs},
+};
+
+use eyre::eyre;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{error::AppError, logger};
+
+// Requests are small, anything bigger is not for us
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+// Minimal HTTP/1.1 plumbing shared by the local api, the metrics endpoint and the mock server.
+// One request per connection, every response is sent with "Connection: close".
+pub struct HttpRequest {
+    pub method: String,
+    // Path with the query string
+    pub target: String,
+    // Lowercased names
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        HttpResponse {
+            status,
+            content_type,
+            headers: vec![],
+            body,
+        }
+    }
+}
+
+pub struct HttpListener {
+    pub addr: SocketAddr,
+    listener: std::net::TcpListener,
+}
+
+impl HttpListener {
+    pub fn bind(addr: impl ToSocketAddrs, component: &str) -> Result<HttpListener, AppError> {
+        let listener = std::net::TcpListener::bind(addr)
+            .map_err(|e| AppError::new(component, eyre!(e.to_string())))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| AppError::new(component, eyre!(e.to_string())))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| AppError::new(component, eyre!(e.to_string())))?;
+        Ok(HttpListener { addr, listener })
+    }
+
+    // Hands every connection to the handler on its own task until the returned handle is aborted.
+    pub fn serve<F, Fut>(
+        self,
+        component: &'static str,
+        handler: F,
+    ) -> tauri::async_runtime::JoinHandle<()>
+    where
+        F: Fn(TcpStream) -> Fut + Send + 'static,
+        Fut: Future<Output = std::io::Result<()>> + Send + 'static,
+    {
+        let listener = self.listener;
+        tauri::async_runtime::spawn(async move {
+            // Registered here so the listener belongs to the runtime that polls it
+            let listener = match TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    logger::error_con(component, format!("Could not listen: {}", e).as_str());
+                    return;
+                }
+            };
+            loop {
+                let (stream, _) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        logger::warning_con(component, format!("Accept failed: {}", e).as_str());
+                        continue;
+                    }
+                };
+                let connection = handler(stream);
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = connection.await {
+                        logger::warning_con(component, format!("Connection error: {}", e).as_str());
+                    }
+                });
+            }
+        })
+    }
+}
+
+pub fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        _ => "Internal Server Error",
+    }
+}
+
+// None when the client hangs up before sending a full head.
+// A head or body over the limits is an InvalidData error.
+pub async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<HttpRequest>> {
+    let mut buffer: Vec<u8> = vec![];
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, "Request too large"));
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("").to_string();
+    let target = request_line.next().unwrap_or("").to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_SIZE {
+        return Err(Error::new(ErrorKind::InvalidData, "Request too large"));
+    }
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body_end = buffer.len().min(header_end + content_length);
+    Ok(Some(HttpRequest {
+        method,
+        target,
+        headers,
+        body: buffer[header_end..body_end].to_vec(),
+    }))
+}
+
+pub async fn write_response(stream: &mut TcpStream, response: HttpResponse) -> std::io::Result<()> {
+    let mut raw = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        status_text(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    for (key, value) in response.headers {
+        raw.push_str(format!("{}: {}\r\n", key, value).as_str());
+    }
+    raw.push_str("\r\n");
+    raw.push_str(&response.body);
+    stream.write_all(raw.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
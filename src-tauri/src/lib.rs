@@ -0,0 +1,31 @@
+// Backend shared by the app (main.rs) and the headless cli (cli.rs)
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use tauri::PackageInfo;
+
+pub mod enums;
+pub mod handler;
+pub mod structs;
+
+pub mod auth;
+pub mod cache;
+pub mod crypto;
+pub mod database;
+pub mod debug;
+pub mod error;
+pub mod helper;
+pub mod http_server;
+pub mod live_scraper;
+pub mod local_api;
+pub mod logger;
+pub mod metrics;
+pub mod notification;
+pub mod price_scraper;
+pub mod profiles;
+pub mod rate_limiter;
+pub mod settings;
+pub mod wf_ee_log_parser;
+pub mod wfm_client;
+
+// Set by the app on setup, stays empty in the cli
+pub static PACKAGEINFO: Lazy<Mutex<Option<PackageInfo>>> = Lazy::new(|| Mutex::new(None));
@@ -1,9 +1,10 @@
 use std::{
+    collections::HashMap,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use serde_json::json;
@@ -11,21 +12,39 @@ use serde_json::json;
 use crate::{
     auth::AuthState,
     database::client::DBClient,
-    enums::{LogLevel, OrderMode, StockMode},
-    error::AppError,
+    enums::{OrderMode, StockMode},
+    error::{self, AppError},
     handler::MonitorHandler,
     helper,
     logger::{self},
+    metrics,
+    notification::{notify, EmbedField, NotificationKind},
     price_scraper::PriceScraper,
+    profiles,
     settings::SettingsState,
     wfm_client::client::WFMClient,
 };
 
-use super::modules::{item::ItemModule, riven::RivenModule};
+use super::modules::{
+    backtest::BacktestModule,
+    dry_run::{DryRunEntry, DryRunModule},
+    item::ItemModule,
+    progress::{LoopProgress, ProgressModule},
+    riven::RivenModule,
+    sniper::SniperModule,
+    valuation::ValuationModule,
+};
+
+// Failed cycles in a row before the loop pauses
+const BREAKER_THRESHOLD: u32 = 3;
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+const MAX_BREAKER_COOLDOWN: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Clone)]
 pub struct LiveScraperClient {
     pub log_file: String,
+    // Profile the scraper trades for, it keeps running when another profile is opened
+    pub profile: String,
     pub is_running: Arc<AtomicBool>,
     pub settings: Arc<Mutex<SettingsState>>,
     pub price_scraper: Arc<Mutex<PriceScraper>>,
@@ -33,6 +52,14 @@ pub struct LiveScraperClient {
     pub auth: Arc<Mutex<AuthState>>,
     pub db: Arc<Mutex<DBClient>>,
     pub mh: Arc<Mutex<MonitorHandler>>,
+    pub dry_run_journal: Arc<Mutex<Vec<DryRunEntry>>>,
+    pub progress: Arc<Mutex<LoopProgress>>,
+    // When the auctions for a riven valuation were last collected, by search
+    pub snapshot_times: Arc<Mutex<HashMap<String, Instant>>>,
+    // When the riven watchlist was last searched
+    pub last_snipe: Arc<Mutex<Option<Instant>>>,
+    // Auctions we notified about, with the price they had then
+    pub sniped_auctions: Arc<Mutex<HashMap<String, i64>>>,
 }
 
 impl LiveScraperClient {
@@ -44,6 +71,7 @@ impl LiveScraperClient {
         db: Arc<Mutex<DBClient>>,
         mh: Arc<Mutex<MonitorHandler>>,
     ) -> Self {
+        let profile = profiles::get_active_id();
         LiveScraperClient {
             log_file: "live_scraper.log".to_string(),
             price_scraper,
@@ -53,71 +81,158 @@ impl LiveScraperClient {
             auth,
             db,
             mh,
+            dry_run_journal: Arc::new(Mutex::new(vec![])),
+            progress: Arc::new(Mutex::new(LoopProgress::read_from_file(&profile))),
+            profile,
+            snapshot_times: Arc::new(Mutex::new(HashMap::new())),
+            last_snipe: Arc::new(Mutex::new(None)),
+            sniped_auctions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // Scraper of a profile that isn't the active one yet
+    pub fn with_profile(self, profile: &str) -> Self {
+        LiveScraperClient {
+            progress: Arc::new(Mutex::new(LoopProgress::read_from_file(profile))),
+            profile: profile.to_string(),
+            ..self
         }
     }
-    fn report_error(&self, error: AppError) {
+    fn report_error(&self, error: &AppError) {
         let component = error.component();
         let cause = error.cause();
         let backtrace = error.backtrace();
         let log_level = error.log_level();
         let extra = error.extra_data();
-        if log_level == LogLevel::Critical || log_level == LogLevel::Error {
-            self.is_running.store(false, Ordering::SeqCst);
-            crate::logger::dolog(
-                log_level.clone(),
-                component.as_str(),
-                format!("{}, {}, {}", backtrace, cause, extra.to_string()).as_str(),
-                true,
-                Some(self.log_file.as_str()),
-            );
+        metrics::record_error(error);
+        crate::logger::dolog(
+            log_level.clone(),
+            component.as_str(),
+            format!("{}, {}, {}", backtrace, cause, extra.to_string()).as_str(),
+            true,
+            Some(self.log_file.as_str()),
+        );
+    }
+    // The loop keeps going, it waits for the cooldown before the next cycle
+    fn pause(&self, error: &AppError, cooldown: Duration) {
+        let minutes = cooldown.as_secs() / 60;
+        logger::warning_con(
+            "LiveScraper",
+            format!("Pausing for {} minutes after repeated errors", minutes).as_str(),
+        );
+        if self.is_active_profile() {
             helper::send_message_to_window("LiveScraper:Error", Some(error.to_json()));
-        } else {
-            crate::logger::dolog(
-                log_level.clone(),
-                component.as_str(),
-                format!("{}, {}, {}", backtrace, cause, extra.to_string()).as_str(),
-                true,
-                Some(self.log_file.as_str()),
-            );
         }
+        self.send_message("paused", Some(json!({ "minutes": minutes })));
+        self.notify_paused(&error.component(), &error.cause(), minutes);
+    }
+    fn notify_paused(&self, component: &str, cause: &str, minutes: u64) {
+        let settings = self.settings.lock().unwrap().clone();
+        let notification = &settings.notifications.on_scraper_error;
+        if !notification.is_enabled() {
+            return;
+        }
+        let mh = self.mh.lock().unwrap().clone();
+        let fields = vec![
+            EmbedField::new("Component", component, true),
+            EmbedField::new("Paused", &format!("{} minutes", minutes), true),
+            EmbedField::new("Error", cause, false),
+        ];
+        let values = HashMap::from([
+            ("COMPONENT", component.to_string()),
+            ("ERROR", cause.to_string()),
+            ("MINUTES", minutes.to_string()),
+        ]);
+        notify(
+            &mh,
+            &settings,
+            notification,
+            NotificationKind::ScraperError,
+            &values,
+            fields,
+            None,
+        );
     }
     pub fn stop_loop(&self) {
         self.is_running.store(false, Ordering::SeqCst);
+        // Stopped on purpose, nothing to resume
+        if let Err(e) = self.progress().set_running(false) {
+            error::create_log_file(self.log_file.clone(), &e);
+        }
+    }
+
+    // The loop was running when the app closed or crashed
+    pub fn should_resume(&self) -> bool {
+        self.progress
+            .lock()
+            .map(|progress| progress.running)
+            .unwrap_or(false)
     }
 
     pub fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
 
+    pub fn is_dry_run(&self) -> bool {
+        self.settings.lock().unwrap().live_scraper.dry_run
+    }
+
     pub fn start_loop(&mut self) -> Result<(), AppError> {
         self.is_running.store(true, Ordering::SeqCst);
         let is_running = Arc::clone(&self.is_running);
         let forced_stop = Arc::clone(&self.is_running);
         let scraper = self.clone();
-        let db = self.db.lock()?.clone();
-        // Reset riven stocks on start
+        // Start every dry run with an empty journal
+        if self.is_dry_run() {
+            self.dry_run().clear()?;
+        }
+        // Orders are left alone when a previous run is resumed, they are still ours.
+        // Without resume_on_start every start is a fresh one.
+        let resume = self.should_resume() && self.settings.lock()?.live_scraper.resume_on_start;
+        if !resume {
+            self.progress().clear()?;
+        }
+        self.progress().set_running(true)?;
         tauri::async_runtime::spawn(async move {
-            logger::info_con("LiveScraper", "Loop live scraper is started");
-
-            scraper.send_message("riven.reset", None);
-            db.stock_riven().reset_listed_price().await.unwrap();
-            scraper.send_message("item.reset", None);
-            db.stock_item().reset_listed_price().await.unwrap();
-            scraper
-                .item()
-                .delete_all_orders(OrderMode::Both)
-                .await
-                .unwrap();
+            if scraper.is_dry_run() {
+                logger::info_con("LiveScraper", "Loop live scraper is started in dry run mode");
+            } else {
+                logger::info_con("LiveScraper", "Loop live scraper is started");
+            }
+
+            let sniper_only =
+                scraper.settings.lock().unwrap().live_scraper.stock_mode == StockMode::Sniper;
+            if resume {
+                logger::info_con("LiveScraper", "Resuming the previous run");
+            } else if !sniper_only {
+                // Reset riven stocks on start
+                let reset: Result<(), AppError> = async {
+                    scraper.dry_run().reset_listed_prices().await?;
+                    scraper.item().delete_all_orders(OrderMode::Both).await?;
+                    Ok(())
+                }
+                .await;
+                if let Err(e) = reset {
+                    scraper.report_error(&e);
+                }
+            }
+            metrics::set(metrics::LIVE_SCRAPER_RUNNING, &[], 1.0);
+            let mut failed_cycles = 0;
             while is_running.load(Ordering::SeqCst) && forced_stop.load(Ordering::SeqCst) {
+                let started = Instant::now();
                 let settings = scraper.settings.lock().unwrap().clone();
+                let mut cycle_error: Option<AppError> = None;
+                if let Err(e) = scraper.progress().start_cycle() {
+                    scraper.report_error(&e);
+                }
                 if settings.live_scraper.stock_mode == StockMode::Riven
                     || settings.live_scraper.stock_mode == StockMode::All
                 {
                     logger::info_con("LiveScraper", "Checking riven stock");
                     scraper.send_message("riven.starting", None);
-                    match scraper.riven().check_stock().await {
-                        Ok(_) => {}
-                        Err(e) => scraper.report_error(e),
+                    if let Err(e) = scraper.riven().check_stock().await {
+                        scraper.report_error(&e);
+                        cycle_error = Some(e);
                     }
                 }
 
@@ -126,13 +241,67 @@ impl LiveScraperClient {
                 {
                     logger::info_con("LiveScraper", "Checking item stock");
                     scraper.send_message("riven.starting", None);
-                    match scraper.item().check_stock().await {
-                        Ok(_) => {}
-                        Err(e) => scraper.report_error(e),
+                    if let Err(e) = scraper.item().check_stock().await {
+                        scraper.report_error(&e);
+                        cycle_error = Some(e);
                     }
                 }
-                tokio::time::sleep(Duration::from_secs(1)).await;
+
+                // The sniper mode is there to run the watchlist without trading
+                if settings.live_scraper.riven_sniper.enabled
+                    || settings.live_scraper.stock_mode == StockMode::Sniper
+                {
+                    if let Err(e) = scraper.sniper().check_watchlist().await {
+                        scraper.report_error(&e);
+                        cycle_error = Some(e);
+                    }
+                }
+
+                // Stopping halfway isn't a finished cycle, the next run continues it
+                if !is_running.load(Ordering::SeqCst) {
+                    break;
+                }
+                let cycle_error = match cycle_error {
+                    Some(e) => e,
+                    None => {
+                        failed_cycles = 0;
+                        if let Err(e) = scraper.progress().finish_cycle() {
+                            scraper.report_error(&e);
+                        }
+                        // A stalled scraper shows up as a last cycle that is getting old
+                        metrics::inc(metrics::LIVE_SCRAPER_CYCLES, &[]);
+                        metrics::observe(
+                            metrics::LIVE_SCRAPER_CYCLE_DURATION,
+                            &[],
+                            started.elapsed(),
+                        );
+                        metrics::set(
+                            metrics::LIVE_SCRAPER_LAST_CYCLE,
+                            &[],
+                            chrono::Utc::now().timestamp() as f64,
+                        );
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+
+                // Circuit breaker, a few quick retries and then longer and longer pauses
+                failed_cycles += 1;
+                let cooldown = if failed_cycles < BREAKER_THRESHOLD {
+                    Duration::from_secs(5)
+                } else {
+                    let cooldown =
+                        BREAKER_COOLDOWN * 2u32.pow((failed_cycles - BREAKER_THRESHOLD).min(4));
+                    let cooldown = cooldown.min(MAX_BREAKER_COOLDOWN);
+                    scraper.pause(&cycle_error, cooldown);
+                    cooldown
+                };
+                let resume_at = Instant::now() + cooldown;
+                while Instant::now() < resume_at && is_running.load(Ordering::SeqCst) {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
             }
+            metrics::set(metrics::LIVE_SCRAPER_RUNNING, &[], 0.0);
             scraper.send_message("", None);
             logger::info_con("LiveScraper", "Loop live scraper is stopped");
         });
@@ -144,8 +313,31 @@ impl LiveScraperClient {
     pub fn riven(&self) -> RivenModule {
         RivenModule { client: self }
     }
+    pub fn dry_run(&self) -> DryRunModule {
+        DryRunModule { client: self }
+    }
+    pub fn backtest(&self) -> BacktestModule {
+        BacktestModule { client: self }
+    }
+    pub fn progress(&self) -> ProgressModule {
+        ProgressModule { client: self }
+    }
+    pub fn valuation(&self) -> ValuationModule {
+        ValuationModule { client: self }
+    }
+    pub fn sniper(&self) -> SniperModule {
+        SniperModule { client: self }
+    }
+
+    // The window only shows the scraper of the open profile
+    pub fn is_active_profile(&self) -> bool {
+        self.profile == profiles::get_active_id()
+    }
 
     pub fn send_message(&self, i18n_key: &str, data: Option<serde_json::Value>) {
+        if !self.is_active_profile() {
+            return;
+        }
         helper::send_message_to_window(
             "LiveScraper:UpdateMessage",
             Some(json!({
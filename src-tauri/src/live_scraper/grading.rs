@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{client::DBClient, modules::riven_snapshot::RivenSnapshotStruct},
+    error::AppError,
+    live_scraper::valuation::{self, Dispositions, RivenRoll},
+    structs::{RivenAttribute, RivenAttributeInfo},
+};
+
+// Fewer comparable rolls than this and the percentile is left out
+const MIN_PERCENTILE_SAMPLES: usize = 5;
+// Weight of the stat choice against the roll quality in the score
+const STAT_CHOICE_WEIGHT: f64 = 0.65;
+// Negatives at or below this desirability don't hurt, they even push the positives up
+const HARMLESS_NEGATIVE: f64 = 0.1;
+
+// How much each stat is worth on a weapon type, 0 to 1. Stats that aren't listed count as 0.
+const GUN_PROFILE: &[(&str, f64)] = &[
+    ("critical_chance", 1.0),
+    ("critical_damage", 1.0),
+    ("multishot", 1.0),
+    ("base_damage_/_melee_damage", 1.0),
+    ("toxin_damage", 0.7),
+    ("heat_damage", 0.7),
+    ("cold_damage", 0.7),
+    ("electric_damage", 0.7),
+    ("fire_rate_/_attack_speed", 0.7),
+    ("status_chance", 0.6),
+    ("damage_vs_corpus", 0.5),
+    ("damage_vs_grineer", 0.5),
+    ("damage_vs_infested", 0.5),
+    ("slash_damage", 0.4),
+    ("punch_through", 0.3),
+    ("reload_speed", 0.3),
+    ("magazine_capacity", 0.3),
+    ("impact_damage", 0.2),
+    ("puncture_damage", 0.2),
+    ("projectile_speed", 0.1),
+    ("status_duration", 0.1),
+    ("ammo_maximum", 0.05),
+    ("recoil", 0.05),
+    ("zoom", 0.05),
+];
+
+const SHOTGUN_PROFILE: &[(&str, f64)] = &[
+    ("critical_chance", 1.0),
+    ("critical_damage", 1.0),
+    ("multishot", 1.0),
+    ("base_damage_/_melee_damage", 1.0),
+    ("status_chance", 0.8),
+    ("toxin_damage", 0.7),
+    ("heat_damage", 0.7),
+    ("cold_damage", 0.7),
+    ("electric_damage", 0.7),
+    ("fire_rate_/_attack_speed", 0.6),
+    ("damage_vs_corpus", 0.5),
+    ("damage_vs_grineer", 0.5),
+    ("damage_vs_infested", 0.5),
+    ("slash_damage", 0.4),
+    ("reload_speed", 0.4),
+    ("magazine_capacity", 0.3),
+    ("punch_through", 0.3),
+    ("impact_damage", 0.2),
+    ("puncture_damage", 0.2),
+    ("projectile_speed", 0.1),
+    ("status_duration", 0.1),
+    ("ammo_maximum", 0.05),
+    ("recoil", 0.05),
+    ("zoom", 0.05),
+];
+
+const MELEE_PROFILE: &[(&str, f64)] = &[
+    ("critical_chance", 1.0),
+    ("critical_damage", 1.0),
+    ("base_damage_/_melee_damage", 1.0),
+    ("range", 0.8),
+    ("fire_rate_/_attack_speed", 0.7),
+    ("toxin_damage", 0.7),
+    ("heat_damage", 0.7),
+    ("cold_damage", 0.7),
+    ("electric_damage", 0.7),
+    ("status_chance", 0.6),
+    ("chance_to_gain_combo_count", 0.5),
+    ("damage_vs_corpus", 0.5),
+    ("damage_vs_grineer", 0.5),
+    ("damage_vs_infested", 0.5),
+    ("slash_damage", 0.4),
+    ("combo_duration", 0.3),
+    ("critical_chance_on_slide_attack", 0.3),
+    ("impact_damage", 0.2),
+    ("puncture_damage", 0.2),
+    ("finisher_damage", 0.2),
+    ("status_duration", 0.1),
+    ("channeling_damage", 0.05),
+    ("channeling_efficiency", 0.05),
+];
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RivenAdvice {
+    // Good enough to sell on its own at fair value or to use
+    Keep,
+    // The stats are poor, only a re-roll can make it worth something
+    Roll,
+    // Usable but unremarkable, sell it with the rest
+    SellBulk,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatGrade {
+    pub url_name: String,
+    pub name: String,
+    pub positive: bool,
+    pub value: f64,
+    pub desirability: f64,
+    // Share of the comparable rolls we've seen that are worse, None without enough of them
+    pub percentile: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RivenGrade {
+    pub stats: Vec<StatGrade>,
+    // How close the stats are to the best ones for the weapon type, 0 to 1
+    pub stat_choice: f64,
+    // Percentile of the positive rolls weighted by desirability
+    pub roll_quality: Option<f64>,
+    pub score: f64,
+    pub grade: String,
+    pub advice: RivenAdvice,
+}
+
+fn profile(weapon_type: &str) -> &'static [(&'static str, f64)] {
+    match weapon_type {
+        "melee" | "zaw" => MELEE_PROFILE,
+        "shotgun" => SHOTGUN_PROFILE,
+        _ => GUN_PROFILE,
+    }
+}
+
+fn desirability(weapon_type: &str, url_name: &str) -> f64 {
+    profile(weapon_type)
+        .iter()
+        .find(|(name, _)| *name == url_name)
+        .map(|(_, weight)| *weight)
+        .unwrap_or(0.0)
+}
+
+// Riven stat ranges depend on the number of positives and whether there is a negative,
+// so a roll is only compared against rivens with the same layout.
+fn same_layout(a: &[RivenAttribute], b: &[RivenAttribute]) -> bool {
+    let positives =
+        |attributes: &[RivenAttribute]| attributes.iter().filter(|a| a.positive).count();
+    let negatives = |attributes: &[RivenAttribute]| attributes.iter().any(|a| !a.positive);
+    positives(a) == positives(b) && negatives(a) == negatives(b)
+}
+
+// Share of the collected auction snapshots this stat beats. The cache has no min/max
+// ranges for riven attributes, so this is relative to what was seen on the market
+// and not to the possible roll range.
+fn percentile(
+    roll: &RivenRoll,
+    attribute: &RivenAttribute,
+    snapshots: &[RivenSnapshotStruct],
+    dispositions: &Dispositions,
+) -> Option<f64> {
+    let comparable =
+        |snapshot: &&RivenSnapshotStruct| same_layout(&roll.attributes, &snapshot.attributes);
+    let find = |snapshot: &RivenSnapshotStruct| {
+        snapshot
+            .attributes
+            .iter()
+            .find(|a| a.url_name == attribute.url_name && a.positive == attribute.positive)
+            .cloned()
+    };
+
+    // Rolls on the same weapon first, other weapons of the type are scaled by their disposition
+    let own: Vec<f64> = snapshots
+        .iter()
+        .filter(|s| s.weapon_url == roll.weapon_url)
+        .filter(comparable)
+        .filter_map(|s| find(s).map(|a| a.value.abs()))
+        .collect();
+    let (value, values) = if own.len() >= MIN_PERCENTILE_SAMPLES {
+        (attribute.value.abs(), own)
+    } else {
+        let values: Vec<f64> = snapshots
+            .iter()
+            .filter(comparable)
+            .filter_map(|s| find(s).and_then(|a| dispositions.relative_value(&s.weapon_url, &a)))
+            .collect();
+        (
+            dispositions.relative_value(&roll.weapon_url, attribute)?,
+            values,
+        )
+    };
+    if values.len() < MIN_PERCENTILE_SAMPLES {
+        return None;
+    }
+    let below = values.iter().filter(|v| **v < value).count() as f64;
+    let equal = values.iter().filter(|v| **v == value).count() as f64;
+    let percentile = (below + equal / 2.0) / values.len() as f64;
+    // A smaller negative is the better roll
+    Some(if attribute.positive {
+        percentile
+    } else {
+        1.0 - percentile
+    })
+}
+
+// Scores the stats against the profile of the weapon type and the rolls against the ones we've seen.
+pub fn grade(
+    roll: &RivenRoll,
+    weapon_type: &str,
+    attribute_infos: &[RivenAttributeInfo],
+    snapshots: &[RivenSnapshotStruct],
+) -> RivenGrade {
+    let dispositions = Dispositions::estimate(snapshots);
+    let stats: Vec<StatGrade> = roll
+        .attributes
+        .iter()
+        .map(|attribute| StatGrade {
+            url_name: attribute.url_name.clone(),
+            name: attribute_infos
+                .iter()
+                .find(|info| info.url_name == attribute.url_name)
+                .map(|info| info.effect.clone())
+                .unwrap_or_else(|| attribute.url_name.clone()),
+            positive: attribute.positive,
+            value: attribute.value,
+            desirability: desirability(weapon_type, &attribute.url_name),
+            percentile: percentile(roll, attribute, snapshots, &dispositions),
+        })
+        .collect();
+    let positives: Vec<&StatGrade> = stats.iter().filter(|s| s.positive).collect();
+
+    // Compared to the best stats the weapon type could have with this many positives
+    let mut best: Vec<f64> = profile(weapon_type).iter().map(|(_, w)| *w).collect();
+    best.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let best: f64 = best.iter().take(positives.len()).sum();
+    let chosen: f64 = positives.iter().map(|s| s.desirability).sum();
+    let mut stat_choice = if best > 0.0 { chosen / best } else { 0.0 };
+    if let Some(negative) = stats.iter().find(|s| !s.positive) {
+        if negative.desirability <= HARMLESS_NEGATIVE {
+            stat_choice += 0.05;
+        } else {
+            stat_choice -= negative.desirability / 2.0;
+        }
+    }
+    let stat_choice = stat_choice.clamp(0.0, 1.0);
+
+    let graded: Vec<(f64, f64)> = positives
+        .iter()
+        .filter_map(|s| s.percentile.map(|p| (p, s.desirability.max(0.05))))
+        .collect();
+    let roll_quality = if graded.is_empty() {
+        None
+    } else {
+        let weights: f64 = graded.iter().map(|(_, w)| w).sum();
+        Some(graded.iter().map(|(p, w)| p * w).sum::<f64>() / weights)
+    };
+
+    let score = match roll_quality {
+        Some(quality) => STAT_CHOICE_WEIGHT * stat_choice + (1.0 - STAT_CHOICE_WEIGHT) * quality,
+        None => stat_choice,
+    };
+    let grade = match score {
+        s if s >= 0.85 => "S",
+        s if s >= 0.7 => "A",
+        s if s >= 0.55 => "B",
+        s if s >= 0.4 => "C",
+        s if s >= 0.25 => "D",
+        _ => "F",
+    };
+    let advice = if score >= 0.7 {
+        RivenAdvice::Keep
+    } else if stat_choice < 0.45 {
+        RivenAdvice::Roll
+    } else {
+        RivenAdvice::SellBulk
+    };
+    RivenGrade {
+        stats,
+        stat_choice: round(stat_choice),
+        roll_quality: roll_quality.map(round),
+        score: round(score),
+        grade: grade.to_string(),
+        advice,
+    }
+}
+
+// Grades every riven in stock, by stock id
+pub async fn grade_stock(db: &DBClient) -> Result<HashMap<i64, RivenGrade>, AppError> {
+    let cache = db.cache.lock()?.clone();
+    let attributes = cache.riven().get_attributes()?;
+    let rivens = db.stock_riven().get_rivens().await?;
+
+    // Snapshots are shared by every riven of the same type
+    let mut snapshots: HashMap<String, Vec<RivenSnapshotStruct>> = HashMap::new();
+    let mut grades = HashMap::new();
+    for riven in rivens {
+        if !snapshots.contains_key(&riven.weapon_type) {
+            let rows = db
+                .riven_snapshot()
+                .get_by_weapon_type(&riven.weapon_type, &valuation::snapshots_since())
+                .await?;
+            snapshots.insert(riven.weapon_type.clone(), rows);
+        }
+        let grade = grade(
+            &RivenRoll::from_stock(&riven),
+            &riven.weapon_type,
+            &attributes,
+            &snapshots[&riven.weapon_type],
+        );
+        grades.insert(riven.id, grade);
+    }
+    Ok(grades)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(url_name: &str, value: f64, positive: bool) -> RivenAttribute {
+        RivenAttribute {
+            positive,
+            value,
+            url_name: url_name.to_string(),
+            match_type: None,
+        }
+    }
+
+    fn roll(attributes: Vec<RivenAttribute>) -> RivenRoll {
+        RivenRoll {
+            weapon_url: "soma".to_string(),
+            attributes,
+            mastery_rank: 10,
+            re_rolls: 0,
+        }
+    }
+
+    fn snapshot(attributes: Vec<RivenAttribute>) -> RivenSnapshotStruct {
+        RivenSnapshotStruct {
+            id: 0,
+            auction_id: "auction".to_string(),
+            weapon_url: "soma".to_string(),
+            weapon_type: "rifle".to_string(),
+            mod_name: "crita-satiatis".to_string(),
+            attributes: sqlx::types::Json(attributes),
+            mastery_rank: 10,
+            re_rolls: 0,
+            mod_rank: 0,
+            polarity: "madurai".to_string(),
+            price: 100,
+            first_seen: "2023-10-01 00:00:00".to_string(),
+            last_seen: "2023-10-01 00:00:00".to_string(),
+        }
+    }
+
+    // Critical chance rolls of 100 to 140 with the same layout as the graded riven
+    fn critical_chance_snapshots(count: usize) -> Vec<RivenSnapshotStruct> {
+        (0..count)
+            .map(|i| {
+                snapshot(vec![
+                    attribute("critical_chance", 100.0 + 10.0 * i as f64, true),
+                    attribute("multishot", 80.0, true),
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn best_stats_with_a_harmless_negative_are_kept() {
+        let riven = roll(vec![
+            attribute("critical_chance", 150.0, true),
+            attribute("critical_damage", 120.0, true),
+            attribute("multishot", 90.0, true),
+            attribute("zoom", -40.0, false),
+        ]);
+        let grade = grade(&riven, "rifle", &[], &[]);
+        assert_eq!(grade.stat_choice, 1.0);
+        // Without snapshots there is nothing to compare the rolls with
+        assert_eq!(grade.roll_quality, None);
+        assert!(grade.stats.iter().all(|s| s.percentile.is_none()));
+        assert_eq!(grade.score, 1.0);
+        assert_eq!(grade.grade, "S");
+        assert_eq!(grade.advice, RivenAdvice::Keep);
+        // The effect name is used when we know it, the url name otherwise
+        assert_eq!(grade.stats[0].name, "critical_chance");
+    }
+
+    #[test]
+    fn poor_stats_with_a_bad_negative_should_be_rolled() {
+        let riven = roll(vec![
+            attribute("zoom", 40.0, true),
+            attribute("recoil", 30.0, true),
+            attribute("critical_chance", -50.0, false),
+        ]);
+        let grade = grade(&riven, "rifle", &[], &[]);
+        assert_eq!(grade.stat_choice, 0.0);
+        assert_eq!(grade.grade, "F");
+        assert_eq!(grade.advice, RivenAdvice::Roll);
+    }
+
+    #[test]
+    fn stats_are_valued_per_weapon_type() {
+        assert_eq!(desirability("melee", "range"), 0.8);
+        assert_eq!(desirability("zaw", "range"), 0.8);
+        assert_eq!(desirability("rifle", "range"), 0.0);
+        assert_eq!(desirability("shotgun", "status_chance"), 0.8);
+        assert_eq!(desirability("pistol", "status_chance"), 0.6);
+    }
+
+    #[test]
+    fn rolls_are_ranked_against_the_same_layout() {
+        let riven = roll(vec![
+            attribute("critical_chance", 130.0, true),
+            attribute("multishot", 80.0, true),
+        ]);
+        let mut snapshots = critical_chance_snapshots(5);
+        // A riven with a negative has other ranges and is left out
+        snapshots.push(snapshot(vec![
+            attribute("critical_chance", 500.0, true),
+            attribute("multishot", 80.0, true),
+            attribute("zoom", -40.0, false),
+        ]));
+        let grade = grade(&riven, "rifle", &[], &snapshots);
+        // 100, 110 and 120 are below and 130 counts half
+        assert_eq!(grade.stats[0].percentile, Some(0.7));
+        assert!(grade.roll_quality.is_some());
+    }
+
+    #[test]
+    fn too_few_rolls_give_no_percentile() {
+        let riven = roll(vec![
+            attribute("critical_chance", 130.0, true),
+            attribute("multishot", 80.0, true),
+        ]);
+        let snapshots = critical_chance_snapshots(MIN_PERCENTILE_SAMPLES - 1);
+        let grade = grade(&riven, "rifle", &[], &snapshots);
+        assert_eq!(grade.stats[0].percentile, None);
+        assert_eq!(grade.roll_quality, None);
+        assert_eq!(grade.score, grade.stat_choice);
+    }
+
+    #[test]
+    fn a_smaller_negative_is_the_better_roll() {
+        let riven = roll(vec![
+            attribute("multishot", 80.0, true),
+            attribute("zoom", -20.0, false),
+        ]);
+        let snapshots: Vec<RivenSnapshotStruct> = (0..5)
+            .map(|i| {
+                snapshot(vec![
+                    attribute("multishot", 80.0, true),
+                    attribute("zoom", -30.0 - 10.0 * i as f64, false),
+                ])
+            })
+            .collect();
+        let grade = grade(&riven, "rifle", &[], &snapshots);
+        assert_eq!(grade.stats[1].percentile, Some(1.0));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod client;
+pub mod grading;
 pub mod modules;
+pub mod pricing;
+pub mod valuation;
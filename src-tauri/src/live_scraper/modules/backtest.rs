@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+
+use eyre::eyre;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::AppError,
+    helper::{self, ColumnType, ColumnValue, ColumnValues},
+    live_scraper::{
+        client::LiveScraperClient,
+        pricing::{self, PricingContext},
+    },
+    logger,
+    settings::StockItemSettings,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BacktestFill {
+    pub date: String,
+    pub name: String,
+    pub order_type: String,
+    pub price: i64,
+    // Only set for sell fills
+    pub profit: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub days: usize,
+    pub fills: Vec<BacktestFill>,
+    pub buys: i64,
+    pub sells: i64,
+    pub profit: i64,
+    // Plat spent on items still held at the end of the backtest
+    pub capital_tied_up: i64,
+    pub max_capital_tied_up: i64,
+    // Items still held, valued at their last closed average
+    pub unrealized_value: i64,
+    // Sold value divided by the average capital tied up
+    pub turnover: f64,
+}
+
+// Replays the daily price history snapshots through the overlap filter and the pricing rules.
+// Orders are placed from one day's snapshot and fill against the next day's closed trades.
+pub struct BacktestModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> BacktestModule<'a> {
+    pub async fn run(
+        &self,
+        settings: &StockItemSettings,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<BacktestReport, AppError> {
+        let price_scraper = self.client.price_scraper.lock()?.clone();
+        let df = price_scraper.get_price_history_range(from, to).await?;
+        self.simulate(settings, df)
+    }
+
+    // Runs the backtest over a DataFrame with the columns of get_price_history_range.
+    pub fn simulate(
+        &self,
+        settings: &StockItemSettings,
+        df: DataFrame,
+    ) -> Result<BacktestReport, AppError> {
+        let df = df
+            .lazy()
+            .with_columns([
+                col("datetime").cast(DataType::Utf8),
+                col("min_price").cast(DataType::Float64),
+                col("max_price").cast(DataType::Float64),
+                col("avg_price").cast(DataType::Float64),
+                col("median").cast(DataType::Float64),
+            ])
+            .collect()
+            .map_err(|e| AppError::new("Backtest", eyre!(e.to_string())))?;
+
+        let mut days: Vec<String> = df
+            .column("datetime")
+            .map_err(|e| AppError::new("Backtest", eyre!(e.to_string())))?
+            .utf8()
+            .map_err(|e| AppError::new("Backtest", eyre!(e.to_string())))?
+            .into_iter()
+            .filter_map(|day| day.map(|day| day.to_string()))
+            .collect();
+        days.sort();
+        days.dedup();
+
+        let mut report = BacktestReport::default();
+        if days.len() < 2 {
+            return Err(AppError::new(
+                "Backtest",
+                eyre!("Need at least 2 days of price data, found {}", days.len()),
+            ));
+        }
+        report.days = days.len() - 1;
+
+        // Bought prices of the items we are holding, oldest first
+        let mut held: HashMap<String, Vec<i64>> = HashMap::new();
+        let mut sold_value = 0;
+        let mut capital_samples: Vec<i64> = vec![];
+
+        for window in days.windows(2) {
+            let (day, next_day) = (&window[0], &window[1]);
+            logger::info_con("Backtest", format!("Simulating day: {}", day).as_str());
+
+            let history = self.filter_days(&df, col("datetime").lt_eq(lit(day.as_str())))?;
+            let today = self.filter_days(&df, col("datetime").eq(lit(day.as_str())))?;
+            let tomorrow = self.filter_days(&df, col("datetime").eq(lit(next_day.as_str())))?;
+
+            let inventory_names: Vec<String> = held
+                .iter()
+                .filter(|(_, prices)| !prices.is_empty())
+                .map(|(name, _)| name.clone())
+                .collect();
+            let overlap = self.client.item().get_buy_sell_overlap_from(
+                &history,
+                settings,
+                inventory_names.clone(),
+            )?;
+
+            // Sell first so the freed capital can be used for buying
+            for name in inventory_names {
+                // FIFO like the profit report, the oldest lot is priced and sold
+                let bought_price = match held.get(&name).and_then(|lots| lots.first()) {
+                    Some(bought_price) => *bought_price,
+                    None => continue,
+                };
+                let strategy = pricing::get_strategy_for_item(settings, &name);
+                let ctx = self.get_pricing_context(&overlap, &today, &name, bought_price)?;
+
+                let post_price = if ctx.lowest_sell == 0 {
+                    strategy.sell_price_without_sellers(&ctx)
+                } else {
+                    match strategy.sell_price(&ctx) {
+                        Some(post_price) => post_price,
+                        None => continue,
+                    }
+                };
+
+                // A sell order fills if someone paid at least our price the next day
+                let closed_max = self.get_price(&tomorrow, &name, "closed", "max_price")?;
+                if closed_max > 0.0 && closed_max >= post_price as f64 {
+                    if let Some(lots) = held.get_mut(&name) {
+                        lots.remove(0);
+                    }
+                    let profit = post_price - bought_price;
+                    sold_value += post_price;
+                    report.sells += 1;
+                    report.profit += profit;
+                    report.fills.push(BacktestFill {
+                        date: next_day.clone(),
+                        name: name.clone(),
+                        order_type: "sell".to_string(),
+                        price: post_price,
+                        profit: Some(profit),
+                    });
+                }
+            }
+
+            let names = match helper::get_column_values(
+                overlap
+                    .clone()
+                    .select(["name"])
+                    .map_err(|e| AppError::new("Backtest", eyre!(e.to_string())))?,
+                None,
+                "name",
+                ColumnType::String,
+            )? {
+                ColumnValues::String(values) => values,
+                _ => return Err(AppError::new("Backtest", eyre!("Expected string values"))),
+            };
+
+            let mut tied_up: i64 = held.values().flatten().sum();
+            for name in names {
+                if settings.blacklist.contains(&name) {
+                    continue;
+                }
+                let strategy = pricing::get_strategy_for_item(settings, &name);
+                let ctx = self.get_pricing_context(&overlap, &today, &name, 0)?;
+                let owned = held.get(&name).map(|prices| prices.len()).unwrap_or(0) as i64;
+
+                // Same rules as compare_live_orders_when_buying
+                if ctx.lowest_sell == 0 {
+                    continue;
+                }
+                let post_price = if ctx.highest_buy == 0 {
+                    if ctx.closed_avg <= 25.0 {
+                        continue;
+                    }
+                    strategy.buy_price_without_buyers(&ctx).max(1)
+                } else {
+                    let post_price = strategy.buy_price(&ctx);
+                    let closed_avg_metric = strategy.potential_profit(&ctx, post_price) as i64;
+                    if owned > 1 && closed_avg_metric < 25 * owned {
+                        continue;
+                    }
+                    if !((closed_avg_metric >= 30 && ctx.price_range >= 15)
+                        || ctx.price_range >= 21)
+                    {
+                        continue;
+                    }
+                    post_price
+                };
+                if post_price > settings.avg_price_cap
+                    || tied_up + post_price > settings.max_total_price_cap
+                {
+                    continue;
+                }
+
+                // A buy order fills if someone sold at or below our price the next day
+                let closed_min = self.get_price(&tomorrow, &name, "closed", "min_price")?;
+                if closed_min > 0.0 && closed_min <= post_price as f64 {
+                    held.entry(name.clone()).or_default().push(post_price);
+                    tied_up += post_price;
+                    report.buys += 1;
+                    report.fills.push(BacktestFill {
+                        date: next_day.clone(),
+                        name: name.clone(),
+                        order_type: "buy".to_string(),
+                        price: post_price,
+                        profit: None,
+                    });
+                }
+            }
+            capital_samples.push(tied_up);
+        }
+
+        // Value what is left at the last closed average
+        let last_day =
+            self.filter_days(&df, col("datetime").eq(lit(days.last().unwrap().as_str())))?;
+        for (name, prices) in held.iter() {
+            let closed_avg = self.get_price(&last_day, name, "closed", "avg_price")?;
+            report.unrealized_value += closed_avg as i64 * prices.len() as i64;
+        }
+
+        report.capital_tied_up = held.values().flatten().sum();
+        report.max_capital_tied_up = capital_samples.iter().max().cloned().unwrap_or(0);
+        let avg_capital =
+            capital_samples.iter().sum::<i64>() as f64 / capital_samples.len().max(1) as f64;
+        if avg_capital > 0.0 {
+            report.turnover = sold_value as f64 / avg_capital;
+        }
+        logger::info_con(
+            "Backtest",
+            format!(
+                "Backtest done, buys: {}, sells: {}, profit: {}, turnover: {:.2}",
+                report.buys, report.sells, report.profit, report.turnover
+            )
+            .as_str(),
+        );
+        Ok(report)
+    }
+
+    fn filter_days(&self, df: &DataFrame, filter: Expr) -> Result<DataFrame, AppError> {
+        df.clone()
+            .lazy()
+            .filter(filter)
+            .collect()
+            .map_err(|e| AppError::new("Backtest", eyre!(e.to_string())))
+    }
+
+    // Returns 0 when the item has no snapshot for the order type.
+    fn get_price(
+        &self,
+        df: &DataFrame,
+        name: &str,
+        order_type: &str,
+        column: &str,
+    ) -> Result<f64, AppError> {
+        let filtered = self.filter_days(
+            df,
+            col("name")
+                .eq(lit(name))
+                .and(col("order_type").eq(lit(order_type))),
+        )?;
+        match helper::get_column_value(filtered, None, column, ColumnType::F64)? {
+            ColumnValue::F64(value) => Ok(value.unwrap_or(0.0)),
+            _ => Err(AppError::new("Backtest", eyre!("Expected f64 values"))),
+        }
+    }
+
+    fn get_pricing_context(
+        &self,
+        overlap: &DataFrame,
+        today: &DataFrame,
+        name: &str,
+        bought_price: i64,
+    ) -> Result<PricingContext, AppError> {
+        // Closed stats come from the overlap like they do in the live scraper
+        let item_stats = self.filter_days(overlap, col("name").eq(lit(name)))?;
+        let closed_avg =
+            match helper::get_column_value(item_stats.clone(), None, "closedAvg", ColumnType::F64)?
+            {
+                ColumnValue::F64(value) => value.unwrap_or(0.0),
+                _ => return Err(AppError::new("Backtest", eyre!("Expected f64 values"))),
+            };
+        let closed_median =
+            match helper::get_column_value(item_stats, None, "closedMedian", ColumnType::F64)? {
+                ColumnValue::F64(value) => value.unwrap_or(0.0),
+                _ => return Err(AppError::new("Backtest", eyre!("Expected f64 values"))),
+            };
+        let highest_buy = self.get_price(today, name, "buy", "max_price")? as i64;
+        let lowest_sell = self.get_price(today, name, "sell", "min_price")? as i64;
+        Ok(PricingContext {
+            closed_avg,
+            closed_median,
+            price_range: lowest_sell - highest_buy,
+            highest_buy,
+            lowest_sell,
+            bought_price,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        auth::AuthState,
+        cache::client::CacheClient,
+        database::client::DBClient,
+        enums::PricingStrategyType,
+        handler::MonitorHandler,
+        price_scraper::PriceScraper,
+        settings::{PricingStrategySettings, SettingsState},
+        wfm_client::client::WFMClient,
+    };
+
+    // The backtest only reads the DataFrame it is given, the database stays empty
+    fn client() -> LiveScraperClient {
+        let settings = Arc::new(Mutex::new(SettingsState::default()));
+        let auth = Arc::new(Mutex::new(AuthState::default()));
+        let wfm = Arc::new(Mutex::new(WFMClient::new(
+            Arc::clone(&auth),
+            Arc::clone(&settings),
+        )));
+        let cache = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm))));
+        let path = std::env::temp_dir().join(format!("quantframe_backtest_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        let db =
+            tauri::async_runtime::block_on(DBClient::open(cache, Arc::clone(&wfm), path)).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let price_scraper = Arc::new(Mutex::new(PriceScraper::new(
+            Arc::clone(&wfm),
+            Arc::clone(&auth),
+            Arc::clone(&db),
+        )));
+        LiveScraperClient::new(
+            settings,
+            price_scraper,
+            wfm,
+            auth,
+            db,
+            Arc::new(Mutex::new(MonitorHandler::headless())),
+        )
+    }
+
+    fn settings() -> StockItemSettings {
+        let mut settings = SettingsState::default().live_scraper.stock_item;
+        settings.volume_threshold = 0;
+        settings.range_threshold = 0;
+        settings.avg_price_cap = 1000;
+        settings.max_total_price_cap = 1000;
+        settings.price_shift_threshold = -1000;
+        settings.strict_whitelist = false;
+        settings.whitelist = vec![];
+        settings.blacklist = vec![];
+        settings.pricing_strategy = PricingStrategySettings {
+            strategy: PricingStrategyType::Default,
+            value: 0,
+        };
+        settings.pricing_overrides.clear();
+        settings
+    }
+
+    // One item, each day is the closed (min, max, avg), the highest buy and the lowest sell
+    fn history(days: &[(&str, (f64, f64, f64), f64, f64)]) -> DataFrame {
+        let mut rows: Vec<(&str, &str, i64, f64, f64, f64)> = vec![];
+        for (date, (min, max, avg), highest_buy, lowest_sell) in days {
+            rows.push((date, "closed", 100, *min, *max, *avg));
+            rows.push((date, "buy", 10, *highest_buy, *highest_buy, *highest_buy));
+            rows.push((date, "sell", 10, *lowest_sell, *lowest_sell, *lowest_sell));
+        }
+        DataFrame::new(vec![
+            Series::new("name", vec!["nova_prime_set"; rows.len()]),
+            Series::new("datetime", rows.iter().map(|r| r.0).collect::<Vec<_>>()),
+            Series::new("order_type", rows.iter().map(|r| r.1).collect::<Vec<_>>()),
+            Series::new("volume", rows.iter().map(|r| Some(r.2)).collect::<Vec<_>>()),
+            Series::new("min_price", rows.iter().map(|r| r.3).collect::<Vec<_>>()),
+            Series::new("max_price", rows.iter().map(|r| r.4).collect::<Vec<_>>()),
+            Series::new("avg_price", rows.iter().map(|r| r.5).collect::<Vec<_>>()),
+            Series::new("median", rows.iter().map(|r| r.5).collect::<Vec<_>>()),
+            Series::new("range", rows.iter().map(|r| r.4 - r.3).collect::<Vec<_>>()),
+            Series::new("mod_rank", vec![None::<f64>; rows.len()]),
+            Series::new("item_id", vec!["nova_prime_set_id"; rows.len()]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn lots_are_sold_oldest_first() {
+        let df = history(&[
+            ("2024-01-01", (40.0, 80.0, 60.0), 30.0, 60.0),
+            ("2024-01-02", (25.0, 50.0, 40.0), 40.0, 62.0),
+            ("2024-01-03", (35.0, 55.0, 45.0), 20.0, 38.0),
+            ("2024-01-04", (30.0, 45.0, 40.0), 20.0, 38.0),
+        ]);
+        let client = client();
+        let report = client.backtest().simulate(&settings(), df).unwrap();
+
+        let fills: Vec<(&str, &str, i64, Option<i64>)> = report
+            .fills
+            .iter()
+            .map(|f| (f.date.as_str(), f.order_type.as_str(), f.price, f.profit))
+            .collect();
+        // Priced from the 30p lot, the average of 35p would have asked 45p
+        assert_eq!(
+            fills,
+            vec![
+                ("2024-01-02", "buy", 30, None),
+                ("2024-01-03", "buy", 40, None),
+                ("2024-01-04", "sell", 40, Some(10)),
+            ]
+        );
+        assert_eq!(report.days, 3);
+        assert_eq!((report.buys, report.sells, report.profit), (2, 1, 10));
+        assert_eq!(report.capital_tied_up, 40);
+        assert_eq!(report.unrealized_value, 40);
+    }
+
+    #[test]
+    fn one_day_is_not_enough() {
+        let df = history(&[("2024-01-01", (40.0, 80.0, 60.0), 30.0, 60.0)]);
+        assert!(client().backtest().simulate(&settings(), df).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    error::AppError,
+    helper,
+    live_scraper::client::LiveScraperClient,
+    logger,
+    metrics,
+    structs::{Auction, AuctionItem, Order},
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DryRunEntry {
+    pub created: String,
+    // "item" or "riven"
+    pub module: String,
+    // "create", "update" or "delete"
+    pub action: String,
+    pub name: String,
+    pub order_id: Option<String>,
+    pub order_type: Option<String>,
+    pub price: Option<i64>,
+    pub previous_price: Option<i64>,
+    pub quantity: Option<i64>,
+    pub reason: String,
+}
+
+impl DryRunEntry {
+    // Key used to collapse the journal into a diff, one entry per listing.
+    pub fn key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.module,
+            self.name,
+            self.order_type.clone().unwrap_or("".to_string())
+        )
+    }
+}
+
+// Every order and auction mutation from the live scraper goes through this module.
+// When dry run is enabled the mutation is only recorded in the journal, and the
+// listed price and status of the stock are left as they are.
+pub struct DryRunModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> DryRunModule<'a> {
+    fn count(&self, module: &str, action: &str) {
+        let dry_run = self.client.is_dry_run().to_string();
+        metrics::inc(
+            metrics::LIVE_SCRAPER_ORDERS,
+            &[("module", module), ("action", action), ("dry_run", &dry_run)],
+        );
+    }
+
+    fn record(&self, entry: DryRunEntry) -> Result<(), AppError> {
+        self.count(&entry.module, &entry.action);
+        logger::info_file(
+            "DryRun",
+            format!(
+                "{} {} {} {:?} price: {:?} (was {:?}), reason: {}",
+                entry.module,
+                entry.action,
+                entry.name,
+                entry.order_type,
+                entry.price,
+                entry.previous_price,
+                entry.reason
+            )
+            .as_str(),
+            Some("dry_run.log"),
+        );
+        helper::send_message_to_window("LiveScraper:DryRun", Some(json!(entry.clone())));
+        self.client.dry_run_journal.lock()?.push(entry);
+        Ok(())
+    }
+
+    fn new_entry(
+        &self,
+        module: &str,
+        action: &str,
+        name: &str,
+        order_id: Option<String>,
+        order_type: Option<&str>,
+        price: Option<i64>,
+        previous_price: Option<i64>,
+        quantity: Option<i64>,
+        reason: &str,
+    ) -> DryRunEntry {
+        DryRunEntry {
+            created: chrono::Local::now().naive_local().to_string(),
+            module: module.to_string(),
+            action: action.to_string(),
+            name: name.to_string(),
+            order_id,
+            order_type: order_type.map(|t| t.to_string()),
+            price,
+            previous_price,
+            quantity,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn get_entries(&self) -> Result<Vec<DryRunEntry>, AppError> {
+        Ok(self.client.dry_run_journal.lock()?.clone())
+    }
+
+    // Returns the last intended change per listing.
+    pub fn get_diff(&self) -> Result<Vec<DryRunEntry>, AppError> {
+        let entries = self.get_entries()?;
+        let mut diff: Vec<DryRunEntry> = vec![];
+        for entry in entries {
+            match diff.iter_mut().find(|e| e.key() == entry.key()) {
+                Some(existing) => {
+                    // Keep the original price so the diff shows the full change
+                    let previous_price = existing.previous_price;
+                    *existing = entry;
+                    if existing.previous_price.is_none() {
+                        existing.previous_price = previous_price;
+                    }
+                }
+                None => diff.push(entry),
+            }
+        }
+        Ok(diff)
+    }
+
+    pub fn clear(&self) -> Result<(), AppError> {
+        self.client.dry_run_journal.lock()?.clear();
+        helper::send_message_to_window("LiveScraper:DryRun", None);
+        Ok(())
+    }
+
+    // Orders
+    pub async fn create_order(
+        &self,
+        name: &str,
+        item_id: &str,
+        order_type: &str,
+        platinum: i64,
+        quantity: i64,
+        visible: bool,
+        rank: Option<f64>,
+        reason: &str,
+    ) -> Result<Option<Order>, AppError> {
+        if self.client.is_dry_run() {
+            self.record(self.new_entry(
+                "item",
+                "create",
+                name,
+                None,
+                Some(order_type),
+                Some(platinum),
+                None,
+                Some(quantity),
+                reason,
+            ))?;
+            return Ok(None);
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        let order = wfm
+            .orders()
+            .create(item_id, order_type, platinum, quantity, visible, rank)
+            .await?;
+        self.count("item", "create");
+        Ok(Some(order))
+    }
+
+    pub async fn update_order(
+        &self,
+        name: &str,
+        order_id: &str,
+        order_type: &str,
+        platinum: i32,
+        previous_price: Option<i64>,
+        quantity: i32,
+        visible: bool,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            self.record(self.new_entry(
+                "item",
+                "update",
+                name,
+                Some(order_id.to_string()),
+                Some(order_type),
+                Some(platinum as i64),
+                previous_price,
+                Some(quantity as i64),
+                reason,
+            ))?;
+            return Ok(());
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        wfm.orders()
+            .update(order_id, platinum, quantity, visible)
+            .await?;
+        self.count("item", "update");
+        Ok(())
+    }
+
+    pub async fn delete_order(
+        &self,
+        name: &str,
+        order_id: &str,
+        order_type: &str,
+        previous_price: Option<i64>,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            self.record(self.new_entry(
+                "item",
+                "delete",
+                name,
+                Some(order_id.to_string()),
+                Some(order_type),
+                None,
+                previous_price,
+                None,
+                reason,
+            ))?;
+            return Ok(());
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        wfm.orders().delete(order_id).await?;
+        self.count("item", "delete");
+        Ok(())
+    }
+
+    // Auctions
+    pub async fn create_auction(
+        &self,
+        name: &str,
+        starting_price: i64,
+        buyout_price: i64,
+        item: AuctionItem,
+        reason: &str,
+    ) -> Result<Option<Auction<String>>, AppError> {
+        if self.client.is_dry_run() {
+            self.record(self.new_entry(
+                "riven",
+                "create",
+                name,
+                None,
+                Some("sell"),
+                Some(buyout_price),
+                None,
+                Some(1),
+                reason,
+            ))?;
+            return Ok(None);
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        let auction = wfm
+            .auction()
+            .create("riven", "", starting_price, buyout_price, 0, 1, false, item)
+            .await?;
+        self.count("riven", "create");
+        Ok(Some(auction))
+    }
+
+    pub async fn update_auction(
+        &self,
+        name: &str,
+        auction_id: &str,
+        price: i32,
+        previous_price: Option<i64>,
+        visible: bool,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            self.record(self.new_entry(
+                "riven",
+                "update",
+                name,
+                Some(auction_id.to_string()),
+                Some("sell"),
+                Some(price as i64),
+                previous_price,
+                Some(1),
+                reason,
+            ))?;
+            return Ok(());
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        wfm.auction()
+            .update(auction_id, price, 0, "", price, visible)
+            .await?;
+        self.count("riven", "update");
+        Ok(())
+    }
+
+    pub async fn delete_auction(
+        &self,
+        name: &str,
+        auction_id: &str,
+        previous_price: Option<i64>,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            self.record(self.new_entry(
+                "riven",
+                "delete",
+                name,
+                Some(auction_id.to_string()),
+                Some("sell"),
+                None,
+                previous_price,
+                None,
+                reason,
+            ))?;
+            return Ok(());
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        wfm.auction().delete(auction_id).await?;
+        self.count("riven", "delete");
+        Ok(())
+    }
+
+    // Stock
+    pub async fn reset_listed_prices(&self) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            return Ok(());
+        }
+        let db = self.client.db.lock()?.clone();
+        self.client.send_message("riven.reset", None);
+        db.stock_riven().reset_listed_price().await?;
+        self.client.send_message("item.reset", None);
+        db.stock_item().reset_listed_price().await?;
+        Ok(())
+    }
+
+    pub async fn update_stock_item(
+        &self,
+        name: &str,
+        listed_price: Option<i32>,
+        status: &str,
+    ) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            return Ok(());
+        }
+        let db = self.client.db.lock()?.clone();
+        db.stock_item()
+            .update_by_url(
+                name,
+                None,
+                None,
+                listed_price,
+                Some(status.to_string()),
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_stock_riven(
+        &self,
+        id: i64,
+        order_id: Option<String>,
+        listed_price: Option<i32>,
+        status: &str,
+    ) -> Result<(), AppError> {
+        if self.client.is_dry_run() {
+            return Ok(());
+        }
+        let db = self.client.db.lock()?.clone();
+        db.stock_riven()
+            .update_by_id(
+                id,
+                order_id,
+                None,
+                listed_price,
+                None,
+                None,
+                None,
+                None,
+                Some(status.to_string()),
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
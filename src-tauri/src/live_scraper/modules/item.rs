@@ -1,16 +1,20 @@
+use crate::notification::{notify, EmbedField, NotificationKind};
 use crate::enums::OrderMode;
 use crate::error;
 use crate::live_scraper::client::LiveScraperClient;
+use crate::live_scraper::pricing::{self, PricingContext, PricingStrategy};
+use crate::settings::StockItemSettings;
 use crate::structs::Order;
 use crate::{
     error::AppError,
     helper::{self, ColumnType, ColumnValue, ColumnValues},
     logger,
+    metrics,
 };
 use eyre::eyre;
 use polars::prelude::*;
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::vec;
 
 pub struct ItemModule<'a> {
@@ -57,7 +61,16 @@ impl<'a> ItemModule<'a> {
                     "item.deleting_orders",
                     Some(json!({ "count": current_index, "total": total})),
                 );
-                wfm.orders().delete(&order.id).await?;
+                self.client
+                    .dry_run()
+                    .delete_order(
+                        &order.item.clone().map(|i| i.url_name).unwrap_or_default(),
+                        &order.id,
+                        "sell",
+                        Some(order.platinum),
+                        "order_mode",
+                    )
+                    .await?;
             }
         } else if order_mode == OrderMode::Sell {
             let mut current_index = 0;
@@ -72,7 +85,16 @@ impl<'a> ItemModule<'a> {
                     "item.deleting_orders",
                     Some(json!({ "count": current_index, "total": total})),
                 );
-                wfm.orders().delete(&order.id).await?;
+                self.client
+                    .dry_run()
+                    .delete_order(
+                        &order.item.clone().map(|i| i.url_name).unwrap_or_default(),
+                        &order.id,
+                        "buy",
+                        Some(order.platinum),
+                        "order_mode",
+                    )
+                    .await?;
             }
         }
 
@@ -149,6 +171,8 @@ impl<'a> ItemModule<'a> {
         );
 
         let mut current_index = all_interesting_items.len();
+        let mut checked = 0;
+        let mut failures_in_row = 0;
         // Loop through all interesting items
         for item in all_interesting_items.clone() {
             if self.client.is_running() == false || item == "" {
@@ -156,107 +180,138 @@ impl<'a> ItemModule<'a> {
             }
             current_index -= 1;
 
-            logger::info_con(
-                "LiveScraper",
-                format!(
-                    "Checking item: {}, ({}/{})",
-                    item,
-                    current_index,
-                    all_interesting_items.len()
-                )
-                .as_str(),
-            );
-            self.client.send_message("item.checking", Some(json!({ "name": item, "count": current_index, "total": all_interesting_items.len()})));
-
-            let item_live_orders_df = wfm.orders().get_ordres_by_item(&item).await?;
-            // Check if item_orders_df is empty and skip if it is
-            if item_live_orders_df.height() == 0 {
+            let key = format!("item:{}", item);
+            if self.client.progress().should_skip(&key)? {
                 continue;
             }
-            let item_stats = popular_items_df
-                .clone()
-                .lazy()
-                .filter(col("name").eq(lit(item.clone())))
-                .collect()
-                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
-            // Check if item is in all_interesting_items
-            if !popular_items.contains(&item) {
-                logger::info_file(
+            checked += 1;
+
+            // One bad item is quarantined instead of ending the cycle
+            let result: Result<(), AppError> = async {
+                logger::info_con(
                     "LiveScraper",
-                    format!("Item: {item} is not in all_interesting_items").as_str(),
-                    Some(self.client.log_file.as_str()),
+                    format!(
+                        "Checking item: {}, ({}/{})",
+                        item,
+                        current_index,
+                        all_interesting_items.len()
+                    )
+                    .as_str(),
                 );
-                let item_info = wfm.items().get_item(item.to_string()).await?;
-
-                let item_id = item_info.id;
-                let item_rank = item_info.items_in_set.get(0).unwrap().mod_max_rank;
-                self.compare_live_orders_when_selling(
-                    &item,
-                    &item_id,
-                    item_rank,
-                    current_sell_orders_df.clone(),
-                    &item_live_orders_df,
-                    &item_stats,
-                    &stock_items_df,
-                )
-                .await?;
-                continue;
-            }
+                self.client.send_message("item.checking", Some(json!({ "name": item, "count": current_index, "total": all_interesting_items.len()})));
 
-            // Get the item_id and item_rank
-            let item_id: String = match helper::get_column_value(
-                popular_items_df.clone(),
-                Some(col("name").eq(lit(item.clone()))),
-                "item_id",
-                ColumnType::String,
-            )? {
-                ColumnValue::String(values) => values.unwrap_or("".to_string()),
-                _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
-            };
+                let item_live_orders_df = wfm.orders().get_ordres_by_item(&item).await?;
+                // Check if item_orders_df is empty and skip if it is
+                if item_live_orders_df.height() == 0 {
+                    return Ok(());
+                }
+                let item_stats = popular_items_df
+                    .clone()
+                    .lazy()
+                    .filter(col("name").eq(lit(item.clone())))
+                    .collect()
+                    .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+                // Check if item is in all_interesting_items
+                if !popular_items.contains(&item) {
+                    logger::info_file(
+                        "LiveScraper",
+                        format!("Item: {item} is not in all_interesting_items").as_str(),
+                        Some(self.client.log_file.as_str()),
+                    );
+                    let item_info = wfm.items().get_item(item.to_string()).await?;
 
-            let item_rank: Option<f64> = match helper::get_column_value(
-                popular_items_df.clone(),
-                Some(col("name").eq(lit(item.clone()))),
-                "mod_rank",
-                ColumnType::F64,
-            )? {
-                ColumnValue::F64(values) => values,
-                _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
-            };
+                    let item_id = item_info.id;
+                    let item_rank = match item_info.items_in_set.first() {
+                        Some(item_in_set) => item_in_set.mod_max_rank,
+                        None => {
+                            return Err(AppError::new(
+                                "LiveScraper",
+                                eyre!("Item {} has no items in its set", item),
+                            ))
+                        }
+                    };
+                    self.compare_live_orders_when_selling(
+                        &item,
+                        &item_id,
+                        item_rank,
+                        current_sell_orders_df.clone(),
+                        &item_live_orders_df,
+                        &item_stats,
+                        &stock_items_df,
+                    )
+                    .await?;
+                    return Ok(());
+                }
 
-            let item_stats = popular_items_df
-                .clone()
-                .lazy()
-                .filter(col("name").eq(lit(item.clone())))
-                .collect()
-                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+                // Get the item_id and item_rank
+                let item_id: String = match helper::get_column_value(
+                    popular_items_df.clone(),
+                    Some(col("name").eq(lit(item.clone()))),
+                    "item_id",
+                    ColumnType::String,
+                )? {
+                    ColumnValue::String(values) => values.unwrap_or("".to_string()),
+                    _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
+                };
+
+                let item_rank: Option<f64> = match helper::get_column_value(
+                    popular_items_df.clone(),
+                    Some(col("name").eq(lit(item.clone()))),
+                    "mod_rank",
+                    ColumnType::F64,
+                )? {
+                    ColumnValue::F64(values) => values,
+                    _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
+                };
+
+                let item_stats = popular_items_df
+                    .clone()
+                    .lazy()
+                    .filter(col("name").eq(lit(item.clone())))
+                    .collect()
+                    .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
 
-            if order_mode == OrderMode::Buy || order_mode == OrderMode::Both {
-                self.compare_live_orders_when_buying(
-                    &item,
-                    &item_id,
-                    item_rank,
-                    current_buy_orders_df.clone(),
-                    &item_live_orders_df,
-                    &item_stats,
-                    &stock_items_df,
-                )
-                .await?;
-            }
+                if order_mode == OrderMode::Buy || order_mode == OrderMode::Both {
+                    self.compare_live_orders_when_buying(
+                        &item,
+                        &item_id,
+                        item_rank,
+                        current_buy_orders_df.clone(),
+                        &item_live_orders_df,
+                        &item_stats,
+                        &stock_items_df,
+                    )
+                    .await?;
+                }
 
-            if order_mode == OrderMode::Sell || order_mode == OrderMode::Both {
-                self.compare_live_orders_when_selling(
-                    &item,
-                    &item_id,
-                    item_rank,
-                    current_sell_orders_df.clone(),
-                    &item_live_orders_df,
-                    &item_stats,
-                    &stock_items_df,
-                )
-                .await?;
+                if order_mode == OrderMode::Sell || order_mode == OrderMode::Both {
+                    self.compare_live_orders_when_selling(
+                        &item,
+                        &item_id,
+                        item_rank,
+                        current_sell_orders_df.clone(),
+                        &item_live_orders_df,
+                        &item_stats,
+                        &stock_items_df,
+                    )
+                    .await?;
+                }
+                Ok(())
             }
+            .await;
+            self.client.progress().finish_item(
+                &key,
+                &item,
+                "item",
+                result,
+                &mut failures_in_row,
+            )?;
         }
+        metrics::set(
+            metrics::LIVE_SCRAPER_ITEMS_CHECKED,
+            &[("module", "item")],
+            checked as f64,
+        );
         Ok(())
     }
     fn get_week_increase(&self, df: &DataFrame, row_name: &str) -> Result<f64, AppError> {
@@ -330,7 +385,18 @@ impl<'a> ItemModule<'a> {
             if blacklist.contains(&order.clone().item.unwrap().url_name) {
                 continue;
             }
-            match wfm.orders().delete(&order.id).await {
+            match self
+                .client
+                .dry_run()
+                .delete_order(
+                    &order.clone().item.unwrap().url_name,
+                    &order.id,
+                    order.order_type.as_str(),
+                    Some(order.platinum),
+                    "reset",
+                )
+                .await
+            {
                 Ok(_) => {}
                 Err(e) => {
                     error::create_log_file(self.client.log_file.to_owned(), &e);
@@ -346,13 +412,28 @@ impl<'a> ItemModule<'a> {
     pub async fn get_buy_sell_overlap(&self) -> Result<DataFrame, AppError> {
         let settings = self.client.settings.lock()?.clone().live_scraper;
         let db = self.client.db.lock()?.clone();
-        let df = self.client.price_scraper.lock()?.get_price_historys()?;
-        let volume_threshold = settings.stock_item.volume_threshold;
-        let range_threshold = settings.stock_item.range_threshold;
-        let avg_price_cap = settings.stock_item.avg_price_cap;
-        let price_shift_threshold = settings.stock_item.price_shift_threshold;
-        let strict_whitelist = settings.stock_item.strict_whitelist;
-        let whitelist = settings.stock_item.whitelist.clone();
+        let price_scraper = self.client.price_scraper.lock()?.clone();
+        let df = price_scraper
+            .get_price_historys(settings.stock_item.price_history_days)
+            .await?;
+        // Call the database to get the inventory names
+        let inventory_names = db.stock_item().get_items_names().await?;
+        self.get_buy_sell_overlap_from(&df, &settings.stock_item, inventory_names)
+    }
+
+    // Runs the overlap filter over any price history DataFrame, the backtester uses this with past snapshots.
+    pub fn get_buy_sell_overlap_from(
+        &self,
+        df: &DataFrame,
+        settings: &StockItemSettings,
+        inventory_names: Vec<String>,
+    ) -> Result<DataFrame, AppError> {
+        let volume_threshold = settings.volume_threshold;
+        let range_threshold = settings.range_threshold;
+        let avg_price_cap = settings.avg_price_cap;
+        let price_shift_threshold = settings.price_shift_threshold;
+        let strict_whitelist = settings.strict_whitelist;
+        let whitelist = settings.whitelist.clone();
 
         // Group by the "name" and "order_type" columns, and compute the mean of the other columns
         let averaged_df = df
@@ -372,8 +453,6 @@ impl<'a> ItemModule<'a> {
             ])
             .collect()
             .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
-        // Call the database to get the inventory names and DataFrame
-        let inventory_names = db.stock_item().get_items_names().await?;
         let inventory_names_s = Series::new("desired_column_name", inventory_names);
 
         // Filters the DataFrame based on the given predicates and returns a new DataFrame.
@@ -427,7 +506,7 @@ impl<'a> ItemModule<'a> {
             .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
             .into_iter()
             .filter_map(|opt_name| {
-                opt_name.map(|name| self.get_week_increase(&df, name).unwrap_or(0.0))
+                opt_name.map(|name| self.get_week_increase(df, name).unwrap_or(0.0))
             })
             .collect();
 
@@ -644,6 +723,63 @@ impl<'a> ItemModule<'a> {
         Ok((buy_orders_df, sell_orders_df, buyers, sellers, range))
     }
 
+    fn get_pricing_strategy(&self, item_name: &str) -> Result<Box<dyn PricingStrategy>, AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper.stock_item;
+        Ok(pricing::get_strategy_for_item(&settings, item_name))
+    }
+
+    fn get_pricing_context(
+        &self,
+        item_stats: &DataFrame,
+        live_buy_orders_df: &DataFrame,
+        live_sell_orders_df: &DataFrame,
+        price_range: i64,
+        bought_price: i64,
+    ) -> Result<PricingContext, AppError> {
+        let closed_avg: f64 =
+            match helper::get_column_value(item_stats.clone(), None, "closedAvg", ColumnType::F64)?
+            {
+                ColumnValue::F64(values) => values.unwrap_or(0.0),
+                _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
+            };
+        let closed_median: f64 = match helper::get_column_value(
+            item_stats.clone(),
+            None,
+            "closedMedian",
+            ColumnType::F64,
+        )? {
+            ColumnValue::F64(values) => values.unwrap_or(0.0),
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
+        };
+        // The live order DataFrames are sorted so the best price is first
+        let highest_buy: i64 = match helper::get_column_value(
+            live_buy_orders_df.clone(),
+            None,
+            "platinum",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(values) => values.unwrap_or(0),
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
+        };
+        let lowest_sell: i64 = match helper::get_column_value(
+            live_sell_orders_df.clone(),
+            None,
+            "platinum",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(values) => values.unwrap_or(0),
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
+        };
+        Ok(PricingContext {
+            closed_avg,
+            closed_median,
+            price_range,
+            highest_buy,
+            lowest_sell,
+            bought_price,
+        })
+    }
+
     fn is_item_blacklisted(&self, item_name: &str) -> Result<bool, AppError> {
         let settings = self.client.settings.lock()?.clone().live_scraper;
         let blacklist = settings.stock_item.blacklist.clone();
@@ -717,7 +853,6 @@ impl<'a> ItemModule<'a> {
         }
 
         let settings = self.client.settings.lock()?.clone().live_scraper;
-        let wfm = self.client.wfm.lock()?.clone();
         let mut current_orders = current_orders.clone();
         let avg_price_cap = settings.stock_item.avg_price_cap;
         let max_total_price_cap = settings.stock_item.max_total_price_cap;
@@ -727,7 +862,7 @@ impl<'a> ItemModule<'a> {
             .await?;
 
         // Get all the live orders for the item from the Warframe Market API
-        let (live_buy_orders_df, _live_sell_orders_df, buyers, sellers, price_range) =
+        let (live_buy_orders_df, live_sell_orders_df, buyers, sellers, price_range) =
             self.restructure_live_order_df(item_live_orders_df).await?;
 
         // Probably don't want to be looking at this item right now if there's literally nobody interested in selling it.
@@ -735,18 +870,19 @@ impl<'a> ItemModule<'a> {
             return Ok(None);
         }
 
-        // Get the average price of the item from the Warframe Market API
-        let item_closed_avg: f64 =
-            match helper::get_column_value(item_stats.clone(), None, "closedAvg", ColumnType::F64)?
-            {
-                ColumnValue::F64(values) => values.unwrap_or(0.0),
-                _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
-            };
+        let strategy = self.get_pricing_strategy(item_name)?;
+        let ctx = self.get_pricing_context(
+            item_stats,
+            &live_buy_orders_df,
+            &live_sell_orders_df,
+            price_range,
+            0,
+        )?;
+        let item_closed_avg = ctx.closed_avg;
 
         // If there are no buyers, and the average price is greater than 25p, then we should probably update our listing.
         if buyers == 0 && item_closed_avg > 25.0 {
-            // If the item is worth more than 40p, then we should probably update our listing.
-            let mut post_price = (price_range - 40).max((price_range / 3) - 1);
+            let mut post_price = strategy.buy_price_without_buyers(&ctx);
 
             if post_price > avg_price_cap as i64 {
                 logger::info_con("LiveScraper",format!("Item {item_name} is higher than the price cap you set. cap: {avg_price_cap}, post_price: {post_price}").as_str());
@@ -762,12 +898,17 @@ impl<'a> ItemModule<'a> {
                     "item.buy.updating",
                     Some(json!({ "name": item_name, "price": post_price})),
                 );
-                wfm.orders()
-                    .update(
+                self.client
+                    .dry_run()
+                    .update_order(
+                        item_name,
                         order_id.clone().unwrap().as_str(),
+                        "buy",
                         post_price as i32,
+                        Some(price),
                         1,
                         visibility,
+                        "no_buyers",
                     )
                     .await?;
                 return Ok(None);
@@ -776,8 +917,11 @@ impl<'a> ItemModule<'a> {
                     "item.buy.creating",
                     Some(json!({ "name": item_name, "price": post_price})),
                 );
-                wfm.orders()
-                    .create(item_id, "buy", post_price, 1, true, item_rank)
+                self.client
+                    .dry_run()
+                    .create_order(
+                        item_name, item_id, "buy", post_price, 1, true, item_rank, "no_buyers",
+                    )
                     .await?;
                 logger::info_con("LiveScraper",format!("Automatically Posted Visible Buy Order Item: {item_name}, ItemId: {item_id}, Price: {post_price}").as_str());
                 return Ok(None);
@@ -786,24 +930,9 @@ impl<'a> ItemModule<'a> {
             return Ok(None);
         }
 
-        // Get highest buy order price
-        let post_price: i64 = match helper::get_column_value(
-            live_buy_orders_df.clone(),
-            None,
-            "platinum",
-            ColumnType::I64,
-        )? {
-            ColumnValue::I64(values) => values.unwrap_or(0),
-            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
-        };
+        let post_price = strategy.buy_price(&ctx);
 
-        // Get the average price of the item from the Warframe Market API
-        let closed_avg_metric: f64 =
-            match helper::get_column_value(item_stats.clone(), None, "closedAvg", ColumnType::F64)?
-            {
-                ColumnValue::F64(values) => values.unwrap_or(0.0) - post_price as f64,
-                _ => return Err(AppError::new("LiveScraper", eyre!("Expected f64 values"))),
-            };
+        let closed_avg_metric: f64 = strategy.potential_profit(&ctx, post_price);
         let potential_profit = closed_avg_metric - 1.0;
 
         // Check if the post price is greater than the average price cap and return if it is
@@ -836,8 +965,15 @@ impl<'a> ItemModule<'a> {
                 );
                 self.client
                     .send_message("item.buy.deleting", Some(json!({ "name": item_name})));
-                wfm.orders()
-                    .delete(order_id.clone().unwrap().as_str())
+                self.client
+                    .dry_run()
+                    .delete_order(
+                        item_name,
+                        order_id.clone().unwrap().as_str(),
+                        "buy",
+                        Some(price),
+                        "holding_too_many",
+                    )
                     .await?;
             }
             return Ok(None);
@@ -850,12 +986,17 @@ impl<'a> ItemModule<'a> {
                         "item.buy.updating",
                         Some(json!({ "name": item_name, "price": post_price})),
                     );
-                    wfm.orders()
-                        .update(
+                    self.client
+                        .dry_run()
+                        .update_order(
+                            item_name,
                             order_id.clone().unwrap().as_str(),
+                            "buy",
                             post_price as i32,
+                            Some(price),
                             1,
                             visibility,
+                            "better_price",
                         )
                         .await?;
                     let df = DataFrame::new(vec![
@@ -873,12 +1014,17 @@ impl<'a> ItemModule<'a> {
                         "item.buy.updating",
                         Some(json!({ "name": item_name, "price": post_price})),
                     );
-                    wfm.orders()
-                        .update(
+                    self.client
+                        .dry_run()
+                        .update_order(
+                            item_name,
                             order_id.clone().unwrap().as_str(),
+                            "buy",
                             post_price as i32,
+                            Some(price),
                             1,
                             visibility,
+                            "good_price",
                         )
                         .await?;
                     logger::info_con("LiveScraper", format!("Your current (possibly hidden) posting on this item {item_name} for {price} plat is a good one. Recommend to make visible.").as_str());
@@ -1001,7 +1147,16 @@ impl<'a> ItemModule<'a> {
                                 "item.buy.deleting",
                                 Some(json!({ "name": unselected_item.2})),
                             );
-                            wfm.orders().delete(unselected_item.3.as_str()).await?;
+                            self.client
+                                .dry_run()
+                                .delete_order(
+                                    &unselected_item.2,
+                                    unselected_item.3.as_str(),
+                                    "buy",
+                                    Some(unselected_item.0),
+                                    "not_optimal",
+                                )
+                                .await?;
                             logger::debug_con(
                                 "component",
                                 format!(
@@ -1016,12 +1171,25 @@ impl<'a> ItemModule<'a> {
                         "item.buy.creating",
                         Some(json!({ "name": item_name, "price": post_price})),
                     );
-                    let new_order = wfm
-                        .orders()
-                        .create(item_id, "buy", post_price, 1, true, item_rank)
-                        .await?;
-                    let current_orders =
-                        self.get_new_buy_data(current_orders.clone(), new_order, item_closed_avg)?;
+                    let new_order = match self
+                        .client
+                        .dry_run()
+                        .create_order(
+                            item_name, item_id, "buy", post_price, 1, true, item_rank, "optimal",
+                        )
+                        .await?
+                    {
+                        Some(new_order) => new_order,
+                        // Dry run, nothing was posted
+                        None => return Ok(Some(current_orders)),
+                    };
+                    let new_order_profit = strategy.potential_profit(&ctx, new_order.platinum);
+                    let current_orders = self.get_new_buy_data(
+                        current_orders.clone(),
+                        new_order,
+                        item_closed_avg,
+                        new_order_profit,
+                    )?;
                     return Ok(Some(current_orders));
                 } else {
                     logger::info_con("LiveScraper",format!("Item {item_name} is too expensive or less optimal than current listings").as_str());
@@ -1031,8 +1199,15 @@ impl<'a> ItemModule<'a> {
             logger::info_con("LiveScraper",format!("Item {item_name} Not a good time to have an order up on this item. Deleted buy order for {price}").as_str());
             self.client
                 .send_message("item.buy.deleting", Some(json!({ "name": item_name})));
-            wfm.orders()
-                .delete(order_id.clone().unwrap().as_str())
+            self.client
+                .dry_run()
+                .delete_order(
+                    item_name,
+                    order_id.clone().unwrap().as_str(),
+                    "buy",
+                    Some(price),
+                    "not_good_time",
+                )
                 .await?;
         }
 
@@ -1045,10 +1220,9 @@ impl<'a> ItemModule<'a> {
         item_rank: Option<f64>,
         current_orders: DataFrame,
         item_live_orders_df: &DataFrame,
-        _item_stats: &DataFrame,
+        item_stats: &DataFrame,
         _inventory_df: &DataFrame,
     ) -> Result<(), AppError> {
-        let wfm = self.client.wfm.lock()?.clone();
         let db = self.client.db.lock()?.clone();
 
         // Get the current orders for the item from the Warframe Market API
@@ -1063,19 +1237,18 @@ impl<'a> ItemModule<'a> {
         } else if !inventory_names.contains(&item_name.to_string()) {
             self.client
                 .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
-            db.stock_item()
-                .update_by_url(
-                    item_name,
-                    None,
-                    None,
-                    None,
-                    Some("to_low_profit".to_string()),
-                    None,
-                )
+            self.client
+                .dry_run()
+                .update_stock_item(item_name, None, "to_low_profit")
                 .await?;
-            wfm.orders()
-                .delete(
-                    order_id.clone().unwrap().as_str()
+            self.client
+                .dry_run()
+                .delete_order(
+                    item_name,
+                    order_id.clone().unwrap().as_str(),
+                    "sell",
+                    Some(price),
+                    "not_in_inventory",
                 )
                 .await?;
             logger::info_con(
@@ -1096,7 +1269,7 @@ impl<'a> ItemModule<'a> {
             .unwrap();
 
         // Get all the live orders for the item from the Warframe Market API
-        let (_live_buy_orders_df, live_sell_orders_df, _buyers, sellers, _price_range) =
+        let (live_buy_orders_df, live_sell_orders_df, _buyers, sellers, price_range) =
             self.restructure_live_order_df(item_live_orders_df).await?;
 
         // Get the average price of the item.
@@ -1109,74 +1282,69 @@ impl<'a> ItemModule<'a> {
         // Get the minimum price of the item.
         let minimum_price = stock_item.minium_price;
 
-        // If there are no buyers, update order to be 30p above average price
+        let strategy = self.get_pricing_strategy(item_name)?;
+        let ctx = self.get_pricing_context(
+            item_stats,
+            &live_buy_orders_df,
+            &live_sell_orders_df,
+            price_range,
+            bought_avg_price,
+        )?;
+
+        // If there are no sellers, let the strategy price the order
         if sellers == 0 {
-            let mut post_price = (bought_avg_price + 30) as i64;
+            let mut post_price = strategy.sell_price_without_sellers(&ctx);
             if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
                 post_price = minimum_price.unwrap() as i64;
             }
 
-            db.stock_item()
-                .update_by_url(
-                    item_name,
-                    None,
-                    None,
-                    Some(post_price as i32),
-                    Some("no_buyers".to_string()),
-                    None,
-                )
+            self.client
+                .dry_run()
+                .update_stock_item(item_name, Some(post_price as i32), "no_buyers")
                 .await?;
             if active {
                 self.client
                     .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
-                wfm.orders()
-                    .update(
+                self.client
+                    .dry_run()
+                    .update_order(
+                        item_name,
                         order_id.clone().unwrap().as_str(),
+                        "sell",
                         post_price as i32,
+                        Some(price),
                         quantity as i32,
-                        visibility
+                        visibility,
+                        "no_sellers",
                     )
                     .await?;
                 return Ok(());
             } else {
-                wfm.orders()
-                    .create(
-                         item_id, "sell", post_price, quantity, true, item_rank,
+                self.client
+                    .dry_run()
+                    .create_order(
+                        item_name,
+                        item_id,
+                        "sell",
+                        post_price,
+                        quantity,
+                        true,
+                        item_rank,
+                        "no_sellers",
                     )
                     .await?;
                 return Ok(());
             }
         }
 
-        // Get the platinum values from the DataFrame of live sell orders
-        let post_prices: Vec<i64> = match helper::get_column_values(
-            live_sell_orders_df.clone(),
-            None,
-            "platinum",
-            ColumnType::I64,
-        )? {
-            ColumnValues::I64(values) => values,
-            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
-        };
-
-        // Get lowest buy order price from the DataFrame of live sell orders
-        let mut post_price = post_prices.get(0).unwrap_or(&0).clone();
+        let post_price = strategy.sell_price(&ctx);
 
-        // Get the profit from the current order
-        let profit = post_price - bought_avg_price as i64;
-
-        if profit <= -10 {
+        if post_price.is_none() {
             // Only update the database if the item is not already marked as to_low_profit
             if stock_item.status != "to_low_profit" {
-                db.stock_item()
-                    .update_by_url(
-                        item_name,
-                        None,
-                        None,
-                        Some(-1),
-                        Some("to_low_profit".to_string()),
-                        None,
-                    )
+                self.client
+                    .dry_run()
+                    .update_stock_item(item_name, Some(-1), "to_low_profit")
                     .await?;
             }
             logger::info_con(
@@ -1186,55 +1354,60 @@ impl<'a> ItemModule<'a> {
             if active {
                 self.client
                     .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
-                wfm.orders()
-                    .delete(
-                        order_id.clone().unwrap().as_str()
+                self.client
+                    .dry_run()
+                    .delete_order(
+                        item_name,
+                        order_id.clone().unwrap().as_str(),
+                        "sell",
+                        Some(price),
+                        "to_low_profit",
                     )
                     .await?;
             }
             return Ok(());
         }
 
-        if post_price + 10 > post_price && sellers >= 2 {
-            post_price = (bought_avg_price + 10).max(post_prices.get(0).unwrap_or(&0).clone());
-        } else {
-            post_price = (bought_avg_price + 10).max(post_price);
-        }
+        let mut post_price = post_price.unwrap();
         if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
             post_price = minimum_price.unwrap() as i64;
         }
         if active {
+            // Somebody listed below us, let the user know we followed
+            if post_price < price && ctx.lowest_sell < price {
+                self.notify_undercut(&stock_item.name, price, post_price, ctx.lowest_sell)?;
+            }
             if price != post_price {
                 self.client.send_message(
                     "item.sell.updating",
                     Some(json!({ "name": item_name, "price": post_price})),
                 );
-                wfm.orders()
-                    .update(
+                self.client
+                    .dry_run()
+                    .update_order(
+                        item_name,
                         order_id.clone().unwrap().as_str(),
+                        "sell",
                         post_price as i32,
+                        Some(price),
                         quantity as i32,
-                        visibility
+                        visibility,
+                        "better_price",
                     )
                     .await?;
-                db.stock_item()
-                    .update_by_url(
-                        item_name,
-                        None,
-                        None,
-                        Some(post_price as i32),
-                        Some("live".to_string()),
-                        None,
-                    )
+                self.client
+                    .dry_run()
+                    .update_stock_item(item_name, Some(post_price as i32), "live")
                     .await?;
                 logger::info_con(
                     "LiveScraper",
                     format!(
-                        "Automatically updated order {} for {} from {} to {} plat",
+                        "Automatically updated order {} for {} from {} to {} plat using the {} strategy",
                         order_id.unwrap_or("None".to_string()),
                         item_name,
                         price,
-                        post_price
+                        post_price,
+                        strategy.name()
                     )
                     .as_str(),
                 );
@@ -1248,30 +1421,62 @@ impl<'a> ItemModule<'a> {
                 "item.sell.creating",
                 Some(json!({ "name": item_name, "price": post_price})),
             );
-            wfm.orders()
-                .create(
-                     item_id, "sell", post_price, quantity, true, item_rank,
+            self.client
+                .dry_run()
+                .create_order(
+                    item_name, item_id, "sell", post_price, quantity, true, item_rank, "live",
                 )
                 .await?;
-            db.stock_item()
-                .update_by_url(
-                    item_name,
-                    None,
-                    None,
-                    Some(post_price as i32),
-                    Some("live".to_string()),
-                    None,
-                )
+            self.client
+                .dry_run()
+                .update_stock_item(item_name, Some(post_price as i32), "live")
                 .await?;
             logger::info_con("LiveScraper",format!("Automatically Posted Visible Sell Order Item: {item_name}, ItemId: {item_id}, Price: {post_price}").as_str());
         }
         Ok(())
     }
+    fn notify_undercut(
+        &self,
+        item_name: &str,
+        old_price: i64,
+        new_price: i64,
+        lowest_sell: i64,
+    ) -> Result<(), AppError> {
+        let settings = self.client.settings.lock()?.clone();
+        let notification = &settings.notifications.on_order_undercut;
+        if !notification.is_enabled() {
+            return Ok(());
+        }
+        let mh = self.client.mh.lock()?.clone();
+        let fields = vec![
+            EmbedField::new("Item", item_name, false),
+            EmbedField::new("Old Price", &format!("{}p", old_price), true),
+            EmbedField::new("Lowest Seller", &format!("{}p", lowest_sell), true),
+            EmbedField::new("New Price", &format!("{}p", new_price), true),
+        ];
+        let values = HashMap::from([
+            ("ITEM_NAME", item_name.to_string()),
+            ("OLD_PRICE", old_price.to_string()),
+            ("LOWEST_PRICE", lowest_sell.to_string()),
+            ("NEW_PRICE", new_price.to_string()),
+        ]);
+        notify(
+            &mh,
+            &settings,
+            notification,
+            NotificationKind::OrderUndercut,
+            &values,
+            fields,
+            None,
+        );
+        Ok(())
+    }
     fn get_new_buy_data(
         &self,
         mut current_orders: DataFrame,
         order: Order,
         item_closed_avg: f64,
+        potential_profit: f64,
     ) -> Result<DataFrame, AppError> {
         let mut order_df = self
             .client
@@ -1282,7 +1487,7 @@ impl<'a> ItemModule<'a> {
         order_df = order_df
             .with_column(Series::new(
                 "potential_profit",
-                vec![item_closed_avg - order.platinum.clone() as f64],
+                vec![potential_profit],
             ))
             .cloned()
             .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
@@ -1293,3 +1498,153 @@ impl<'a> ItemModule<'a> {
         Ok(helper::merge_dataframes(vec![current_orders, order_df])?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::Ordering, Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        auth::AuthState,
+        cache::client::CacheClient,
+        database::client::DBClient,
+        handler::MonitorHandler,
+        live_scraper::modules::progress::LoopProgress,
+        price_scraper::PriceScraper,
+        profiles,
+        settings::SettingsState,
+        wfm_client::{client::WFMClient, mock_server::MockServer},
+    };
+
+    // A dry running scraper against the mock server, with its own profile and database
+    async fn client(server: &MockServer, settings: SettingsState) -> LiveScraperClient {
+        let settings = Arc::new(Mutex::new(settings));
+        let auth = Arc::new(Mutex::new(AuthState {
+            ingame_name: "MockUser".to_string(),
+            ..AuthState::default()
+        }));
+        let mut wfm = WFMClient::new(Arc::clone(&auth), Arc::clone(&settings));
+        wfm.set_endpoint(&server.endpoint());
+        let wfm = Arc::new(Mutex::new(wfm));
+        let cache = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm))));
+        let path = std::env::temp_dir().join(format!("quantframe_item_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        let db = DBClient::open(cache, Arc::clone(&wfm), path).await.unwrap();
+        db.initialize().await.unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let price_scraper = Arc::new(Mutex::new(PriceScraper::new(
+            Arc::clone(&wfm),
+            Arc::clone(&auth),
+            Arc::clone(&db),
+        )));
+        let mut client = LiveScraperClient::new(
+            settings,
+            price_scraper,
+            wfm,
+            auth,
+            db,
+            Arc::new(Mutex::new(MonitorHandler::headless())),
+        );
+        // Keeps the progress of the test out of the real profile
+        client.profile = format!("test_item_{}", std::process::id());
+        client.progress = Arc::new(Mutex::new(LoopProgress::default()));
+        client.is_running.store(true, Ordering::SeqCst);
+        client
+    }
+
+    // Two days of nova_prime_set trading around 60p, with buyers at 30p and sellers at 60p
+    fn price_history() -> DataFrame {
+        let rows = [
+            ("2024-01-01", "closed", 40.0, 80.0, 60.0),
+            ("2024-01-01", "buy", 30.0, 30.0, 30.0),
+            ("2024-01-01", "sell", 60.0, 60.0, 60.0),
+            ("2024-01-02", "closed", 40.0, 80.0, 60.0),
+            ("2024-01-02", "buy", 30.0, 30.0, 30.0),
+            ("2024-01-02", "sell", 60.0, 60.0, 60.0),
+        ];
+        DataFrame::new(vec![
+            Series::new("name", vec!["nova_prime_set"; rows.len()]),
+            Series::new("item_id", vec!["nova_id"; rows.len()]),
+            Series::new("datetime", rows.iter().map(|r| r.0).collect::<Vec<_>>()),
+            Series::new("order_type", rows.iter().map(|r| r.1).collect::<Vec<_>>()),
+            Series::new("mod_rank", vec![None::<i64>; rows.len()]),
+            Series::new("volume", vec![Some(100_i64); rows.len()]),
+            Series::new("min_price", rows.iter().map(|r| r.2).collect::<Vec<_>>()),
+            Series::new("max_price", rows.iter().map(|r| r.3).collect::<Vec<_>>()),
+            Series::new("avg_price", rows.iter().map(|r| r.4).collect::<Vec<_>>()),
+            Series::new("median", rows.iter().map(|r| r.4).collect::<Vec<_>>()),
+        ])
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn check_stock_posts_a_buy_order_in_dry_run() {
+        let server = MockServer::start().await.unwrap();
+        {
+            let mut state = server.state.lock().unwrap();
+            state.add_item("nova_id", "nova_prime_set", "Nova Prime Set", None);
+            state.add_live_order("nova_prime_set", "Buyer", "buy", 30, 1);
+            state.add_live_order("nova_prime_set", "Seller", "sell", 60, 1);
+        }
+        let mut settings = SettingsState::default();
+        settings.live_scraper.dry_run = true;
+        settings.live_scraper.stock_item.order_mode = OrderMode::Buy;
+        settings.live_scraper.stock_item.volume_threshold = 0;
+        settings.live_scraper.stock_item.range_threshold = 0;
+        settings.live_scraper.stock_item.avg_price_cap = 1000;
+        settings.live_scraper.stock_item.max_total_price_cap = 1000;
+        settings.live_scraper.stock_item.price_shift_threshold = -1000;
+        settings.live_scraper.stock_item.strict_whitelist = false;
+        settings.live_scraper.stock_item.whitelist = vec![];
+        settings.live_scraper.stock_item.blacklist = vec![];
+        let client = client(&server, settings).await;
+        let db = client.db.lock().unwrap().clone();
+        db.price_history()
+            .upsert_dataframe(&price_history())
+            .await
+            .unwrap();
+        // A sell order of ours, buy mode takes it down
+        let wfm = client.wfm.lock().unwrap().clone();
+        let sell_order = wfm
+            .orders()
+            .create("nova_id", "sell", 100, 1, true, None)
+            .await
+            .unwrap();
+
+        client.item().check_stock().await.unwrap();
+
+        let entries: Vec<(String, String, Option<String>, Option<i64>, String)> = client
+            .dry_run()
+            .get_entries()
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.action, e.name, e.order_type, e.price, e.reason))
+            .collect();
+        let entry = |action: &str, order_type: &str, price: Option<i64>, reason: &str| {
+            (
+                action.to_string(),
+                "nova_prime_set".to_string(),
+                Some(order_type.to_string()),
+                price,
+                reason.to_string(),
+            )
+        };
+        assert_eq!(
+            entries,
+            vec![
+                entry("delete", "sell", None, "order_mode"),
+                entry("create", "buy", Some(30), "optimal"),
+            ]
+        );
+        // Nothing was sent to the market
+        let orders = wfm.orders().get_my_orders().await.unwrap();
+        assert_eq!(orders.sell_orders.len(), 1);
+        assert_eq!(orders.sell_orders[0].id, sell_order.id);
+        assert!(orders.buy_orders.is_empty());
+
+        server.stop();
+        if let Ok(path) = profiles::get_data_path(&client.profile) {
+            let _ = std::fs::remove_dir_all(path);
+        }
+    }
+}
@@ -1,3 +1,8 @@
+pub mod backtest;
+pub mod dry_run;
 pub mod helper;
 pub mod item;
-pub mod riven;
\ No newline at end of file
+pub mod progress;
+pub mod riven;
+pub mod sniper;
+pub mod valuation;
\ No newline at end of file
@@ -0,0 +1,178 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    enums::LogLevel,
+    error::{self, AppError},
+    live_scraper::client::LiveScraperClient,
+    logger, profiles,
+};
+
+// Failing items in a row before the cycle is given up, more likely a wider problem than a bad item
+const MAX_FAILURES_IN_ROW: u32 = 3;
+const QUARANTINE_MINUTES: i64 = 15;
+const MAX_QUARANTINE_MINUTES: i64 = 24 * 60;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub name: String,
+    pub failures: u32,
+    // RFC 3339
+    pub until: String,
+    pub error: String,
+}
+
+// Saved after every item so a restart continues the cycle instead of starting over
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LoopProgress {
+    // Still true after a crash or when the app was closed with the loop running
+    pub running: bool,
+    pub cycle_started: Option<String>,
+    // Keys of the items and rivens done in the current cycle
+    pub checked: Vec<String>,
+    pub quarantine: HashMap<String, QuarantineEntry>,
+}
+
+impl LoopProgress {
+    fn get_file_path(profile: &str) -> Result<PathBuf, AppError> {
+        Ok(profiles::get_data_path(profile)?.join("live_scraper_progress.json"))
+    }
+    // A missing or broken file just means there is nothing to resume
+    pub fn read_from_file(profile: &str) -> LoopProgress {
+        Self::get_file_path(profile)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+    fn save_to_file(&self, profile: &str) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        fs::write(Self::get_file_path(profile)?, json)
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        Ok(())
+    }
+}
+
+pub struct ProgressModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> ProgressModule<'a> {
+    fn update(&self, apply: impl FnOnce(&mut LoopProgress)) -> Result<(), AppError> {
+        let mut progress = self.client.progress.lock()?;
+        apply(&mut progress);
+        progress.save_to_file(&self.client.profile)
+    }
+
+    pub fn get(&self) -> Result<LoopProgress, AppError> {
+        Ok(self.client.progress.lock()?.clone())
+    }
+
+    pub fn set_running(&self, running: bool) -> Result<(), AppError> {
+        self.update(|progress| progress.running = running)
+    }
+
+    // Forgets the previous run, its checked items and quarantine
+    pub fn clear(&self) -> Result<(), AppError> {
+        self.update(|progress| *progress = LoopProgress::default())
+    }
+
+    pub fn start_cycle(&self) -> Result<(), AppError> {
+        self.update(|progress| {
+            if progress.cycle_started.is_none() {
+                progress.cycle_started = Some(Utc::now().to_rfc3339());
+            }
+        })
+    }
+
+    pub fn finish_cycle(&self) -> Result<(), AppError> {
+        self.update(|progress| {
+            progress.cycle_started = None;
+            progress.checked.clear();
+        })
+    }
+
+    // Done earlier in this cycle or still in quarantine
+    pub fn should_skip(&self, key: &str) -> Result<bool, AppError> {
+        let progress = self.client.progress.lock()?;
+        let quarantined = progress
+            .quarantine
+            .get(key)
+            .and_then(|entry| DateTime::parse_from_rfc3339(&entry.until).ok())
+            .map(|until| until > Utc::now())
+            .unwrap_or(false);
+        Ok(quarantined || progress.checked.iter().any(|k| k == key))
+    }
+
+    // Transient errors end the cycle so the loop can back off, anything else only affects this item.
+    pub fn finish_item(
+        &self,
+        key: &str,
+        name: &str,
+        module: &str,
+        result: Result<(), AppError>,
+        failures_in_row: &mut u32,
+    ) -> Result<(), AppError> {
+        let error = match result {
+            Ok(_) => {
+                *failures_in_row = 0;
+                return self.update(|progress| {
+                    progress.quarantine.remove(key);
+                    progress.checked.push(key.to_string());
+                });
+            }
+            Err(e) => e,
+        };
+        let log_level = error.log_level();
+        if log_level != LogLevel::Critical && log_level != LogLevel::Error {
+            return Err(error);
+        }
+        *failures_in_row += 1;
+        if *failures_in_row >= MAX_FAILURES_IN_ROW {
+            return Err(error);
+        }
+
+        error::create_log_file(self.client.log_file.clone(), &error);
+        let mut minutes = QUARANTINE_MINUTES;
+        self.update(|progress| {
+            let failures = progress
+                .quarantine
+                .get(key)
+                .map(|entry| entry.failures)
+                .unwrap_or(0)
+                + 1;
+            // Every failure in a row doubles the time the item is left alone
+            minutes = (QUARANTINE_MINUTES << (failures - 1).min(10)).min(MAX_QUARANTINE_MINUTES);
+            progress.quarantine.insert(
+                key.to_string(),
+                QuarantineEntry {
+                    name: name.to_string(),
+                    failures,
+                    until: (Utc::now() + Duration::minutes(minutes)).to_rfc3339(),
+                    error: error.cause(),
+                },
+            );
+            progress.checked.push(key.to_string());
+        })?;
+        logger::warning_con(
+            "LiveScraper",
+            format!(
+                "Skipping {} for {} minutes: {}",
+                name,
+                minutes,
+                error.cause()
+            )
+            .as_str(),
+        );
+        self.client.send_message(
+            &format!("{}.quarantined", module),
+            Some(json!({ "name": name, "minutes": minutes })),
+        );
+        Ok(())
+    }
+}
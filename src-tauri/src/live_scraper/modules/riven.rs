@@ -2,7 +2,10 @@ use reqwest::header::HeaderMap;
 use serde_json::json;
 
 use crate::{
-    auth::AuthState, error::AppError, live_scraper::client::LiveScraperClient, logger,
+    auth::AuthState,
+    error::{self, AppError},
+    live_scraper::client::LiveScraperClient,
+    logger, metrics,
     structs::AuctionItem,
 };
 pub struct RivenModule<'a> {
@@ -22,276 +25,327 @@ impl<'a> RivenModule<'a> {
             .iter()
             .filter(|a| a.item.item_type == "riven".to_string())
             .collect::<Vec<_>>();
+        if let Err(e) = self.client.valuation().prune().await {
+            error::create_log_file(self.client.log_file.clone(), &e);
+        }
         let mut current_index = stockrivens.len();
         let total = stockrivens.len();
+        let mut checked = 0;
+        let mut failures_in_row = 0;
         for riven in stockrivens {
             current_index -= 1;
-            self.client.send_message("riven.checking", Some(json!({ "name": riven.weapon_name, "count": current_index, "total": total})));
+            self.client.send_message(
+                "riven.checking",
+                Some(json!({ "name": riven.weapon_name, "count": current_index, "total": total})),
+            );
             // Check if client is running
             if self.client.is_running() == false {
                 break;
             }
+            let key = format!("riven:{}", riven.id);
+            if self.client.progress().should_skip(&key)? {
+                continue;
+            }
+            checked += 1;
+            let name = format!("{} {}", riven.weapon_name, riven.mod_name);
 
-            // Find my auction for this riven if exists
-            let auction = my_rivens
-                .iter()
-                .find(|a| a.id == riven.order_id.clone().unwrap_or("".to_string()));
+            // One bad riven is quarantined instead of ending the cycle
+            let result: Result<(), AppError> = async {
+                // Find my auction for this riven if exists
+                let auction = my_rivens
+                    .iter()
+                    .find(|a| a.id == riven.order_id.clone().unwrap_or("".to_string()));
 
-            // Check if riven is private
-            if riven.private {
-                // Update Auction on warframe.market
-                if auction.is_some() {
-                    let auction = auction.unwrap();
-                    self.client
-                        .send_message("riven.deleting", Some(json!({ "name": riven.weapon_url})));
-                    wfm.auction().delete(auction.id.as_str()).await?;
-                }
+                // Check if riven is private
+                if riven.private {
+                    // Update Auction on warframe.market
+                    if auction.is_some() {
+                        let auction = auction.unwrap();
+                        self.client.send_message(
+                            "riven.deleting",
+                            Some(json!({ "name": riven.weapon_url})),
+                        );
+                        self.client
+                            .dry_run()
+                            .delete_auction(
+                                &riven.weapon_url,
+                                auction.id.as_str(),
+                                Some(auction.starting_price),
+                                "private",
+                            )
+                            .await?;
+                    }
 
-                // Update database status to inactive
-                if riven.status != "inactive" {
-                    db.stock_riven()
-                        .update_by_id(
-                            riven.id,
-                            Some("".to_string()),
-                            None,
-                            None,
-                            None,
-                            None,
-                            None,
-                            None,
-                            Some("inactive".to_string()),
-                            None,
-                        )
-                        .await?;
+                    // Update database status to inactive
+                    if riven.status != "inactive" {
+                        self.client
+                            .dry_run()
+                            .update_stock_riven(riven.id, Some("".to_string()), None, "inactive")
+                            .await?;
+                    }
+                    return Ok(());
                 }
-                continue;
-            }
-
-            // Find Positive stats
-            let positive_stats = riven
-                .attributes
-                .iter()
-                .cloned()
-                .filter(|a| a.positive && a.match_type.unwrap_or(false))
-                .collect::<Vec<_>>();
 
-            // Find Negative stats
-            let negative_stats = riven
-                .attributes
-                .iter()
-                .cloned()
-                .filter(|a| !a.positive && a.match_type.unwrap_or(false))
-                .collect::<Vec<_>>();
+                // Auctions for the fair value, without them the estimate is only less certain
+                if let Err(e) = self.client.valuation().collect(&riven).await {
+                    error::create_log_file(self.client.log_file.clone(), &e);
+                }
 
-            // Match Rerolls
-            let mut min_rerolls: Option<u8> = None;
-            let mut max_rerolls: Option<u8> = None;
-            if riven.match_riven.re_rolls.is_some() {
-                let re_rolls = riven.match_riven.re_rolls.clone().unwrap();
-                min_rerolls = Some(re_rolls.min as u8);
-                max_rerolls = Some(re_rolls.max as u8);
-            }
+                // Find Positive stats
+                let positive_stats = riven
+                    .attributes
+                    .iter()
+                    .cloned()
+                    .filter(|a| a.positive && a.match_type.unwrap_or(false))
+                    .collect::<Vec<_>>();
 
-            // Search for live auctions for this riven
-            self.client
-                .send_message("riven.searching", Some(json!({ "name": riven.weapon_url})));
-            let live_auctions = wfm
-                .auction()
-                .search(
-                    "riven",
-                    &riven.weapon_url,
-                    Some(positive_stats.clone()),
-                    negative_stats.get(0).cloned(),
-                    None,
-                    None,
-                    None,
-                    min_rerolls,
-                    max_rerolls,
-                    Some("direct"),
-                    Some("price_asc"),
-                )
-                .await?;
+                // Find Negative stats
+                let negative_stats = riven
+                    .attributes
+                    .iter()
+                    .cloned()
+                    .filter(|a| !a.positive && a.match_type.unwrap_or(false))
+                    .collect::<Vec<_>>();
 
-            // Filter auctions that are not mine and are not closed and are player is ingame
-            let live_auctions = live_auctions
-                .iter()
-                .filter(|a| {
-                    a.owner.ingame_name != auth.ingame_name
-                        && a.closed == false
-                        && a.visible
-                        && a.owner.status == "ingame"
-                })
-                .collect::<Vec<_>>();
+                // Match Rerolls
+                let mut min_rerolls: Option<u8> = None;
+                let mut max_rerolls: Option<u8> = None;
+                if riven.match_riven.re_rolls.is_some() {
+                    let re_rolls = riven.match_riven.re_rolls.clone().unwrap();
+                    min_rerolls = Some(re_rolls.min as u8);
+                    max_rerolls = Some(re_rolls.max as u8);
+                }
 
-            // Check if there are no live auctions continue to next riven if there is no live auctions
-            if live_auctions.len() == 0 {
-                logger::info_con(
-                    "RivenModule",
-                    format!("No live auctions for {}", riven.weapon_url).as_str(),
-                );
+                // Search for live auctions for this riven
                 self.client
-                    .send_message("riven.no_offers", Some(json!({ "name": riven.weapon_url})));
-                db.stock_riven()
-                    .update_by_id(
-                        riven.id,
-                        None,
-                        None,
-                        None,
-                        None,
-                        None,
+                    .send_message("riven.searching", Some(json!({ "name": riven.weapon_url})));
+                let live_auctions = wfm
+                    .auction()
+                    .search(
+                        "riven",
+                        &riven.weapon_url,
+                        Some(positive_stats.clone()),
+                        negative_stats.get(0).cloned(),
                         None,
                         None,
-                        Some("no_offers".to_string()),
                         None,
+                        min_rerolls,
+                        max_rerolls,
+                        Some("direct"),
+                        Some("price_asc"),
                     )
                     .await?;
-                continue;
-            }
 
-            // Get the minimum price of the riven.
-            let minimum_price = riven.minium_price;
+                // Filter auctions that are not mine and are not closed and are player is ingame
+                let live_auctions = live_auctions
+                    .iter()
+                    .filter(|a| {
+                        a.owner.ingame_name != auth.ingame_name
+                            && a.closed == false
+                            && a.visible
+                            && a.owner.status == "ingame"
+                    })
+                    .collect::<Vec<_>>();
 
-            // Get the lowest price
-            let lowest_price = live_auctions.get(0).unwrap().starting_price;
+                // Value the riven against the auctions we collected
+                let lowest_price = live_auctions.get(0).map(|a| a.starting_price);
+                let valuation = self
+                    .client
+                    .valuation()
+                    .estimate(&riven, lowest_price)
+                    .await?;
+                let fair_value_confidence = valuation.fair_value.map(|_| valuation.confidence);
+                if riven.lowest_price.map(|p| p as i64) != lowest_price
+                    || riven.fair_value.map(|p| p as i64) != valuation.fair_value
+                    || riven.fair_value_confidence != fair_value_confidence
+                {
+                    db.stock_riven()
+                        .update_valuation(
+                            riven.id,
+                            lowest_price.map(|p| p as i32),
+                            valuation.fair_value.map(|p| p as i32),
+                            fair_value_confidence,
+                        )
+                        .await?;
+                }
+                self.client.send_message(
+                    "riven.valuation",
+                    Some(json!({
+                        "name": riven.weapon_url,
+                        "lowest_price": lowest_price.unwrap_or(0),
+                        "fair_value": valuation.fair_value.unwrap_or(0),
+                        "confidence": (valuation.confidence * 100.0).round(),
+                    })),
+                );
 
-            // The new price of the riven
-            let mut post_price = lowest_price;
+                // Check if there are no live auctions continue to next riven if there is no live auctions
+                if live_auctions.len() == 0 {
+                    logger::info_con(
+                        "RivenModule",
+                        format!("No live auctions for {}", riven.weapon_url).as_str(),
+                    );
+                    self.client
+                        .send_message("riven.no_offers", Some(json!({ "name": riven.weapon_url})));
+                    self.client
+                        .dry_run()
+                        .update_stock_riven(riven.id, None, None, "no_offers")
+                        .await?;
+                    return Ok(());
+                }
 
-            // Check if the rivens price is lower than the minimum price
-            if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
-                post_price = minimum_price.unwrap() as i64;
-            }
+                // Get the minimum price of the riven.
+                let minimum_price = riven.minium_price;
 
-            // Calculate profit of the riven
-            let profit = post_price as f64 - riven.price;
+                // Get the lowest price
+                let lowest_price = live_auctions.get(0).unwrap().starting_price;
 
-            // Print lowest price and profit
-            logger::debug_con(
-                "RivenModule",
-                format!(
-                    "Lowest price for {} is {} and yours is {} and profit is {}",
-                    riven.weapon_url, lowest_price, riven.price, profit
-                )
-                .as_str(),
-            );
+                // The new price of the riven
+                let mut post_price = lowest_price;
 
-            // Check if profit is greater than the range threshold
-            let mut order_id: Option<String> = None;
-            if profit > settings.stock_riven.range_threshold as f64 {
-                // If profit is greater than the range threshold
-                match auction {
-                    Some(auction) => {
-                        if auction.starting_price != post_price as i64 {
-                            // Update auction
+                // A good roll is worth more than the cheapest auction with the same stats
+                if settings.stock_riven.use_fair_value
+                    && valuation.confidence >= settings.stock_riven.fair_value_confidence
+                {
+                    if let Some(fair_value) = valuation.fair_value {
+                        post_price = post_price.max(fair_value);
+                    }
+                }
+
+                // Check if the rivens price is lower than the minimum price
+                if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
+                    post_price = minimum_price.unwrap() as i64;
+                }
+
+                // Calculate profit of the riven
+                let profit = post_price as f64 - riven.price;
+
+                // Print lowest price and profit
+                logger::debug_con(
+                    "RivenModule",
+                    format!(
+                        "Lowest price for {} is {}, fair value is {:?} ({}) and yours is {} and profit is {}",
+                        riven.weapon_url,
+                        lowest_price,
+                        valuation.fair_value,
+                        valuation.confidence,
+                        riven.price,
+                        profit
+                    )
+                    .as_str(),
+                );
+
+                // Check if profit is greater than the range threshold
+                let mut order_id: Option<String> = None;
+                if profit > settings.stock_riven.range_threshold as f64 {
+                    // If profit is greater than the range threshold
+                    match auction {
+                        Some(auction) => {
+                            if auction.starting_price != post_price as i64 {
+                                // Update auction
+                                self.client.send_message(
+                                    "riven.updating",
+                                    Some(json!({ "name": riven.weapon_url, "price": post_price})),
+                                );
+                                self.client
+                                    .dry_run()
+                                    .update_auction(
+                                        &riven.weapon_url,
+                                        auction.id.as_str(),
+                                        post_price as i32,
+                                        Some(auction.starting_price),
+                                        true,
+                                        "better_price",
+                                    )
+                                    .await?;
+                            }
+                        }
+                        None => {
+                            // Post auction on warframe.market
                             self.client.send_message(
-                                "riven.updating",
+                                "riven.creating",
                                 Some(json!({ "name": riven.weapon_url, "price": post_price})),
                             );
-                            wfm.auction()
-                                .update(
-                                    auction.id.as_str(),
-                                    post_price as i32,
-                                    0,
-                                    "",
-                                    post_price as i32,
-                                    true,
+                            let new_aut = self
+                                .client
+                                .dry_run()
+                                .create_auction(
+                                    &riven.weapon_url,
+                                    post_price,
+                                    post_price,
+                                    AuctionItem {
+                                        item_type: "riven".to_string(),
+                                        weapon_url_name: Some(riven.weapon_url.clone()),
+                                        re_rolls: Some(riven.re_rolls as i64),
+                                        attributes: Some(riven.attributes.0.clone()),
+                                        name: Some(riven.mod_name),
+                                        mod_rank: Some(riven.rank as i64),
+                                        polarity: Some(riven.polarity),
+                                        mastery_level: Some(riven.mastery_rank as i64),
+                                        element: None,
+                                        quirk: None,
+                                        having_ephemera: None,
+                                        damage: None,
+                                    },
+                                    "live",
                                 )
                                 .await?;
+                            order_id = new_aut.map(|a| a.id);
                         }
                     }
-                    None => {
-                        // Post auction on warframe.market
-                        self.client.send_message(
-                            "riven.creating",
-                            Some(json!({ "name": riven.weapon_url, "price": post_price})),
-                        );
-                        let new_aut = wfm
-                            .auction()
-                            .create(
-                                "riven",
-                                "",
-                                post_price,
-                                post_price,
-                                0,
-                                1,
-                                false,
-                                AuctionItem {
-                                    item_type: "riven".to_string(),
-                                    weapon_url_name: Some(riven.weapon_url.clone()),
-                                    re_rolls: Some(riven.re_rolls as i64),
-                                    attributes: Some(riven.attributes.0.clone()),
-                                    name: Some(riven.mod_name),
-                                    mod_rank: Some(riven.rank as i64),
-                                    polarity: Some(riven.polarity),
-                                    mastery_level: Some(riven.mastery_rank as i64),
-                                    element: None,
-                                    quirk: None,
-                                    having_ephemera: None,
-                                    damage: None,
-                                },
-                            )
+                    // Update database status to live
+                    if post_price != riven.listed_price.unwrap_or(0) as i64
+                        || riven.status != "live"
+                        || order_id.is_some()
+                    {
+                        self.client
+                            .dry_run()
+                            .update_stock_riven(riven.id, order_id, Some(post_price as i32), "live")
                             .await?;
-                        order_id = Some(new_aut.id);
                     }
-                }
-                // Update database status to live
-                if post_price != riven.listed_price.unwrap_or(0) as i64
-                    || riven.status != "live"
-                    || order_id.is_some()
-                {
-                    db.stock_riven()
-                        .update_by_id(
-                            riven.id,
-                            order_id,
-                            None,
-                            Some(post_price as i32),
-                            None,
-                            None,
-                            None,
-                            None,
-                            Some("live".to_string()),
-                            None,
-                        )
-                        .await?;
-                }
-            } else {
-                // If profit is not greater than the range threshold
-                match auction {
-                    Some(auction) => {
-                        if auction.visible {
-                            // Update auction
-                            wfm.auction()
-                                .update(
-                                    auction.id.as_str(),
-                                    (riven.price + 50.0) as i32,
-                                    0,
-                                    "",
-                                    (riven.price + 50.0) as i32,
-                                    false,
-                                )
-                                .await?;
+                } else {
+                    // If profit is not greater than the range threshold
+                    match auction {
+                        Some(auction) => {
+                            if auction.visible {
+                                // Update auction
+                                self.client
+                                    .dry_run()
+                                    .update_auction(
+                                        &riven.weapon_url,
+                                        auction.id.as_str(),
+                                        (riven.price + 50.0) as i32,
+                                        Some(auction.starting_price),
+                                        false,
+                                        "to_low_profit",
+                                    )
+                                    .await?;
+                            }
                         }
+                        None => {}
+                    }
+                    if riven.listed_price.is_some() || riven.status != "to_low_profit" {
+                        self.client
+                            .dry_run()
+                            .update_stock_riven(riven.id, None, None, "to_low_profit")
+                            .await?;
                     }
-                    None => {}
-                }
-                if riven.listed_price.is_some() || riven.status != "to_low_profit" {
-                    db.stock_riven()
-                        .update_by_id(
-                            riven.id,
-                            None,
-                            None,
-                            None,
-                            None,
-                            None,
-                            None,
-                            None,
-                            Some("to_low_profit".to_string()),
-                            None,
-                        )
-                        .await?;
                 }
+                Ok(())
             }
+            .await;
+            self.client.progress().finish_item(
+                &key,
+                &name,
+                "riven",
+                result,
+                &mut failures_in_row,
+            )?;
         }
+        metrics::set(
+            metrics::LIVE_SCRAPER_ITEMS_CHECKED,
+            &[("module", "riven")],
+            checked as f64,
+        );
         Ok(())
     }
 }
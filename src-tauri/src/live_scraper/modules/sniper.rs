@@ -0,0 +1,479 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::{
+    database::modules::riven_snapshot::RivenSnapshotStruct,
+    enums::SnipeTargetType,
+    error::{self, AppError},
+    live_scraper::{
+        client::LiveScraperClient,
+        valuation::{self, RivenRoll},
+    },
+    logger,
+    notification::{notify, wfm_thumbnail, EmbedField, NotificationKind},
+    settings::RivenWatchSettings,
+    structs::{Auction, AuctionOwner, RivenAttribute, RivenTypeInfo},
+};
+
+// Other listings needed before their median is used as the recent price
+const MIN_MEDIAN_LISTINGS: usize = 3;
+
+// Searches the riven watchlist for auctions listed below their target.
+pub struct SniperModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> SniperModule<'a> {
+    // Runs the watchlist once the interval has passed since the last run
+    pub async fn check_watchlist(&self) -> Result<(), AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let sniper = &settings.riven_sniper;
+        if sniper.watchlist.is_empty() {
+            return Ok(());
+        }
+        let interval = Duration::from_secs(sniper.interval.max(1) as u64 * 60);
+        {
+            let mut last_run = self.client.last_snipe.lock()?;
+            if last_run.map(|t| t.elapsed() < interval).unwrap_or(false) {
+                return Ok(());
+            }
+            *last_run = Some(Instant::now());
+        }
+        logger::info_con("SniperModule", "Run riven sniper");
+        let total = sniper.watchlist.len();
+        let mut seen = HashSet::new();
+        let mut complete = true;
+        for (index, watch) in sniper.watchlist.iter().enumerate() {
+            if !self.client.is_running() {
+                complete = false;
+                break;
+            }
+            self.client.send_message(
+                "riven_sniper.searching",
+                Some(json!({ "name": watch.weapon_url, "count": index + 1, "total": total })),
+            );
+            // One bad entry doesn't stop the rest of the watchlist
+            match self
+                .check_watch(watch, settings.stock_riven.fair_value_confidence)
+                .await
+            {
+                Ok(ids) => seen.extend(ids),
+                Err(e) => {
+                    complete = false;
+                    error::create_log_file(self.client.log_file.clone(), &e);
+                }
+            }
+        }
+        // Auctions that are gone won't come back, the rest are kept until every search went through
+        if complete {
+            self.client
+                .sniped_auctions
+                .lock()?
+                .retain(|id, _| seen.contains(id));
+        }
+        Ok(())
+    }
+
+    // Returns the ids of the auctions the search found
+    async fn check_watch(
+        &self,
+        watch: &RivenWatchSettings,
+        min_confidence: f64,
+    ) -> Result<Vec<String>, AppError> {
+        if let SnipeTargetType::Unknown(_) = watch.target {
+            logger::warning_con(
+                "SniperModule",
+                format!(
+                    "Unknown target {} for {}",
+                    watch.target.as_str(),
+                    watch.weapon_url
+                )
+                .as_str(),
+            );
+            return Ok(vec![]);
+        }
+        let db = self.client.db.lock()?.clone();
+        let wfm = self.client.wfm.lock()?.clone();
+        let auth = self.client.auth.lock()?.clone();
+        let cache = db.cache.lock()?.clone();
+        let riven_type = match cache.riven().find_type(&watch.weapon_url)? {
+            Some(riven_type) => riven_type,
+            None => return Ok(vec![]),
+        };
+        let weapon_type = riven_type
+            .riven_type
+            .clone()
+            .unwrap_or("Unknown".to_string());
+
+        let positive_stats = watch
+            .positive_stats
+            .iter()
+            .filter(|s| !s.is_empty())
+            .map(|url_name| RivenAttribute {
+                positive: true,
+                value: 0.0,
+                url_name: url_name.clone(),
+                match_type: Some(true),
+            })
+            .collect::<Vec<_>>();
+        let negative_stat = watch
+            .negative_stat
+            .clone()
+            .filter(|s| !s.is_empty())
+            .map(|url_name| RivenAttribute {
+                positive: false,
+                value: 0.0,
+                url_name,
+                match_type: Some(true),
+            });
+        let auctions = wfm
+            .auction()
+            .search(
+                "riven",
+                &watch.weapon_url,
+                Some(positive_stats),
+                negative_stat,
+                None,
+                None,
+                watch.mastery_rank_max,
+                None,
+                watch.re_rolls_max,
+                Some("direct"),
+                Some("price_asc"),
+            )
+            .await?;
+        // Every search adds to the auctions the fair values are estimated from
+        db.riven_snapshot()
+            .upsert_auctions(&weapon_type, &auctions)
+            .await?;
+
+        // Only sellers that are in game can be whispered
+        let listings = auctions
+            .iter()
+            .filter(|a| {
+                a.owner.ingame_name != auth.ingame_name
+                    && !a.closed
+                    && a.visible
+                    && a.owner.status == "ingame"
+                    && has_stats(watch, a)
+            })
+            .collect::<Vec<_>>();
+        let snapshots = match watch.target {
+            SnipeTargetType::Relative => {
+                db.riven_snapshot()
+                    .get_by_weapon_type(&weapon_type, &valuation::snapshots_since())
+                    .await?
+            }
+            _ => vec![],
+        };
+
+        for auction in listings.iter() {
+            let price = auction.buyout_price.unwrap_or(auction.starting_price);
+            let target = match watch.target {
+                SnipeTargetType::Fixed => Some(watch.value),
+                SnipeTargetType::Relative => {
+                    recent_price(auction, &listings, &snapshots, min_confidence)
+                        .map(|recent| recent * watch.value / 100)
+                }
+                SnipeTargetType::Unknown(_) => None,
+            };
+            let target = match target {
+                Some(target) if price <= target => target,
+                _ => continue,
+            };
+
+            // Only notified again when the price drops further
+            {
+                let mut sniped = self.client.sniped_auctions.lock()?;
+                if sniped
+                    .get(&auction.id)
+                    .map(|p| *p <= price)
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+                sniped.insert(auction.id.clone(), price);
+            }
+            let mod_name = auction.item.name.clone().unwrap_or_default();
+            let whisper = format!(
+                "/w {} Hi! I want to buy: \"{} {}\" riven for {} platinum. (warframe.market)",
+                auction.owner.ingame_name, riven_type.item_name, mod_name, price
+            );
+            logger::info_con(
+                "SniperModule",
+                format!(
+                    "Found {} {} for {}p, target is {}p",
+                    riven_type.item_name, mod_name, price, target
+                )
+                .as_str(),
+            );
+            self.client.send_message(
+                "riven_sniper.found",
+                Some(json!({
+                    "name": riven_type.item_name,
+                    "mod_name": mod_name,
+                    "price": price,
+                    "target": target,
+                    "whisper": whisper,
+                })),
+            );
+            self.notify_deal(&riven_type, auction, price, target, &whisper)?;
+        }
+        Ok(auctions.into_iter().map(|a| a.id).collect())
+    }
+
+    fn notify_deal(
+        &self,
+        riven_type: &RivenTypeInfo,
+        auction: &Auction<AuctionOwner>,
+        price: i64,
+        target: i64,
+        whisper: &str,
+    ) -> Result<(), AppError> {
+        let settings = self.client.settings.lock()?.clone();
+        let notification = &settings.notifications.on_riven_snipe;
+        if !notification.is_enabled() {
+            return Ok(());
+        }
+        let mh = self.client.mh.lock()?.clone();
+        let mod_name = auction.item.name.clone().unwrap_or_default();
+        let fields = vec![
+            EmbedField::new("Weapon", &riven_type.item_name, true),
+            EmbedField::new("Mod", &mod_name, true),
+            EmbedField::new("Seller", &auction.owner.ingame_name, true),
+            EmbedField::new("Price", &format!("{}p", price), true),
+            EmbedField::new("Target", &format!("{}p", target), true),
+            EmbedField::new("Whisper", whisper, false),
+        ];
+        let values = HashMap::from([
+            ("WEAPON_NAME", riven_type.item_name.clone()),
+            ("MOD_NAME", mod_name),
+            ("PLAYER_NAME", auction.owner.ingame_name.clone()),
+            ("PRICE", price.to_string()),
+            ("TARGET", target.to_string()),
+            ("WHISPER", whisper.to_string()),
+        ]);
+        notify(
+            &mh,
+            &settings,
+            notification,
+            NotificationKind::RivenSnipe,
+            &values,
+            fields,
+            wfm_thumbnail(&riven_type.thumb),
+        );
+        Ok(())
+    }
+}
+
+// The search only filters on two or more positives, so the stats are checked again
+fn has_stats(watch: &RivenWatchSettings, auction: &Auction<AuctionOwner>) -> bool {
+    let attributes = match &auction.item.attributes {
+        Some(attributes) => attributes,
+        None => return false,
+    };
+    let positives = watch
+        .positive_stats
+        .iter()
+        .filter(|s| !s.is_empty())
+        .all(|stat| attributes.iter().any(|a| a.positive && &a.url_name == stat));
+    let negative = match watch.negative_stat.as_deref().filter(|s| !s.is_empty()) {
+        Some(stat) => attributes.iter().any(|a| !a.positive && a.url_name == stat),
+        None => true,
+    };
+    positives && negative
+}
+
+// Fair value of the roll when it can be trusted, the median of the other listings otherwise
+fn recent_price(
+    auction: &Auction<AuctionOwner>,
+    listings: &[&Auction<AuctionOwner>],
+    snapshots: &[RivenSnapshotStruct],
+    min_confidence: f64,
+) -> Option<i64> {
+    if let Some(roll) = RivenRoll::from_auction(auction) {
+        // The auction itself would pull its own value down
+        let others = snapshots
+            .iter()
+            .filter(|s| s.auction_id != auction.id)
+            .cloned()
+            .collect::<Vec<_>>();
+        let valuation = valuation::estimate(&roll, &others, None);
+        if valuation.confidence >= min_confidence {
+            if let Some(fair_value) = valuation.fair_value {
+                return Some(fair_value);
+            }
+        }
+    }
+    let mut prices = listings
+        .iter()
+        .filter(|a| a.id != auction.id)
+        .map(|a| a.buyout_price.unwrap_or(a.starting_price))
+        .collect::<Vec<_>>();
+    if prices.len() < MIN_MEDIAN_LISTINGS {
+        return None;
+    }
+    prices.sort();
+    Some(prices[prices.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(url_name: &str, value: f64, positive: bool) -> RivenAttribute {
+        RivenAttribute {
+            positive,
+            value,
+            url_name: url_name.to_string(),
+            match_type: None,
+        }
+    }
+
+    fn watch(positive_stats: Vec<&str>, negative_stat: Option<&str>) -> RivenWatchSettings {
+        RivenWatchSettings {
+            weapon_url: "soma".to_string(),
+            positive_stats: positive_stats.iter().map(|s| s.to_string()).collect(),
+            negative_stat: negative_stat.map(|s| s.to_string()),
+            mastery_rank_max: None,
+            re_rolls_max: None,
+            target: SnipeTargetType::Relative,
+            value: 80,
+        }
+    }
+
+    fn auction(
+        id: &str,
+        price: i64,
+        attributes: Option<Vec<RivenAttribute>>,
+    ) -> Auction<AuctionOwner> {
+        serde_json::from_value(json!({
+            "visible": true,
+            "minimal_reputation": 0,
+            "item": {
+                "type": "riven",
+                "weapon_url_name": "soma",
+                "re_rolls": 5,
+                "attributes": attributes,
+                "name": "crita-satiatis",
+                "mod_rank": 0,
+                "polarity": "madurai",
+                "mastery_level": 10
+            },
+            "buyout_price": price,
+            "note": "",
+            "starting_price": price,
+            "owner": {
+                "ingame_name": "seller",
+                "last_seen": "2024-01-01T00:00:00.000+00:00",
+                "reputation": 0,
+                "locale": "en",
+                "status": "ingame",
+                "id": "seller_id",
+                "region": "en",
+                "avatar": null
+            },
+            "platform": "pc",
+            "closed": false,
+            "top_bid": null,
+            "winner": null,
+            "is_marked_for": null,
+            "marked_operation_at": null,
+            "created": "2024-01-01T00:00:00.000+00:00",
+            "updated": "2024-01-01T00:00:00.000+00:00",
+            "note_raw": "",
+            "is_direct_sell": true,
+            "id": id,
+            "private": false
+        }))
+        .unwrap()
+    }
+
+    fn roll() -> Vec<RivenAttribute> {
+        vec![
+            attribute("critical_chance", 150.0, true),
+            attribute("multishot", 90.0, true),
+            attribute("zoom", -40.0, false),
+        ]
+    }
+
+    fn snapshot(auction_id: &str, price: i64) -> RivenSnapshotStruct {
+        RivenSnapshotStruct {
+            id: 0,
+            auction_id: auction_id.to_string(),
+            weapon_url: "soma".to_string(),
+            weapon_type: "rifle".to_string(),
+            mod_name: "crita-satiatis".to_string(),
+            attributes: sqlx::types::Json(roll()),
+            mastery_rank: 10,
+            re_rolls: 5,
+            mod_rank: 0,
+            polarity: "madurai".to_string(),
+            price,
+            first_seen: chrono::Local::now().naive_local().to_string(),
+            last_seen: chrono::Local::now().naive_local().to_string(),
+        }
+    }
+
+    #[test]
+    fn has_stats_needs_every_positive_and_the_negative() {
+        let listed = auction("a", 100, Some(roll()));
+        assert!(has_stats(
+            &watch(vec!["critical_chance", "multishot"], Some("zoom")),
+            &listed
+        ));
+        // Empty entries from the settings form are ignored
+        assert!(has_stats(
+            &watch(vec!["critical_chance", ""], Some("")),
+            &listed
+        ));
+        assert!(!has_stats(
+            &watch(vec!["critical_chance", "toxin_damage"], None),
+            &listed
+        ));
+        assert!(!has_stats(
+            &watch(vec!["critical_chance"], Some("recoil")),
+            &listed
+        ));
+        // The zoom is a negative here, so it doesn't count as a positive
+        assert!(!has_stats(&watch(vec!["zoom"], None), &listed));
+        assert!(!has_stats(&watch(vec![], None), &auction("b", 100, None)));
+    }
+
+    #[test]
+    fn recent_price_is_the_median_of_the_other_listings() {
+        let target = auction("a", 10, Some(roll()));
+        let others = vec![
+            auction("b", 300, Some(roll())),
+            auction("c", 100, Some(roll())),
+            auction("d", 200, Some(roll())),
+        ];
+        let mut listings = others.iter().collect::<Vec<_>>();
+        listings.push(&target);
+        assert_eq!(recent_price(&target, &listings, &[], 0.5), Some(200));
+        // The auction itself isn't one of the other listings
+        assert_eq!(recent_price(&target, &listings[1..], &[], 0.5), None);
+    }
+
+    #[test]
+    fn recent_price_uses_a_trusted_fair_value() {
+        let target = auction("a", 10, Some(roll()));
+        let others = vec![
+            auction("b", 300, Some(roll())),
+            auction("c", 100, Some(roll())),
+            auction("d", 200, Some(roll())),
+        ];
+        let listings = others.iter().collect::<Vec<_>>();
+        let snapshots = (0..8).map(|_| snapshot("old", 150)).collect::<Vec<_>>();
+        assert_eq!(recent_price(&target, &listings, &snapshots, 0.5), Some(150));
+        // Not trusted enough, the listings are used instead
+        assert_eq!(recent_price(&target, &listings, &snapshots, 1.1), Some(200));
+        // Snapshots of the auction itself don't count
+        let own = (0..8).map(|_| snapshot("a", 150)).collect::<Vec<_>>();
+        assert_eq!(recent_price(&target, &listings, &own, 0.5), Some(200));
+    }
+}
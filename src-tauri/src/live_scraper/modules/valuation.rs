@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    database::modules::stock_riven::StockRivenStruct,
+    error::AppError,
+    live_scraper::{
+        client::LiveScraperClient,
+        valuation::{self, RivenRoll, RivenValuation},
+    },
+    logger,
+    structs::RivenAttribute,
+};
+
+// How often the auctions around one of our rivens are collected again
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+// Collects riven auctions over time and values our rivens against them.
+pub struct ValuationModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> ValuationModule<'a> {
+    // Stores the auctions with the same positive stats, whatever the negative or the re-rolls.
+    // Searching by stats keeps the expensive rolls in, a plain weapon search only returns the cheapest.
+    pub async fn collect(&self, riven: &StockRivenStruct) -> Result<(), AppError> {
+        let positive_stats = riven
+            .attributes
+            .iter()
+            .filter(|a| a.positive)
+            .map(|a| RivenAttribute {
+                match_type: Some(true),
+                ..a.clone()
+            })
+            .collect::<Vec<_>>();
+        let mut stat_names = positive_stats
+            .iter()
+            .map(|a| a.url_name.clone())
+            .collect::<Vec<_>>();
+        stat_names.sort();
+        let key = format!("{}:{}", riven.weapon_url, stat_names.join(","));
+        {
+            let snapshot_times = self.client.snapshot_times.lock()?;
+            if let Some(collected) = snapshot_times.get(&key) {
+                if collected.elapsed() < SNAPSHOT_INTERVAL {
+                    return Ok(());
+                }
+            }
+        }
+
+        let db = self.client.db.lock()?.clone();
+        let wfm = self.client.wfm.lock()?.clone();
+        let auctions = wfm
+            .auction()
+            .search(
+                "riven",
+                &riven.weapon_url,
+                Some(positive_stats),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("direct"),
+                Some("price_asc"),
+            )
+            .await?;
+        let total = db
+            .riven_snapshot()
+            .upsert_auctions(&riven.weapon_type, &auctions)
+            .await?;
+        self.client
+            .snapshot_times
+            .lock()?
+            .insert(key, Instant::now());
+        logger::debug_con(
+            "RivenValuation",
+            format!("Collected {} auctions for {}", total, riven.weapon_url).as_str(),
+        );
+        Ok(())
+    }
+
+    pub async fn estimate(
+        &self,
+        riven: &StockRivenStruct,
+        lowest_price: Option<i64>,
+    ) -> Result<RivenValuation, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let snapshots = db
+            .riven_snapshot()
+            .get_by_weapon_type(&riven.weapon_type, &valuation::snapshots_since())
+            .await?;
+        Ok(valuation::estimate(
+            &RivenRoll::from_stock(riven),
+            &snapshots,
+            lowest_price,
+        ))
+    }
+
+    pub async fn prune(&self) -> Result<(), AppError> {
+        let db = self.client.db.lock()?.clone();
+        db.riven_snapshot()
+            .prune(&valuation::snapshots_since())
+            .await?;
+        Ok(())
+    }
+}
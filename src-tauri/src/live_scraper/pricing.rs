@@ -0,0 +1,300 @@
+use crate::{
+    enums::PricingStrategyType,
+    logger,
+    settings::{PricingStrategySettings, StockItemSettings},
+};
+
+// Market data a strategy can price an item from.
+#[derive(Clone, Debug, Default)]
+pub struct PricingContext {
+    pub closed_avg: f64,
+    pub closed_median: f64,
+    // Lowest sell order minus highest buy order
+    pub price_range: i64,
+    pub highest_buy: i64,
+    pub lowest_sell: i64,
+    // Average price paid for the item, only set when selling
+    pub bought_price: i64,
+}
+
+pub trait PricingStrategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    // Buy price when nobody else has a buy order up
+    fn buy_price_without_buyers(&self, ctx: &PricingContext) -> i64;
+
+    fn buy_price(&self, ctx: &PricingContext) -> i64;
+
+    // Sell price when nobody else has a sell order up
+    fn sell_price_without_sellers(&self, ctx: &PricingContext) -> i64;
+
+    // None means the item is too cheap to sell right now
+    fn sell_price(&self, ctx: &PricingContext) -> Option<i64>;
+
+    fn potential_profit(&self, ctx: &PricingContext, price: i64) -> f64 {
+        ctx.closed_avg - price as f64
+    }
+}
+
+// The original live scraper behaviour.
+pub struct DefaultStrategy;
+
+impl PricingStrategy for DefaultStrategy {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn buy_price_without_buyers(&self, ctx: &PricingContext) -> i64 {
+        (ctx.price_range - 40).max((ctx.price_range / 3) - 1)
+    }
+
+    fn buy_price(&self, ctx: &PricingContext) -> i64 {
+        ctx.highest_buy
+    }
+
+    fn sell_price_without_sellers(&self, ctx: &PricingContext) -> i64 {
+        ctx.bought_price + 30
+    }
+
+    fn sell_price(&self, ctx: &PricingContext) -> Option<i64> {
+        if ctx.lowest_sell - ctx.bought_price <= -10 {
+            return None;
+        }
+        Some((ctx.bought_price + 10).max(ctx.lowest_sell))
+    }
+}
+
+// Outbid the highest buyer and undercut the lowest seller by a fixed amount.
+pub struct UndercutStrategy {
+    pub amount: i64,
+}
+
+impl PricingStrategy for UndercutStrategy {
+    fn name(&self) -> &str {
+        "undercut"
+    }
+
+    fn buy_price_without_buyers(&self, ctx: &PricingContext) -> i64 {
+        DefaultStrategy.buy_price_without_buyers(ctx)
+    }
+
+    fn buy_price(&self, ctx: &PricingContext) -> i64 {
+        ctx.highest_buy + self.amount
+    }
+
+    fn sell_price_without_sellers(&self, ctx: &PricingContext) -> i64 {
+        DefaultStrategy.sell_price_without_sellers(ctx)
+    }
+
+    fn sell_price(&self, ctx: &PricingContext) -> Option<i64> {
+        let post_price = (ctx.lowest_sell - self.amount).max(1);
+        if post_price - ctx.bought_price <= -10 {
+            return None;
+        }
+        Some(post_price)
+    }
+}
+
+// Buy below and sell above the median of closed trades.
+// Falls back to the default strategy when there is no closed median for the item.
+pub struct MedianMarginStrategy {
+    pub margin: i64,
+}
+
+impl PricingStrategy for MedianMarginStrategy {
+    fn name(&self) -> &str {
+        "median_margin"
+    }
+
+    fn buy_price_without_buyers(&self, ctx: &PricingContext) -> i64 {
+        self.buy_price(ctx)
+    }
+
+    fn buy_price(&self, ctx: &PricingContext) -> i64 {
+        if ctx.closed_median <= 0.0 {
+            return DefaultStrategy.buy_price(ctx);
+        }
+        (ctx.closed_median as i64 - self.margin).max(1)
+    }
+
+    fn sell_price_without_sellers(&self, ctx: &PricingContext) -> i64 {
+        if ctx.closed_median <= 0.0 {
+            return DefaultStrategy.sell_price_without_sellers(ctx);
+        }
+        ctx.closed_median as i64 + self.margin
+    }
+
+    fn sell_price(&self, ctx: &PricingContext) -> Option<i64> {
+        if ctx.closed_median <= 0.0 {
+            return DefaultStrategy.sell_price(ctx);
+        }
+        let post_price = ctx.closed_median as i64 + self.margin;
+        if post_price - ctx.bought_price <= -10 {
+            return None;
+        }
+        Some(post_price)
+    }
+
+    fn potential_profit(&self, ctx: &PricingContext, price: i64) -> f64 {
+        if ctx.closed_median <= 0.0 {
+            return DefaultStrategy.potential_profit(ctx, price);
+        }
+        ctx.closed_median - price as f64
+    }
+}
+
+pub fn get_strategy(settings: &PricingStrategySettings) -> Box<dyn PricingStrategy> {
+    match &settings.strategy {
+        PricingStrategyType::Default => Box::new(DefaultStrategy),
+        PricingStrategyType::Undercut => Box::new(UndercutStrategy {
+            amount: settings.value,
+        }),
+        PricingStrategyType::MedianMargin => Box::new(MedianMarginStrategy {
+            margin: settings.value,
+        }),
+        PricingStrategyType::Unknown(name) => {
+            logger::warning_con(
+                "PricingStrategy",
+                format!("Unknown pricing strategy {}, using default", name).as_str(),
+            );
+            Box::new(DefaultStrategy)
+        }
+    }
+}
+
+// Uses the per item override when there is one.
+pub fn get_strategy_for_item(
+    settings: &StockItemSettings,
+    item_name: &str,
+) -> Box<dyn PricingStrategy> {
+    let strategy = settings
+        .pricing_overrides
+        .get(item_name)
+        .unwrap_or(&settings.pricing_strategy);
+    get_strategy(strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SettingsState;
+
+    fn context() -> PricingContext {
+        PricingContext {
+            closed_avg: 50.0,
+            closed_median: 48.0,
+            price_range: 30,
+            highest_buy: 40,
+            lowest_sell: 55,
+            bought_price: 42,
+        }
+    }
+
+    fn strategy(strategy: PricingStrategyType, value: i64) -> PricingStrategySettings {
+        PricingStrategySettings { strategy, value }
+    }
+
+    #[test]
+    fn default_strategy_matches_the_old_scraper() {
+        let ctx = context();
+        assert_eq!(DefaultStrategy.buy_price(&ctx), 40);
+        assert_eq!(DefaultStrategy.buy_price_without_buyers(&ctx), 9);
+        assert_eq!(DefaultStrategy.sell_price_without_sellers(&ctx), 72);
+        assert_eq!(DefaultStrategy.sell_price(&ctx), Some(55));
+        assert_eq!(DefaultStrategy.potential_profit(&ctx, 40), 10.0);
+
+        // Never below what we paid plus 10
+        let ctx = PricingContext {
+            lowest_sell: 45,
+            ..context()
+        };
+        assert_eq!(DefaultStrategy.sell_price(&ctx), Some(52));
+        // Too cheap to sell
+        let ctx = PricingContext {
+            lowest_sell: 32,
+            ..context()
+        };
+        assert_eq!(DefaultStrategy.sell_price(&ctx), None);
+    }
+
+    #[test]
+    fn default_strategy_can_go_below_one_without_buyers() {
+        // Not clamped here, the live scraper posts at least 1p
+        let ctx = PricingContext {
+            price_range: 2,
+            ..context()
+        };
+        assert_eq!(DefaultStrategy.buy_price_without_buyers(&ctx), -1);
+    }
+
+    #[test]
+    fn undercut_strategy_outbids_and_undercuts() {
+        let ctx = context();
+        let undercut = UndercutStrategy { amount: 2 };
+        assert_eq!(undercut.buy_price(&ctx), 42);
+        assert_eq!(undercut.sell_price(&ctx), Some(53));
+        assert_eq!(
+            undercut.buy_price_without_buyers(&ctx),
+            DefaultStrategy.buy_price_without_buyers(&ctx)
+        );
+
+        let ctx = PricingContext {
+            lowest_sell: 34,
+            ..context()
+        };
+        assert_eq!(undercut.sell_price(&ctx), None);
+    }
+
+    #[test]
+    fn median_margin_strategy_uses_the_closed_median() {
+        let ctx = context();
+        let median = MedianMarginStrategy { margin: 5 };
+        assert_eq!(median.buy_price(&ctx), 43);
+        assert_eq!(median.buy_price_without_buyers(&ctx), 43);
+        assert_eq!(median.sell_price_without_sellers(&ctx), 53);
+        assert_eq!(median.sell_price(&ctx), Some(53));
+        assert_eq!(median.potential_profit(&ctx, 43), 5.0);
+    }
+
+    #[test]
+    fn median_margin_strategy_falls_back_without_a_median() {
+        let ctx = PricingContext {
+            closed_median: 0.0,
+            ..context()
+        };
+        let median = MedianMarginStrategy { margin: 5 };
+        assert_eq!(median.buy_price(&ctx), DefaultStrategy.buy_price(&ctx));
+        assert_eq!(median.sell_price(&ctx), DefaultStrategy.sell_price(&ctx));
+        assert_eq!(
+            median.potential_profit(&ctx, 40),
+            DefaultStrategy.potential_profit(&ctx, 40)
+        );
+    }
+
+    #[test]
+    fn strategies_are_picked_from_the_settings() {
+        assert_eq!(
+            get_strategy(&strategy(PricingStrategyType::Undercut, 1)).name(),
+            "undercut"
+        );
+        assert_eq!(
+            get_strategy(&strategy(PricingStrategyType::Unknown("x".to_string()), 1)).name(),
+            "default"
+        );
+
+        let mut settings = SettingsState::default().live_scraper.stock_item;
+        settings.pricing_strategy = strategy(PricingStrategyType::Default, 0);
+        settings.pricing_overrides.insert(
+            "mirage_prime_set".to_string(),
+            strategy(PricingStrategyType::MedianMargin, 3),
+        );
+        assert_eq!(
+            get_strategy_for_item(&settings, "mirage_prime_set").name(),
+            "median_margin"
+        );
+        assert_eq!(
+            get_strategy_for_item(&settings, "nova_prime_set").name(),
+            "default"
+        );
+    }
+}
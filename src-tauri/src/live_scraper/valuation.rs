@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::modules::{riven_snapshot::RivenSnapshotStruct, stock_riven::StockRivenStruct},
+    structs::{Auction, AuctionOwner, RivenAttribute},
+};
+
+// A snapshot a week old counts for half as much
+const HALF_LIFE_DAYS: f64 = 7.0;
+// Snapshots less similar than this are ignored
+const MIN_SIMILARITY: f64 = 0.05;
+// Other weapons of the same riven type are only used when the weapon itself has fewer snapshots
+const MIN_WEAPON_SAMPLES: usize = 10;
+// Rolls this much apart in disposition still count for a bit more than a third
+const DISPOSITION_SCALE: f64 = 0.15;
+// Snapshots older than this aren't used and are removed
+const SNAPSHOT_DAYS: i64 = 30;
+
+// The parts of a riven the valuation looks at
+#[derive(Clone, Debug)]
+pub struct RivenRoll {
+    pub weapon_url: String,
+    pub attributes: Vec<RivenAttribute>,
+    pub mastery_rank: i64,
+    pub re_rolls: i64,
+}
+
+impl RivenRoll {
+    pub fn from_stock(riven: &StockRivenStruct) -> Self {
+        RivenRoll {
+            weapon_url: riven.weapon_url.clone(),
+            attributes: riven.attributes.0.clone(),
+            mastery_rank: riven.mastery_rank as i64,
+            re_rolls: riven.re_rolls as i64,
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &RivenSnapshotStruct) -> Self {
+        RivenRoll {
+            weapon_url: snapshot.weapon_url.clone(),
+            attributes: snapshot.attributes.0.clone(),
+            mastery_rank: snapshot.mastery_rank,
+            re_rolls: snapshot.re_rolls,
+        }
+    }
+
+    // None for auctions that aren't rivens with known stats
+    pub fn from_auction(auction: &Auction<AuctionOwner>) -> Option<Self> {
+        let item = &auction.item;
+        Some(RivenRoll {
+            weapon_url: item.weapon_url_name.clone()?,
+            attributes: item.attributes.clone()?,
+            mastery_rank: item.mastery_level.unwrap_or(0),
+            re_rolls: item.re_rolls.unwrap_or(0),
+        })
+    }
+
+    fn positives(&self) -> Vec<&RivenAttribute> {
+        self.attributes.iter().filter(|a| a.positive).collect()
+    }
+
+    fn negative(&self) -> Option<&RivenAttribute> {
+        self.attributes.iter().find(|a| !a.positive)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RivenValuation {
+    // Cheapest matching live auction
+    pub lowest_price: Option<i64>,
+    pub fair_value: Option<i64>,
+    // 0 to 1, how far the fair value can be trusted
+    pub confidence: f64,
+    // Snapshots that went into the fair value
+    pub samples: usize,
+}
+
+// warframe.market doesn't publish dispositions, so they are estimated from how large the rolls on a
+// weapon are compared to the other weapons of the same riven type. 1.0 is an average weapon.
+pub struct Dispositions {
+    attribute_medians: HashMap<String, f64>,
+    weapons: HashMap<String, f64>,
+}
+
+impl Dispositions {
+    pub fn estimate(snapshots: &[RivenSnapshotStruct]) -> Self {
+        let mut values: HashMap<String, Vec<f64>> = HashMap::new();
+        for snapshot in snapshots {
+            for attribute in snapshot.attributes.iter().filter(|a| a.positive) {
+                values
+                    .entry(attribute.url_name.clone())
+                    .or_default()
+                    .push(attribute.value.abs());
+            }
+        }
+        let attribute_medians: HashMap<String, f64> = values
+            .into_iter()
+            .filter_map(|(name, mut values)| median(&mut values).map(|m| (name, m)))
+            .filter(|(_, m)| *m > 0.0)
+            .collect();
+
+        let mut ratios: HashMap<String, Vec<f64>> = HashMap::new();
+        for snapshot in snapshots {
+            for attribute in snapshot.attributes.iter().filter(|a| a.positive) {
+                if let Some(m) = attribute_medians.get(&attribute.url_name) {
+                    ratios
+                        .entry(snapshot.weapon_url.clone())
+                        .or_default()
+                        .push(attribute.value.abs() / m);
+                }
+            }
+        }
+        let weapons = ratios
+            .into_iter()
+            .filter_map(|(weapon, mut ratios)| median(&mut ratios).map(|m| (weapon, m)))
+            .collect();
+        Dispositions {
+            attribute_medians,
+            weapons,
+        }
+    }
+
+    pub fn get(&self, weapon_url: &str) -> f64 {
+        *self.weapons.get(weapon_url).unwrap_or(&1.0)
+    }
+
+    // Size of a roll compared to the average roll of the stat on an average weapon
+    pub fn relative_value(&self, weapon_url: &str, attribute: &RivenAttribute) -> Option<f64> {
+        let m = self.attribute_medians.get(&attribute.url_name)?;
+        Some(attribute.value.abs() / (m * self.get(weapon_url)))
+    }
+}
+
+// 0 to 1, how much a snapshot says about the price of the target
+fn similarity(target: &RivenRoll, other: &RivenRoll, dispositions: &Dispositions) -> f64 {
+    let target_positives = target.positives();
+    let other_positives = other.positives();
+    if target_positives.is_empty() || other_positives.is_empty() {
+        return 0.0;
+    }
+
+    // Shared positive stats, weighted by how close the rolls are
+    let mut shared = 0.0;
+    for a in target_positives.iter() {
+        if let Some(b) = other_positives.iter().find(|b| b.url_name == a.url_name) {
+            shared += match (
+                dispositions.relative_value(&target.weapon_url, a),
+                dispositions.relative_value(&other.weapon_url, b),
+            ) {
+                (Some(x), Some(y)) if x > 0.0 && y > 0.0 => (-2.0 * (x / y).ln().abs()).exp(),
+                _ => 0.5,
+            };
+        }
+    }
+    let stats = shared / target_positives.len().max(other_positives.len()) as f64;
+
+    let negative = match (target.negative(), other.negative()) {
+        (None, None) => 1.0,
+        (Some(a), Some(b)) if a.url_name == b.url_name => 1.0,
+        (Some(_), Some(_)) => 0.8,
+        _ => 0.7,
+    };
+    let mastery_rank = (-((target.mastery_rank - other.mastery_rank).abs() as f64) / 4.0).exp();
+    // Going from 0 to 10 re-rolls matters a lot more than from 100 to 110
+    let re_rolls = (-((1 + target.re_rolls.max(0)) as f64 / (1 + other.re_rolls.max(0)) as f64)
+        .ln()
+        .abs())
+    .exp();
+    let weapon = if target.weapon_url == other.weapon_url {
+        1.0
+    } else {
+        let difference =
+            (dispositions.get(&target.weapon_url) - dispositions.get(&other.weapon_url)).abs();
+        0.5 * (-difference / DISPOSITION_SCALE).exp()
+    };
+    stats * negative * mastery_rank * re_rolls * weapon
+}
+
+// Weighted median of the asking prices of similar rivens, newer snapshots count for more.
+pub fn estimate(
+    target: &RivenRoll,
+    snapshots: &[RivenSnapshotStruct],
+    lowest_price: Option<i64>,
+) -> RivenValuation {
+    let dispositions = Dispositions::estimate(snapshots);
+    let now = chrono::Local::now().naive_local();
+    let own_snapshots = snapshots
+        .iter()
+        .filter(|s| s.weapon_url == target.weapon_url)
+        .count();
+
+    // (price, weight, similarity)
+    let mut samples: Vec<(f64, f64, f64)> = vec![];
+    for snapshot in snapshots {
+        if own_snapshots >= MIN_WEAPON_SAMPLES && snapshot.weapon_url != target.weapon_url {
+            continue;
+        }
+        if snapshot.price <= 0 {
+            continue;
+        }
+        let similarity = similarity(target, &RivenRoll::from_snapshot(snapshot), &dispositions);
+        if similarity < MIN_SIMILARITY {
+            continue;
+        }
+        let age_days =
+            chrono::NaiveDateTime::parse_from_str(&snapshot.last_seen, "%Y-%m-%d %H:%M:%S%.f")
+                .map(|seen| (now - seen).num_hours() as f64 / 24.0)
+                .unwrap_or(0.0)
+                .max(0.0);
+        let weight = similarity * 0.5f64.powf(age_days / HALF_LIFE_DAYS);
+        samples.push((snapshot.price as f64, weight, similarity));
+    }
+    if samples.is_empty() {
+        return RivenValuation {
+            lowest_price,
+            ..Default::default()
+        };
+    }
+    samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let fair_value = weighted_quantile(&samples, 0.5);
+    let total: f64 = samples.iter().map(|s| s.1).sum();
+    let squares: f64 = samples.iter().map(|s| s.1 * s.1).sum();
+    let effective_samples = total * total / squares;
+
+    // Many loose matches don't make a confident estimate, so look at the closest ones
+    let mut similarities: Vec<f64> = samples.iter().map(|s| s.2).collect();
+    similarities.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let closest = similarities.iter().take(5).sum::<f64>() / similarities.len().min(5) as f64;
+
+    let spread = (weighted_quantile(&samples, 0.75) - weighted_quantile(&samples, 0.25))
+        / fair_value.max(1.0);
+    let confidence = (1.0 - (-effective_samples / 4.0).exp()) * closest / (1.0 + spread);
+
+    RivenValuation {
+        lowest_price,
+        fair_value: Some(fair_value.round() as i64),
+        confidence: (confidence.clamp(0.0, 1.0) * 100.0).round() / 100.0,
+        samples: samples.len(),
+    }
+}
+
+// Oldest snapshot that is still used
+pub fn snapshots_since() -> String {
+    (chrono::Local::now().naive_local() - chrono::Duration::days(SNAPSHOT_DAYS)).to_string()
+}
+
+// Samples have to be sorted by price
+fn weighted_quantile(samples: &[(f64, f64, f64)], quantile: f64) -> f64 {
+    let total: f64 = samples.iter().map(|s| s.1).sum();
+    let mut cumulative = 0.0;
+    for (price, weight, _) in samples {
+        cumulative += weight;
+        if cumulative >= total * quantile {
+            return *price;
+        }
+    }
+    samples.last().map(|s| s.0).unwrap_or(0.0)
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 {
+        Some((values[middle - 1] + values[middle]) / 2.0)
+    } else {
+        Some(values[middle])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(url_name: &str, value: f64, positive: bool) -> RivenAttribute {
+        RivenAttribute {
+            positive,
+            value,
+            url_name: url_name.to_string(),
+            match_type: None,
+        }
+    }
+
+    fn roll(weapon_url: &str) -> RivenRoll {
+        RivenRoll {
+            weapon_url: weapon_url.to_string(),
+            attributes: vec![
+                attribute("critical_chance", 150.0, true),
+                attribute("multishot", 90.0, true),
+                attribute("zoom", -40.0, false),
+            ],
+            mastery_rank: 10,
+            re_rolls: 5,
+        }
+    }
+
+    fn snapshot(roll: &RivenRoll, price: i64) -> RivenSnapshotStruct {
+        RivenSnapshotStruct {
+            id: 0,
+            auction_id: "auction".to_string(),
+            weapon_url: roll.weapon_url.clone(),
+            weapon_type: "rifle".to_string(),
+            mod_name: "crita-satiatis".to_string(),
+            attributes: sqlx::types::Json(roll.attributes.clone()),
+            mastery_rank: roll.mastery_rank,
+            re_rolls: roll.re_rolls,
+            mod_rank: 0,
+            polarity: "madurai".to_string(),
+            price,
+            first_seen: chrono::Local::now().naive_local().to_string(),
+            last_seen: chrono::Local::now().naive_local().to_string(),
+        }
+    }
+
+    #[test]
+    fn no_snapshots_gives_no_fair_value() {
+        let valuation = estimate(&roll("soma"), &[], Some(80));
+        assert_eq!(valuation.lowest_price, Some(80));
+        assert_eq!(valuation.fair_value, None);
+        assert_eq!(valuation.confidence, 0.0);
+        assert_eq!(valuation.samples, 0);
+    }
+
+    #[test]
+    fn fair_value_is_the_median_of_similar_rolls() {
+        let target = roll("soma");
+        let snapshots = vec![
+            snapshot(&target, 120),
+            snapshot(&target, 100),
+            snapshot(&target, 110),
+            // Auctions without a price aren't used
+            snapshot(&target, 0),
+        ];
+        let valuation = estimate(&target, &snapshots, None);
+        assert_eq!(valuation.fair_value, Some(110));
+        assert_eq!(valuation.samples, 3);
+        assert!(valuation.confidence > 0.0 && valuation.confidence <= 1.0);
+    }
+
+    #[test]
+    fn other_weapons_are_ignored_with_enough_own_snapshots() {
+        let target = roll("soma");
+        let mut snapshots: Vec<RivenSnapshotStruct> = (0..MIN_WEAPON_SAMPLES)
+            .map(|_| snapshot(&target, 100))
+            .collect();
+        snapshots.push(snapshot(&roll("braton"), 1000));
+        let valuation = estimate(&target, &snapshots, None);
+        assert_eq!(valuation.fair_value, Some(100));
+        assert_eq!(valuation.samples, MIN_WEAPON_SAMPLES);
+    }
+
+    #[test]
+    fn more_samples_give_more_confidence() {
+        let target = roll("soma");
+        let few = estimate(&target, &[snapshot(&target, 100)], None);
+        let many: Vec<RivenSnapshotStruct> = (0..8).map(|_| snapshot(&target, 100)).collect();
+        let many = estimate(&target, &many, None);
+        assert!(many.confidence > few.confidence);
+    }
+
+    #[test]
+    fn weighted_quantile_follows_the_weights() {
+        let samples = vec![(10.0, 1.0, 1.0), (20.0, 1.0, 1.0), (30.0, 4.0, 1.0)];
+        assert_eq!(weighted_quantile(&samples, 0.25), 20.0);
+        assert_eq!(weighted_quantile(&samples, 0.5), 30.0);
+        assert_eq!(weighted_quantile(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn median_of_even_and_odd_counts() {
+        assert_eq!(median(&mut [3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&mut [4.0, 1.0, 3.0, 2.0]), Some(2.5));
+        assert_eq!(median(&mut []), None);
+    }
+}
@@ -0,0 +1,21 @@
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+pub mod routes;
+pub mod server;
+
+// Events kept for a slow WebSocket client before it starts missing them
+const EVENT_BUFFER: usize = 256;
+
+static EVENTS: Lazy<broadcast::Sender<Value>> = Lazy::new(|| broadcast::channel(EVENT_BUFFER).0);
+
+// Mirrors a window event to the WebSocket clients of the local api.
+pub fn publish(event: &str, data: &Value) {
+    // Sending only fails when nobody is listening
+    let _ = EVENTS.send(json!({ "event": event, "data": data }));
+}
+
+pub fn subscribe() -> broadcast::Receiver<Value> {
+    EVENTS.subscribe()
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    database::client::DBClient,
+    error::{self, AppError},
+    live_scraper::client::LiveScraperClient,
+    wf_ee_log_parser::client::EELogParser,
+    PACKAGEINFO,
+};
+
+static LOG_FILE: &str = "local_api.log";
+
+pub struct ApiRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+}
+
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn data(data: Value) -> Self {
+        ApiResponse {
+            status: 200,
+            body: json!({ "data": data }),
+        }
+    }
+    pub fn error(status: u16, message: &str) -> Self {
+        ApiResponse {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+// The app state the routes can reach
+#[derive(Clone)]
+pub struct ApiContext {
+    // Swapped for the scraper of the profile that is switched to
+    pub live_scraper: Arc<Mutex<LiveScraperClient>>,
+    pub ee_log: Arc<Mutex<EELogParser>>,
+}
+
+impl ApiContext {
+    // Database of the active profile, the one its live scraper trades with
+    fn db(&self) -> Result<DBClient, AppError> {
+        let db = Arc::clone(&self.live_scraper.lock()?.db);
+        let db = db.lock()?.clone();
+        Ok(db)
+    }
+}
+
+pub async fn handle(ctx: &ApiContext, request: &ApiRequest) -> ApiResponse {
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/status") => get_status(ctx),
+        ("GET", "/api/stock/items") => get_stock_items(ctx).await,
+        ("GET", "/api/stock/rivens") => get_stock_rivens(ctx).await,
+        ("GET", "/api/transactions") => get_transactions(ctx).await,
+        ("GET", "/api/price_history") => get_price_history(ctx, &request.query).await,
+        ("POST", "/api/live_scraper/start") => start_live_scraper(ctx),
+        ("POST", "/api/live_scraper/stop") => stop_live_scraper(ctx),
+        _ => return ApiResponse::error(404, "Not found"),
+    };
+    match result {
+        Ok(data) => ApiResponse::data(data),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.to_string(), &e);
+            ApiResponse {
+                status: 500,
+                body: json!({ "error": e.to_json() }),
+            }
+        }
+    }
+}
+
+fn get_status(ctx: &ApiContext) -> Result<Value, AppError> {
+    let live_scraper = ctx.live_scraper.lock()?.clone();
+    let ee_log = ctx.ee_log.lock()?.clone();
+    let version = PACKAGEINFO
+        .lock()?
+        .clone()
+        .map(|info| info.version.to_string());
+    Ok(json!({
+        "version": version,
+        "live_scraper": {
+            "running": live_scraper.is_running(),
+            "dry_run": live_scraper.is_dry_run(),
+        },
+        "ee_log": {
+            "running": ee_log.is_running(),
+        },
+    }))
+}
+
+async fn get_stock_items(ctx: &ApiContext) -> Result<Value, AppError> {
+    let db = ctx.db()?;
+    Ok(json!(db.stock_item().get_items().await?))
+}
+
+async fn get_stock_rivens(ctx: &ApiContext) -> Result<Value, AppError> {
+    let db = ctx.db()?;
+    Ok(json!(db.stock_riven().get_rivens().await?))
+}
+
+async fn get_transactions(ctx: &ApiContext) -> Result<Value, AppError> {
+    let db = ctx.db()?;
+    Ok(json!(db.transaction().get_items().await?))
+}
+
+// ?from=2024-01-01&to=2024-01-31&name=mirage_prime_set, every parameter is optional
+async fn get_price_history(
+    ctx: &ApiContext,
+    query: &HashMap<String, String>,
+) -> Result<Value, AppError> {
+    let db = ctx.db()?;
+    let mut rows = db
+        .price_history()
+        .get_history(query.get("from").cloned(), query.get("to").cloned())
+        .await?;
+    if let Some(name) = query.get("name") {
+        rows.retain(|row| &row.name == name);
+    }
+    Ok(json!(rows))
+}
+
+fn start_live_scraper(ctx: &ApiContext) -> Result<Value, AppError> {
+    let mut live_scraper = ctx.live_scraper.lock()?;
+    if !live_scraper.is_running() {
+        live_scraper.start_loop()?;
+    }
+    Ok(json!({ "running": true }))
+}
+
+fn stop_live_scraper(ctx: &ApiContext) -> Result<Value, AppError> {
+    let live_scraper = ctx.live_scraper.lock()?;
+    live_scraper.stop_loop();
+    Ok(json!({ "running": false }))
+}
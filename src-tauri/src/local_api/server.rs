@@ -0,0 +1,398 @@
+use std::{collections::HashMap, io::ErrorKind, net::SocketAddr};
+
+use eyre::eyre;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Url;
+use serde_json::Value;
+use tokio::{net::TcpStream, sync::broadcast::error::RecvError};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message,
+};
+
+use crate::{
+    error::AppError,
+    http_server::{self, HttpListener, HttpResponse},
+    logger,
+    settings::LocalApiSettings,
+};
+
+use super::routes::{self, ApiContext, ApiRequest, ApiResponse};
+
+static COMPONENT: &str = "LocalApi";
+// Only the request line is needed to spot a WebSocket upgrade
+const PEEK_SIZE: usize = 1024;
+
+// Optional HTTP and WebSocket server on localhost for scripts and dashboards.
+pub struct LocalApiServer {
+    pub addr: SocketAddr,
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl LocalApiServer {
+    pub async fn start(
+        settings: &LocalApiSettings,
+        ctx: ApiContext,
+    ) -> Result<LocalApiServer, AppError> {
+        if settings.token.is_empty() {
+            return Err(AppError::new(
+                COMPONENT,
+                eyre!("The local api needs a token, set local_api.token in the settings"),
+            ));
+        }
+        // Only reachable from this machine
+        let listener = HttpListener::bind(("127.0.0.1", settings.port), COMPONENT)?;
+        let addr = listener.addr;
+        let token = settings.token.clone();
+        let handle = listener.serve(COMPONENT, move |stream| {
+            let ctx = ctx.clone();
+            let token = token.clone();
+            async move { handle_connection(stream, &ctx, &token).await }
+        });
+        logger::info_con(
+            COMPONENT,
+            format!("Local api listening on http://{}", addr).as_str(),
+        );
+        Ok(LocalApiServer { addr, handle })
+    }
+
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+// Tokens are compared without stopping at the first difference
+fn is_valid_token(given: Option<&str>, token: &str) -> bool {
+    let given = match given {
+        Some(given) => given.as_bytes(),
+        None => return false,
+    };
+    given.len() == token.len()
+        && given
+            .iter()
+            .zip(token.as_bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+// Query keys and values are percent-decoded, "+" is a space like in a form
+fn parse_target(target: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = match Url::parse(format!("http://localhost/?{}", query).as_str()) {
+        Ok(url) => url
+            .query_pairs()
+            .filter(|(key, _)| !key.is_empty())
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect(),
+        Err(_) => HashMap::new(),
+    };
+    (path.trim_end_matches('/').to_string(), query)
+}
+
+// "Authorization: Bearer <token>" or ?token=<token> for clients that can't set headers
+fn get_request_token<'a>(
+    headers: &'a HashMap<String, String>,
+    query: &'a HashMap<String, String>,
+) -> Option<&'a str> {
+    headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| query.get("token").map(|token| token.as_str()))
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    ctx: &ApiContext,
+    token: &str,
+) -> std::io::Result<()> {
+    // Peek so the WebSocket handshake can read the request itself
+    let mut peek = vec![0u8; PEEK_SIZE];
+    let read = stream.peek(&mut peek).await?;
+    let first_line = String::from_utf8_lossy(&peek[..read])
+        .lines()
+        .next()
+        .unwrap_or("")
+        .to_string();
+    let mut parts = first_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let (path, _) = parse_target(parts.next().unwrap_or(""));
+    if method == "GET" && path == "/ws" {
+        return handle_websocket(stream, token).await;
+    }
+
+    let request = match http_server::read_request(&mut stream).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return Ok(()),
+        Err(e) if e.kind() == ErrorKind::InvalidData => {
+            return write_response(&mut stream, ApiResponse::error(400, "Request too large")).await;
+        }
+        Err(e) => return Err(e),
+    };
+    let (path, query) = parse_target(&request.target);
+
+    let response = if !is_valid_token(get_request_token(&request.headers, &query), token) {
+        ApiResponse::error(401, "Missing or invalid token")
+    } else {
+        routes::handle(
+            ctx,
+            &ApiRequest {
+                method: request.method,
+                path,
+                query,
+            },
+        )
+        .await
+    };
+    write_response(&mut stream, response).await
+}
+
+async fn write_response(stream: &mut TcpStream, response: ApiResponse) -> std::io::Result<()> {
+    http_server::write_response(
+        stream,
+        HttpResponse::new(
+            response.status,
+            "application/json",
+            response.body.to_string(),
+        ),
+    )
+    .await
+}
+
+// Streams the Client:Update and Client:Update:Progress events until the client goes away
+async fn handle_websocket(stream: TcpStream, token: &str) -> std::io::Result<()> {
+    let authorize = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let headers: HashMap<String, String> = request
+            .headers()
+            .iter()
+            .filter_map(|(key, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (key.as_str().to_lowercase(), value.to_string()))
+            })
+            .collect();
+        let (_, query) = parse_target(
+            request
+                .uri()
+                .path_and_query()
+                .map(|p| p.as_str())
+                .unwrap_or(""),
+        );
+        if is_valid_token(get_request_token(&headers, &query), token) {
+            return Ok(response);
+        }
+        let mut error = ErrorResponse::new(Some("Missing or invalid token".to_string()));
+        *error.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(error)
+    };
+    let socket = tokio_tungstenite::accept_hdr_async(stream, authorize)
+        .await
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    let (mut write, mut read) = socket.split();
+    let mut events = super::subscribe();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event: Value = match event {
+                    Ok(event) => event,
+                    // A slow client misses events instead of holding up the app
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if write.send(Message::Text(event.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            message = read.next() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Clients don't send us anything, pings are answered by tungstenite
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use polars::prelude::{DataFrame, NamedFrom, Series};
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::AuthState,
+        cache::client::CacheClient,
+        database::client::DBClient,
+        handler::MonitorHandler,
+        live_scraper::client::LiveScraperClient,
+        price_scraper::PriceScraper,
+        settings::SettingsState,
+        wf_ee_log_parser::{client::EELogParser, events::trade_reconciliation::TradeReconciler},
+        wfm_client::client::WFMClient,
+    };
+
+    const TOKEN: &str = "local-api-token";
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(is_valid_token(Some(TOKEN), TOKEN));
+        assert!(!is_valid_token(Some("local-api-tokem"), TOKEN));
+        assert!(!is_valid_token(Some("local-api"), TOKEN));
+        assert!(!is_valid_token(Some(""), TOKEN));
+        assert!(!is_valid_token(None, TOKEN));
+    }
+
+    #[test]
+    fn the_token_comes_from_the_header_or_the_query() {
+        let bearer = HashMap::from([("authorization".to_string(), "Bearer abc".to_string())]);
+        let basic = HashMap::from([("authorization".to_string(), "Basic abc".to_string())]);
+        let query = HashMap::from([("token".to_string(), "def".to_string())]);
+        let none = HashMap::new();
+        assert_eq!(get_request_token(&bearer, &none), Some("abc"));
+        assert_eq!(get_request_token(&bearer, &query), Some("abc"));
+        assert_eq!(get_request_token(&none, &query), Some("def"));
+        assert_eq!(get_request_token(&basic, &none), None);
+        assert_eq!(get_request_token(&none, &none), None);
+    }
+
+    #[test]
+    fn query_values_are_decoded() {
+        let (path, query) =
+            parse_target("/api/price_history/?name=mirage%20prime+set&token=a%2Bb%3D&empty");
+        assert_eq!(path, "/api/price_history");
+        assert_eq!(query["name"], "mirage prime set");
+        assert_eq!(query["token"], "a+b=");
+        assert_eq!(query["empty"], "");
+
+        let (path, query) = parse_target("/api/status");
+        assert_eq!(path, "/api/status");
+        assert!(query.is_empty());
+    }
+
+    // A server on a free port over an empty database
+    async fn start(name: &str) -> (LocalApiServer, DBClient) {
+        let settings = Arc::new(Mutex::new(SettingsState::default()));
+        let auth = Arc::new(Mutex::new(AuthState::default()));
+        let wfm = Arc::new(Mutex::new(WFMClient::new(
+            Arc::clone(&auth),
+            Arc::clone(&settings),
+        )));
+        let cache = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm))));
+        let path = std::env::temp_dir().join(format!(
+            "quantframe_local_api_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        let db = DBClient::open(Arc::clone(&cache), Arc::clone(&wfm), path)
+            .await
+            .unwrap();
+        db.initialize().await.unwrap();
+        let mh = Arc::new(Mutex::new(MonitorHandler::headless()));
+        let db_lock = Arc::new(Mutex::new(db.clone()));
+        let price_scraper = Arc::new(Mutex::new(PriceScraper::new(
+            Arc::clone(&wfm),
+            Arc::clone(&auth),
+            Arc::clone(&db_lock),
+        )));
+        let live_scraper = LiveScraperClient::new(
+            Arc::clone(&settings),
+            price_scraper,
+            Arc::clone(&wfm),
+            auth,
+            Arc::clone(&db_lock),
+            Arc::clone(&mh),
+        );
+        let reconciler = TradeReconciler::new(settings, db_lock, wfm);
+        let ee_log = EELogParser::new(Arc::new(Mutex::new(reconciler)), mh, cache);
+        let ctx = ApiContext {
+            live_scraper: Arc::new(Mutex::new(live_scraper)),
+            ee_log: Arc::new(Mutex::new(ee_log)),
+        };
+        let settings = LocalApiSettings {
+            enabled: true,
+            port: 0,
+            token: TOKEN.to_string(),
+        };
+        (LocalApiServer::start(&settings, ctx).await.unwrap(), db)
+    }
+
+    async fn get(
+        server: &LocalApiServer,
+        target: &str,
+        token: Option<&str>,
+    ) -> (StatusCode, Value) {
+        let mut request = reqwest::Client::new().get(format!("http://{}{}", server.addr, target));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token() {
+        let (server, _) = start("token").await;
+        let (status, body) = get(&server, "/api/status", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, json!({ "error": "Missing or invalid token" }));
+        let (status, _) = get(&server, "/api/status", Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = get(&server, &format!("/api/status?token={}", TOKEN), None).await;
+        assert_eq!(status, StatusCode::OK);
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn requests_are_routed_by_method_and_path() {
+        let (server, db) = start("routes").await;
+        let (status, body) = get(&server, "/api/stock/items/", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "data": [] }));
+        let (status, body) = get(&server, "/api/nothing", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, json!({ "error": "Not found" }));
+        // Starting the scraper is a POST
+        let (status, _) = get(&server, "/api/live_scraper/start", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The name filter is matched after decoding
+        db.price_history()
+            .upsert_dataframe(
+                &DataFrame::new(vec![
+                    Series::new("name", ["mirage prime set", "nova_prime_set"]),
+                    Series::new("item_id", ["mirage_id", "nova_id"]),
+                    Series::new("datetime", ["2024-01-01", "2024-01-01"]),
+                    Series::new("order_type", ["closed", "closed"]),
+                    Series::new("mod_rank", [None::<i64>, None]),
+                    Series::new("volume", [10_i64, 20]),
+                    Series::new("min_price", [40.0, 50.0]),
+                    Series::new("max_price", [60.0, 70.0]),
+                    Series::new("avg_price", [50.0, 60.0]),
+                    Series::new("median", [50.0, 60.0]),
+                ])
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let (status, body) = get(
+            &server,
+            "/api/price_history?name=mirage%20prime%20set",
+            Some(TOKEN),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let rows = body["data"].as_array().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "mirage prime set");
+        server.stop();
+    }
+}
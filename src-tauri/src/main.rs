@@ -1,47 +1,38 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use auth::AuthState;
-use cache::client::CacheClient;
-use database::client::DBClient;
-use debug::DebugClient;
-use error::AppError;
-use handler::MonitorHandler;
-use live_scraper::client::LiveScraperClient;
-use once_cell::sync::Lazy;
-use price_scraper::PriceScraper;
-use settings::SettingsState;
+use quantframe_lib::{
+    auth::{self, AuthState},
+    cache::{self, client::CacheClient},
+    crypto,
+    database::{self, client::DBClient},
+    debug::{self, DebugClient},
+    enums,
+    error::{self, AppError},
+    handler::{self, MonitorHandler},
+    helper::{self, WINDOW as HE_WINDOW},
+    live_scraper::{self, client::LiveScraperClient},
+    local_api::{self, server::LocalApiServer},
+    logger,
+    metrics::server::MetricsServer,
+    notification,
+    price_scraper::{self, PriceScraper},
+    profiles::{self, client::ProfileClient, ProfileStore},
+    settings::{self, SettingsState},
+    structs,
+    wf_ee_log_parser::{self, client::EELogParser},
+    wfm_client, PACKAGEINFO,
+};
 use std::path::{self, PathBuf};
 use std::sync::Arc;
 use std::{env, sync::Mutex};
 use std::{fs, panic};
 use tauri::api::notification::Notification;
 use tauri::async_runtime::block_on;
-use tauri::{App, Manager, PackageInfo, SystemTrayEvent};
-use wf_ee_log_parser::client::EELogParser;
-mod enums;
-mod handler;
-mod structs;
 use tauri::SystemTray;
+use tauri::{App, Manager, SystemTrayEvent};
 
-mod auth;
-mod cache;
 mod commands;
-mod database;
-mod debug;
-mod error;
-mod helper;
-mod live_scraper;
-mod logger;
-mod price_scraper;
-mod rate_limiter;
-mod settings;
 mod system_tray;
-mod wf_ee_log_parser;
-mod wfm_client;
-
-use helper::WINDOW as HE_WINDOW;
-
-pub static PACKAGEINFO: Lazy<Mutex<Option<PackageInfo>>> = Lazy::new(|| Mutex::new(None));
 
 async fn setup_async(app: &mut App) -> Result<(), AppError> {
     // Get the main window
@@ -52,6 +43,12 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     ));
     app.manage(monitor_handler_arc.clone());
 
+    // Before anything reads or writes a secret
+    crypto::setup()?;
+
+    // Load the profiles first, the states below are read from the active one
+    let profile_store = ProfileStore::setup()?;
+
     // create and manage Settings state
     let settings_arc = Arc::new(Mutex::new(SettingsState::setup()?));
     app.manage(settings_arc.clone());
@@ -61,10 +58,18 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     app.manage(auth_arc.clone());
 
     // create and manage Warframe Market API client state
-    let wfm_client = Arc::new(Mutex::new(wfm_client::client::WFMClient::new(
-        Arc::clone(&auth_arc),
-        Arc::clone(&settings_arc),
-    )));
+    #[cfg_attr(not(feature = "mock-server"), allow(unused_mut))]
+    let mut wfm =
+        wfm_client::client::WFMClient::new(Arc::clone(&auth_arc), Arc::clone(&settings_arc));
+
+    // Point the client at the in-process mock server
+    #[cfg(feature = "mock-server")]
+    {
+        let mock_server = wfm_client::mock_server::MockServer::start().await?;
+        wfm.set_endpoint(&mock_server.endpoint());
+        app.manage(mock_server);
+    }
+    let wfm_client = Arc::new(Mutex::new(wfm));
     app.manage(wfm_client.clone());
 
     // create and manage Cache state
@@ -79,31 +84,31 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     ));
     app.manage(database_client.clone());
 
-    // create and manage PriceScraper state
-    let price_scraper: Arc<Mutex<PriceScraper>> = Arc::new(Mutex::new(PriceScraper::new(
-        Arc::clone(&wfm_client),
-        Arc::clone(&auth_arc),
-    )));
-    app.manage(price_scraper.clone());
-
-    // create and manage LiveScraper state
-    let live_scraper = LiveScraperClient::new(
+    // create and manage Profiles state, it also holds the scrapers of the active profile
+    let profile_client = ProfileClient::new(
+        profile_store,
         Arc::clone(&settings_arc),
-        Arc::clone(&price_scraper),
-        Arc::clone(&wfm_client),
         Arc::clone(&auth_arc),
+        Arc::clone(&wfm_client),
+        Arc::clone(&cache_arc),
         Arc::clone(&database_client),
         Arc::clone(&monitor_handler_arc),
-    );
-    app.manage(Arc::new(Mutex::new(live_scraper)));
+    )?;
+    let price_scraper: Arc<Mutex<PriceScraper>> = Arc::clone(&profile_client.price_scraper);
+    app.manage(price_scraper.clone());
+    let live_scraper: Arc<Mutex<LiveScraperClient>> = Arc::clone(&profile_client.live_scraper);
+    app.manage(live_scraper.clone());
+    let reconciler = Arc::clone(&profile_client.reconciler);
+    app.manage(Arc::new(Mutex::new(profile_client)));
 
     // create and manage WhisperScraper state
     let ee_log = EELogParser::new(
-        Arc::clone(&settings_arc),
+        reconciler,
         Arc::clone(&monitor_handler_arc),
         Arc::clone(&cache_arc),
     );
-    app.manage(Arc::new(Mutex::new(ee_log)));
+    let ee_log = Arc::new(Mutex::new(ee_log));
+    app.manage(ee_log.clone());
     // create and manage WhisperScraper state
     let debug_client = DebugClient::new(
         Arc::clone(&cache_arc),
@@ -114,6 +119,32 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     );
     app.manage(Arc::new(Mutex::new(debug_client)));
 
+    // Start the local api for scripts and dashboards
+    let local_api_settings = settings_arc.lock()?.local_api.clone();
+    if local_api_settings.enabled {
+        let ctx = local_api::routes::ApiContext {
+            live_scraper: Arc::clone(&live_scraper),
+            ee_log: Arc::clone(&ee_log),
+        };
+        match LocalApiServer::start(&local_api_settings, ctx).await {
+            Ok(server) => {
+                app.manage(server);
+            }
+            Err(e) => error::create_log_file("local_api.log".to_string(), &e),
+        }
+    }
+
+    // Start the Prometheus endpoint
+    let metrics_settings = settings_arc.lock()?.metrics.clone();
+    if metrics_settings.enabled {
+        match MetricsServer::start(&metrics_settings).await {
+            Ok(server) => {
+                app.manage(server);
+            }
+            Err(e) => error::create_log_file("metrics.log".to_string(), &e),
+        }
+    }
+
     Ok(())
 }
 fn main() {
@@ -178,8 +209,17 @@ fn main() {
             commands::transaction::create_transaction_entry,
             commands::transaction::delete_transaction_entry,
             commands::transaction::update_transaction_entry,
+            commands::profit_report::get_profit_report,
+            commands::profit_report::get_profit_holdings,
             commands::live_scraper::toggle_live_scraper,
+            commands::live_scraper::get_dry_run_journal,
+            commands::live_scraper::clear_dry_run_journal,
+            commands::live_scraper::run_backtest,
             commands::price_scraper::generate_price_history,
+            commands::price_scraper::get_price_history,
+            commands::data_transfer::export_data,
+            commands::data_transfer::import_data,
+            commands::ee_log::replay_ee_log,
             commands::debug::import_warframe_algo_trader_data,
             commands::debug::reset_data,
             commands::auctions::refresh_auctions,
@@ -203,6 +243,13 @@ fn main() {
             commands::stock::delete_riven_stock,
             commands::stock::update_riven_stock,
             commands::stock::sell_riven_stock,
+            commands::stock::get_riven_grades,
+            // Profile commands
+            commands::profiles::get_profiles,
+            commands::profiles::create_profile,
+            commands::profiles::switch_profile,
+            commands::profiles::delete_profile,
+            commands::profiles::get_profiles_report,
             // Warframe Market Commands
             wfm_client::modules::auction::auction_search,
         ])
@@ -0,0 +1,279 @@
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use once_cell::sync::Lazy;
+
+use crate::error::AppError;
+
+pub mod server;
+
+pub const WFM_REQUESTS: &str = "quantframe_wfm_requests_total";
+pub const WFM_REQUEST_DURATION: &str = "quantframe_wfm_request_duration_seconds";
+pub const RATE_LIMITER_WAIT: &str = "quantframe_rate_limiter_wait_seconds";
+pub const LIVE_SCRAPER_RUNNING: &str = "quantframe_live_scraper_running";
+pub const LIVE_SCRAPER_CYCLES: &str = "quantframe_live_scraper_cycles_total";
+pub const LIVE_SCRAPER_CYCLE_DURATION: &str = "quantframe_live_scraper_cycle_duration_seconds";
+pub const LIVE_SCRAPER_LAST_CYCLE: &str = "quantframe_live_scraper_last_cycle_timestamp_seconds";
+pub const LIVE_SCRAPER_ORDERS: &str = "quantframe_live_scraper_orders_total";
+pub const LIVE_SCRAPER_ITEMS_CHECKED: &str = "quantframe_live_scraper_items_checked";
+pub const ERRORS: &str = "quantframe_errors_total";
+
+const REQUEST_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+const CYCLE_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0];
+
+#[derive(Clone, Copy)]
+enum MetricType {
+    Counter,
+    Gauge,
+    Histogram(&'static [f64]),
+}
+
+struct Description {
+    name: &'static str,
+    help: &'static str,
+    kind: MetricType,
+}
+
+// Every metric has to be described here, values for unknown names are dropped
+static DESCRIPTIONS: &[Description] = &[
+    Description {
+        name: WFM_REQUESTS,
+        help: "Requests sent to warframe.market by route and status",
+        kind: MetricType::Counter,
+    },
+    Description {
+        name: WFM_REQUEST_DURATION,
+        help: "Latency of requests to warframe.market by route",
+        kind: MetricType::Histogram(REQUEST_BUCKETS),
+    },
+    Description {
+        name: RATE_LIMITER_WAIT,
+        help: "Time spent waiting for a rate limiter token",
+        kind: MetricType::Histogram(REQUEST_BUCKETS),
+    },
+    Description {
+        name: LIVE_SCRAPER_RUNNING,
+        help: "1 while the live scraper loop is running",
+        kind: MetricType::Gauge,
+    },
+    Description {
+        name: LIVE_SCRAPER_CYCLES,
+        help: "Completed live scraper cycles",
+        kind: MetricType::Counter,
+    },
+    Description {
+        name: LIVE_SCRAPER_CYCLE_DURATION,
+        help: "Duration of a live scraper cycle",
+        kind: MetricType::Histogram(CYCLE_BUCKETS),
+    },
+    Description {
+        name: LIVE_SCRAPER_LAST_CYCLE,
+        help: "Unix time of the last completed live scraper cycle",
+        kind: MetricType::Gauge,
+    },
+    Description {
+        name: LIVE_SCRAPER_ORDERS,
+        help: "Orders and auctions created, updated or deleted by the live scraper",
+        kind: MetricType::Counter,
+    },
+    Description {
+        name: LIVE_SCRAPER_ITEMS_CHECKED,
+        help: "Items or rivens checked in the last live scraper cycle",
+        kind: MetricType::Gauge,
+    },
+    Description {
+        name: ERRORS,
+        help: "Logged errors by component and level",
+        kind: MetricType::Counter,
+    },
+];
+
+type Labels = Vec<(String, String)>;
+
+enum MetricValue {
+    Value(f64),
+    Histogram {
+        buckets: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+static REGISTRY: Lazy<Mutex<BTreeMap<&'static str, BTreeMap<Labels, MetricValue>>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+fn describe(name: &str) -> Option<&'static Description> {
+    DESCRIPTIONS.iter().find(|d| d.name == name)
+}
+
+fn to_labels(labels: &[(&str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn update(name: &str, labels: &[(&str, &str)], apply: impl FnOnce(MetricType, &mut MetricValue)) {
+    let description = match describe(name) {
+        Some(description) => description,
+        None => return,
+    };
+    let mut registry = match REGISTRY.lock() {
+        Ok(registry) => registry,
+        Err(_) => return,
+    };
+    let value = registry
+        .entry(description.name)
+        .or_default()
+        .entry(to_labels(labels))
+        .or_insert_with(|| match description.kind {
+            MetricType::Histogram(buckets) => MetricValue::Histogram {
+                buckets: vec![0; buckets.len()],
+                sum: 0.0,
+                count: 0,
+            },
+            _ => MetricValue::Value(0.0),
+        });
+    apply(description.kind, value);
+}
+
+pub fn inc(name: &str, labels: &[(&str, &str)]) {
+    add(name, labels, 1.0);
+}
+
+pub fn add(name: &str, labels: &[(&str, &str)], amount: f64) {
+    update(name, labels, |_, value| {
+        if let MetricValue::Value(value) = value {
+            *value += amount;
+        }
+    });
+}
+
+pub fn set(name: &str, labels: &[(&str, &str)], amount: f64) {
+    update(name, labels, |_, value| {
+        if let MetricValue::Value(value) = value {
+            *value = amount;
+        }
+    });
+}
+
+pub fn observe(name: &str, labels: &[(&str, &str)], duration: Duration) {
+    let seconds = duration.as_secs_f64();
+    update(name, labels, |kind, value| {
+        if let (
+            MetricType::Histogram(bounds),
+            MetricValue::Histogram {
+                buckets,
+                sum,
+                count,
+            },
+        ) = (kind, value)
+        {
+            for (bucket, bound) in buckets.iter_mut().zip(bounds.iter()) {
+                if seconds <= *bound {
+                    *bucket += 1;
+                }
+            }
+            *sum += seconds;
+            *count += 1;
+        }
+    });
+}
+
+pub fn record_error(error: &AppError) {
+    let level = error.log_level();
+    inc(
+        ERRORS,
+        &[("component", &error.component()), ("level", level.as_str())],
+    );
+}
+
+fn format_labels(labels: &Labels, extra: Option<(&str, String)>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    if let Some((key, value)) = extra {
+        pairs.push(format!("{}=\"{}\"", key, value));
+    }
+    if pairs.is_empty() {
+        return "".to_string();
+    }
+    format!("{{{}}}", pairs.join(","))
+}
+
+// The registry in the Prometheus text format
+pub fn render() -> String {
+    let registry = match REGISTRY.lock() {
+        Ok(registry) => registry,
+        Err(_) => return "".to_string(),
+    };
+    let mut output = String::new();
+    for (name, series) in registry.iter() {
+        let description = match describe(name) {
+            Some(description) => description,
+            None => continue,
+        };
+        let kind = match description.kind {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Histogram(_) => "histogram",
+        };
+        output.push_str(&format!("# HELP {} {}\n", name, description.help));
+        output.push_str(&format!("# TYPE {} {}\n", name, kind));
+        for (labels, value) in series.iter() {
+            match (description.kind, value) {
+                (
+                    MetricType::Histogram(bounds),
+                    MetricValue::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    },
+                ) => {
+                    for (bucket, bound) in buckets.iter().zip(bounds.iter()) {
+                        output.push_str(&format!(
+                            "{}_bucket{} {}\n",
+                            name,
+                            format_labels(labels, Some(("le", bound.to_string()))),
+                            bucket
+                        ));
+                    }
+                    output.push_str(&format!(
+                        "{}_bucket{} {}\n",
+                        name,
+                        format_labels(labels, Some(("le", "+Inf".to_string()))),
+                        count
+                    ));
+                    output.push_str(&format!(
+                        "{}_sum{} {}\n",
+                        name,
+                        format_labels(labels, None),
+                        sum
+                    ));
+                    output.push_str(&format!(
+                        "{}_count{} {}\n",
+                        name,
+                        format_labels(labels, None),
+                        count
+                    ));
+                }
+                (_, MetricValue::Value(value)) => {
+                    output.push_str(&format!(
+                        "{}{} {}\n",
+                        name,
+                        format_labels(labels, None),
+                        value
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    output
+}
@@ -0,0 +1,52 @@
+use std::net::SocketAddr;
+
+use tokio::net::TcpStream;
+
+use crate::{
+    error::AppError,
+    http_server::{self, HttpListener, HttpResponse},
+    logger,
+    settings::MetricsSettings,
+};
+
+static COMPONENT: &str = "Metrics";
+
+// Serves GET /metrics for Prometheus, there is nothing else to protect so no auth.
+pub struct MetricsServer {
+    pub addr: SocketAddr,
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl MetricsServer {
+    pub async fn start(settings: &MetricsSettings) -> Result<MetricsServer, AppError> {
+        let listener = HttpListener::bind((settings.host.as_str(), settings.port), COMPONENT)?;
+        let addr = listener.addr;
+        let handle = listener.serve(COMPONENT, handle_connection);
+        logger::info_con(
+            COMPONENT,
+            format!("Metrics available on http://{}/metrics", addr).as_str(),
+        );
+        Ok(MetricsServer { addr, handle })
+    }
+
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream) -> std::io::Result<()> {
+    let request = match http_server::read_request(&mut stream).await? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+    let path = request.target.split('?').next().unwrap_or("");
+    let (status, content) = match (request.method.as_str(), path) {
+        ("GET", "/metrics") => (200, super::render()),
+        _ => (404, "Not found\n".to_string()),
+    };
+    http_server::write_response(
+        &mut stream,
+        HttpResponse::new(status, "text/plain; version=0.0.4", content),
+    )
+    .await
+}
@@ -0,0 +1,113 @@
+use serde_json::json;
+
+use crate::{
+    error::{self, AppError},
+    logger,
+    settings::{NotificationChannel, NotificationChannelKind},
+};
+
+use super::{email, send_with_retry, NotificationMessage};
+
+static COMPONENT: &str = "NotificationChannel";
+static LOG_FILE: &str = "notification_channels.log";
+
+// Sends the message in the background, a failing channel doesn't affect the others.
+pub fn send(channel: NotificationChannel, message: NotificationMessage) {
+    tauri::async_runtime::spawn(async move {
+        match send_to_channel(&channel, &message).await {
+            Ok(_) => logger::info_con(
+                COMPONENT,
+                format!("Message sent to channel {}", channel.name).as_str(),
+            ),
+            Err(e) => error::create_log_file(LOG_FILE.to_string(), &e),
+        }
+    });
+}
+
+pub async fn send_to_channel(
+    channel: &NotificationChannel,
+    message: &NotificationMessage,
+) -> Result<(), AppError> {
+    let component = format!("{}:{}", COMPONENT, channel.name);
+    let client = reqwest::Client::new();
+    match &channel.kind {
+        NotificationChannelKind::Webhook { url, headers } => {
+            let mut request = client.post(url).json(&json!({
+                "kind": message.kind,
+                "title": message.title,
+                "content": message.content,
+                "fields": message.fields,
+                "thumbnail": message.thumbnail,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }));
+            for (key, value) in headers {
+                request = request.header(key, value);
+            }
+            send_with_retry(&component, request).await
+        }
+        NotificationChannelKind::Telegram { bot_token, chat_id } => {
+            let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+            let request = client.post(url).json(&json!({
+                "chat_id": chat_id,
+                "text": format!("{}\n{}", message.title, message.to_plain_text()),
+                "disable_web_page_preview": true,
+            }));
+            send_with_retry(&component, request).await
+        }
+        NotificationChannelKind::Ntfy {
+            server,
+            topic,
+            token,
+        } => {
+            // JSON publishing keeps non-ASCII titles intact, headers can't carry them
+            let mut request = client.post(server.trim_end_matches('/')).json(&json!({
+                "topic": topic,
+                "title": message.title,
+                "message": message.to_plain_text(),
+                "icon": message.thumbnail,
+            }));
+            if let Some(token) = token.as_ref().filter(|t| !t.is_empty()) {
+                request = request.bearer_auth(token);
+            }
+            send_with_retry(&component, request).await
+        }
+        NotificationChannelKind::Gotify {
+            server,
+            token,
+            priority,
+        } => {
+            let url = format!("{}/message", server.trim_end_matches('/'));
+            let request = client
+                .post(url)
+                .header("X-Gotify-Key", token.as_str())
+                .json(&json!({
+                    "title": message.title,
+                    "message": message.to_plain_text(),
+                    "priority": priority,
+                }));
+            send_with_retry(&component, request).await
+        }
+        NotificationChannelKind::Email {
+            host,
+            port,
+            username,
+            password,
+            from,
+            to,
+        } => {
+            email::send(
+                &component,
+                email::SmtpSettings {
+                    host,
+                    port: *port,
+                    username,
+                    password,
+                    from,
+                    to,
+                },
+                message,
+            )
+            .await
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{error, logger, PACKAGEINFO};
+
+use super::{EmbedField, NotificationKind, NotificationMessage};
+
+static COMPONENT: &str = "Discord";
+static LOG_FILE: &str = "discord.log";
+// Discord allows 25 fields per embed
+const MAX_FIELDS: usize = 25;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordEmbed {
+    pub title: String,
+    pub description: String,
+    pub color: u32,
+    pub thumbnail: Option<String>,
+    pub fields: Vec<EmbedField>,
+}
+
+impl DiscordEmbed {
+    pub fn new(title: &str, description: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            description: description.to_string(),
+            color: NotificationKind::NewConversation.color(),
+            thumbnail: None,
+            fields: vec![],
+        }
+    }
+
+    pub fn color(mut self, color: u32) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn thumbnail(mut self, url: Option<String>) -> Self {
+        self.thumbnail = url;
+        self
+    }
+
+    pub fn fields(mut self, fields: Vec<EmbedField>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let version = PACKAGEINFO
+            .lock()
+            .unwrap()
+            .clone()
+            .map(|info| info.version.to_string())
+            .unwrap_or_default();
+        let mut embed = json!({
+            "title": self.title,
+            "description": self.description,
+            "color": self.color,
+            "fields": self.fields.iter().take(MAX_FIELDS).collect::<Vec<_>>(),
+            "footer": {
+                "text": format!("Quantframe v{}", version),
+            },
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+        if let Some(thumbnail) = &self.thumbnail {
+            embed["thumbnail"] = json!({ "url": thumbnail });
+        }
+        embed
+    }
+}
+
+pub fn send_message(webhook: String, message: &NotificationMessage, user_ids: Option<Vec<String>>) {
+    let embed = DiscordEmbed::new(&message.title, &message.content)
+        .color(message.kind.color())
+        .thumbnail(message.thumbnail.clone())
+        .fields(message.fields.clone());
+    send_embed(webhook, embed, user_ids);
+}
+
+// Posts the embed in the background, rate limits are retried with a backoff.
+pub fn send_embed(webhook: String, embed: DiscordEmbed, user_ids: Option<Vec<String>>) {
+    if webhook.is_empty() {
+        logger::warning_con(COMPONENT, "Discord webhook is empty");
+        return;
+    }
+    let pings: Vec<String> = user_ids
+        .unwrap_or_default()
+        .iter()
+        .filter(|id| !id.is_empty())
+        .map(|id| format!("<@{}>", id.replace('"', "")))
+        .collect();
+    let body = json!({
+        "username": "Quantframe",
+        "avatar_url": "https://i.imgur.com/bgR6vAd.png",
+        "content": pings.join(" "),
+        "embeds": [embed.to_json()],
+    });
+    tauri::async_runtime::spawn(async move {
+        let request = reqwest::Client::new().post(webhook).json(&body);
+        match super::send_with_retry(COMPONENT, request).await {
+            Ok(_) => logger::info_con(COMPONENT, "Message sent to discord"),
+            Err(e) => error::create_log_file(LOG_FILE.to_string(), &e),
+        }
+    });
+}
@@ -0,0 +1,57 @@
+use eyre::eyre;
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::error::AppError;
+
+use super::NotificationMessage;
+
+// Port for SMTP over implicit TLS, every other port is upgraded with STARTTLS
+const SMTPS_PORT: u16 = 465;
+
+pub struct SmtpSettings<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    pub username: &'a str,
+    pub password: &'a str,
+    pub from: &'a str,
+    pub to: &'a [String],
+}
+
+pub async fn send(
+    component: &str,
+    smtp: SmtpSettings<'_>,
+    message: &NotificationMessage,
+) -> Result<(), AppError> {
+    let err = |e: String| AppError::new(component, eyre!(e));
+
+    let mut builder = Message::builder()
+        .from(smtp.from.parse().map_err(|e| err(format!("{:?}", e)))?)
+        .subject(message.title.as_str())
+        .header(ContentType::TEXT_PLAIN);
+    for to in smtp.to.iter() {
+        builder = builder.to(to.parse().map_err(|e| err(format!("{:?}", e)))?);
+    }
+    let email = builder
+        .body(message.to_plain_text())
+        .map_err(|e| err(e.to_string()))?;
+
+    let transport = if smtp.port == SMTPS_PORT {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(smtp.host)
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp.host)
+    }
+    .map_err(|e| err(e.to_string()))?;
+    let mailer = transport
+        .port(smtp.port)
+        .credentials(Credentials::new(
+            smtp.username.to_string(),
+            smtp.password.to_string(),
+        ))
+        .build();
+
+    mailer.send(email).await.map_err(|e| err(e.to_string()))?;
+    Ok(())
+}
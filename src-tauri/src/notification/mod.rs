@@ -0,0 +1,221 @@
+use std::{collections::HashMap, time::Duration};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::AppError,
+    handler::MonitorHandler,
+    logger,
+    settings::{Notification, SettingsState},
+};
+
+pub mod channels;
+pub mod discord;
+pub mod email;
+
+// Discord allows 1024 characters per field value, the other channels get the same limit
+const MAX_FIELD_VALUE: usize = 1024;
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub fn wfm_thumbnail(thumb: &str) -> Option<String> {
+    if thumb.is_empty() {
+        return None;
+    }
+    Some(format!("https://warframe.market/static/assets/{}", thumb))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    NewConversation,
+    WfmChatMessage,
+    TradeCompleted,
+    OrderUndercut,
+    RivenSold,
+    ScraperError,
+    RivenSnipe,
+}
+impl NotificationKind {
+    // Embed colour, so the kinds can be told apart at a glance in a busy channel
+    pub fn color(&self) -> u32 {
+        match self {
+            NotificationKind::NewConversation => 5814783,
+            NotificationKind::WfmChatMessage => 3447003,
+            NotificationKind::TradeCompleted => 3066993,
+            NotificationKind::OrderUndercut => 15105570,
+            NotificationKind::RivenSold => 10181046,
+            NotificationKind::ScraperError => 15158332,
+            NotificationKind::RivenSnipe => 1752220,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+impl EmbedField {
+    pub fn new(name: &str, value: &str, inline: bool) -> Self {
+        // Discord rejects the whole message for an empty field value
+        let value = if value.is_empty() { "-" } else { value };
+        Self {
+            name: name.to_string(),
+            value: value.chars().take(MAX_FIELD_VALUE).collect(),
+            inline,
+        }
+    }
+}
+
+// A rendered notification, ready to be sent to any channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationMessage {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub content: String,
+    pub fields: Vec<EmbedField>,
+    pub thumbnail: Option<String>,
+}
+
+impl NotificationMessage {
+    // Content followed by the fields, for channels without embeds
+    pub fn to_plain_text(&self) -> String {
+        let mut lines = vec![self.content.clone()];
+        for field in self.fields.iter() {
+            lines.push(format!("{}: {}", field.name, field.value));
+        }
+        lines.retain(|line| !line.is_empty());
+        lines.join("\n")
+    }
+}
+
+// Replaces every <KEY> placeholder with its value, unknown placeholders are left as they are.
+pub fn render_template(template: &str, values: &HashMap<&str, String>) -> String {
+    let mut rendered = template.to_string();
+    for (key, value) in values {
+        rendered = rendered.replace(&format!("<{}>", key), value);
+    }
+    rendered
+}
+
+// Shows the notification on the enabled channels, the title and content of the
+// notification are rendered with the values before they are sent.
+pub fn notify(
+    mh: &MonitorHandler,
+    settings: &SettingsState,
+    notification: &Notification,
+    kind: NotificationKind,
+    values: &HashMap<&str, String>,
+    fields: Vec<EmbedField>,
+    thumbnail: Option<String>,
+) {
+    let message = NotificationMessage {
+        kind,
+        title: render_template(&notification.title, values),
+        content: render_template(&notification.content, values),
+        fields,
+        thumbnail,
+    };
+    if notification.system_notify {
+        mh.show_notification(
+            &message.title,
+            &message.content,
+            Some("assets/icons/icon.png"),
+            Some("Default"),
+        );
+    }
+    if notification.discord_notify {
+        if let Some(webhook) = notification.webhook.clone() {
+            discord::send_message(webhook, &message, notification.user_ids.clone());
+        }
+    }
+    for name in notification.channels.iter().filter(|name| !name.is_empty()) {
+        match settings
+            .notification_channels
+            .iter()
+            .find(|channel| &channel.name == name)
+        {
+            Some(channel) if channel.enabled => channels::send(channel.clone(), message.clone()),
+            Some(_) => {}
+            None => logger::warning_con(
+                "Notification",
+                format!("Notification channel {} does not exist", name).as_str(),
+            ),
+        }
+    }
+}
+
+// Sends the request until it succeeds, rate limits and server errors are retried
+// with the delay the server asks for or an exponential backoff.
+pub async fn send_with_retry(
+    component: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<(), AppError> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let backoff = (BASE_BACKOFF * 2u32.pow(attempt - 1)).min(MAX_BACKOFF);
+        let attempt_request = request
+            .try_clone()
+            .ok_or_else(|| AppError::new(component, eyre!("Request can't be retried")))?;
+        let response = match attempt_request.send().await {
+            Ok(response) => response,
+            Err(e) if attempt < MAX_ATTEMPTS => {
+                logger::warning_con(
+                    component,
+                    format!("Sending failed: {}, retrying in {:?}", e, backoff).as_str(),
+                );
+                tokio::time::sleep(backoff).await;
+                continue;
+            }
+            Err(e) => return Err(AppError::new(component, eyre!(e.to_string()))),
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let retryable = status.as_u16() == 429 || status.is_server_error();
+        if !retryable || attempt >= MAX_ATTEMPTS {
+            let text = response.text().await.unwrap_or_default();
+            return Err(AppError::new(
+                component,
+                eyre!("Returned {} after {} attempts: {}", status, attempt, text),
+            ));
+        }
+
+        // The wait time is in the Retry-After header, or in the body for Discord and Telegram
+        let mut delay = backoff;
+        if status.as_u16() == 429 {
+            let header = response
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<f64>().ok());
+            let retry_after = match header {
+                Some(seconds) => Some(seconds),
+                None => response.json::<Value>().await.ok().and_then(|json| {
+                    json["retry_after"]
+                        .as_f64()
+                        .or_else(|| json["parameters"]["retry_after"].as_f64())
+                }),
+            };
+            // inf or a huge value doesn't fit in a Duration, wait the longest backoff then
+            if let Some(seconds) = retry_after {
+                delay = Duration::try_from_secs_f64(seconds.max(0.0))
+                    .map_or(MAX_BACKOFF, |retry_after| retry_after.min(MAX_BACKOFF));
+            }
+        }
+        logger::warning_con(
+            component,
+            format!("Returned {}, retrying in {:?}", status, delay).as_str(),
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
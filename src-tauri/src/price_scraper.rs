@@ -17,6 +17,7 @@ use std::{
 extern crate chrono;
 
 use crate::auth::AuthState;
+use crate::database::client::DBClient;
 
 // Structs for the Warframe Market API
 
@@ -26,10 +27,15 @@ pub struct PriceScraper {
     csv_backop_path: String,
     wfm: Arc<Mutex<WFMClient>>,
     auth: Arc<Mutex<AuthState>>,
+    db: Arc<Mutex<DBClient>>,
 }
 
 impl PriceScraper {
-    pub fn new(wfm: Arc<Mutex<WFMClient>>, auth: Arc<Mutex<AuthState>>) -> Self {
+    pub fn new(
+        wfm: Arc<Mutex<WFMClient>>,
+        auth: Arc<Mutex<AuthState>>,
+        db: Arc<Mutex<DBClient>>,
+    ) -> Self {
         PriceScraper {
             csv_path: helper::get_app_roaming_path()
                 .join("price_data.csv")
@@ -43,11 +49,32 @@ impl PriceScraper {
                 .to_string(),
             wfm,
             auth,
+            db,
         }
     }
+    /// Returns the last `days` days of price history stored in the database as a DataFrame.
+    /// The window is counted from the newest day we have data for.
+    pub async fn get_price_historys(&self, days: i64) -> Result<DataFrame, AppError> {
+        let db = self.db.lock()?.clone();
+        self.import_csv_if_empty().await?;
+        let from = db.price_history().get_window_start(days).await?;
+        db.price_history().get_history_dataframe(from, None).await
+    }
+
+    /// Returns the price history between `from` and `to` (both "YYYY-MM-DD", inclusive).
+    pub async fn get_price_history_range(
+        &self,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<DataFrame, AppError> {
+        let db = self.db.lock()?.clone();
+        self.import_csv_if_empty().await?;
+        db.price_history().get_history_dataframe(from, to).await
+    }
+
     /// Reads the price history data from a CSV file and returns it as a DataFrame.
     /// If the backup file is available, it is used instead of the main file.
-    pub fn get_price_historys(&self) -> Result<DataFrame, AppError> {
+    fn read_csv(&self) -> Result<DataFrame, AppError> {
         // Try to read from "allItemDataBackup.csv", and if it fails, read from "allItemData.csv".
         let file = File::open(&self.csv_path)
             .or_else(|_| File::open(&self.csv_backop_path))
@@ -61,6 +88,25 @@ impl PriceScraper {
             .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))
     }
 
+    /// Seeds the database from the old price_data.csv the first time it is used.
+    async fn import_csv_if_empty(&self) -> Result<(), AppError> {
+        let db = self.db.lock()?.clone();
+        if db.price_history().count().await? > 0 {
+            return Ok(());
+        }
+        let df = match self.read_csv() {
+            Ok(df) => df,
+            // Nothing to import
+            Err(_) => return Ok(()),
+        };
+        let rows = db.price_history().upsert_dataframe(&df).await?;
+        logger::info_con(
+            "PriceScraper",
+            format!("Imported {} rows from the price history csv", rows).as_str(),
+        );
+        Ok(())
+    }
+
     pub fn get_status(&self) -> Option<u128> {
         // Try to read from "allItemDataBackup.csv", and if it fails, read from "allItemData.csv".
         let file = File::open(&self.csv_path).or_else(|_| File::open(&self.csv_backop_path));
@@ -429,6 +475,15 @@ impl PriceScraper {
             .collect()
             .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
 
+        // Store the snapshot in the database, days we already have are updated
+        let db = self.db.lock()?.clone();
+        let rows = db.price_history().upsert_dataframe(&filtered_df).await?;
+        logger::info_con(
+            "PriceScraper",
+            format!("Stored {} price history rows", rows).as_str(),
+        );
+
+        // Keep writing the latest snapshot to a csv file, it is used for the last run status
         // Cerate a csv file with the sorted DataFrame of price data
         let output_file: File = File::create(csv_path)
             .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
@@ -0,0 +1,369 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::AuthState,
+    cache::client::CacheClient,
+    database::client::DBClient,
+    error::{self, AppError},
+    handler::MonitorHandler,
+    live_scraper::client::LiveScraperClient,
+    logger,
+    price_scraper::PriceScraper,
+    settings::SettingsState,
+    wf_ee_log_parser::events::trade_reconciliation::TradeReconciler,
+    wfm_client::client::WFMClient,
+};
+
+use super::{Profile, ProfileStore};
+
+// The states one profile trades with
+#[derive(Clone)]
+pub struct ProfileSession {
+    pub settings: Arc<Mutex<SettingsState>>,
+    pub auth: Arc<Mutex<AuthState>>,
+    pub wfm: Arc<Mutex<WFMClient>>,
+    pub db: Arc<Mutex<DBClient>>,
+    pub price_scraper: Arc<Mutex<PriceScraper>>,
+    pub live_scraper: Arc<Mutex<LiveScraperClient>>,
+    pub reconciler: TradeReconciler,
+}
+
+impl ProfileSession {
+    // A session has its own clients, the app states it was made from can be swapped after it
+    pub fn new(
+        profile: &str,
+        settings: SettingsState,
+        auth: AuthState,
+        db: DBClient,
+        wfm: &WFMClient,
+        mh: &Arc<Mutex<MonitorHandler>>,
+    ) -> Self {
+        let settings = Arc::new(Mutex::new(settings));
+        let auth = Arc::new(Mutex::new(auth));
+        let wfm = Arc::new(Mutex::new(
+            wfm.with_account(Arc::clone(&auth), Arc::clone(&settings)),
+        ));
+        let db = Arc::new(Mutex::new(db));
+        let price_scraper = Arc::new(Mutex::new(PriceScraper::new(
+            Arc::clone(&wfm),
+            Arc::clone(&auth),
+            Arc::clone(&db),
+        )));
+        let live_scraper = LiveScraperClient::new(
+            Arc::clone(&settings),
+            Arc::clone(&price_scraper),
+            Arc::clone(&wfm),
+            Arc::clone(&auth),
+            Arc::clone(&db),
+            Arc::clone(mh),
+        )
+        .with_profile(profile);
+        let reconciler =
+            TradeReconciler::new(Arc::clone(&settings), Arc::clone(&db), Arc::clone(&wfm));
+        ProfileSession {
+            settings,
+            auth,
+            wfm,
+            db,
+            price_scraper,
+            live_scraper: Arc::new(Mutex::new(live_scraper)),
+            reconciler,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub ingame_name: Option<String>,
+    pub active: bool,
+    pub live_scraper_running: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    pub revenue: i64,
+    pub cost: i64,
+    pub realised_profit: i64,
+    pub unrealised_value: f64,
+    pub unrealised_profit: f64,
+    pub stock_items: i64,
+    pub stock_rivens: i64,
+}
+
+impl ProfileSummary {
+    fn add(&mut self, other: &ProfileSummary) {
+        self.revenue += other.revenue;
+        self.cost += other.cost;
+        self.realised_profit += other.realised_profit;
+        self.unrealised_value += other.unrealised_value;
+        self.unrealised_profit += other.unrealised_profit;
+        self.stock_items += other.stock_items;
+        self.stock_rivens += other.stock_rivens;
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileReport {
+    pub profile: ProfileInfo,
+    pub summary: ProfileSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfilesReport {
+    pub profiles: Vec<ProfileReport>,
+    pub total: ProfileSummary,
+}
+
+// Keeps a session per profile. The app states the commands use hold the values of the
+// active profile, switching swaps them while the sessions stay as they are.
+#[derive(Clone)]
+pub struct ProfileClient {
+    pub log_file: String,
+    pub store: Arc<Mutex<ProfileStore>>,
+    // Every profile opened since the app started, by id
+    pub sessions: Arc<Mutex<HashMap<String, ProfileSession>>>,
+    pub settings: Arc<Mutex<SettingsState>>,
+    pub auth: Arc<Mutex<AuthState>>,
+    pub wfm: Arc<Mutex<WFMClient>>,
+    pub cache: Arc<Mutex<CacheClient>>,
+    pub db: Arc<Mutex<DBClient>>,
+    pub price_scraper: Arc<Mutex<PriceScraper>>,
+    pub mh: Arc<Mutex<MonitorHandler>>,
+    pub live_scraper: Arc<Mutex<LiveScraperClient>>,
+    // The EE.log is reconciled into the session of the active profile
+    pub reconciler: Arc<Mutex<TradeReconciler>>,
+}
+
+impl ProfileClient {
+    // The app states were loaded from the active profile, its session starts from them
+    pub fn new(
+        store: ProfileStore,
+        settings: Arc<Mutex<SettingsState>>,
+        auth: Arc<Mutex<AuthState>>,
+        wfm: Arc<Mutex<WFMClient>>,
+        cache: Arc<Mutex<CacheClient>>,
+        db: Arc<Mutex<DBClient>>,
+        mh: Arc<Mutex<MonitorHandler>>,
+    ) -> Result<Self, AppError> {
+        let wfm_client = wfm.lock()?.clone();
+        let session = ProfileSession::new(
+            &store.active,
+            settings.lock()?.clone(),
+            auth.lock()?.clone(),
+            db.lock()?.clone(),
+            &wfm_client,
+            &mh,
+        );
+        let price_scraper = Arc::new(Mutex::new(session.price_scraper.lock()?.clone()));
+        let live_scraper = Arc::new(Mutex::new(session.live_scraper.lock()?.clone()));
+        let reconciler = Arc::new(Mutex::new(session.reconciler.clone()));
+        let sessions = HashMap::from([(store.active.clone(), session)]);
+        Ok(ProfileClient {
+            log_file: "profiles.log".to_string(),
+            store: Arc::new(Mutex::new(store)),
+            sessions: Arc::new(Mutex::new(sessions)),
+            settings,
+            auth,
+            wfm,
+            cache,
+            db,
+            price_scraper,
+            mh,
+            live_scraper,
+            reconciler,
+        })
+    }
+
+    pub fn get_profiles(&self) -> Result<Vec<ProfileInfo>, AppError> {
+        let store = self.store.lock()?.clone();
+        let sessions = self.sessions.lock()?.clone();
+        let mut profiles = vec![];
+        for profile in store.profiles {
+            let (ingame_name, live_scraper_running) = match sessions.get(&profile.id) {
+                Some(session) => (
+                    session.auth.lock()?.ingame_name.clone(),
+                    session.live_scraper.lock()?.is_running(),
+                ),
+                None => (
+                    AuthState::read_from_profile(&profile.id)
+                        .map(|auth| auth.ingame_name)
+                        .unwrap_or_default(),
+                    false,
+                ),
+            };
+            profiles.push(ProfileInfo {
+                active: profile.id == store.active,
+                ingame_name: Some(ingame_name).filter(|name| !name.is_empty()),
+                live_scraper_running,
+                profile,
+            });
+        }
+        Ok(profiles)
+    }
+
+    pub fn create(&self, name: &str) -> Result<Profile, AppError> {
+        let mut store = self.store.lock()?;
+        let profile = store.create(name)?;
+        store.save_to_file()?;
+        logger::info_con(
+            "Profiles",
+            format!("Created profile {}", profile.id).as_str(),
+        );
+        Ok(profile)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<Profile, AppError> {
+        if let Some(session) = self.sessions.lock()?.get(id) {
+            if session.live_scraper.lock()?.is_running() {
+                return Err(AppError::new(
+                    "Profiles",
+                    eyre!("Stop the live scraper of the profile before deleting it"),
+                ));
+            }
+        }
+        let mut store = self.store.lock()?;
+        let profile = store.delete(id)?;
+        store.save_to_file()?;
+        self.sessions.lock()?.remove(id);
+        Ok(profile)
+    }
+
+    // Makes the profile the one the app works on, the live scraper of the profile we leave keeps running
+    pub async fn switch(&self, id: &str) -> Result<Profile, AppError> {
+        let profile = self
+            .store
+            .lock()?
+            .find(id)
+            .cloned()
+            .ok_or_else(|| AppError::new("Profiles", eyre!("Profile {} not found", id)))?;
+        let previous = super::get_active_id();
+        if profile.id == previous {
+            return Ok(profile);
+        }
+
+        // Nothing changes for the app until the session of the profile is open
+        let session = match self.open_session(&profile.id).await {
+            Ok(session) => session,
+            Err(e) => {
+                error::create_log_file(self.log_file.clone(), &e);
+                return Err(e);
+            }
+        };
+        super::set_active(&profile.id);
+        *self.settings.lock()? = session.settings.lock()?.clone();
+        *self.auth.lock()? = session.auth.lock()?.clone();
+        *self.db.lock()? = session.db.lock()?.clone();
+        *self.price_scraper.lock()? = session.price_scraper.lock()?.clone();
+        *self.live_scraper.lock()? = session.live_scraper.lock()?.clone();
+        *self.reconciler.lock()? = session.reconciler.clone();
+
+        let mut store = self.store.lock()?;
+        store.active = profile.id.clone();
+        store.save_to_file()?;
+        logger::info_con(
+            "Profiles",
+            format!("Switched from {} to {}", previous, profile.id).as_str(),
+        );
+        Ok(profile)
+    }
+
+    async fn open_session(&self, id: &str) -> Result<ProfileSession, AppError> {
+        if let Some(session) = self.sessions.lock()?.get(id) {
+            return Ok(session.clone());
+        }
+        let db = DBClient::open(
+            Arc::clone(&self.cache),
+            Arc::clone(&self.wfm),
+            super::get_data_path(id)?,
+        )
+        .await?;
+        let wfm = self.wfm.lock()?.clone();
+        let session = ProfileSession::new(
+            id,
+            SettingsState::setup_profile(id)?,
+            AuthState::setup_profile(id)?,
+            db,
+            &wfm,
+            &self.mh,
+        );
+        self.sessions
+            .lock()?
+            .insert(id.to_string(), session.clone());
+        Ok(session)
+    }
+
+    // The commands change the app states, the session of the active profile gets the same values
+    pub fn sync_active(&self) -> Result<(), AppError> {
+        let sessions = self.sessions.lock()?;
+        if let Some(session) = sessions.get(&super::get_active_id()) {
+            *session.settings.lock()? = self.settings.lock()?.clone();
+            *session.auth.lock()? = self.auth.lock()?.clone();
+        }
+        Ok(())
+    }
+
+    // Profit and stock of every profile, profiles that were never opened are read from their database file
+    pub async fn get_report(
+        &self,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<ProfilesReport, AppError> {
+        let profiles = self.get_profiles()?;
+        let mut reports = vec![];
+        let mut total = ProfileSummary::default();
+        for profile in profiles {
+            let session_db = self
+                .sessions
+                .lock()?
+                .get(&profile.profile.id)
+                .map(|session| session.db.lock().map(|db| db.clone()))
+                .transpose()?;
+            let db = match session_db {
+                Some(db) => Some(db),
+                None => {
+                    let data_path = super::get_data_path(&profile.profile.id)?;
+                    if data_path.join("quantframe.sqlite").exists() {
+                        let db = DBClient::open(
+                            Arc::clone(&self.cache),
+                            Arc::clone(&self.wfm),
+                            data_path,
+                        )
+                        .await?;
+                        db.migrate().await?;
+                        Some(db)
+                    } else {
+                        None
+                    }
+                }
+            };
+            let mut summary = ProfileSummary::default();
+            if let Some(db) = db {
+                let report = db
+                    .profit_report()
+                    .get_report(from.clone(), to.clone())
+                    .await?;
+                summary.revenue = report.revenue;
+                summary.cost = report.cost;
+                summary.realised_profit = report.realised_profit;
+                summary.unrealised_value = report.unrealised_value;
+                summary.unrealised_profit = report.unrealised_profit;
+                summary.stock_items = db.stock_item().get_items().await?.len() as i64;
+                summary.stock_rivens = db.stock_riven().get_rivens().await?.len() as i64;
+            }
+            total.add(&summary);
+            reports.push(ProfileReport { profile, summary });
+        }
+        Ok(ProfilesReport {
+            profiles: reports,
+            total,
+        })
+    }
+}
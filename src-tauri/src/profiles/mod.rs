@@ -0,0 +1,175 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AppError, helper};
+
+pub mod client;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Profile the auth.json, settings.json and database of the app states are read from
+static ACTIVE_PROFILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(DEFAULT_PROFILE.to_string()));
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileStore {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+// Allow us to run ProfileStore::default()
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE.to_string(),
+                name: "Default".to_string(),
+                created: chrono::Local::now().naive_local().to_string(),
+            }],
+        }
+    }
+}
+
+impl ProfileStore {
+    fn get_file_path() -> PathBuf {
+        helper::get_app_roaming_path().join("profiles.json")
+    }
+    // Also makes the stored active profile the one the app works on
+    pub fn setup() -> Result<Self, AppError> {
+        let mut store = if Self::get_file_path().exists() {
+            Self::read_from_file()?
+        } else {
+            let default_store = ProfileStore::default();
+            default_store.save_to_file()?;
+            default_store
+        };
+        if store.find(&store.active).is_none() {
+            store.active = DEFAULT_PROFILE.to_string();
+        }
+        set_active(&store.active);
+        Ok(store)
+    }
+    pub fn save_to_file(&self) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+        let mut file = File::create(Self::get_file_path())
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+        file.write_all(json.as_bytes())
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+        Ok(())
+    }
+    pub fn read_from_file() -> Result<Self, AppError> {
+        let mut file = File::open(Self::get_file_path())
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+        let store = serde_json::from_str(&content)
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+        Ok(store)
+    }
+
+    // By id or, for the cli, by name
+    pub fn find(&self, id_or_name: &str) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|p| p.id == id_or_name)
+            .or_else(|| {
+                self.profiles
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(id_or_name))
+            })
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<Profile, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::new("Profiles", eyre!("A profile needs a name")));
+        }
+        // The id names the folder, so it is kept to letters, digits and dashes
+        let slug = name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = if slug.is_empty() {
+            "profile".to_string()
+        } else {
+            slug
+        };
+        let mut id = slug.clone();
+        let mut suffix = 2;
+        while self.profiles.iter().any(|p| p.id == id) {
+            id = format!("{}-{}", slug, suffix);
+            suffix += 1;
+        }
+        let profile = Profile {
+            id,
+            name: name.to_string(),
+            created: chrono::Local::now().naive_local().to_string(),
+        };
+        self.profiles.push(profile.clone());
+        Ok(profile)
+    }
+
+    // The folder of the profile is left, so its data can still be recovered
+    pub fn delete(&mut self, id: &str) -> Result<Profile, AppError> {
+        if id == DEFAULT_PROFILE || id == self.active {
+            return Err(AppError::new(
+                "Profiles",
+                eyre!("The default and the active profile can't be deleted"),
+            ));
+        }
+        let index = self
+            .profiles
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| AppError::new("Profiles", eyre!("Profile {} not found", id)))?;
+        Ok(self.profiles.remove(index))
+    }
+}
+
+// Folder with the files of a profile, the default profile keeps using the app folder
+pub fn get_data_path(id: &str) -> Result<PathBuf, AppError> {
+    let app_path = helper::get_app_roaming_path();
+    if id == DEFAULT_PROFILE {
+        return Ok(app_path);
+    }
+    let profile_path = app_path.join("profiles").join(id);
+    if !profile_path.exists() {
+        fs::create_dir_all(profile_path.clone())
+            .map_err(|e| AppError::new("Profiles", eyre!(e.to_string())))?;
+    }
+    Ok(profile_path)
+}
+
+pub fn get_active_id() -> String {
+    ACTIVE_PROFILE.lock().unwrap().clone()
+}
+
+pub fn get_active_path() -> Result<PathBuf, AppError> {
+    get_data_path(&get_active_id())
+}
+
+pub fn set_active(id: &str) {
+    *ACTIVE_PROFILE.lock().unwrap() = id.to_string();
+}
@@ -1,12 +1,26 @@
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::time::sleep;
 
+use crate::metrics;
+
+// Never slow down below one request per this many seconds
+const MIN_RATE_FACTOR: f64 = 0.2;
+// How much of the rate is won back per successful request after throttling
+const RECOVERY_STEP: f64 = 0.05;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
 #[derive(Clone, Debug)]
 pub struct RateLimiter {
     pub tokens: f64,
     last_timestamp: Instant,
     rate: f64,
     per: Duration,
+    // The configured rate, the current rate is lowered when the server throttles us
+    max_rate: f64,
+    // Nothing is sent before this, set from Retry-After or our own backoff
+    backoff_until: Option<Instant>,
+    // Throttled responses in a row, grows the backoff exponentially
+    throttled: u32,
 }
 
 impl RateLimiter {
@@ -16,6 +30,9 @@ impl RateLimiter {
             last_timestamp: Instant::now(),
             rate,
             per,
+            max_rate: rate,
+            backoff_until: None,
+            throttled: 0,
         }
     }
 
@@ -29,7 +46,7 @@ impl RateLimiter {
         self.last_timestamp = now;
 
         // Ensure the number of tokens doesn't exceed the rate
-        self.tokens = self.tokens.min(self.rate);
+        self.tokens = self.tokens.min(self.rate.max(1.0));
 
         if self.tokens < 1.0 {
             false
@@ -41,11 +58,69 @@ impl RateLimiter {
     }
 
     pub async fn wait_for_token(&mut self) {
+        let started = Instant::now();
+        if let Some(until) = self.backoff_until.take() {
+            let now = Instant::now();
+            if until > now {
+                sleep(until - now).await;
+            }
+        }
         while !self.can_make_request() {
-            let time_to_wait_secs =
-                self.per.as_secs_f64() / self.rate - self.last_timestamp.elapsed().as_secs_f64();
-            let time_to_wait = Duration::from_secs_f64(time_to_wait_secs);
+            let time_to_wait_secs = (1.0 - self.tokens) * self.per.as_secs_f64() / self.rate;
+            let time_to_wait = Duration::from_secs_f64(time_to_wait_secs.max(0.0));
             sleep(time_to_wait).await;
         }
+        metrics::observe(metrics::RATE_LIMITER_WAIT, &[], started.elapsed());
+    }
+
+    // Feeds the response back, 429 and 503 slow us down and the rate recovers with every other response.
+    pub fn on_response(&mut self, status: u16, retry_after: Option<Duration>) {
+        if status != 429 && status != 503 {
+            self.throttled = 0;
+            self.rate = (self.rate + self.max_rate * RECOVERY_STEP).min(self.max_rate);
+            return;
+        }
+        self.throttled += 1;
+        self.rate = (self.rate / 2.0).max(self.max_rate * MIN_RATE_FACTOR);
+        self.tokens = 0.0;
+        let backoff = match retry_after {
+            Some(retry_after) => retry_after.min(MAX_BACKOFF),
+            None => backoff_delay(self.throttled),
+        };
+        self.backoff_until = Some(Instant::now() + backoff);
+    }
+}
+
+// Exponential backoff from one second, with up to 50% jitter so retries don't line up
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let base = Duration::from_secs(1) * 2u32.pow(attempt.saturating_sub(1).min(6));
+    let base = base.min(MAX_BACKOFF);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let jitter = (nanos % 1000) as f64 / 2000.0;
+    base.mul_f64(1.0 + jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_with_up_to_half_jitter() {
+        for (attempt, base) in [(0, 1), (1, 1), (2, 2), (3, 4), (6, 32)] {
+            let base = Duration::from_secs(base);
+            let delay = backoff_delay(attempt);
+            assert!(delay >= base && delay <= base.mul_f64(1.5), "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        for attempt in [7, 20, u32::MAX] {
+            let delay = backoff_delay(attempt);
+            assert!(delay >= MAX_BACKOFF && delay <= MAX_BACKOFF.mul_f64(1.5));
+        }
     }
 }
@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
-use crate::enums::{OrderMode, StockMode};
+use crate::enums::{OrderMode, PricingStrategyType, SnipeTargetType, StockMode};
 use crate::error::AppError;
-use crate::{helper, logger};
+use crate::{crypto, helper, logger, profiles};
 use eyre::eyre;
 
+// Sent to the window instead of a secret, a value still masked on update keeps the stored one
+pub const SECRET_MASK: &str = "********";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SettingsState {
     // Debug Mode
@@ -16,17 +20,31 @@ pub struct SettingsState {
     pub dev_mode: bool,
     pub live_scraper: LiveScraperSettings,
     pub notifications: Notifications,
+    // Extra places notifications can be sent to, referenced by name from a notification
+    pub notification_channels: Vec<NotificationChannel>,
+    pub ee_log: EELogSettings,
+    pub local_api: LocalApiSettings,
+    pub metrics: MetricsSettings,
+    // Profile the settings were read from, a background scraper saves to its own profile
+    #[serde(skip)]
+    pub profile: Option<String>,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LiveScraperSettings {
     // Stock Mode
     pub stock_mode: StockMode,
+    // Only record order/auction changes instead of sending them to warframe.market
+    pub dry_run: bool,
+    // Start the loop again on launch when it was running when the app closed
+    pub resume_on_start: bool,
     // Discord Webhook
     pub webhook: String,
     // Stock Item Settings
     pub stock_item: StockItemSettings,
     // Stock Riven Settings
     pub stock_riven: StockRivenSettings,
+    // Riven auctions to buy when they are listed below a target
+    pub riven_sniper: RivenSniperSettings,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StockItemSettings {
@@ -44,10 +62,45 @@ pub struct StockItemSettings {
     pub strict_whitelist: bool,
     // What to post sell, buy, or both
     pub order_mode: OrderMode,
+    // Pricing strategy used for every item without an override
+    pub pricing_strategy: PricingStrategySettings,
+    // Pricing strategy overrides by item url name
+    pub pricing_overrides: HashMap<String, PricingStrategySettings>,
+    // Days of price history the overlap filter looks at
+    pub price_history_days: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PricingStrategySettings {
+    pub strategy: PricingStrategyType,
+    // Undercut amount or margin, depending on the strategy
+    pub value: i64,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StockRivenSettings {
     pub range_threshold: i64,
+    // List above the cheapest matching auction when the estimated fair value is higher
+    pub use_fair_value: bool,
+    // 0 to 1, estimates below this are only shown
+    pub fair_value_confidence: f64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RivenSniperSettings {
+    pub enabled: bool,
+    // Minutes between two searches of the watchlist
+    pub interval: i64,
+    pub watchlist: Vec<RivenWatchSettings>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RivenWatchSettings {
+    pub weapon_url: String,
+    // Attribute url names the riven needs to have
+    pub positive_stats: Vec<String>,
+    pub negative_stat: Option<String>,
+    pub mastery_rank_max: Option<u8>,
+    pub re_rolls_max: Option<u8>,
+    pub target: SnipeTargetType,
+    // Platinum for a fixed target, percent of the recent price for a relative one
+    pub value: i64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -59,12 +112,85 @@ pub struct Notification {
     // Use For Discord
     pub webhook: Option<String>,
     pub user_ids: Option<Vec<String>>,
+    // Names of the notification channels this notification is also sent to
+    pub channels: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Notifications {
     pub on_new_conversation: Notification,
     pub on_wfm_chat_message: Notification,
+    pub on_trade_completed: Notification,
+    pub on_order_undercut: Notification,
+    pub on_riven_sold: Notification,
+    pub on_scraper_error: Notification,
+    pub on_riven_snipe: Notification,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub name: String,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub kind: NotificationChannelKind,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannelKind {
+    // Posts the notification as JSON
+    Webhook {
+        url: String,
+        headers: HashMap<String, String>,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+    },
+    Ntfy {
+        server: String,
+        topic: String,
+        token: Option<String>,
+    },
+    Gotify {
+        server: String,
+        token: String,
+        priority: i64,
+    },
+    Email {
+        host: String,
+        // 465 uses implicit TLS, every other port STARTTLS
+        port: u16,
+        username: String,
+        password: String,
+        from: String,
+        to: Vec<String>,
+    },
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EELogSettings {
+    // Keyed by the name of the event handler
+    pub events: HashMap<String, EELogEventSettings>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EELogEventSettings {
+    pub enabled: bool,
+    // Handlers with a higher priority see a line first, the first one to claim it wins
+    pub priority: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalApiSettings {
+    // Read when the app starts
+    pub enabled: bool,
+    pub port: u16,
+    // Sent as "Authorization: Bearer <token>", the server won't start without one
+    pub token: String,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MetricsSettings {
+    // Read when the app starts
+    pub enabled: bool,
+    // Use 0.0.0.0 to let a Prometheus server on another machine scrape it
+    pub host: String,
+    pub port: u16,
 }
 // Allow us to run AuthState::default()
 impl Default for SettingsState {
@@ -74,6 +200,8 @@ impl Default for SettingsState {
             dev_mode: false,
             live_scraper: LiveScraperSettings {
                 stock_mode: StockMode::All,
+                dry_run: false,
+                resume_on_start: false,
                 webhook: "".to_string(),
                 stock_item: StockItemSettings {
                     volume_threshold: 15,
@@ -87,9 +215,22 @@ impl Default for SettingsState {
                     report_to_wfm: true,
                     auto_trade: true,
                     order_mode: OrderMode::Both,
+                    pricing_strategy: PricingStrategySettings {
+                        strategy: PricingStrategyType::Default,
+                        value: 0,
+                    },
+                    pricing_overrides: HashMap::new(),
+                    price_history_days: 7,
                 },
                 stock_riven: StockRivenSettings {
                     range_threshold: 25,
+                    use_fair_value: true,
+                    fair_value_confidence: 0.6,
+                },
+                riven_sniper: RivenSniperSettings {
+                    enabled: false,
+                    interval: 5,
+                    watchlist: vec![],
                 },
             },
             notifications: Notifications {
@@ -100,6 +241,7 @@ impl Default for SettingsState {
                     title: "New Conversation".to_string(),
                     webhook: Some("".to_string()),
                     user_ids: Some(vec![]),
+                    channels: vec![],
                 },
                 on_wfm_chat_message: Notification {
                     discord_notify: false,
@@ -108,21 +250,149 @@ impl Default for SettingsState {
                     title: "New WFM Message".to_string(),
                     webhook: Some("".to_string()),
                     user_ids: Some(vec![]),
+                    channels: vec![],
                 },
+                on_trade_completed: Notification {
+                    discord_notify: false,
+                    system_notify: true,
+                    content: "Trade accepted from <PLAYER_NAME>".to_string(),
+                    title: "Trade Accepted".to_string(),
+                    webhook: Some("".to_string()),
+                    user_ids: Some(vec![]),
+                    channels: vec![],
+                },
+                on_order_undercut: Notification {
+                    discord_notify: false,
+                    system_notify: false,
+                    content: "<ITEM_NAME> was undercut, moved from <OLD_PRICE>p to <NEW_PRICE>p"
+                        .to_string(),
+                    title: "Order Undercut".to_string(),
+                    webhook: Some("".to_string()),
+                    user_ids: Some(vec![]),
+                    channels: vec![],
+                },
+                on_riven_sold: Notification {
+                    discord_notify: false,
+                    system_notify: false,
+                    content: "Sold <WEAPON_NAME> <MOD_NAME> for <PLATINUM>p".to_string(),
+                    title: "Riven Sold".to_string(),
+                    webhook: Some("".to_string()),
+                    user_ids: Some(vec![]),
+                    channels: vec![],
+                },
+                on_scraper_error: Notification {
+                    discord_notify: false,
+                    system_notify: true,
+                    content: "The live scraper paused for <MINUTES> minutes: <ERROR>".to_string(),
+                    title: "Live Scraper Paused".to_string(),
+                    webhook: Some("".to_string()),
+                    user_ids: Some(vec![]),
+                    channels: vec![],
+                },
+                on_riven_snipe: Notification {
+                    discord_notify: false,
+                    system_notify: true,
+                    content: "<WEAPON_NAME> <MOD_NAME> for <PRICE>p, target <TARGET>p\n<WHISPER>"
+                        .to_string(),
+                    title: "Riven Deal".to_string(),
+                    webhook: Some("".to_string()),
+                    user_ids: Some(vec![]),
+                    channels: vec![],
+                },
+            },
+            notification_channels: vec![],
+            ee_log: EELogSettings {
+                events: HashMap::from([
+                    ee_log_event("conversation", 100),
+                    ee_log_event("trading", 90),
+                    ee_log_event("login", 50),
+                    ee_log_event("mission", 40),
+                    ee_log_event("relic_reward", 30),
+                ]),
             },
+            local_api: LocalApiSettings {
+                enabled: false,
+                port: 7878,
+                token: "".to_string(),
+            },
+            metrics: MetricsSettings {
+                enabled: false,
+                host: "127.0.0.1".to_string(),
+                port: 9185,
+            },
+            profile: None,
+        }
+    }
+}
+fn ee_log_event(name: &str, priority: i64) -> (String, EELogEventSettings) {
+    (
+        name.to_string(),
+        EELogEventSettings {
+            enabled: true,
+            priority,
+        },
+    )
+}
+impl NotificationChannelKind {
+    // Tokens, passwords and header values, keyed by the kind and the field they are in
+    fn secrets_mut(&mut self) -> Vec<(String, &mut String)> {
+        match self {
+            NotificationChannelKind::Webhook { headers, .. } => headers
+                .iter_mut()
+                .map(|(name, value)| (format!("webhook.headers.{}", name), value))
+                .collect(),
+            NotificationChannelKind::Telegram { bot_token, .. } => {
+                vec![("telegram.bot_token".to_string(), bot_token)]
+            }
+            NotificationChannelKind::Ntfy { token, .. } => token
+                .iter_mut()
+                .map(|token| ("ntfy.token".to_string(), token))
+                .collect(),
+            NotificationChannelKind::Gotify { token, .. } => {
+                vec![("gotify.token".to_string(), token)]
+            }
+            NotificationChannelKind::Email { password, .. } => {
+                vec![("email.password".to_string(), password)]
+            }
         }
     }
 }
+impl Notifications {
+    fn all_mut(&mut self) -> Vec<(&str, &mut Notification)> {
+        vec![
+            ("on_new_conversation", &mut self.on_new_conversation),
+            ("on_wfm_chat_message", &mut self.on_wfm_chat_message),
+            ("on_trade_completed", &mut self.on_trade_completed),
+            ("on_order_undercut", &mut self.on_order_undercut),
+            ("on_riven_sold", &mut self.on_riven_sold),
+            ("on_scraper_error", &mut self.on_scraper_error),
+            ("on_riven_snipe", &mut self.on_riven_snipe),
+        ]
+    }
+}
+impl Notification {
+    pub fn is_enabled(&self) -> bool {
+        self.system_notify || self.discord_notify || self.channels.iter().any(|c| !c.is_empty())
+    }
+}
 impl SettingsState {
-    fn get_file_path() -> PathBuf {
-        let app_path = helper::get_app_roaming_path();
+    fn get_file_path(profile: Option<&str>) -> Result<PathBuf, AppError> {
+        let app_path = match profile {
+            Some(profile) => profiles::get_data_path(profile)?,
+            None => profiles::get_active_path()?,
+        };
         let settings_path = app_path.join("settings.json");
-        settings_path
+        Ok(settings_path)
     }
     pub fn setup() -> Result<Self, AppError> {
-        let path_ref = Self::get_file_path();
+        Self::setup_profile(&profiles::get_active_id())
+    }
+    // The profile doesn't have to be the active one
+    pub fn setup_profile(profile: &str) -> Result<Self, AppError> {
+        let path_ref = Self::get_file_path(Some(profile))?;
         if path_ref.exists() {
-            let (se, vaild) = Self::read_from_file()?;
+            let (mut se, vaild) = Self::read_from_file(profile)?;
+            se.profile = Some(profile.to_string());
             if vaild {
                 Ok(se)
             } else {
@@ -130,24 +400,83 @@ impl SettingsState {
                 Ok(se)
             }
         } else {
-            let default_settings = SettingsState::default();
+            let mut default_settings = SettingsState::default();
+            default_settings.profile = Some(profile.to_string());
             default_settings.save_to_file()?;
             Ok(default_settings)
         }
     }
 
+    // Keyed by where they are, a channel by its name
+    fn secrets_mut(&mut self) -> Vec<(String, &mut String)> {
+        let mut secrets = vec![
+            ("local_api.token".to_string(), &mut self.local_api.token),
+            // Anyone with a Discord webhook url can post to the channel
+            (
+                "live_scraper.webhook".to_string(),
+                &mut self.live_scraper.webhook,
+            ),
+        ];
+        for (name, notification) in self.notifications.all_mut() {
+            if let Some(webhook) = notification.webhook.as_mut() {
+                secrets.push((format!("notifications.{}.webhook", name), webhook));
+            }
+        }
+        for channel in self.notification_channels.iter_mut() {
+            for (key, secret) in channel.kind.secrets_mut() {
+                secrets.push((
+                    format!("notification_channels.{}.{}", channel.name, key),
+                    secret,
+                ));
+            }
+        }
+        secrets
+    }
+
+    // Copy for the window, the secrets stay in the backend
+    pub fn masked(&self) -> Self {
+        let mut settings = self.clone();
+        for (_, secret) in settings.secrets_mut() {
+            if !secret.is_empty() {
+                *secret = SECRET_MASK.to_string();
+            }
+        }
+        settings
+    }
+
+    // Puts back the secrets the window sent masked, channels are matched by name and kind
+    pub fn restore_secrets(&mut self, current: &SettingsState) {
+        let mut current = current.clone();
+        let stored = current
+            .secrets_mut()
+            .into_iter()
+            .map(|(key, secret)| (key, secret.clone()))
+            .collect::<HashMap<_, _>>();
+        for (key, secret) in self.secrets_mut() {
+            if *secret == SECRET_MASK {
+                *secret = stored.get(&key).cloned().unwrap_or_default();
+            }
+        }
+    }
+
     pub fn save_to_file(&self) -> Result<(), AppError> {
-        let json = serde_json::to_string_pretty(self)
+        let mut stored = self.clone();
+        for (_, secret) in stored.secrets_mut() {
+            if !secret.is_empty() && !crypto::is_encrypted(secret) {
+                *secret = crypto::encrypt(secret)?;
+            }
+        }
+        let json = serde_json::to_string_pretty(&stored)
             .map_err(|e| AppError::new("Settings", eyre!(e.to_string())))?;
-        let mut file = File::create(Self::get_file_path())
+        let mut file = File::create(Self::get_file_path(self.profile.as_deref())?)
             .map_err(|e| AppError::new("Settings", eyre!(e.to_string())))?;
         file.write_all(json.as_bytes())
             .map_err(|e| AppError::new("Settings", eyre!(e.to_string())))?;
         Ok(())
     }
 
-    pub fn read_from_file() -> Result<(Self, bool), AppError> {
-        let mut file = File::open(Self::get_file_path())
+    pub fn read_from_file(profile: &str) -> Result<(Self, bool), AppError> {
+        let mut file = File::open(Self::get_file_path(Some(profile))?)
             .map_err(|e| AppError::new("Settings", eyre!(e.to_string())))?;
         let mut content = String::new();
         file.read_to_string(&mut content)
@@ -176,9 +505,150 @@ impl SettingsState {
         }
 
         // Deserialize the updated JSON object into a SettingsState struct
-        let deserialized: SettingsState = serde_json::from_value(validated_json)
+        let mut deserialized: SettingsState = serde_json::from_value(validated_json)
             .map_err(|e| AppError::new("Settings", eyre!(e.to_string())))?;
 
-        Ok((deserialized, missing_properties.is_empty()))
+        // Secrets saved in plain text by older versions are encrypted right away
+        let mut plain = false;
+        for (_, secret) in deserialized.secrets_mut() {
+            if secret.is_empty() {
+                continue;
+            }
+            plain |= !crypto::is_encrypted(secret);
+            // Without the key the secret has to be entered again
+            *secret = match crypto::decrypt(secret) {
+                Ok(value) => value,
+                Err(e) => {
+                    logger::warning_con("Settings", e.cause().as_str());
+                    "".to_string()
+                }
+            };
+        }
+
+        Ok((deserialized, missing_properties.is_empty() && !plain))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(name: &str, token: Option<&str>) -> NotificationChannel {
+        NotificationChannel {
+            name: name.to_string(),
+            enabled: true,
+            kind: NotificationChannelKind::Ntfy {
+                server: "https://ntfy.sh".to_string(),
+                topic: "trades".to_string(),
+                token: token.map(|t| t.to_string()),
+            },
+        }
+    }
+
+    fn ntfy_token(settings: &SettingsState, index: usize) -> Option<String> {
+        match &settings.notification_channels[index].kind {
+            NotificationChannelKind::Ntfy { token, .. } => token.clone(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn secrets_are_masked_for_the_window_and_restored() {
+        let mut current = SettingsState::default();
+        current.local_api.token = "api-token".to_string();
+        current.notification_channels =
+            vec![channel("phone", Some("secret")), channel("open", None)];
+
+        let mut window = current.masked();
+        assert_eq!(window.local_api.token, SECRET_MASK);
+        assert_eq!(ntfy_token(&window, 0).as_deref(), Some(SECRET_MASK));
+        assert_eq!(ntfy_token(&window, 1), None);
+
+        window
+            .notification_channels
+            .push(channel("new", Some("typed")));
+        window.restore_secrets(&current);
+        assert_eq!(window.local_api.token, "api-token");
+        assert_eq!(ntfy_token(&window, 0).as_deref(), Some("secret"));
+        assert_eq!(ntfy_token(&window, 2).as_deref(), Some("typed"));
+    }
+
+    #[test]
+    fn masked_secrets_of_a_renamed_channel_are_dropped() {
+        let mut current = SettingsState::default();
+        current.notification_channels = vec![channel("phone", Some("secret"))];
+
+        let mut window = current.masked();
+        window.notification_channels[0].name = "tablet".to_string();
+        window.restore_secrets(&current);
+        assert_eq!(ntfy_token(&window, 0).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn webhook_headers_are_restored_by_name() {
+        let mut current = SettingsState::default();
+        current.notification_channels = vec![NotificationChannel {
+            name: "hook".to_string(),
+            enabled: true,
+            kind: NotificationChannelKind::Webhook {
+                url: "https://example.com/hook".to_string(),
+                headers: HashMap::from([
+                    ("Authorization".to_string(), "Bearer abc".to_string()),
+                    ("X-Api-Key".to_string(), "key".to_string()),
+                ]),
+            },
+        }];
+
+        let mut window = current.masked();
+        match &mut window.notification_channels[0].kind {
+            NotificationChannelKind::Webhook { headers, .. } => {
+                assert_eq!(headers["Authorization"], SECRET_MASK);
+                assert_eq!(headers["X-Api-Key"], SECRET_MASK);
+                headers.insert("Accept".to_string(), "application/json".to_string());
+                headers.remove("X-Api-Key");
+            }
+            _ => unreachable!(),
+        }
+        window.restore_secrets(&current);
+        match &window.notification_channels[0].kind {
+            NotificationChannelKind::Webhook { headers, .. } => {
+                assert_eq!(headers["Authorization"], "Bearer abc");
+                assert_eq!(headers["Accept"], "application/json");
+                assert!(!headers.contains_key("X-Api-Key"));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn discord_webhooks_are_masked_and_restored() {
+        let url = "https://discord.com/api/webhooks/1/abc".to_string();
+        let mut current = SettingsState::default();
+        current.live_scraper.webhook = url.clone();
+        current.notifications.on_trade_completed.webhook = Some(url.clone());
+
+        let mut window = current.masked();
+        assert_eq!(window.live_scraper.webhook, SECRET_MASK);
+        assert_eq!(
+            window.notifications.on_trade_completed.webhook.as_deref(),
+            Some(SECRET_MASK)
+        );
+        // Empty webhooks stay empty, so the window can tell they aren't set
+        assert_eq!(
+            window.notifications.on_riven_sold.webhook.as_deref(),
+            Some("")
+        );
+
+        window.notifications.on_riven_sold.webhook = Some("typed".to_string());
+        window.restore_secrets(&current);
+        assert_eq!(window.live_scraper.webhook, url);
+        assert_eq!(
+            window.notifications.on_trade_completed.webhook.as_deref(),
+            Some(url.as_str())
+        );
+        assert_eq!(
+            window.notifications.on_riven_sold.webhook.as_deref(),
+            Some("typed")
+        );
     }
 }
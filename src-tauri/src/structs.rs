@@ -2,13 +2,20 @@ use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 
 use crate::enums::OrderType;
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum WarframeLanguage {
     English, // Add other language variants as needed
     French,
     Spanish,
     German,
     Russian,
+    Portuguese,
+    Polish,
+    Italian,
+    Japanese,
+    Korean,
+    Chinese,
+    Turkish,
     Unknown,
 }
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
@@ -32,6 +39,13 @@ impl WarframeLanguage {
             "es" => WarframeLanguage::Spanish,
             "de" => WarframeLanguage::German,
             "ru" => WarframeLanguage::Russian,
+            "pt" => WarframeLanguage::Portuguese,
+            "pl" => WarframeLanguage::Polish,
+            "it" => WarframeLanguage::Italian,
+            "ja" => WarframeLanguage::Japanese,
+            "ko" => WarframeLanguage::Korean,
+            "zh" => WarframeLanguage::Chinese,
+            "tr" => WarframeLanguage::Turkish,
             _ => WarframeLanguage::Unknown,
         }
     }
@@ -72,7 +86,7 @@ pub struct RivenAttributeInfo {
     negative_only: bool,
 
     #[serde(rename = "effect")]
-    effect: String,
+    pub effect: String,
 
     #[serde(rename = "id")]
     id: String,
@@ -1,7 +1,6 @@
 use crate::cache::client::CacheClient;
 use crate::error::AppError;
 use crate::handler::MonitorHandler;
-use crate::settings::SettingsState;
 use crate::{helper, logger};
 use serde_json::json;
 use std::fs::File;
@@ -12,8 +11,14 @@ use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
+use super::events::on_login::OnLoginEvent;
+use super::events::on_mission::OnMissionEvent;
 use super::events::on_new_conversation::OnNewConversationEvent;
-use super::events::on_new_trading::OnTradingEvent;
+use super::events::on_new_trading::{OnTradingEvent, TradeEventMode};
+use super::events::on_relic_reward::OnRelicRewardEvent;
+use super::events::trade_reconciliation::TradeReconciler;
+use super::registry::{EELogEvent, EventRegistry};
+use super::replay::{self, ReplayReport};
 
 #[derive(Clone, Debug)]
 pub struct EELogParser {
@@ -24,20 +29,22 @@ pub struct EELogParser {
     last_line_index: Arc<Mutex<usize>>,
     handle: Arc<Mutex<Option<JoinHandle<()>>>>,
     cold_start: Arc<AtomicBool>,
-    pub settings: Arc<Mutex<crate::settings::SettingsState>>,
+    // Session of the active profile, swapped when the profile is switched
+    pub reconciler: Arc<Mutex<TradeReconciler>>,
+    mh: Arc<Mutex<MonitorHandler>>,
+    cache: Arc<Mutex<CacheClient>>,
     // Events
-    event_conversation: Arc<Mutex<OnNewConversationEvent>>,
-    event_trading: Arc<Mutex<OnTradingEvent>>,
+    events: Arc<Mutex<EventRegistry>>,
 }
 
 impl EELogParser {
     pub fn new(
-        settings: Arc<Mutex<SettingsState>>,
+        reconciler: Arc<Mutex<TradeReconciler>>,
         mh: Arc<Mutex<MonitorHandler>>,
         cache: Arc<Mutex<CacheClient>>,
     ) -> Self {
         let wf_ee_path = helper::get_app_local_path().join("Warframe").join("EE.log");
-        Self {
+        let parser = Self {
             is_running: Arc::new(AtomicBool::new(false)),
             component: "EELogParser".to_string(),
             wf_ee_path: wf_ee_path.clone(),
@@ -45,23 +52,42 @@ impl EELogParser {
             last_line_index: Arc::new(Mutex::new(0)),
             handle: Arc::new(Mutex::new(None)),
             cold_start: Arc::new(AtomicBool::new(true)),
-            settings: Arc::clone(&settings),
-            event_conversation: Arc::new(Mutex::new(OnNewConversationEvent::new(
-                Arc::clone(&settings),
-                Arc::clone(&mh),
-                wf_ee_path.clone(),
-            ))),
-            event_trading: Arc::new(Mutex::new(OnTradingEvent::new(
-                Arc::clone(&settings),
-                Arc::clone(&mh),
-                Arc::clone(&cache),
-                wf_ee_path.clone(),
-            ))),
-        }
+            reconciler: Arc::clone(&reconciler),
+            mh: Arc::clone(&mh),
+            cache: Arc::clone(&cache),
+            events: Arc::new(Mutex::new(EventRegistry::default())),
+        };
+        parser.register_event(Box::new(OnNewConversationEvent::new(
+            Arc::clone(&reconciler),
+            Arc::clone(&mh),
+            wf_ee_path.clone(),
+        )));
+        parser.register_event(Box::new(OnTradingEvent::new(
+            Arc::clone(&reconciler),
+            Arc::clone(&mh),
+            Arc::clone(&cache),
+            wf_ee_path.clone(),
+        )));
+        parser.register_event(Box::new(OnLoginEvent::new()));
+        parser.register_event(Box::new(OnMissionEvent::new()));
+        parser.register_event(Box::new(OnRelicRewardEvent::new()));
+        parser
+    }
+
+    // Adds a handler for new lines, a handler with the same name is replaced
+    pub fn register_event(&self, event: Box<dyn EELogEvent>) {
+        self.events.lock().unwrap().register(event);
     }
 
     pub fn start_loop(&mut self) {
-        logger::info_con(self.component.as_str(), "Starting EE Log Parser");
+        logger::info_con(
+            self.component.as_str(),
+            format!(
+                "Starting EE Log Parser with events: {}",
+                self.events.lock().unwrap().names().join(", ")
+            )
+            .as_str(),
+        );
         let is_running = Arc::clone(&self.is_running);
 
         let scraper = self.clone();
@@ -92,23 +118,59 @@ impl EELogParser {
         self.is_running.load(Ordering::SeqCst)
     }
 
+    // Feeds old log files through fresh event handlers, the live parser is not affected.
+    pub fn replay(
+        &self,
+        path: PathBuf,
+        speed: f64,
+        reconcile: bool,
+    ) -> Result<ReplayReport, AppError> {
+        let files = replay::get_log_files(&path)?;
+        let mut report = ReplayReport::default();
+        for file in files {
+            let event_conversation = OnNewConversationEvent::new(
+                Arc::clone(&self.reconciler),
+                Arc::clone(&self.mh),
+                file.clone(),
+            );
+            let mut event_trading = OnTradingEvent::new(
+                Arc::clone(&self.reconciler),
+                Arc::clone(&self.mh),
+                Arc::clone(&self.cache),
+                file.clone(),
+            );
+            event_trading.set_mode(TradeEventMode::Replay { reconcile });
+            replay::replay_file(
+                &file,
+                speed,
+                &event_conversation,
+                &mut event_trading,
+                &mut report,
+            )?;
+        }
+        logger::info_con(
+            self.component.as_str(),
+            format!(
+                "Replayed {} lines from {} files, found {} trades",
+                report.lines,
+                report.files.len(),
+                report.trades.len()
+            )
+            .as_str(),
+        );
+        Ok(report)
+    }
+
     fn check(&self) -> Result<(), AppError> {
         let new_lines_result = self.read_new_lines(self.cold_start.load(Ordering::SeqCst));
 
         // Events to check
-        let event_conversation = self.event_conversation.lock()?.clone();
-        let mut event_trading = self.event_trading.lock()?;
+        let settings = self.reconciler.lock()?.settings.lock()?.ee_log.clone();
+        let mut events = self.events.lock()?;
 
         match new_lines_result {
             Ok(new_lines) => {
-                for line in new_lines {
-                    if event_conversation.check(line.0, &line.1)? {
-                        continue;
-                    }
-                    if event_trading.check(line.0, &line.1)? {
-                        continue;
-                    }
-                }
+                events.check_lines(&settings, &new_lines)?;
             }
             Err(err) => {
                 helper::send_message_to_window("EELogParser", Some(json!({ "error": "err" })));
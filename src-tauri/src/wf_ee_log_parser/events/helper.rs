@@ -23,6 +23,11 @@ pub fn match_pattern(
     Ok((false, vec![]))
 }
 
+// Lines start with the seconds since the game was started, "123.456 Sys [Info]: ..."
+pub fn get_timestamp(line: &str) -> Option<f64> {
+    line.split_whitespace().next()?.parse::<f64>().ok()
+}
+
 pub fn get_range_of_lines(
     file_path: &str,
     start_line: i32,
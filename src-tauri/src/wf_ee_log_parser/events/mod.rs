@@ -1,3 +1,8 @@
 pub mod helper;
+pub mod on_login;
+pub mod on_mission;
 pub mod on_new_conversation;
 pub mod on_new_trading;
+pub mod on_relic_reward;
+pub mod trade_log_messages;
+pub mod trade_reconciliation;
@@ -0,0 +1,115 @@
+use eyre::eyre;
+use serde_json::json;
+
+use crate::{error::AppError, helper, logger, wf_ee_log_parser::registry::EELogEvent};
+
+enum Events {
+    Login,
+    Logout,
+}
+impl Events {
+    fn as_str_list(&self) -> Vec<String> {
+        match self {
+            Events::Login => vec![r"Sys \[Info\]: Logged in (?<name>\S+) \(".to_string()],
+            Events::Logout => vec![
+                r"Sys \[Info\]: Logged out".to_string(),
+                r"Sys \[Info\]: Main Shutdown Initiated".to_string(),
+            ],
+        }
+    }
+}
+
+// Tracks which account is logged in to the game
+#[derive(Debug, Default)]
+pub struct OnLoginEvent {
+    user_name: Option<String>,
+}
+
+impl OnLoginEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EELogEvent for OnLoginEvent {
+    fn name(&self) -> &'static str {
+        "login"
+    }
+
+    fn default_priority(&self) -> i64 {
+        50
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
+        if !input.contains("Sys [Info]: ") {
+            return Ok(false);
+        }
+        let (found, captures) = super::helper::match_pattern(input, Events::Login.as_str_list())
+            .map_err(|e| AppError::new("OnLoginEvent", eyre!(e)))?;
+        if found {
+            let user_name = captures.get(0).cloned().flatten().unwrap_or_default();
+            logger::info_con("OnLoginEvent", &format!("Logged in as {}", user_name));
+            helper::send_message_to_window("Client:Login", Some(json!({ "user_name": user_name })));
+            self.user_name = Some(user_name);
+            return Ok(true);
+        }
+
+        let (found, _) = super::helper::match_pattern(input, Events::Logout.as_str_list())
+            .map_err(|e| AppError::new("OnLoginEvent", eyre!(e)))?;
+        if found {
+            // The shutdown line also shows up when the game closes without logging in
+            if let Some(user_name) = self.user_name.take() {
+                logger::info_con("OnLoginEvent", &format!("{} logged out", user_name));
+                helper::send_message_to_window(
+                    "Client:Logout",
+                    Some(json!({ "user_name": user_name })),
+                );
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOGIN: &str = "12.801 Sys [Info]: Logged in Trader123 (5a8c3f2e9d1b4c0017e6a2b4) (Steam)";
+    const LOGOUT: &str = "4021.337 Sys [Info]: Logged out";
+    const SHUTDOWN: &str = "4025.410 Sys [Info]: Main Shutdown Initiated.";
+
+    #[test]
+    fn login_and_logout_lines_are_claimed() {
+        let mut event = OnLoginEvent::new();
+        assert!(event.check(0, LOGIN).unwrap());
+        assert_eq!(event.user_name.as_deref(), Some("Trader123"));
+
+        assert!(event.check(1, LOGOUT).unwrap());
+        assert_eq!(event.user_name, None);
+        // The shutdown after a logout is still ours, there is just nobody to log out
+        assert!(event.check(2, SHUTDOWN).unwrap());
+        assert_eq!(event.user_name, None);
+    }
+
+    #[test]
+    fn shutdown_logs_out_the_user() {
+        let mut event = OnLoginEvent::new();
+        event.check(0, LOGIN).unwrap();
+        assert!(event.check(1, SHUTDOWN).unwrap());
+        assert_eq!(event.user_name, None);
+    }
+
+    #[test]
+    fn other_lines_are_left_alone() {
+        let mut event = OnLoginEvent::new();
+        let lines = [
+            "1030.118 Sys [Info]: Dialog.lua: Dialog::CreateOk(description=The trade was successful!, leftItem=/Menu/Confirm_Item_Ok)",
+            "1021.004 Script [Info]: ChatRedux.lua: ChatRedux::AddTab: Adding tab with channel name: FTrader to index 5",
+        ];
+        for (index, line) in lines.iter().enumerate() {
+            assert!(!event.check(index, line).unwrap());
+        }
+        assert_eq!(event.user_name, None);
+    }
+}
@@ -0,0 +1,154 @@
+use eyre::eyre;
+use serde_json::json;
+
+use crate::{error::AppError, helper, logger, wf_ee_log_parser::registry::EELogEvent};
+
+enum Events {
+    Start,
+    End,
+}
+impl Events {
+    fn as_str_list(&self) -> Vec<String> {
+        match self {
+            Events::Start => vec![
+                r"Script \[Info\]: ThemedSquadOverlay\.lua: Mission name: (?<name>.+)".to_string(),
+            ],
+            Events::End => vec![
+                r"Script \[Info\]: EndOfMatch\.lua: Initialize".to_string(),
+                r"GameRulesImpl - changing state from SS_STARTED to SS_ENDED".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CurrentMission {
+    name: String,
+    // Seconds since the game was started, from the start of the line
+    started_at: Option<f64>,
+}
+
+// Reports when a mission starts and how long it took when it ends
+#[derive(Debug, Default)]
+pub struct OnMissionEvent {
+    current_mission: Option<CurrentMission>,
+}
+
+impl OnMissionEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EELogEvent for OnMissionEvent {
+    fn name(&self) -> &'static str {
+        "mission"
+    }
+
+    fn default_priority(&self) -> i64 {
+        40
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
+        if !input.contains("ThemedSquadOverlay.lua")
+            && !input.contains("EndOfMatch.lua")
+            && !input.contains("GameRulesImpl")
+        {
+            return Ok(false);
+        }
+        let timestamp = super::helper::get_timestamp(input);
+
+        let (found, captures) = super::helper::match_pattern(input, Events::Start.as_str_list())
+            .map_err(|e| AppError::new("OnMissionEvent", eyre!(e)))?;
+        if found {
+            let name = captures
+                .get(0)
+                .cloned()
+                .flatten()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            logger::info_con("OnMissionEvent", &format!("Mission started: {}", name));
+            helper::send_message_to_window("Client:Mission:Started", Some(json!({ "name": name })));
+            self.current_mission = Some(CurrentMission {
+                name,
+                started_at: timestamp,
+            });
+            return Ok(true);
+        }
+
+        let (found, _) = super::helper::match_pattern(input, Events::End.as_str_list())
+            .map_err(|e| AppError::new("OnMissionEvent", eyre!(e)))?;
+        if found {
+            // Both end lines are written for the same mission, only report it once
+            if let Some(mission) = self.current_mission.take() {
+                let duration = match (mission.started_at, timestamp) {
+                    (Some(start), Some(end)) if end >= start => Some(end - start),
+                    _ => None,
+                };
+                logger::info_con(
+                    "OnMissionEvent",
+                    &format!("Mission ended: {} ({:?}s)", mission.name, duration),
+                );
+                helper::send_message_to_window(
+                    "Client:Mission:Ended",
+                    Some(json!({ "name": mission.name, "duration": duration })),
+                );
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str =
+        "1204.250 Script [Info]: ThemedSquadOverlay.lua: Mission name: Hydron (Sedna)";
+    const END: &str = "1544.750 Script [Info]: EndOfMatch.lua: Initialize";
+    const ENDED: &str =
+        "1544.812 Game [Info]: GameRulesImpl - changing state from SS_STARTED to SS_ENDED";
+
+    #[test]
+    fn a_mission_is_tracked_from_start_to_end() {
+        let mut event = OnMissionEvent::new();
+        assert!(event.check(0, START).unwrap());
+        let mission = event.current_mission.as_ref().unwrap();
+        assert_eq!(mission.name, "Hydron (Sedna)");
+        assert_eq!(mission.started_at, Some(1204.25));
+
+        assert!(event.check(1, END).unwrap());
+        assert!(event.current_mission.is_none());
+        // The second end line of the same mission is claimed without a mission
+        assert!(event.check(2, ENDED).unwrap());
+        assert!(event.current_mission.is_none());
+    }
+
+    #[test]
+    fn a_new_start_replaces_the_current_mission() {
+        let mut event = OnMissionEvent::new();
+        event.check(0, START).unwrap();
+        let next = "1702.031 Script [Info]: ThemedSquadOverlay.lua: Mission name: Kappa (Sedna)";
+        assert!(event.check(1, next).unwrap());
+        assert_eq!(
+            event.current_mission.as_ref().unwrap().name,
+            "Kappa (Sedna)"
+        );
+    }
+
+    #[test]
+    fn other_lines_are_left_alone() {
+        let mut event = OnMissionEvent::new();
+        let lines = [
+            "1204.300 Script [Info]: ThemedSquadOverlay.lua: ShowMissionInfo",
+            "1544.900 Game [Info]: GameRulesImpl::EndSession",
+            "1021.004 Script [Info]: ChatRedux.lua: ChatRedux::AddTab: Adding tab with channel name: FTrader to index 5",
+        ];
+        for (index, line) in lines.iter().enumerate() {
+            assert!(!event.check(index, line).unwrap());
+        }
+        assert!(event.current_mission.is_none());
+    }
+}
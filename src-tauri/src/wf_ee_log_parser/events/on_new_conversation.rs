@@ -1,9 +1,17 @@
 use std::{
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
 };
 
-use crate::{error::AppError, handler::MonitorHandler, settings::SettingsState};
+use crate::{
+    error::AppError,
+    handler::MonitorHandler,
+    notification::{notify, EmbedField, NotificationKind},
+    wf_ee_log_parser::registry::EELogEvent,
+};
+
+use super::trade_reconciliation::TradeReconciler;
 use eyre::eyre;
 
 enum Events {
@@ -21,58 +29,64 @@ impl Events {
 
 #[derive(Clone, Debug)]
 pub struct OnNewConversationEvent {
-    settings: Arc<Mutex<SettingsState>>,
+    reconciler: Arc<Mutex<TradeReconciler>>,
     helper: Arc<Mutex<MonitorHandler>>,
 }
 
 impl OnNewConversationEvent {
     pub fn new(
-        settings: Arc<Mutex<SettingsState>>,
+        reconciler: Arc<Mutex<TradeReconciler>>,
         helper: Arc<Mutex<MonitorHandler>>,
         _: PathBuf,
     ) -> Self {
-        Self { settings, helper }
+        Self { reconciler, helper }
     }
 
-    pub fn check(&self, _: usize, input: &str) -> Result<bool, AppError> {
-        let settings = self
-            .settings
-            .lock()?
-            .clone()
-            .notifications
-            .on_new_conversation;
-        let helper = self.helper.lock()?;
-
-        if !settings.system_notify && !settings.discord_notify {
-            return Ok(false);
-        }
+    // Returns the name of the player if the line opens a new conversation
+    pub fn find_conversation(&self, input: &str) -> Result<Option<String>, AppError> {
         let (found, captures) = crate::wf_ee_log_parser::events::helper::match_pattern(
             input,
             Events::Conversation.as_str_list(),
         )
         .map_err(|e| AppError::new("OnNewConversationEvent", eyre!(e)))?;
-        if found {
-            let username = captures.get(0).unwrap().clone().unwrap();
-            let content = settings.content.replace("<PLAYER_NAME>", username.as_str());
-            // If system notification is enabled, show it
-            if settings.system_notify {
-                helper.show_notification(
-                    settings.title.as_str(),
-                    &content,
-                    Some("assets/icons/icon.png"),
-                    Some("Default"),
-                );
-            }
-            // If discord webhook is enabled, send it
-            if settings.discord_notify && settings.webhook.is_some() {
-                crate::helper::send_message_to_discord(
-                    settings.webhook.unwrap_or("".to_string()),
-                    settings.title,
-                    content,
-                    settings.user_ids.clone(),
-                );
-            }
+        if !found {
+            return Ok(None);
+        }
+        Ok(captures.get(0).cloned().flatten())
+    }
+}
+
+impl EELogEvent for OnNewConversationEvent {
+    fn name(&self) -> &'static str {
+        "conversation"
+    }
+
+    fn default_priority(&self) -> i64 {
+        100
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
+        let settings = self.reconciler.lock()?.settings.lock()?.clone();
+        let notification = &settings.notifications.on_new_conversation;
+        let helper = self.helper.lock()?;
+
+        if !notification.is_enabled() {
+            return Ok(false);
+        }
+        let username = self.find_conversation(input)?;
+        if let Some(username) = username.clone() {
+            let fields = vec![EmbedField::new("Player", &username, true)];
+            let values = HashMap::from([("PLAYER_NAME", username)]);
+            notify(
+                &helper,
+                &settings,
+                notification,
+                NotificationKind::NewConversation,
+                &values,
+                fields,
+                None,
+            );
         }
-        Ok(found)
+        Ok(username.is_some())
     }
 }
@@ -6,55 +6,92 @@ use std::{
 
 use crate::{
     cache::client::CacheClient,
-    database::client::DBClient,
     error::{self, AppError},
     handler::MonitorHandler,
     helper, logger,
-    settings::SettingsState,
+    notification::{notify, wfm_thumbnail, EmbedField, NotificationKind},
     structs::TradeClassification,
     structs::WarframeLanguage,
+    wf_ee_log_parser::registry::EELogEvent,
 };
 use eyre::eyre;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-#[derive(Debug)]
-struct TradeLogMessages {
-    detect_line: &'static str,
-    detect_trade_confirmation_line: &'static str,
-    detect_trade_failed_line: &'static str,
-    will_receive_line_first_part: &'static str,
-    will_receive_line_second_part: &'static str,
-    platinum_name: &'static str,
-}
+use super::{
+    trade_log_messages::{self, TradeLogMessages},
+    trade_reconciliation::TradeReconciler,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerTradeStruct {
-    crated_at: String,
-    user_name: String,
-    trade_type: TradeClassification,
-    total_platinum: i32,
-    offerings: Vec<TradeItemStruct>,
-    receiving: Vec<TradeItemStruct>,
+    pub crated_at: String,
+    // Seconds since the game started of the first trade line, None for trades not read from the log
+    #[serde(default)]
+    pub log_timestamp: Option<f64>,
+    pub user_name: String,
+    pub trade_type: TradeClassification,
+    pub total_platinum: i32,
+    pub offerings: Vec<TradeItemStruct>,
+    pub receiving: Vec<TradeItemStruct>,
+}
+
+impl PlayerTradeStruct {
+    // The same for a trade read live and read again from a replayed log, used to skip recorded trades.
+    // Quotes are left out, the transaction insert can't store them.
+    pub fn key(&self) -> String {
+        let describe = |items: &[TradeItemStruct]| {
+            let mut items: Vec<String> = items
+                .iter()
+                .map(|item| format!("{} x {}", item.name, item.quantity))
+                .collect();
+            items.sort();
+            items.join(",")
+        };
+        let time = match self.log_timestamp {
+            Some(timestamp) => format!("{:.3}", timestamp),
+            None => self.crated_at.clone(),
+        };
+        format!(
+            "{}|{}|{}|{}",
+            self.user_name,
+            time,
+            describe(&self.offerings),
+            describe(&self.receiving)
+        )
+        .replace(['\'', '"'], "")
+    }
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeItemStruct {
-    name: String,
-    wfm_id: Option<String>,
-    wfm_url_name: Option<String>,
-    display_name: String,
-    quantity: i32,
-    rank: i32,
+    pub name: String,
+    pub wfm_id: Option<String>,
+    pub wfm_url_name: Option<String>,
+    pub display_name: String,
+    pub quantity: i32,
+    pub rank: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeEventMode {
+    Live,
+    // Reading an old log, trades are only collected unless reconcile is set
+    Replay { reconcile: bool },
 }
 
 #[derive(Debug)]
 pub struct OnTradingEvent {
     wf_ee_path: PathBuf,
-    settings: Arc<Mutex<SettingsState>>,
+    reconciler: Arc<Mutex<TradeReconciler>>,
     chche: Arc<Mutex<CacheClient>>,
     helper: Arc<Mutex<MonitorHandler>>,
+    mode: TradeEventMode,
+    replayed_trades: Vec<PlayerTradeStruct>,
     // Current trade
     trade_log_messages_by_language: HashMap<WarframeLanguage, TradeLogMessages>,
+    // Language the current trade was detected in
+    trade_language: Option<WarframeLanguage>,
+    warned_unsupported_language: bool,
     current_trade_logs: Vec<String>,
     getting_trade_message_multiline: bool,
     waiting_for_trade_message_confirmation: bool,
@@ -63,35 +100,27 @@ pub struct OnTradingEvent {
 
 impl OnTradingEvent {
     pub fn new(
-        settings: Arc<Mutex<SettingsState>>,
+        reconciler: Arc<Mutex<TradeReconciler>>,
         helper: Arc<Mutex<MonitorHandler>>,
         chche: Arc<Mutex<CacheClient>>,
         wf_ee_path: PathBuf,
     ) -> Self {
         Self {
-            settings,
+            reconciler,
             helper,
             chche,
             wf_ee_path,
-            trade_log_messages_by_language: HashMap::from([(
-                WarframeLanguage::English,
-                TradeLogMessages {
-                    detect_line:
-                        "description=Are you sure you want to accept this trade? You are offering",
-                    detect_trade_confirmation_line:
-                        "description=The trade was successful!, leftItem=/Menu/Confirm_Item_Ok",
-                    detect_trade_failed_line:
-                        "description=The trade failed., leftItem=/Menu/Confirm_Item_Ok",
-                    will_receive_line_first_part: "and will receive from ",
-                    will_receive_line_second_part: " the following:",
-                    platinum_name: "Platinum",
-                },
-            )]),
+            mode: TradeEventMode::Live,
+            replayed_trades: Vec::new(),
+            trade_log_messages_by_language: trade_log_messages::get_trade_log_messages(),
+            trade_language: None,
+            warned_unsupported_language: false,
             current_trade_logs: Vec::new(),
             getting_trade_message_multiline: false,
             waiting_for_trade_message_confirmation: false,
             current_trade: Arc::new(Mutex::new(PlayerTradeStruct {
                 crated_at: chrono::Local::now().to_string(),
+                log_timestamp: None,
                 total_platinum: -1,
                 user_name: "".to_string(),
                 trade_type: TradeClassification::Unknown,
@@ -100,49 +129,20 @@ impl OnTradingEvent {
             })),
         }
     }
-    pub fn check(&mut self, _index: usize, input: &str) -> Result<bool, AppError> {
-        while self.getting_trade_message_multiline {
-            if input.contains("[Info]") || input.contains("[Error]") || input.contains("[Warning]")
-            {
-                self.getting_trade_message_multiline = false;
-                self.trade_logs_finished()?;
-                self.waiting_for_trade_message_confirmation = true;
-            } else {
-                self.received_trade_log_message(input);
-                return Ok(true);
-            }
-        }
+    pub fn set_mode(&mut self, mode: TradeEventMode) {
+        self.mode = mode;
+    }
 
-        // Start of a Trade
-        if input.contains("[Info]: Dialog.lua: Dialog::CreateOkCancel(description=")
-            && self.is_beginninig_of_tradelog(input)?
-        {
-            self.start_trade_log(input);
-            if input
-                .contains(", leftItem=/Menu/Confirm_Item_Ok, rightItem=/Menu/Confirm_Item_Cancel)")
-            {
-                self.waiting_for_trade_message_confirmation = true;
-            } else {
-                self.getting_trade_message_multiline = true;
-            }
-            return Ok(true);
-        }
-        // Waiting for trade confirmation / trade failed
-        else if self.waiting_for_trade_message_confirmation
-            && input.contains("[Info]: Dialog.lua: Dialog::CreateOk(description=")
-        {
-            if self.is_trade_confirmation(input)? {
-                self.trade_accepted()?;
-            } else if self.is_trade_failed(input)? {
-                self.trade_failed();
-            }
-            return Ok(true);
-        }
-        Ok(false)
+    // Trades accepted since the last call while in replay mode
+    pub fn take_replayed_trades(&mut self) -> Vec<PlayerTradeStruct> {
+        std::mem::take(&mut self.replayed_trades)
     }
 
     fn start_trade_log(&mut self, msg: &str) {
+        let lang = self.trade_language.take();
         self.reset_trade();
+        self.trade_language = lang;
+        self.current_trade.lock().unwrap().log_timestamp = super::helper::get_timestamp(msg);
         self.received_trade_log_message(msg);
     }
     fn received_trade_log_message(&mut self, msg: &str) {
@@ -153,9 +153,10 @@ impl OnTradingEvent {
         let trade_struct_mutex = Arc::clone(&self.current_trade);
         let mut trade_struct = trade_struct_mutex.lock()?;
 
-        let lang = helper::get_warframe_language();
-
-        let trade_log_messages = self.trade_log_messages_by_language.get(&lang).unwrap();
+        let trade_log_messages = match self.get_trade_log_messages() {
+            Some(messages) => messages,
+            None => return Ok(()),
+        };
 
         let mut logs = self.current_trade_logs.clone();
 
@@ -182,6 +183,14 @@ impl OnTradingEvent {
                     .trim()
                     .to_string();
                 flag = false;
+            } else if flag && log.contains('\u{e000}') && log.trim_end().ends_with([':', '：']) {
+                // The receive line of a locale we have wrong texts for, the user name is the word in front of the icon
+                trade_struct.user_name = log[..log.find('\u{e000}').unwrap()]
+                    .split_whitespace()
+                    .last()
+                    .unwrap_or_default()
+                    .to_string();
+                flag = false;
             } else {
                 let mut str2 = log.clone();
                 if log.contains(", leftItem=/") {
@@ -266,23 +275,19 @@ impl OnTradingEvent {
                 item.display_name = item.name.clone();
             }
         }
-        let num3 = trade_struct
-            .receiving
-            .iter()
-            .any(|p| p.name == "plat")
-            .then(|| 1)
-            .unwrap_or(0);
-
-        let receiving_plat = trade_struct.offerings.iter().any(|p| p.name == "plat");
-
-        if num3 == 0 || trade_struct.offerings.len() != 1 {
-            if !receiving_plat || trade_struct.receiving.len() != 1 {
-                trade_struct.trade_type = TradeClassification::Trade;
-            } else {
-                trade_struct.trade_type = TradeClassification::Purchase;
-            }
-        } else {
+        // A sale is only items for platinum and a purchase only platinum for items
+        let is_plat = |p: &TradeItemStruct| p.name == "plat";
+        let offering_plat = trade_struct.offerings.iter().any(is_plat);
+        let receiving_plat = trade_struct.receiving.iter().any(is_plat);
+        let offering_items = trade_struct.offerings.iter().any(|p| !is_plat(p));
+        let receiving_items = trade_struct.receiving.iter().any(|p| !is_plat(p));
+
+        if receiving_plat && !receiving_items && offering_items && !offering_plat {
             trade_struct.trade_type = TradeClassification::Sale;
+        } else if offering_plat && !offering_items && receiving_items && !receiving_plat {
+            trade_struct.trade_type = TradeClassification::Purchase;
+        } else {
+            trade_struct.trade_type = TradeClassification::Trade;
         }
         Ok(())
     }
@@ -348,17 +353,27 @@ impl OnTradingEvent {
         let mh = self.helper.lock()?.clone();
         let trade = self.current_trade.lock()?.clone();
 
+        if let TradeEventMode::Replay { reconcile } = self.mode {
+            self.replay_trade_accepted(trade, reconcile)?;
+            self.reset_trade();
+            return Ok(());
+        }
+
         // Send a notification to the user
-        mh.show_notification(
-            "Trade Accepted",
-            &format!("Trade accepted from {}", trade.user_name),
-            Some("assets/icons/icon.png"),
-            Some("Default"),
-        );
+        self.notify_trade_completed(&mh, &trade)?;
 
         // Send the trade to the main window
         helper::send_message_to_window("Client:Trade:Received", Some(json!(trade.clone())));
 
+        // Update the stock, transactions and orders of the profile that was active for the trade
+        let reconciler = self.reconciler.lock()?.clone();
+        let reconcile_trade = trade.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = reconciler.reconcile(mh, reconcile_trade).await {
+                error::create_log_file("trade_reconciliation.log".to_string(), &e);
+            }
+        });
+
         match self.read_json_file(file_path) {
             Ok(data) => {
                 // Modify the data
@@ -383,6 +398,85 @@ impl OnTradingEvent {
         Ok(())
     }
 
+    fn notify_trade_completed(
+        &self,
+        mh: &MonitorHandler,
+        trade: &PlayerTradeStruct,
+    ) -> Result<(), AppError> {
+        let settings = self.reconciler.lock()?.settings.lock()?.clone();
+        let notification = &settings.notifications.on_trade_completed;
+        if !notification.is_enabled() {
+            return Ok(());
+        }
+        let describe = |items: &[TradeItemStruct]| {
+            items
+                .iter()
+                .map(|item| {
+                    let name = if item.name == "plat" {
+                        "Platinum"
+                    } else {
+                        item.display_name.as_str()
+                    };
+                    format!("{}x {}", item.quantity, name)
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let offered = describe(&trade.offerings);
+        let received = describe(&trade.receiving);
+        let trade_type = format!("{:?}", trade.trade_type);
+
+        // Show the first market item of the trade
+        let cache = self.chche.lock()?.clone();
+        let thumbnail = trade
+            .offerings
+            .iter()
+            .chain(trade.receiving.iter())
+            .find_map(|item| item.wfm_url_name.clone())
+            .and_then(|url| cache.items().find_type(&url).ok().flatten())
+            .and_then(|item| wfm_thumbnail(&item.thumb));
+
+        let fields = vec![
+            EmbedField::new("Player", &trade.user_name, true),
+            EmbedField::new("Type", &trade_type, true),
+            EmbedField::new("Platinum", &trade.total_platinum.to_string(), true),
+            EmbedField::new("Offered", &offered, false),
+            EmbedField::new("Received", &received, false),
+        ];
+        let values = HashMap::from([
+            ("PLAYER_NAME", trade.user_name.clone()),
+            ("TRADE_TYPE", trade_type),
+            ("PLATINUM", trade.total_platinum.to_string()),
+            ("OFFERED", offered),
+            ("RECEIVED", received),
+        ]);
+        notify(
+            mh,
+            &settings,
+            notification,
+            NotificationKind::TradeCompleted,
+            &values,
+            fields,
+            thumbnail,
+        );
+        Ok(())
+    }
+
+    // Old trades don't notify anyone, they are reconciled in order so the stock adds up.
+    // Only the database is updated, the orders on warframe.market are the ones we have now.
+    fn replay_trade_accepted(
+        &mut self,
+        trade: PlayerTradeStruct,
+        reconcile: bool,
+    ) -> Result<(), AppError> {
+        if reconcile {
+            let reconciler = self.reconciler.lock()?.clone();
+            tauri::async_runtime::block_on(reconciler.reconcile_replayed(trade.clone()))?;
+        }
+        self.replayed_trades.push(trade);
+        Ok(())
+    }
+
     fn trade_failed(&mut self) {
         self.reset_trade();
     }
@@ -394,45 +488,81 @@ impl OnTradingEvent {
         trade_struct.receiving.clear();
         trade_struct.user_name = "".to_string();
         trade_struct.total_platinum = 0;
+        trade_struct.log_timestamp = None;
         self.current_trade_logs = Vec::new();
+        self.trade_language = None;
         self.getting_trade_message_multiline = false;
         self.waiting_for_trade_message_confirmation = false;
     }
 
-    fn is_beginninig_of_tradelog(&self, msg: &str) -> Result<bool, AppError> {
-        let lang = helper::get_warframe_language();
-        // Find trade log messages
-        let trade_log_messages = self.trade_log_messages_by_language.get(&lang).unwrap();
-
-        // Check if the message is the beginning of a trade log
-        if msg.contains(trade_log_messages.detect_line) {
-            return Ok(true);
+    fn is_beginninig_of_tradelog(&mut self, msg: &str) -> Result<bool, AppError> {
+        // Remember the language so the rest of the trade is read with the same texts
+        match self.find_language(msg, |m| m.detect_line) {
+            Some(lang) => {
+                self.trade_language = Some(lang);
+                Ok(true)
+            }
+            None => Ok(false),
         }
-        Ok(false)
     }
 
-    fn is_trade_confirmation(&self, msg: &str) -> Result<bool, AppError> {
-        let lang = helper::get_warframe_language();
-        // Find trade log messages
-        let trade_log_messages = self.trade_log_messages_by_language.get(&lang).unwrap();
+    fn is_trade_confirmation(&mut self, msg: &str) -> Result<bool, AppError> {
+        Ok(self
+            .find_language(msg, |m| m.detect_trade_confirmation_line)
+            .is_some())
+    }
 
-        // Check if the message is the beginning of a trade log
-        if msg.contains(trade_log_messages.detect_trade_confirmation_line) {
-            return Ok(true);
-        }
-        Ok(false)
+    fn is_trade_failed(&mut self, msg: &str) -> Result<bool, AppError> {
+        Ok(self
+            .find_language(msg, |m| m.detect_trade_failed_line)
+            .is_some())
     }
 
-    fn is_trade_failed(&self, msg: &str) -> Result<bool, AppError> {
-        let lang = helper::get_warframe_language();
-        // Find trade log messages
-        let trade_log_messages = self.trade_log_messages_by_language.get(&lang).unwrap();
+    fn get_trade_log_messages(&self) -> Option<&TradeLogMessages> {
+        let lang = self
+            .trade_language
+            .clone()
+            .unwrap_or_else(helper::get_warframe_language);
+        self.trade_log_messages_by_language.get(&lang)
+    }
 
-        // Check if the message is the beginning of a trade log
-        if msg.contains(trade_log_messages.detect_trade_failed_line) {
-            return Ok(true);
+    // Checks the game language first and then every other language we have texts for,
+    // the launcher log is not always right and players can switch language in game.
+    fn find_language(
+        &mut self,
+        msg: &str,
+        line: fn(&TradeLogMessages) -> &'static str,
+    ) -> Option<WarframeLanguage> {
+        let lang = self
+            .trade_language
+            .clone()
+            .unwrap_or_else(helper::get_warframe_language);
+
+        match self.trade_log_messages_by_language.get(&lang) {
+            Some(messages) => {
+                if msg.contains(line(messages)) {
+                    return Some(lang);
+                }
+            }
+            None => {
+                if !self.warned_unsupported_language {
+                    self.warned_unsupported_language = true;
+                    logger::warning_con(
+                        "OnTradingEvent",
+                        format!(
+                            "No trade messages for the game language {:?}, trying all known languages",
+                            lang
+                        )
+                        .as_str(),
+                    );
+                }
+            }
         }
-        Ok(false)
+
+        self.trade_log_messages_by_language
+            .iter()
+            .find(|(_, messages)| msg.contains(line(messages)))
+            .map(|(lang, _)| lang.clone())
     }
 
     fn read_json_file(&self, file_path: &str) -> Result<Vec<Value>, AppError> {
@@ -463,3 +593,54 @@ impl OnTradingEvent {
         Ok(())
     }
 }
+
+impl EELogEvent for OnTradingEvent {
+    fn name(&self) -> &'static str {
+        "trading"
+    }
+
+    fn default_priority(&self) -> i64 {
+        90
+    }
+
+    fn check(&mut self, _index: usize, input: &str) -> Result<bool, AppError> {
+        while self.getting_trade_message_multiline {
+            if input.contains("[Info]") || input.contains("[Error]") || input.contains("[Warning]")
+            {
+                self.getting_trade_message_multiline = false;
+                self.trade_logs_finished()?;
+                self.waiting_for_trade_message_confirmation = true;
+            } else {
+                self.received_trade_log_message(input);
+                return Ok(true);
+            }
+        }
+
+        // Start of a Trade
+        if input.contains("[Info]: Dialog.lua: Dialog::CreateOkCancel(description=")
+            && self.is_beginninig_of_tradelog(input)?
+        {
+            self.start_trade_log(input);
+            if input
+                .contains(", leftItem=/Menu/Confirm_Item_Ok, rightItem=/Menu/Confirm_Item_Cancel)")
+            {
+                self.waiting_for_trade_message_confirmation = true;
+            } else {
+                self.getting_trade_message_multiline = true;
+            }
+            return Ok(true);
+        }
+        // Waiting for trade confirmation / trade failed
+        else if self.waiting_for_trade_message_confirmation
+            && input.contains("[Info]: Dialog.lua: Dialog::CreateOk(description=")
+        {
+            if self.is_trade_confirmation(input)? {
+                self.trade_accepted()?;
+            } else if self.is_trade_failed(input)? {
+                self.trade_failed();
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,72 @@
+use eyre::eyre;
+use serde_json::json;
+
+use crate::{error::AppError, helper, logger, wf_ee_log_parser::registry::EELogEvent};
+
+enum Events {
+    Opened,
+    Rewarded,
+}
+impl Events {
+    fn as_str_list(&self) -> Vec<String> {
+        match self {
+            Events::Opened => vec![
+                r"Script \[Info\]: ProjectionRewardChoice\.lua: Relic rewards initialized"
+                    .to_string(),
+            ],
+            Events::Rewarded => {
+                vec![r"Script \[Info\]: ProjectionRewardChoice\.lua: Got rewards".to_string()]
+            }
+        }
+    }
+}
+
+// The relic reward screen at the end of a fissure, counted per game session
+#[derive(Debug, Default)]
+pub struct OnRelicRewardEvent {
+    reward_screens: u32,
+}
+
+impl OnRelicRewardEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EELogEvent for OnRelicRewardEvent {
+    fn name(&self) -> &'static str {
+        "relic_reward"
+    }
+
+    fn default_priority(&self) -> i64 {
+        30
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
+        if !input.contains("ProjectionRewardChoice.lua") {
+            return Ok(false);
+        }
+        let (found, _) = super::helper::match_pattern(input, Events::Opened.as_str_list())
+            .map_err(|e| AppError::new("OnRelicRewardEvent", eyre!(e)))?;
+        if found {
+            self.reward_screens += 1;
+            helper::send_message_to_window(
+                "Client:RelicReward:Opened",
+                Some(json!({ "count": self.reward_screens })),
+            );
+            return Ok(true);
+        }
+
+        let (found, _) = super::helper::match_pattern(input, Events::Rewarded.as_str_list())
+            .map_err(|e| AppError::new("OnRelicRewardEvent", eyre!(e)))?;
+        if found {
+            logger::info_con("OnRelicRewardEvent", "Relic reward screen shown");
+            helper::send_message_to_window(
+                "Client:RelicReward:Shown",
+                Some(json!({ "count": self.reward_screens })),
+            );
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
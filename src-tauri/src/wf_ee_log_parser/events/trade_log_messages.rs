@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::structs::WarframeLanguage;
+
+// The dialog texts Warframe writes to EE.log during a trade.
+// Only add a language with texts taken from a real EE.log and a test with the lines,
+// until then trades in that language go through the unsupported language fallback.
+#[derive(Debug)]
+pub struct TradeLogMessages {
+    pub detect_line: &'static str,
+    pub detect_trade_confirmation_line: &'static str,
+    pub detect_trade_failed_line: &'static str,
+    pub will_receive_line_first_part: &'static str,
+    pub will_receive_line_second_part: &'static str,
+    pub platinum_name: &'static str,
+}
+
+pub fn get_trade_log_messages() -> HashMap<WarframeLanguage, TradeLogMessages> {
+    HashMap::from([(
+        WarframeLanguage::English,
+        TradeLogMessages {
+            detect_line: "description=Are you sure you want to accept this trade? You are offering",
+            detect_trade_confirmation_line:
+                "description=The trade was successful!, leftItem=/Menu/Confirm_Item_Ok",
+            detect_trade_failed_line:
+                "description=The trade failed., leftItem=/Menu/Confirm_Item_Ok",
+            will_receive_line_first_part: "and will receive from ",
+            will_receive_line_second_part: " the following:",
+            platinum_name: "Platinum",
+        },
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A sale of one item for 25 platinum as the English client logs it
+    const ENGLISH_TRADE: &[&str] = &[
+        "1034.512 Sys [Info]: Dialog.lua: Dialog::CreateOkCancel(description=Are you sure you want to accept this trade? You are offering",
+        "Mirage Prime Systems Blueprint",
+        "and will receive from Trader\u{e000} the following:",
+        "Platinum x 25",
+        ", leftItem=/Menu/Confirm_Item_Ok, rightItem=/Menu/Confirm_Item_Cancel)",
+        "1041.201 Sys [Info]: Dialog.lua: Dialog::CreateOk(description=The trade was successful!, leftItem=/Menu/Confirm_Item_Ok)",
+    ];
+
+    #[test]
+    fn english_trade_lines_are_recognised() {
+        let messages = get_trade_log_messages();
+        let english = messages.get(&WarframeLanguage::English).unwrap();
+
+        assert!(ENGLISH_TRADE[0].contains(english.detect_line));
+        let receive_line = ENGLISH_TRADE[2];
+        assert!(receive_line.contains(english.will_receive_line_first_part));
+        assert!(receive_line.contains(english.will_receive_line_second_part));
+        let user_name = receive_line
+            .replace(english.will_receive_line_first_part, "")
+            .replace(english.will_receive_line_second_part, "")
+            .replace('\u{e000}', "");
+        assert_eq!(user_name.trim(), "Trader");
+        assert!(ENGLISH_TRADE[3].starts_with(english.platinum_name));
+        assert!(ENGLISH_TRADE[5].contains(english.detect_trade_confirmation_line));
+        assert!(!ENGLISH_TRADE[5].contains(english.detect_trade_failed_line));
+    }
+
+    #[test]
+    fn unverified_languages_have_no_texts() {
+        let messages = get_trade_log_messages();
+        assert_eq!(messages.len(), 1);
+        assert!(messages.get(&WarframeLanguage::German).is_none());
+    }
+}
@@ -0,0 +1,548 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::json;
+
+use crate::{
+    database::client::DBClient,
+    enums::{LogLevel, OrderType},
+    error::{self, AppError},
+    handler::MonitorHandler,
+    logger,
+    notification::{notify, EmbedField, NotificationKind},
+    settings::SettingsState,
+    structs::Order,
+    wfm_client::client::WFMClient,
+};
+
+use super::on_new_trading::{PlayerTradeStruct, TradeItemStruct};
+
+static COMPONENT: &str = "TradeReconciliation";
+static LOG_FILE: &str = "trade_reconciliation.log";
+
+// A traded item together with the platinum that was allocated to it
+struct AllocatedItem<'a> {
+    item: &'a TradeItemStruct,
+    platinum: i64,
+}
+
+// The states of one profile session, the EE.log is read and its trades reconciled into them
+#[derive(Clone, Debug)]
+pub struct TradeReconciler {
+    pub settings: Arc<Mutex<SettingsState>>,
+    pub db: Arc<Mutex<DBClient>>,
+    pub wfm: Arc<Mutex<WFMClient>>,
+}
+
+impl TradeReconciler {
+    pub fn new(
+        settings: Arc<Mutex<SettingsState>>,
+        db: Arc<Mutex<DBClient>>,
+        wfm: Arc<Mutex<WFMClient>>,
+    ) -> Self {
+        TradeReconciler { settings, db, wfm }
+    }
+
+    // A live trade also changes the orders and is notified about
+    pub async fn reconcile(
+        &self,
+        mh: MonitorHandler,
+        trade: PlayerTradeStruct,
+    ) -> Result<(), AppError> {
+        let db = self.db.lock()?.clone();
+        let wfm = self.wfm.lock()?.clone();
+        let settings = self.settings.lock()?.clone();
+        reconcile_trade(db, Some(wfm), settings, Some(mh), trade).await
+    }
+
+    // Only the stock and transactions, a trade from an old log
+    pub async fn reconcile_replayed(&self, trade: PlayerTradeStruct) -> Result<(), AppError> {
+        let db = self.db.lock()?.clone();
+        let settings = self.settings.lock()?.clone();
+        reconcile_trade(db, None, settings, None, trade).await
+    }
+}
+
+// Brings the stock, transactions and warframe.market orders in line with an accepted trade.
+// Only plain sales (items for platinum) and purchases (platinum for items) are handled,
+// item for item trades are left for the user to record.
+// Notifications are only sent when a handler is given and the orders are only changed
+// when a client is given, replayed trades pass neither.
+// A trade that is already in the transactions is skipped, so a log can be replayed again.
+pub async fn reconcile_trade(
+    db: DBClient,
+    wfm: Option<WFMClient>,
+    settings: SettingsState,
+    mh: Option<MonitorHandler>,
+    trade: PlayerTradeStruct,
+) -> Result<(), AppError> {
+    let trade_key = trade.key();
+    if is_recorded(&db, &trade_key).await? {
+        logger::info_con(
+            COMPONENT,
+            format!(
+                "Trade with {} is already recorded, skipping it",
+                trade.user_name
+            )
+            .as_str(),
+        );
+        return Ok(());
+    }
+
+    let offered_plat = get_platinum(&trade.offerings);
+    let received_plat = get_platinum(&trade.receiving);
+    let offered_items: Vec<&TradeItemStruct> = get_items(&trade.offerings);
+    let received_items: Vec<&TradeItemStruct> = get_items(&trade.receiving);
+
+    let report_to_wfm = settings.live_scraper.stock_item.report_to_wfm;
+    if received_plat > 0 && offered_plat == 0 && received_items.is_empty() {
+        let items = allocate(&db, received_plat, &offered_items).await?;
+        for item in items {
+            match sell_item(&db, wfm.as_ref(), report_to_wfm, &trade_key, &item).await {
+                Ok(true) => notify_riven_sold(&settings, mh.as_ref(), &trade, &item),
+                // Nothing was recorded, so there is no sale to notify about
+                Ok(false) => {}
+                Err(e) => error::create_log_file(LOG_FILE.to_string(), &e),
+            }
+        }
+    } else if offered_plat > 0 && received_plat == 0 && offered_items.is_empty() {
+        let items = allocate(&db, offered_plat, &received_items).await?;
+        for item in items {
+            if let Err(e) = buy_item(&db, wfm.as_ref(), report_to_wfm, &trade_key, &item).await {
+                error::create_log_file(LOG_FILE.to_string(), &e);
+            }
+        }
+    } else {
+        logger::info_con(
+            COMPONENT,
+            format!(
+                "Trade with {} is not a plain sale or purchase, it was not recorded",
+                trade.user_name
+            )
+            .as_str(),
+        );
+    }
+    Ok(())
+}
+
+// Transactions of a reconciled trade carry its key
+async fn is_recorded(db: &DBClient, trade_key: &str) -> Result<bool, AppError> {
+    let transactions = db.transaction().get_items().await?;
+    Ok(transactions.iter().any(|transaction| {
+        transaction
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.0.as_ref())
+            .and_then(|properties| properties.get("trade_key"))
+            .and_then(|key| key.as_str())
+            == Some(trade_key)
+    }))
+}
+
+async fn sell_item(
+    db: &DBClient,
+    wfm: Option<&WFMClient>,
+    report_to_wfm: bool,
+    trade_key: &str,
+    allocated: &AllocatedItem<'_>,
+) -> Result<bool, AppError> {
+    let item = allocated.item;
+    if let Some((weapon_name, mod_name)) = get_riven_name(item) {
+        return sell_riven(
+            db,
+            wfm,
+            trade_key,
+            weapon_name,
+            mod_name,
+            allocated.platinum,
+        )
+        .await;
+    }
+    let url = match &item.wfm_url_name {
+        Some(url) => url.clone(),
+        None => {
+            logger::warning_con(
+                COMPONENT,
+                format!("Sold {} is not a known item, skipping it", item.name).as_str(),
+            );
+            return Ok(false);
+        }
+    };
+    let rank = item.rank.max(0);
+
+    // Update the stock if we have the item
+    let stock = db.stock_item().get_items().await?;
+    let stock_item = stock
+        .iter()
+        .find(|s| s.url == url && s.rank == rank)
+        .or_else(|| stock.iter().find(|s| s.url == url))
+        .cloned();
+    let owned = match stock_item {
+        Some(stock_item) => {
+            let inventory = db
+                .stock_item()
+                .sell_item(stock_item.id, item.quantity)
+                .await?;
+            if inventory.owned <= 0 {
+                db.stock_item().emit("DELETE", json!(inventory.clone()));
+            } else {
+                db.stock_item()
+                    .emit("CREATE_OR_UPDATE", json!(inventory.clone()));
+            }
+            inventory.owned
+        }
+        None => 0,
+    };
+
+    db.transaction()
+        .create(
+            &url,
+            "item",
+            "sell",
+            item.quantity,
+            allocated.platinum as i32,
+            rank,
+            Some(json!({ "trade_key": trade_key })),
+        )
+        .await?;
+
+    let wfm = match wfm {
+        Some(wfm) => wfm,
+        None => return Ok(()),
+    };
+    if report_to_wfm {
+        wfm.orders()
+            .close_quantity(
+                &url,
+                OrderType::Sell,
+                Some(rank as i64),
+                item.quantity as i64,
+            )
+            .await?;
+        return Ok(true);
+    }
+
+    // Remove the sell order or lower its quantity to what we have left
+    let orders: Vec<Order> = wfm.orders().get_my_orders().await?.sell_orders;
+    let order = match orders.iter().find(|order| {
+        order
+            .item
+            .as_ref()
+            .map(|i| i.url_name == url)
+            .unwrap_or(false)
+            && order.mod_rank.unwrap_or(0) == rank as i64
+    }) {
+        Some(order) => order.clone(),
+        None => return Ok(()),
+    };
+    if owned <= 0 {
+        if let Err(e) = wfm.orders().delete(&order.id).await {
+            if e.log_level() != LogLevel::Error {
+                return Err(e);
+            }
+            error::create_log_file(LOG_FILE.to_string(), &e);
+        }
+    } else {
+        wfm.orders()
+            .update(&order.id, order.platinum as i32, owned, order.visible)
+            .await?;
+    }
+    Ok(true)
+}
+
+async fn sell_riven(
+    db: &DBClient,
+    wfm: Option<&WFMClient>,
+    trade_key: &str,
+    weapon_name: &str,
+    mod_name: &str,
+    platinum: i64,
+) -> Result<bool, AppError> {
+    let rivens = db.stock_riven().get_rivens().await?;
+    let riven = rivens.iter().find(|r| {
+        r.weapon_name.eq_ignore_ascii_case(weapon_name) && r.mod_name.eq_ignore_ascii_case(mod_name)
+    });
+    let riven = match riven {
+        Some(riven) => riven.clone(),
+        None => {
+            logger::warning_con(
+                COMPONENT,
+                format!(
+                    "Sold riven {} {} is not in the stock, record it manually",
+                    weapon_name, mod_name
+                )
+                .as_str(),
+            );
+            return Ok(false);
+        }
+    };
+
+    // Recorded first, so the sale is kept when the riven can't be removed from the stock
+    db.transaction()
+        .create(
+            &riven.weapon_url,
+            "riven",
+            "sell",
+            1,
+            platinum as i32,
+            riven.rank,
+            Some(json!({
+                "type": "riven",
+                "weapon_url_name": riven.weapon_url,
+                "re_rolls": riven.re_rolls,
+                "polarity": riven.polarity,
+                "name": riven.mod_name,
+                "mod_rank": riven.rank,
+                "mastery_level": riven.mastery_rank,
+                "attributes": riven.attributes,
+                "trade_key": trade_key,
+            })),
+        )
+        .await?;
+    if let Err(e) = db.stock_riven().delete(riven.id).await {
+        error::create_log_file(LOG_FILE.to_string(), &e);
+    }
+
+    if let (Some(wfm), Some(order_id)) = (wfm, &riven.order_id) {
+        if let Err(e) = wfm.auction().delete(order_id).await {
+            error::create_log_file(LOG_FILE.to_string(), &e);
+        }
+    }
+    Ok(true)
+}
+
+fn notify_riven_sold(
+    settings: &SettingsState,
+    mh: Option<&MonitorHandler>,
+    trade: &PlayerTradeStruct,
+    allocated: &AllocatedItem<'_>,
+) {
+    let (mh, (weapon_name, mod_name)) = match (mh, get_riven_name(allocated.item)) {
+        (Some(mh), Some(name)) => (mh, name),
+        _ => return,
+    };
+    let notification = &settings.notifications.on_riven_sold;
+    if !notification.is_enabled() {
+        return;
+    }
+    let platinum = allocated.platinum.to_string();
+    let fields = vec![
+        EmbedField::new("Weapon", weapon_name, true),
+        EmbedField::new("Mod", mod_name, true),
+        EmbedField::new("Platinum", &platinum, true),
+        EmbedField::new("Buyer", &trade.user_name, true),
+    ];
+    let values = HashMap::from([
+        ("WEAPON_NAME", weapon_name.to_string()),
+        ("MOD_NAME", mod_name.to_string()),
+        ("PLATINUM", platinum),
+        ("PLAYER_NAME", trade.user_name.clone()),
+    ]);
+    notify(
+        mh,
+        settings,
+        notification,
+        NotificationKind::RivenSold,
+        &values,
+        fields,
+        None,
+    );
+}
+
+async fn buy_item(
+    db: &DBClient,
+    wfm: Option<&WFMClient>,
+    report_to_wfm: bool,
+    trade_key: &str,
+    allocated: &AllocatedItem<'_>,
+) -> Result<(), AppError> {
+    let item = allocated.item;
+    // The log does not contain the riven attributes, so we can't add it to the stock
+    let url = match (&item.wfm_url_name, get_riven_name(item)) {
+        (Some(url), None) => url.clone(),
+        _ => {
+            logger::warning_con(
+                COMPONENT,
+                format!(
+                    "Bought {} can't be added to the stock, add it manually",
+                    item.display_name
+                )
+                .as_str(),
+            );
+            return Ok(());
+        }
+    };
+    let rank = item.rank.max(0);
+
+    db.stock_item()
+        .create(
+            &url,
+            item.quantity,
+            allocated.platinum as f64,
+            None,
+            rank,
+            None,
+        )
+        .await?;
+    db.transaction()
+        .create(
+            &url,
+            "item",
+            "buy",
+            item.quantity,
+            allocated.platinum as i32,
+            rank,
+            Some(json!({ "trade_key": trade_key })),
+        )
+        .await?;
+
+    let wfm = match wfm {
+        Some(wfm) => wfm,
+        None => return Ok(()),
+    };
+    if report_to_wfm {
+        wfm.orders()
+            .close_quantity(
+                &url,
+                OrderType::Buy,
+                Some(rank as i64),
+                item.quantity as i64,
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+// Splits the platinum over the items by their market value,
+// if we don't know the value of every item it is split by quantity instead.
+async fn allocate<'a>(
+    db: &DBClient,
+    platinum: i64,
+    items: &[&'a TradeItemStruct],
+) -> Result<Vec<AllocatedItem<'a>>, AppError> {
+    let prices = get_market_prices(db).await?;
+    let rivens = db.stock_riven().get_rivens().await?;
+
+    let estimates: Vec<Option<f64>> = items
+        .iter()
+        .map(|item| {
+            if let Some((weapon_name, mod_name)) = get_riven_name(item) {
+                return rivens
+                    .iter()
+                    .find(|r| {
+                        r.weapon_name.eq_ignore_ascii_case(weapon_name)
+                            && r.mod_name.eq_ignore_ascii_case(mod_name)
+                    })
+                    .map(|r| r.listed_price.map(|p| p as f64).unwrap_or(r.price))
+                    .filter(|p| *p > 0.0);
+            }
+            let url = item.wfm_url_name.as_ref()?;
+            let rank = if item.rank > 0 { item.rank as i64 } else { -1 };
+            prices
+                .get(&(url.clone(), rank))
+                .or_else(|| prices.get(&(url.clone(), -1)))
+                .map(|p| p * item.quantity as f64)
+        })
+        .collect();
+
+    let weights: Vec<f64> = if estimates.iter().all(|e| e.is_some()) {
+        estimates.iter().map(|e| e.unwrap()).collect()
+    } else {
+        items
+            .iter()
+            .map(|item| item.quantity.max(1) as f64)
+            .collect()
+    };
+
+    let platinum = allocate_platinum(platinum, &weights);
+    Ok(items
+        .iter()
+        .zip(platinum)
+        .map(|(item, platinum)| AllocatedItem { item, platinum })
+        .collect())
+}
+
+// Integer split of the total by weight, the remainder goes to the largest fractions.
+fn allocate_platinum(total: i64, weights: &[f64]) -> Vec<i64> {
+    let mut sum: f64 = weights.iter().sum();
+    let weights = if sum > 0.0 {
+        weights.to_vec()
+    } else {
+        sum = weights.len() as f64;
+        vec![1.0; weights.len()]
+    };
+    let shares: Vec<f64> = weights.iter().map(|w| total as f64 * w / sum).collect();
+    let mut allocated: Vec<i64> = shares.iter().map(|s| s.floor() as i64).collect();
+    let mut remainder = total - allocated.iter().sum::<i64>();
+
+    let mut order: Vec<usize> = (0..shares.len()).collect();
+    order.sort_by(|a, b| {
+        let fa = shares[*a] - shares[*a].floor();
+        let fb = shares[*b] - shares[*b].floor();
+        fb.partial_cmp(&fa).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for index in order.iter().cycle() {
+        if remainder <= 0 {
+            break;
+        }
+        allocated[*index] += 1;
+        remainder -= 1;
+    }
+    allocated
+}
+
+// Latest closed average per item and rank, -1 is used for items without a rank
+async fn get_market_prices(db: &DBClient) -> Result<HashMap<(String, i64), f64>, AppError> {
+    let mut prices = HashMap::new();
+    for row in db.price_history().get_latest("closed").await? {
+        if let Some(avg_price) = row.avg_price {
+            prices.insert((row.name, row.mod_rank), avg_price);
+        }
+    }
+    Ok(prices)
+}
+
+fn get_platinum(items: &[TradeItemStruct]) -> i64 {
+    items
+        .iter()
+        .filter(|p| p.name == "plat")
+        .map(|p| p.quantity as i64)
+        .sum()
+}
+
+fn get_items(items: &[TradeItemStruct]) -> Vec<&TradeItemStruct> {
+    items.iter().filter(|p| p.name != "plat").collect()
+}
+
+// Unveiled rivens are named "/AF_Special/Riven/{weapon}/{mod}" by the trade parser
+fn get_riven_name(item: &TradeItemStruct) -> Option<(&str, &str)> {
+    item.name
+        .strip_prefix("/AF_Special/Riven/")
+        .and_then(|name| name.rsplit_once('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::allocate_platinum;
+
+    #[test]
+    fn platinum_is_split_by_weight() {
+        assert_eq!(allocate_platinum(100, &[3.0, 1.0]), vec![75, 25]);
+        assert_eq!(allocate_platinum(7, &[6.0, 3.0, 1.0]), vec![4, 2, 1]);
+    }
+
+    #[test]
+    fn remainder_goes_to_the_largest_fractions() {
+        let platinum = allocate_platinum(100, &[1.0, 1.0, 1.0]);
+        assert_eq!(platinum, vec![34, 33, 33]);
+        assert_eq!(platinum.iter().sum::<i64>(), 100);
+    }
+
+    #[test]
+    fn items_without_a_price_are_split_evenly() {
+        assert_eq!(allocate_platinum(5, &[0.0, 0.0]), vec![3, 2]);
+        assert!(allocate_platinum(5, &[]).is_empty());
+    }
+}
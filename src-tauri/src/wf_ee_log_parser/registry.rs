@@ -0,0 +1,185 @@
+use std::fmt::Debug;
+
+use crate::{error::AppError, settings::EELogSettings};
+
+// A detector for one kind of EE.log signal, it keeps its own state between lines.
+pub trait EELogEvent: Send + Debug {
+    // Key of the handler in the ee_log settings
+    fn name(&self) -> &'static str;
+
+    // Used when the settings have no priority for the handler
+    fn default_priority(&self) -> i64 {
+        0
+    }
+
+    // Returns true when the line belongs to this handler, no other handler will see it
+    fn check(&mut self, index: usize, input: &str) -> Result<bool, AppError>;
+}
+
+#[derive(Debug, Default)]
+pub struct EventRegistry {
+    events: Vec<Box<dyn EELogEvent>>,
+}
+
+impl EventRegistry {
+    pub fn register(&mut self, event: Box<dyn EELogEvent>) {
+        self.events.retain(|e| e.name() != event.name());
+        self.events.push(event);
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.events.iter().map(|e| e.name()).collect()
+    }
+
+    // Runs the lines through the enabled handlers, highest priority first.
+    pub fn check_lines(
+        &mut self,
+        settings: &EELogSettings,
+        lines: &[(usize, String)],
+    ) -> Result<(), AppError> {
+        let mut order: Vec<(i64, usize)> = self
+            .events
+            .iter()
+            .enumerate()
+            .filter_map(|(i, event)| match settings.events.get(event.name()) {
+                Some(s) if !s.enabled => None,
+                Some(s) => Some((s.priority, i)),
+                None => Some((event.default_priority(), i)),
+            })
+            .collect();
+        order.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        for (index, line) in lines {
+            for (_, i) in order.iter() {
+                if self.events[*i].check(*index, line)? {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::settings::EELogEventSettings;
+
+    // Writes every line it sees to the shared log, claims the lines that contain `claims`
+    #[derive(Debug)]
+    struct StubEvent {
+        name: &'static str,
+        priority: i64,
+        claims: &'static str,
+        seen: Arc<Mutex<Vec<(&'static str, usize)>>>,
+    }
+
+    impl EELogEvent for StubEvent {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn default_priority(&self) -> i64 {
+            self.priority
+        }
+
+        fn check(&mut self, index: usize, input: &str) -> Result<bool, AppError> {
+            self.seen.lock().unwrap().push((self.name, index));
+            Ok(input.contains(self.claims))
+        }
+    }
+
+    fn registry(
+        events: &[(&'static str, i64, &'static str)],
+    ) -> (EventRegistry, Arc<Mutex<Vec<(&'static str, usize)>>>) {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut registry = EventRegistry::default();
+        for (name, priority, claims) in events {
+            registry.register(Box::new(StubEvent {
+                name: *name,
+                priority: *priority,
+                claims: *claims,
+                seen: Arc::clone(&seen),
+            }));
+        }
+        (registry, seen)
+    }
+
+    fn settings(events: &[(&str, bool, i64)]) -> EELogSettings {
+        EELogSettings {
+            events: events
+                .iter()
+                .map(|(name, enabled, priority)| {
+                    (
+                        name.to_string(),
+                        EELogEventSettings {
+                            enabled: *enabled,
+                            priority: *priority,
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn lines(lines: &[&str]) -> Vec<(usize, String)> {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| (i, line.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn higher_priorities_see_a_line_first() {
+        let (mut registry, seen) = registry(&[("low", 10, "low"), ("high", 20, "high")]);
+        registry
+            .check_lines(&settings(&[]), &lines(&["nobody"]))
+            .unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![("high", 0), ("low", 0)]);
+    }
+
+    #[test]
+    fn the_first_handler_to_claim_a_line_wins() {
+        let (mut registry, seen) = registry(&[("low", 10, "trade"), ("high", 20, "trade")]);
+        registry
+            .check_lines(&settings(&[]), &lines(&["trade", "other"]))
+            .unwrap();
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![("high", 0), ("high", 1), ("low", 1)]
+        );
+    }
+
+    #[test]
+    fn the_settings_priority_replaces_the_default() {
+        let (mut registry, seen) = registry(&[("low", 10, "trade"), ("high", 20, "trade")]);
+        registry
+            .check_lines(&settings(&[("low", true, 30)]), &lines(&["trade"]))
+            .unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![("low", 0)]);
+    }
+
+    #[test]
+    fn disabled_handlers_see_nothing() {
+        let (mut registry, seen) = registry(&[("low", 10, "trade"), ("high", 20, "trade")]);
+        registry
+            .check_lines(&settings(&[("high", false, 20)]), &lines(&["trade"]))
+            .unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![("low", 0)]);
+    }
+
+    #[test]
+    fn equal_priorities_keep_the_registration_order() {
+        let (mut registry, seen) = registry(&[("first", 0, "x"), ("second", 0, "x")]);
+        registry
+            .check_lines(&settings(&[]), &lines(&["line"]))
+            .unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![("first", 0), ("second", 0)]);
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+use super::events::{
+    helper,
+    on_new_conversation::OnNewConversationEvent,
+    on_new_trading::{OnTradingEvent, PlayerTradeStruct},
+};
+use super::registry::EELogEvent;
+
+// Long idle periods in the log are cut short, even at a slow speed
+const MAX_LINE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayConversation {
+    pub file: String,
+    pub line: usize,
+    pub user_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayTrade {
+    pub file: String,
+    // Line of the trade confirmation
+    pub line: usize,
+    pub trade: PlayerTradeStruct,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub files: Vec<String>,
+    pub lines: usize,
+    pub conversations: Vec<ReplayConversation>,
+    pub trades: Vec<ReplayTrade>,
+}
+
+// A single log file, or every log file in a directory ordered from old to new.
+pub fn get_log_files(path: &Path) -> Result<Vec<PathBuf>, AppError> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Err(AppError::new(
+            "EELogReplay",
+            eyre!("Log path {:?} does not exist", path),
+        ));
+    }
+
+    let entries =
+        std::fs::read_dir(path).map_err(|e| AppError::new("EELogReplay", eyre!(e.to_string())))?;
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase().contains(".log"))
+                    .unwrap_or(false)
+        })
+        .map(|path| {
+            let modified = path
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            (modified, path)
+        })
+        .collect();
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+// Runs every line of the file through the events, from the start of the file.
+// A speed of 0 replays as fast as possible, 1 follows the timestamps of the log.
+pub fn replay_file(
+    path: &Path,
+    speed: f64,
+    event_conversation: &OnNewConversationEvent,
+    event_trading: &mut OnTradingEvent,
+    report: &mut ReplayReport,
+) -> Result<(), AppError> {
+    let file_name = path.to_string_lossy().to_string();
+    let file = File::open(path).map_err(|e| AppError::new("EELogReplay", eyre!(e.to_string())))?;
+    let mut reader = BufReader::new(file);
+
+    let mut last_timestamp: Option<f64> = None;
+    let mut buffer = Vec::new();
+    let mut index = 0;
+    loop {
+        buffer.clear();
+        let read = reader
+            .read_until(b'\n', &mut buffer)
+            .map_err(|e| AppError::new("EELogReplay", eyre!(e.to_string())))?;
+        if read == 0 {
+            break;
+        }
+        index += 1;
+        // The log is not always valid utf-8, player names can break it
+        let line = String::from_utf8_lossy(&buffer)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+
+        if speed > 0.0 {
+            if let Some(timestamp) = helper::get_timestamp(&line) {
+                if let Some(last) = last_timestamp {
+                    let delay = Duration::from_secs_f64(((timestamp - last) / speed).max(0.0));
+                    thread::sleep(delay.min(MAX_LINE_DELAY));
+                }
+                last_timestamp = Some(timestamp);
+            }
+        }
+
+        if let Some(user_name) = event_conversation.find_conversation(&line)? {
+            report.conversations.push(ReplayConversation {
+                file: file_name.clone(),
+                line: index,
+                user_name,
+            });
+            continue;
+        }
+        event_trading.check(index, &line)?;
+        for trade in event_trading.take_replayed_trades() {
+            report.trades.push(ReplayTrade {
+                file: file_name.clone(),
+                line: index,
+                trade,
+            });
+        }
+    }
+    report.lines += index;
+    report.files.push(file_name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    };
+
+    use super::{replay_file, ReplayReport};
+    use crate::{
+        auth::AuthState,
+        cache::client::CacheClient,
+        database::client::DBClient,
+        handler::MonitorHandler,
+        settings::SettingsState,
+        structs::{Item, TradeClassification},
+        wf_ee_log_parser::events::{
+            on_new_conversation::OnNewConversationEvent,
+            on_new_trading::{OnTradingEvent, TradeEventMode},
+            trade_reconciliation::TradeReconciler,
+        },
+        wfm_client::client::WFMClient,
+    };
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/wf_ee_log_parser/fixtures")
+            .join(name)
+    }
+
+    // A fresh database in its own folder, the trade parser only knows Primed Continuity
+    fn open_db(name: &str) -> Arc<Mutex<DBClient>> {
+        let settings = Arc::new(Mutex::new(SettingsState::default()));
+        let wfm = Arc::new(Mutex::new(WFMClient::new(
+            Arc::new(Mutex::new(AuthState::default())),
+            settings,
+        )));
+        let cache = CacheClient::new(Arc::clone(&wfm));
+        cache.cache_data.lock().unwrap().item.items.push(Item {
+            item_name: "Primed Continuity".to_string(),
+            id: "primed_continuity_id".to_string(),
+            url_name: "primed_continuity".to_string(),
+            thumb: "".to_string(),
+            wikia_url: None,
+            trade_tax: None,
+            mr_requirement: None,
+            set_items: None,
+            tags: Some(vec!["mod".to_string()]),
+            mod_max_rank: Some(10),
+            subtypes: None,
+        });
+
+        let path =
+            std::env::temp_dir().join(format!("quantframe_replay_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        let db = tauri::async_runtime::block_on(async {
+            let db = DBClient::open(Arc::new(Mutex::new(cache)), wfm, path)
+                .await
+                .unwrap();
+            db.initialize().await.unwrap();
+            db
+        });
+        Arc::new(Mutex::new(db))
+    }
+
+    fn replay(db: &Arc<Mutex<DBClient>>, path: &Path, reconcile: bool) -> ReplayReport {
+        let settings = Arc::new(Mutex::new(SettingsState::default()));
+        let mh = Arc::new(Mutex::new(MonitorHandler::headless()));
+        let (cache, wfm) = {
+            let db = db.lock().unwrap();
+            (Arc::clone(&db.cache), Arc::clone(&db.wfm))
+        };
+        let reconciler = Arc::new(Mutex::new(TradeReconciler::new(
+            settings,
+            Arc::clone(db),
+            wfm,
+        )));
+        let event_conversation =
+            OnNewConversationEvent::new(Arc::clone(&reconciler), Arc::clone(&mh), path.into());
+        let mut event_trading = OnTradingEvent::new(reconciler, mh, cache, path.into());
+        event_trading.set_mode(TradeEventMode::Replay { reconcile });
+
+        let mut report = ReplayReport::default();
+        replay_file(
+            path,
+            0.0,
+            &event_conversation,
+            &mut event_trading,
+            &mut report,
+        )
+        .unwrap();
+        report
+    }
+
+    #[test]
+    fn sale_is_collected_from_the_log() {
+        let db = open_db("collect");
+        let report = replay(&db, &fixture("sale.log"), false);
+
+        assert_eq!(report.lines, 7);
+        assert_eq!(report.conversations.len(), 1);
+        assert_eq!(report.conversations[0].user_name, "Trader");
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!(report.trades[0].line, 7);
+
+        let trade = &report.trades[0].trade;
+        assert_eq!(trade.user_name, "Trader");
+        assert_eq!(trade.trade_type, TradeClassification::Sale);
+        assert_eq!(trade.total_platinum, 25);
+        assert_eq!(trade.log_timestamp, Some(1030.118));
+        assert_eq!(trade.offerings.len(), 1);
+        assert_eq!(
+            trade.offerings[0].wfm_url_name.as_deref(),
+            Some("primed_continuity")
+        );
+
+        // Nothing is recorded without reconcile
+        let transactions =
+            tauri::async_runtime::block_on(db.lock().unwrap().transaction().get_items()).unwrap();
+        assert!(transactions.is_empty());
+    }
+
+    #[test]
+    fn replaying_a_log_again_records_the_sale_once() {
+        let db = open_db("reconcile");
+        let first = replay(&db, &fixture("sale.log"), true);
+        let second = replay(&db, &fixture("sale.log"), true);
+        assert_eq!(first.trades.len(), 1);
+        assert_eq!(second.trades.len(), 1);
+
+        let transactions =
+            tauri::async_runtime::block_on(db.lock().unwrap().transaction().get_items()).unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].transaction_type, "sell");
+        assert_eq!(transactions[0].price, 25);
+        let properties = transactions[0].properties.clone().unwrap().0.unwrap();
+        assert_eq!(properties["trade_key"], first.trades[0].trade.key());
+    }
+}
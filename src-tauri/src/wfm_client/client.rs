@@ -1,7 +1,9 @@
 use std::{
     collections::HashMap,
+    future::Future,
+    pin::Pin,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use eyre::eyre;
@@ -16,10 +18,11 @@ use serde_json::{json, Value};
 use crate::{
     auth::AuthState,
     enums::LogLevel,
-    error::{ApiResult, AppError, ErrorApiResponse},
+    error::{self, ApiResult, AppError, ErrorApiResponse},
     helper,
     logger::{self},
-    rate_limiter::RateLimiter,
+    metrics,
+    rate_limiter::{backoff_delay, RateLimiter},
 };
 
 use super::modules::{
@@ -27,11 +30,70 @@ use super::modules::{
     order::OrderModule,
 };
 
+// Attempts for reads that hit a server or network error, and for throttled requests
+const MAX_ATTEMPTS: u32 = 4;
+
+// Retry-After in seconds, the HTTP date form isn't used by warframe.market.
+// A value too large for a Duration (inf, 1e30) is ignored and the normal backoff is used.
+fn get_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds.max(0.0)).ok())
+}
+
+// Expired or missing token
+fn is_unauthorized<T>(result: &Result<ApiResult<T>, AppError>) -> bool {
+    match result {
+        Ok(ApiResult::Error(e, _)) => e.status_code == 401,
+        Err(e) => e.extra_data()["ApiError"]["statusCode"] == 401,
+        _ => false,
+    }
+}
+
+// Path segments that are part of a route, anything else is an item, user or id
+const ROUTE_SEGMENTS: &[&str] = &[
+    "items",
+    "orders",
+    "profile",
+    "auctions",
+    "create",
+    "entry",
+    "close",
+    "search",
+    "im",
+    "chats",
+    "auth",
+    "signin",
+    "riven",
+    "attributes",
+];
+
+// "items/mirage_prime_set/orders" becomes "items/:id/orders" so every item shares a label
+fn route_label(url: &str) -> String {
+    let path = url.split('?').next().unwrap_or("");
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if ROUTE_SEGMENTS.contains(&segment) {
+                segment
+            } else {
+                ":id"
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 #[derive(Clone, Debug)]
 pub struct WFMClient {
     endpoint: String,
     component: String,
     limiter: Arc<tokio::sync::Mutex<RateLimiter>>,
+    order_limiter: Arc<tokio::sync::Mutex<RateLimiter>>,
+    // Held while the session of the account is renewed
+    renew_lock: Arc<tokio::sync::Mutex<()>>,
     pub log_file: String,
     pub auth: Arc<Mutex<AuthState>>,
     pub settings: Arc<Mutex<crate::settings::SettingsState>>,
@@ -43,18 +105,43 @@ impl WFMClient {
         settings: Arc<Mutex<crate::settings::SettingsState>>,
     ) -> Self {
         WFMClient {
-            endpoint: "https://api.warframe.market/v1/".to_string(),
+            // Can be pointed at another server, e.g. the mock server
+            endpoint: std::env::var("WFM_ENDPOINT")
+                .unwrap_or("https://api.warframe.market/v1/".to_string()),
             component: "WarframeMarket".to_string(),
             limiter: Arc::new(tokio::sync::Mutex::new(RateLimiter::new(
                 1.0,
                 Duration::new(1, 0),
             ))),
+            order_limiter: Arc::new(tokio::sync::Mutex::new(RateLimiter::new(
+                1.0,
+                Duration::new(1, 0),
+            ))),
+            renew_lock: Arc::new(tokio::sync::Mutex::new(())),
             log_file: "wfmAPICalls.log".to_string(),
             auth,
             settings,
         }
     }
 
+    pub fn set_endpoint(&mut self, endpoint: &str) {
+        self.endpoint = endpoint.to_string();
+    }
+
+    // Client for another account, the endpoint and rate limits are shared
+    pub fn with_account(
+        &self,
+        auth: Arc<Mutex<AuthState>>,
+        settings: Arc<Mutex<crate::settings::SettingsState>>,
+    ) -> Self {
+        WFMClient {
+            auth,
+            settings,
+            renew_lock: Arc::new(tokio::sync::Mutex::new(())),
+            ..self.clone()
+        }
+    }
+
     pub fn debug(&self, id: &str, component: &str, msg: &str, file: Option<bool>) {
         let settings = self.settings.lock().unwrap().clone();
         if !settings.debug.contains(&"*".to_owned()) && !settings.debug.contains(&id.to_owned()) {
@@ -93,6 +180,7 @@ impl WFMClient {
         );
     }
 
+    // An expired session is renewed with the stored credentials and the request is sent once more
     async fn send_request<T: DeserializeOwned>(
         &self,
         method: Method,
@@ -100,16 +188,91 @@ impl WFMClient {
         payload_key: Option<&str>,
         body: Option<Value>,
     ) -> Result<ApiResult<T>, AppError> {
-        let auth = self.auth.lock()?.clone();
-        let mut rate_limiter = self.limiter.lock().await;
+        let token = self.auth.lock()?.access_token.clone();
+        let result = self
+            .send_attempt(method.clone(), url, payload_key, body.clone())
+            .await;
+        if url == "/auth/signin" || !is_unauthorized(&result) || !self.renew_session(token).await {
+            return result;
+        }
+        self.send_attempt(method, url, payload_key, body).await
+    }
 
-        rate_limiter.wait_for_token().await;
+    // Boxed, logging in sends a request itself.
+    // Requests that fail at the same time wait for one renewal instead of each logging in,
+    // the token they failed with tells if it was renewed while they waited.
+    pub fn renew_session(
+        &self,
+        expired_token: Option<String>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + '_>> {
+        Box::pin(async move {
+            let _renewing = self.renew_lock.lock().await;
+            let (token, credentials) = match self
+                .auth
+                .lock()
+                .map(|auth| (auth.access_token.clone(), auth.credentials.clone()))
+            {
+                Ok(values) => values,
+                Err(_) => return false,
+            };
+            if token.is_some() && token != expired_token {
+                return true;
+            }
+            let credentials = match credentials {
+                Some(credentials) => credentials,
+                None => return false,
+            };
+            logger::warning_con(&self.component, "Session expired, logging in again");
+            let user = match self
+                .auth()
+                .login(credentials.email, credentials.password)
+                .await
+            {
+                Ok(user) if user.access_token.is_some() => user,
+                Ok(_) => return false,
+                Err(e) => {
+                    error::create_log_file("auth.log".to_string(), &e);
+                    return false;
+                }
+            };
+            let auth = match self.auth.lock() {
+                Ok(mut auth) => {
+                    auth.set_user(user);
+                    auth.clone()
+                }
+                Err(_) => return false,
+            };
+            if let Err(e) = auth.save_to_file() {
+                error::create_log_file("auth.log".to_string(), &e);
+            }
+            if auth.is_active_profile() {
+                auth.send_to_window();
+            }
+            true
+        })
+    }
 
-        let packageinfo = crate::PACKAGEINFO
-            .lock()
-            .unwrap()
-            .clone()
-            .expect("Could not get package info");
+    async fn send_attempt<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        payload_key: Option<&str>,
+        body: Option<Value>,
+    ) -> Result<ApiResult<T>, AppError> {
+        let auth = self.auth.lock()?.clone();
+        // Order mutations have their own budget so a busy scan doesn't hold them up
+        let is_read = method == Method::GET;
+        let limiter = if is_read {
+            &self.limiter
+        } else {
+            &self.order_limiter
+        };
+
+        // Package info is only set once tauri is running
+        let version = match crate::PACKAGEINFO.lock().unwrap().clone() {
+            Some(packageinfo) => packageinfo.version.to_string(),
+            None => "dev".to_string(),
+        };
 
         let client = Client::new();
         let new_url = format!("{}{}", self.endpoint, url);
@@ -121,7 +284,7 @@ impl WFMClient {
             )
             .header(
                 "User-Agent",
-                format!("Quantframe {}", packageinfo.version.to_string()),
+                format!("Quantframe {}", version),
             )
             .header("Language", auth.region);
 
@@ -130,8 +293,6 @@ impl WFMClient {
             None => request,
         };
 
-        let response = request.send().await;
-
         // Create default error response
         let mut error_def = ErrorApiResponse {
             status_code: 500,
@@ -143,23 +304,110 @@ impl WFMClient {
             method: Some(method.to_string()),
         };
 
-        if let Err(e) = response {
-            error_def.messages.push(e.to_string());
-            return Err(AppError::new_api(
-                "WarframeMarket",
-                error_def,
-                eyre!(format!("There was an error sending the request: {}", e)),
-                LogLevel::Critical,
-            ));
-        }
+        let route = route_label(url);
+        let mut attempt = 0;
+        let response_data = loop {
+            attempt += 1;
+            limiter.lock().await.wait_for_token().await;
+            let attempt_request = request
+                .try_clone()
+                .ok_or_else(|| AppError::new(&self.component, eyre!("Request can't be retried")))?;
+            let started = Instant::now();
+            let response = attempt_request.send().await;
+            let status = match &response {
+                Ok(response) => response.status().as_u16().to_string(),
+                Err(_) => "error".to_string(),
+            };
+            metrics::inc(
+                metrics::WFM_REQUESTS,
+                &[
+                    ("method", method.as_str()),
+                    ("route", &route),
+                    ("status", &status),
+                ],
+            );
+            metrics::observe(
+                metrics::WFM_REQUEST_DURATION,
+                &[("method", method.as_str()), ("route", &route)],
+                started.elapsed(),
+            );
+
+            match response {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    limiter
+                        .lock()
+                        .await
+                        .on_response(status, get_retry_after(response.headers()));
+                    // A mutation that got a 5xx may have gone through, only throttled ones are sent again
+                    let retryable = status == 429 || (is_read && status >= 500);
+                    if !retryable || attempt >= MAX_ATTEMPTS {
+                        break response;
+                    }
+                    logger::warning_con(
+                        &self.component,
+                        format!(
+                            "{} {} returned {}, retrying ({}/{})",
+                            method, url, status, attempt, MAX_ATTEMPTS
+                        )
+                        .as_str(),
+                    );
+                    // The limiter already backs off for throttled responses
+                    if status != 429 && status != 503 {
+                        tokio::time::sleep(backoff_delay(attempt)).await;
+                    }
+                }
+                Err(e) => {
+                    // A mutation is only sent again when it never reached the server
+                    let retryable = is_read || e.is_connect();
+                    if !retryable || attempt >= MAX_ATTEMPTS {
+                        error_def.messages.push(e.to_string());
+                        // Transient, the live scraper logs it and tries again next cycle
+                        return Err(AppError::new_api(
+                            "WarframeMarket",
+                            error_def,
+                            eyre!(format!(
+                                "There was an error sending the request after {} attempts: {}",
+                                attempt, e
+                            )),
+                            LogLevel::Warning,
+                        ));
+                    }
+                    let delay = backoff_delay(attempt);
+                    logger::warning_con(
+                        &self.component,
+                        format!(
+                            "{} {} failed: {}, retrying in {:?} ({}/{})",
+                            method, url, e, delay, attempt, MAX_ATTEMPTS
+                        )
+                        .as_str(),
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        };
 
         // Get the response data from the response
-        let response_data = response.unwrap();
-        error_def.status_code = response_data.status().as_u16() as i64;
+        let status = response_data.status().as_u16();
+        error_def.status_code = status as i64;
         let headers = response_data.headers().clone();
         let content = response_data.text().await.unwrap_or_default();
         error_def.raw_response = Some(content.clone());
 
+        // Still failing after the retries, transient so the live scraper keeps going
+        if status == 429 || status >= 500 {
+            error_def.error = "ServerError".to_string();
+            return Err(AppError::new_api(
+                self.component.as_str(),
+                error_def,
+                eyre!(format!(
+                    "Server returned {} after {} attempts",
+                    status, attempt
+                )),
+                LogLevel::Warning,
+            ));
+        }
+
         // Convert the response to a Value object
         let response: Value = serde_json::from_str(content.as_str()).map_err(|e| {
             error_def.messages.push(e.to_string());
@@ -309,3 +557,100 @@ impl WFMClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use std::sync::{Arc, Mutex};
+
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    use super::{get_retry_after, route_label, WFMClient};
+    use crate::{
+        auth::{AuthState, Credentials},
+        profiles,
+        settings::SettingsState,
+        wfm_client::mock_server::MockServer,
+    };
+
+    fn retry_after(value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_str(value).unwrap());
+        get_retry_after(&headers)
+    }
+
+    #[test]
+    fn retry_after_is_read_in_seconds() {
+        assert_eq!(retry_after("2"), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after(" 0.5 "), Some(Duration::from_millis(500)));
+        assert_eq!(retry_after("-3"), Some(Duration::ZERO));
+        assert_eq!(get_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn retry_after_out_of_range_is_ignored() {
+        assert_eq!(retry_after("inf"), None);
+        assert_eq!(retry_after("1e30"), None);
+        assert_eq!(retry_after("soon"), None);
+    }
+
+    #[test]
+    fn ids_are_left_out_of_the_route() {
+        assert_eq!(
+            route_label("items/nova_prime_set/orders?include=item"),
+            "items/:id/orders"
+        );
+        assert_eq!(route_label("/profile/orders/5f1e"), "profile/orders/:id");
+        assert_eq!(
+            route_label("/auctions/search?type=riven"),
+            "auctions/search"
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_renewals_log_in_once() {
+        let server = MockServer::start().await.unwrap();
+        let auth = AuthState {
+            access_token: Some("expired".to_string()),
+            credentials: Some(Credentials {
+                email: "mock@example.com".to_string(),
+                password: "password".to_string(),
+            }),
+            // Not the active profile, so the window isn't told about the new token
+            profile: Some(format!("test_renew_{}", std::process::id())),
+            ..AuthState::default()
+        };
+        let auth = Arc::new(Mutex::new(auth));
+        let mut wfm = WFMClient::new(
+            Arc::clone(&auth),
+            Arc::new(Mutex::new(SettingsState::default())),
+        );
+        wfm.set_endpoint(&server.endpoint());
+
+        let expired = Some("expired".to_string());
+        let renewed = tokio::join!(
+            wfm.renew_session(expired.clone()),
+            wfm.renew_session(expired.clone()),
+            wfm.renew_session(expired.clone()),
+        );
+        assert_eq!(renewed, (true, true, true));
+        assert_eq!(
+            auth.lock().unwrap().access_token.as_deref(),
+            Some("mock_access_token")
+        );
+        let signins = server
+            .state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.path == "auth/signin")
+            .count();
+        assert_eq!(signins, 1);
+
+        if let Ok(path) = profiles::get_data_path(&format!("test_renew_{}", std::process::id())) {
+            let _ = std::fs::remove_dir_all(path);
+        }
+    }
+}
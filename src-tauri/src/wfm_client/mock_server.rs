@@ -0,0 +1,775 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+
+use crate::{
+    error::AppError,
+    http_server::{self, HttpListener, HttpResponse},
+    logger,
+};
+
+// Every timestamp the mock hands out, keeps responses deterministic.
+const MOCK_TIMESTAMP: &str = "2024-01-01T00:00:00.000+00:00";
+
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Option<Value>,
+}
+
+pub struct MockResponse {
+    pub status: u16,
+    pub body: Value,
+    pub headers: Vec<(String, String)>,
+}
+
+impl MockResponse {
+    fn payload(payload: Value) -> Self {
+        MockResponse {
+            status: 200,
+            body: json!({ "payload": payload }),
+            headers: vec![],
+        }
+    }
+    // Same shape as the real api, parsed by WFMClient::send_request into an ErrorApiResponse
+    fn error(status: u16, key: &str, message: &str) -> Self {
+        let mut error = serde_json::Map::new();
+        error.insert(key.to_string(), json!([message]));
+        MockResponse {
+            status,
+            body: json!({ "error": error }),
+            headers: vec![],
+        }
+    }
+}
+
+// In memory warframe.market state. Tests seed it and inspect it through MockServer::state.
+pub struct MockState {
+    pub user: Value,
+    pub items: Vec<Value>,
+    pub item_details: HashMap<String, Value>,
+    // Orders from other traders by item url name
+    pub live_orders: HashMap<String, Vec<Value>>,
+    pub my_orders: Vec<Value>,
+    pub auctions: Vec<Value>,
+    pub search_auctions: Vec<Value>,
+    pub riven_items: Vec<Value>,
+    pub riven_attributes: Vec<Value>,
+    pub chats: Vec<Value>,
+    pub chat_messages: HashMap<String, Vec<Value>>,
+    // Forced error responses keyed by "METHOD path", e.g. "DELETE profile/orders/123"
+    pub errors: HashMap<String, (u16, Value)>,
+    pub requests: Vec<MockRequest>,
+    next_id: u64,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        MockState {
+            user: json!({
+                "banned": false,
+                "id": "mock_user_id",
+                "avatar": null,
+                "ingame_name": "MockUser",
+                "locale": "en",
+                "platform": "pc",
+                "region": "en",
+                "role": "user",
+                "status": "invisible"
+            }),
+            items: vec![],
+            item_details: HashMap::new(),
+            live_orders: HashMap::new(),
+            my_orders: vec![],
+            auctions: vec![],
+            search_auctions: vec![],
+            riven_items: vec![],
+            riven_attributes: vec![],
+            chats: vec![],
+            chat_messages: HashMap::new(),
+            errors: HashMap::new(),
+            requests: vec![],
+            next_id: 0,
+        }
+    }
+}
+
+impl MockState {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}_{}", prefix, self.next_id)
+    }
+
+    fn ingame_name(&self) -> String {
+        self.user["ingame_name"].as_str().unwrap_or("").to_string()
+    }
+
+    fn order_user(&self, ingame_name: &str) -> Value {
+        json!({
+            "reputation": 0.0,
+            "ingame_name": ingame_name,
+            "id": format!("{}_id", ingame_name),
+            "status": "ingame"
+        })
+    }
+
+    fn order_item(&self, item_id: &str) -> Value {
+        let item = self.items.iter().find(|item| item["id"] == item_id);
+        match item {
+            Some(item) => json!({
+                "id": item["id"],
+                "url_name": item["url_name"],
+                "icon": "",
+                "icon_format": null,
+                "thumb": item["thumb"],
+                "sub_icon": null,
+                "mod_max_rank": item["mod_max_rank"],
+                "subtypes": null,
+                "tags": item["tags"].as_array().cloned().unwrap_or_default(),
+                "ducats": null,
+                "quantity_for_set": null,
+                "vaulted": null,
+                "en": { "item_name": item["item_name"] }
+            }),
+            // Unknown items are accepted so auth validation works without seeding
+            None => json!({
+                "id": item_id,
+                "url_name": item_id,
+                "icon": "",
+                "icon_format": null,
+                "thumb": "",
+                "sub_icon": null,
+                "mod_max_rank": null,
+                "subtypes": null,
+                "tags": [],
+                "ducats": null,
+                "quantity_for_set": null,
+                "vaulted": null,
+                "en": { "item_name": item_id }
+            }),
+        }
+    }
+
+    fn new_order(
+        &mut self,
+        ingame_name: &str,
+        item_id: &str,
+        order_type: &str,
+        platinum: i64,
+        quantity: i64,
+        visible: bool,
+        rank: Option<i64>,
+    ) -> Value {
+        json!({
+            "id": self.next_id("order"),
+            "platinum": platinum,
+            "visible": visible,
+            "order_type": order_type,
+            "user": self.order_user(ingame_name),
+            "last_update": MOCK_TIMESTAMP,
+            "region": "en",
+            "platform": "pc",
+            "creation_date": MOCK_TIMESTAMP,
+            "subtype": null,
+            "quantity": quantity,
+            "mod_rank": rank,
+            "item": self.order_item(item_id)
+        })
+    }
+
+    // Seeding
+    pub fn add_item(
+        &mut self,
+        id: &str,
+        url_name: &str,
+        item_name: &str,
+        mod_max_rank: Option<i64>,
+    ) {
+        self.items.push(json!({
+            "item_name": item_name,
+            "id": id,
+            "url_name": url_name,
+            "thumb": "",
+            "wikia_url": null,
+            "trade_tax": null,
+            "mr_requirement": null,
+            "set_items": null,
+            "tags": [],
+            "mod_max_rank": mod_max_rank,
+            "subtypes": null
+        }));
+        self.item_details.insert(
+            url_name.to_string(),
+            json!({
+                "id": id,
+                "items_in_set": [{ "id": id, "mod_max_rank": mod_max_rank }]
+            }),
+        );
+    }
+
+    pub fn add_live_order(
+        &mut self,
+        url_name: &str,
+        ingame_name: &str,
+        order_type: &str,
+        platinum: i64,
+        quantity: i64,
+    ) -> Value {
+        let item_id = self
+            .items
+            .iter()
+            .find(|item| item["url_name"] == url_name)
+            .map(|item| item["id"].as_str().unwrap_or(url_name).to_string())
+            .unwrap_or(url_name.to_string());
+        let order = self.new_order(
+            ingame_name,
+            &item_id,
+            order_type,
+            platinum,
+            quantity,
+            true,
+            None,
+        );
+        self.live_orders
+            .entry(url_name.to_string())
+            .or_default()
+            .push(order.clone());
+        order
+    }
+
+    pub fn add_search_auction(&mut self, weapon_url_name: &str, owner: &str, buyout_price: i64) {
+        let id = self.next_id("auction");
+        self.search_auctions.push(json!({
+            "visible": true,
+            "minimal_reputation": 0,
+            "item": {
+                "type": "riven",
+                "weapon_url_name": weapon_url_name,
+                "re_rolls": 0,
+                "attributes": [],
+                "name": "mock",
+                "mod_rank": 0,
+                "polarity": "madurai",
+                "mastery_level": 8
+            },
+            "buyout_price": buyout_price,
+            "note": "",
+            "starting_price": buyout_price,
+            "owner": {
+                "ingame_name": owner,
+                "last_seen": MOCK_TIMESTAMP,
+                "reputation": 0,
+                "locale": "en",
+                "status": "ingame",
+                "id": format!("{}_id", owner),
+                "region": "en",
+                "avatar": null
+            },
+            "platform": "pc",
+            "closed": false,
+            "top_bid": null,
+            "winner": null,
+            "is_marked_for": null,
+            "marked_operation_at": null,
+            "created": MOCK_TIMESTAMP,
+            "updated": MOCK_TIMESTAMP,
+            "note_raw": "",
+            "is_direct_sell": true,
+            "id": id,
+            "private": false
+        }));
+    }
+
+    pub fn add_chat(&mut self, chat_with: &str, messages: Vec<&str>) -> String {
+        let chat_id = self.next_id("chat");
+        let messages: Vec<Value> = messages
+            .into_iter()
+            .map(|message| {
+                json!({
+                    "message": message,
+                    "id": self.next_id("message"),
+                    "chat_id": chat_id,
+                    "send_date": MOCK_TIMESTAMP,
+                    "message_from": format!("{}_id", chat_with),
+                    "raw_message": message
+                })
+            })
+            .collect();
+        self.chats.push(json!({
+            "id": chat_id,
+            "chat_with": [{
+                "reputation": 0,
+                "locale": "en",
+                "avatar": null,
+                "last_seen": MOCK_TIMESTAMP,
+                "ingame_name": chat_with,
+                "status": "ingame",
+                "id": format!("{}_id", chat_with),
+                "region": "en"
+            }],
+            "unread_count": messages.len(),
+            "chat_name": chat_with,
+            "messages": [],
+            "last_update": MOCK_TIMESTAMP
+        }));
+        self.chat_messages.insert(chat_id.clone(), messages);
+        chat_id
+    }
+
+    // Forces the given route to answer with {"error": error} until cleared.
+    pub fn set_error(&mut self, method: &str, path: &str, status: u16, error: Value) {
+        self.errors.insert(
+            format!("{} {}", method, path.trim_matches('/')),
+            (status, error),
+        );
+    }
+
+    pub fn clear_errors(&mut self) {
+        self.errors.clear();
+    }
+
+    fn handle(&mut self, method: &str, target: &str, body: Option<Value>) -> MockResponse {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .skip_while(|segment| *segment == "v1")
+            .collect::<Vec<_>>()
+            .join("/");
+        let query: HashMap<&str, &str> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .collect();
+        self.requests.push(MockRequest {
+            method: method.to_string(),
+            path: path.clone(),
+            body: body.clone(),
+        });
+
+        if let Some((status, error)) = self.errors.get(&format!("{} {}", method, path)) {
+            return MockResponse {
+                status: *status,
+                body: json!({ "error": error }),
+                headers: vec![],
+            };
+        }
+
+        let body = body.unwrap_or(json!({}));
+        let segments: Vec<&str> = path.split('/').collect();
+        match (method, segments.as_slice()) {
+            // Auth
+            ("POST", ["auth", "signin"]) => MockResponse {
+                status: 200,
+                body: json!({ "payload": { "user": self.user } }),
+                headers: vec![(
+                    "Set-Cookie".to_string(),
+                    "JWT=mock_access_token; Path=/; HttpOnly".to_string(),
+                )],
+            },
+
+            // Items
+            ("GET", ["items"]) => MockResponse::payload(json!({ "items": self.items })),
+            ("GET", ["items", url_name]) => match self.item_details.get(*url_name) {
+                Some(item) => MockResponse::payload(json!({ "item": item })),
+                None => MockResponse::error(404, "request", "app.item.not_exist"),
+            },
+            ("GET", ["items", url_name, "orders"]) => {
+                let mut orders = self.live_orders.get(*url_name).cloned().unwrap_or_default();
+                orders.extend(
+                    self.my_orders
+                        .iter()
+                        .filter(|order| order["item"]["url_name"] == *url_name)
+                        .cloned(),
+                );
+                MockResponse::payload(json!({ "orders": orders }))
+            }
+
+            // Orders
+            ("GET", ["profile", _, "orders"]) => {
+                let (buy_orders, sell_orders): (Vec<Value>, Vec<Value>) = self
+                    .my_orders
+                    .iter()
+                    .cloned()
+                    .partition(|order| order["order_type"] == "buy");
+                MockResponse::payload(json!({
+                    "buy_orders": buy_orders,
+                    "sell_orders": sell_orders
+                }))
+            }
+            ("POST", ["profile", "orders"]) => {
+                let item_id = body["item"].as_str().unwrap_or("").to_string();
+                let order_type = body["order_type"].as_str().unwrap_or("").to_string();
+                let exists = self.my_orders.iter().any(|order| {
+                    order["item"]["id"] == item_id.as_str()
+                        && order["order_type"] == order_type.as_str()
+                });
+                if exists {
+                    return MockResponse::error(
+                        400,
+                        "item_id",
+                        "app.post_order.already_created_no_duplicates",
+                    );
+                }
+                let ingame_name = self.ingame_name();
+                let order = self.new_order(
+                    &ingame_name,
+                    &item_id,
+                    &order_type,
+                    body["platinum"].as_i64().unwrap_or(0),
+                    body["quantity"].as_i64().unwrap_or(1),
+                    body["visible"].as_bool().unwrap_or(true),
+                    body["rank"].as_f64().map(|rank| rank as i64),
+                );
+                self.my_orders.push(order.clone());
+                MockResponse::payload(json!({ "order": order }))
+            }
+            ("PUT", ["profile", "orders", "close", order_id]) => {
+                let index = self
+                    .my_orders
+                    .iter()
+                    .position(|order| order["id"] == *order_id);
+                match index {
+                    Some(index) => {
+                        let quantity = self.my_orders[index]["quantity"].as_i64().unwrap_or(1) - 1;
+                        if quantity <= 0 {
+                            self.my_orders.remove(index);
+                            MockResponse::payload(json!({ "order": null }))
+                        } else {
+                            self.my_orders[index]["quantity"] = json!(quantity);
+                            MockResponse::payload(json!({ "order": self.my_orders[index] }))
+                        }
+                    }
+                    None => MockResponse::error(400, "order_id", "app.close_order.order_not_exist"),
+                }
+            }
+            ("PUT", ["profile", "orders", order_id]) => {
+                match self
+                    .my_orders
+                    .iter_mut()
+                    .find(|order| order["id"] == *order_id)
+                {
+                    Some(order) => {
+                        for key in ["platinum", "quantity", "visible"] {
+                            if !body[key].is_null() {
+                                order[key] = body[key].clone();
+                            }
+                        }
+                        MockResponse::payload(json!({ "order": order }))
+                    }
+                    None => {
+                        MockResponse::error(400, "order_id", "app.delete_order.order_not_exist")
+                    }
+                }
+            }
+            ("DELETE", ["profile", "orders", order_id]) => {
+                let index = self
+                    .my_orders
+                    .iter()
+                    .position(|order| order["id"] == *order_id);
+                match index {
+                    Some(index) => {
+                        self.my_orders.remove(index);
+                        MockResponse::payload(json!({ "order_id": order_id }))
+                    }
+                    None => {
+                        MockResponse::error(400, "order_id", "app.delete_order.order_not_exist")
+                    }
+                }
+            }
+
+            // Rivens
+            ("GET", ["riven", "items"]) => {
+                MockResponse::payload(json!({ "items": self.riven_items }))
+            }
+            ("GET", ["riven", "attributes"]) => {
+                MockResponse::payload(json!({ "attributes": self.riven_attributes }))
+            }
+
+            // Auctions
+            ("GET", ["profile", _, "auctions"]) => {
+                MockResponse::payload(json!({ "auctions": self.auctions }))
+            }
+            ("GET", ["auctions", "search"]) => {
+                let auctions: Vec<Value> = self
+                    .search_auctions
+                    .iter()
+                    .filter(|auction| match query.get("weapon_url_name") {
+                        Some(weapon) => auction["item"]["weapon_url_name"] == *weapon,
+                        None => true,
+                    })
+                    .cloned()
+                    .collect();
+                MockResponse::payload(json!({ "auctions": auctions }))
+            }
+            ("POST", ["auctions", "create"]) => {
+                let auction = json!({
+                    "visible": true,
+                    "minimal_reputation": body["minimal_reputation"].as_i64().unwrap_or(0),
+                    "item": body["item"],
+                    "buyout_price": body["buyout_price"],
+                    "note": body["note"].as_str().unwrap_or(""),
+                    "starting_price": body["starting_price"].as_i64().unwrap_or(0),
+                    "owner": self.user["id"],
+                    "platform": "pc",
+                    "closed": false,
+                    "top_bid": null,
+                    "winner": null,
+                    "is_marked_for": null,
+                    "marked_operation_at": null,
+                    "created": MOCK_TIMESTAMP,
+                    "updated": MOCK_TIMESTAMP,
+                    "note_raw": body["note"].as_str().unwrap_or(""),
+                    "is_direct_sell": body["buyout_price"] == body["starting_price"],
+                    "id": self.next_id("auction"),
+                    "private": body["private"].as_bool().unwrap_or(false)
+                });
+                self.auctions.push(auction.clone());
+                MockResponse::payload(json!({ "auction": auction }))
+            }
+            ("PUT", ["auctions", "entry", auction_id, "close"]) => {
+                let index = self
+                    .auctions
+                    .iter()
+                    .position(|auction| auction["id"] == *auction_id);
+                match index {
+                    Some(index) => {
+                        self.auctions.remove(index);
+                        MockResponse::payload(json!({ "auction_id": auction_id }))
+                    }
+                    None => MockResponse::error(400, "auction_id", "app.auction.not_exist"),
+                }
+            }
+            ("PUT", ["auctions", "entry", auction_id]) => {
+                match self
+                    .auctions
+                    .iter_mut()
+                    .find(|auction| auction["id"] == *auction_id)
+                {
+                    Some(auction) => {
+                        for key in [
+                            "buyout_price",
+                            "minimal_reputation",
+                            "note",
+                            "starting_price",
+                            "visible",
+                        ] {
+                            if !body[key].is_null() {
+                                auction[key] = body[key].clone();
+                            }
+                        }
+                        MockResponse::payload(json!({ "auction": auction }))
+                    }
+                    None => MockResponse::error(400, "auction_id", "app.auction.not_exist"),
+                }
+            }
+
+            // Chats
+            ("GET", ["im", "chats"]) => MockResponse::payload(json!({ "chats": self.chats })),
+            ("GET", ["im", "chats", chat_id]) => match self.chat_messages.get(*chat_id) {
+                Some(messages) => MockResponse::payload(json!({ "messages": messages })),
+                None => MockResponse::error(400, "chat_id", "app.chat.not_exist"),
+            },
+            ("DELETE", ["im", "chats", chat_id]) => {
+                let index = self.chats.iter().position(|chat| chat["id"] == *chat_id);
+                match index {
+                    Some(index) => {
+                        self.chats.remove(index);
+                        self.chat_messages.remove(*chat_id);
+                        MockResponse::payload(json!({ "chat_id": chat_id }))
+                    }
+                    None => MockResponse::error(400, "chat_id", "app.chat.not_exist"),
+                }
+            }
+
+            _ => MockResponse::error(404, "request", "app.general.not_found"),
+        }
+    }
+}
+
+// In-process warframe.market server, point a WFMClient at it with WFMClient::set_endpoint.
+pub struct MockServer {
+    pub addr: SocketAddr,
+    pub state: Arc<Mutex<MockState>>,
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start() -> Result<MockServer, AppError> {
+        Self::start_with_state(MockState::default()).await
+    }
+
+    pub async fn start_with_state(state: MockState) -> Result<MockServer, AppError> {
+        let listener = HttpListener::bind("127.0.0.1:0", "MockServer")?;
+        let addr = listener.addr;
+        let state = Arc::new(Mutex::new(state));
+
+        let server_state = Arc::clone(&state);
+        let handle = listener.serve("MockServer", move |stream| {
+            handle_connection(stream, Arc::clone(&server_state))
+        });
+        logger::info_con(
+            "MockServer",
+            format!("Mock warframe.market listening on {}", addr).as_str(),
+        );
+        Ok(MockServer {
+            addr,
+            state,
+            handle,
+        })
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://{}/v1/", self.addr)
+    }
+
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+// One request per connection, enough for reqwest with "Connection: close".
+async fn handle_connection(
+    mut stream: TcpStream,
+    state: Arc<Mutex<MockState>>,
+) -> std::io::Result<()> {
+    let request = match http_server::read_request(&mut stream).await? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+    let body: Option<Value> = serde_json::from_slice(&request.body).ok();
+
+    let response = state
+        .lock()
+        .unwrap()
+        .handle(&request.method, &request.target, body);
+
+    http_server::write_response(
+        &mut stream,
+        HttpResponse {
+            status: response.status,
+            content_type: "application/json",
+            headers: response.headers,
+            body: response.body.to_string(),
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::MockServer;
+    use crate::{
+        auth::AuthState, settings::SettingsState, structs::AuctionItem,
+        wfm_client::client::WFMClient,
+    };
+
+    async fn start() -> (MockServer, WFMClient) {
+        let server = MockServer::start().await.unwrap();
+        let auth = AuthState {
+            ingame_name: "MockUser".to_string(),
+            ..AuthState::default()
+        };
+        let mut wfm = WFMClient::new(
+            Arc::new(Mutex::new(auth)),
+            Arc::new(Mutex::new(SettingsState::default())),
+        );
+        wfm.set_endpoint(&server.endpoint());
+        (server, wfm)
+    }
+
+    #[tokio::test]
+    async fn orders_are_created_updated_and_deleted() {
+        let (server, wfm) = start().await;
+        server.state.lock().unwrap().add_item(
+            "item_id",
+            "mirage_prime_set",
+            "Mirage Prime Set",
+            None,
+        );
+
+        let order = wfm
+            .orders()
+            .create("item_id", "sell", 100, 2, true, None)
+            .await
+            .unwrap();
+        assert_eq!(order.platinum, 100);
+
+        wfm.orders().update(&order.id, 90, 2, true).await.unwrap();
+        let orders = wfm.orders().get_my_orders().await.unwrap();
+        assert_eq!(orders.sell_orders.len(), 1);
+        assert_eq!(orders.sell_orders[0].platinum, 90);
+        assert!(orders.buy_orders.is_empty());
+
+        wfm.orders().delete(&order.id).await.unwrap();
+        let orders = wfm.orders().get_my_orders().await.unwrap();
+        assert!(orders.sell_orders.is_empty());
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn auctions_are_created_updated_and_closed() {
+        let (server, wfm) = start().await;
+        let item: AuctionItem = serde_json::from_value(json!({
+            "type": "riven",
+            "weapon_url_name": "soma",
+            "re_rolls": 0,
+            "attributes": [],
+            "name": "crita-satiatis",
+            "mod_rank": 0,
+            "polarity": "madurai",
+            "mastery_level": 8
+        }))
+        .unwrap();
+
+        let auction = wfm
+            .auction()
+            .create("riven", "", 200, 200, 0, 1, false, item)
+            .await
+            .unwrap();
+        assert_eq!(auction.buyout_price, Some(200));
+
+        wfm.auction()
+            .update(&auction.id, 150, 0, "", 150, true)
+            .await
+            .unwrap();
+        let auctions = wfm.auction().get_my_auctions().await.unwrap();
+        assert_eq!(auctions.len(), 1);
+        assert_eq!(auctions[0].buyout_price, Some(150));
+
+        wfm.auction().delete(&auction.id).await.unwrap();
+        assert!(wfm.auction().get_my_auctions().await.unwrap().is_empty());
+        server.stop();
+    }
+
+    #[tokio::test]
+    async fn api_errors_become_app_errors() {
+        let (server, wfm) = start().await;
+        server.state.lock().unwrap().set_error(
+            "DELETE",
+            "profile/orders/order_1",
+            400,
+            json!({ "order_id": ["app.delete_order.order_not_exist"] }),
+        );
+
+        let error = wfm.orders().delete("order_1").await.unwrap_err();
+        assert_eq!(error.component(), "WarframeMarket:Order:Delete");
+        let api_error = &error.extra_data()["ApiError"];
+        assert_eq!(api_error["statusCode"], 400);
+        assert_eq!(api_error["error"], "ApiError");
+        assert_eq!(
+            api_error["messages"],
+            json!(["order_id: app.delete_order.order_not_exist"])
+        );
+        server.stop();
+    }
+}
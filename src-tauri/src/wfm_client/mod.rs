@@ -1,2 +1,4 @@
 pub mod client;
 pub mod modules;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
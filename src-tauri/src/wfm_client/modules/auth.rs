@@ -63,10 +63,12 @@ impl<'a> AuthModule<'a> {
     }
 
     pub async fn validate(&self) -> Result<bool, AppError> {
-        let mut auth = self.client.auth.lock()?.clone();
-        if auth.access_token.is_none() {
+        let has_token = self.client.auth.lock()?.access_token.is_some();
+        // A session that was dropped earlier can still be renewed with stored credentials
+        if !has_token && !self.client.renew_session(None).await {
             return Ok(false);
         }
+        let mut auth = self.client.auth.lock()?.clone();
 
         match self
             .client
@@ -211,15 +211,29 @@ impl<'a> OrderModule<'a> {
     }
 
     pub async fn close(&self, item: &str, order_type: OrderType) -> Result<String, AppError> {
+        self.close_quantity(item, order_type, None, 1).await
+    }
+
+    // Closes the order once per unit, warframe.market takes one off the quantity per close.
+    // With a rank only the order for that rank is closed.
+    pub async fn close_quantity(
+        &self,
+        item: &str,
+        order_type: OrderType,
+        rank: Option<i64>,
+        quantity: i64,
+    ) -> Result<String, AppError> {
         // Get the user orders and find the order
         let mut ordres_vec = self.get_my_orders().await?;
         let mut ordres: Vec<Order> = ordres_vec.buy_orders;
         ordres.append(&mut ordres_vec.sell_orders);
-        // Find Order by name.
+        // Find Order by name and rank.
         let order = ordres
             .iter()
             .find(|order| {
-                order.item.as_ref().unwrap().url_name == item && order.order_type == order_type
+                order.item.as_ref().unwrap().url_name == item
+                    && order.order_type == order_type
+                    && (rank.is_none() || order.mod_rank.unwrap_or(0) == rank.unwrap_or(0))
             })
             .clone();
 
@@ -231,8 +245,9 @@ impl<'a> OrderModule<'a> {
 
         let url = format!("profile/orders/close/{}", order.id);
 
-        let result: Option<serde_json::Value> =
-            match self.client.put(&url, Some("order"), None).await {
+        let mut result: Option<serde_json::Value> = None;
+        for _ in 0..quantity.max(1) {
+            result = match self.client.put(&url, Some("order"), None).await {
                 Ok(ApiResult::Success(payload, _headers)) => {
                     self.client.debug(
                         &self.debug_id,
@@ -268,6 +283,11 @@ impl<'a> OrderModule<'a> {
                     return Err(err);
                 }
             };
+            // The last unit was closed and the order is gone
+            if result.is_none() {
+                break;
+            }
+        }
 
         if result.is_none() {
             self.emit("DELETE", json!({ "id": &order.id }));
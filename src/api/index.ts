@@ -1,4 +1,4 @@
-import { SetupResponse, Wfm, TransactionEntryDto, Settings, CreateTransactionEntryDto, CreateStockItemEntryDto, StockItemDto, CreateStockRivenEntryDto, StockRivenDto } from '../types'
+import { SetupResponse, Wfm, TransactionEntryDto, Settings, CreateTransactionEntryDto, CreateStockItemEntryDto, StockItemDto, CreateStockRivenEntryDto, StockRivenDto, RivenGradeDto, StockItemSettings, ProfitReportDto, HoldingProfitDto, DataTransferTable, DataTransferFormat, ImportReportDto, ReplayReportDto, ProfileDto, ProfileInfoDto, ProfilesReportDto } from '../types'
 import { invoke } from '@tauri-apps/api';
 import { SendTauriEvent, SendTauriUpdateDataEvent } from '../utils/tauri';
 const api = {
@@ -48,11 +48,27 @@ const api = {
       }
     }
   },
+  data_transfer: {
+    async export(table: DataTransferTable, format: DataTransferFormat, path: string): Promise<number> {
+      return await invoke("export_data", { table, format, path }) as number;
+    },
+    async import(table: DataTransferTable, format: DataTransferFormat, path: string, replace: boolean = false): Promise<ImportReportDto> {
+      return await invoke("import_data", { table, format, path, replace }) as ImportReportDto;
+    }
+  },
+  ee_log: {
+    // speed 0 replays as fast as possible, reconcile also updates stock and transactions
+    async replay(path: string, speed: number = 0, reconcile: boolean = false): Promise<ReplayReportDto> {
+      return await invoke("replay_ee_log", { path, speed, reconcile }) as ReplayReportDto;
+    }
+  },
   auth: {
-    async login(email: string, password: string): Promise<Wfm.UserDto> {
+    // remember stores the credentials encrypted so an expired session is renewed
+    async login(email: string, password: string, remember: boolean = false): Promise<Wfm.UserDto> {
       const user = await invoke("login", {
         email: email,
         password: password,
+        remember: remember,
       }) as Wfm.UserDto;
       return user
     },
@@ -95,6 +111,30 @@ const api = {
         quantity: transaction.quantity,
         rank: transaction.rank
       }) as TransactionEntryDto;
+    },
+    async profit_report(from?: string, to?: string): Promise<ProfitReportDto> {
+      return await invoke("get_profit_report", { from, to }) as ProfitReportDto;
+    },
+    async profit_holdings(): Promise<HoldingProfitDto[]> {
+      return await invoke("get_profit_holdings") as HoldingProfitDto[];
+    }
+  },
+  profiles: {
+    async list(): Promise<ProfileInfoDto[]> {
+      return await invoke("get_profiles") as ProfileInfoDto[];
+    },
+    async create(name: string): Promise<ProfileDto> {
+      return await invoke("create_profile", { name }) as ProfileDto;
+    },
+    // The app states are swapped, the caller reloads the window to run init again
+    async switch(id: string): Promise<ProfileDto> {
+      return await invoke("switch_profile", { id }) as ProfileDto;
+    },
+    async delete(id: string): Promise<ProfileDto> {
+      return await invoke("delete_profile", { id }) as ProfileDto;
+    },
+    async report(from?: string, to?: string): Promise<ProfilesReportDto> {
+      return await invoke("get_profiles_report", { from, to }) as ProfilesReportDto;
     }
   },
   price_scraper: {
@@ -102,11 +142,23 @@ const api = {
       SendTauriEvent("PriceScraper:OnChange", { max: 7, min: 0, current: 0.1 })
       await invoke("generate_price_history", { platform: "pc", days })
     },
+    async get_price_history(from?: string, to?: string): Promise<any[]> {
+      return await invoke("get_price_history", { from, to })
+    },
   },
   live_scraper: {
     async start_scraper(): Promise<any> {
       SendTauriEvent("LiveScraper:Toggle")
       await invoke("toggle_live_scraper")
+    },
+    async get_dry_run_journal(): Promise<any> {
+      return await invoke("get_dry_run_journal")
+    },
+    async clear_dry_run_journal(): Promise<any> {
+      return await invoke("clear_dry_run_journal")
+    },
+    async run_backtest(stock_item?: StockItemSettings, from?: string, to?: string): Promise<any> {
+      return await invoke("run_backtest", { stockItem: stock_item, from, to })
     }
   },
   stock: {
@@ -162,6 +214,9 @@ const api = {
         if (riven.minium_price && riven.minium_price <= 0)
           riven.minium_price = -1;
         return await invoke("update_riven_stock", { id, private: riven.private, attributes: riven.attributes, matchRiven: riven.match_riven, miniumPrice: riven.minium_price }) as StockRivenDto;
+      },
+      get_grades: async (): Promise<Record<number, RivenGradeDto>> => {
+        return await invoke("get_riven_grades") as Record<number, RivenGradeDto>;
       }
     }
   },
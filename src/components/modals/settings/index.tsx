@@ -11,10 +11,12 @@ import { LoggingPanel } from "./logging.panel";
 interface SettingsModalProps {
   settings: Settings | undefined;
   tradable_items: Wfm.ItemDto[];
+  riven_items: Wfm.RivenItemTypeDto[];
+  riven_attributes: Wfm.RivenAttributeInfoDto[];
   updateSettings: (user: DeepPartial<Settings>) => void;
 }
 
-export function SettingsModal({ tradable_items, settings: settingsIn, updateSettings }: SettingsModalProps) {
+export function SettingsModal({ tradable_items, riven_items, riven_attributes, settings: settingsIn, updateSettings }: SettingsModalProps) {
   const useTranslateSettingsPanels = (key: string, context?: { [key: string]: any }) => useTranslateModal(`settings.panels.${key}`, { ...context })
   const [settings, setSettings] = useState<Settings | undefined>(settingsIn);
 
@@ -44,7 +46,7 @@ export function SettingsModal({ tradable_items, settings: settingsIn, updateSett
         <Box h={"75vh"} sx={{ position: "relative" }}>
           <LiveScraperPanel settings={settings?.live_scraper} updateSettings={(set) => {
             handleUpdateSettings({ live_scraper: set })
-          }} tradable_items={tradable_items} />
+          }} tradable_items={tradable_items} riven_items={riven_items} riven_attributes={riven_attributes} />
         </Box>
       </Tabs.Panel>
       <Tabs.Panel value="logging" pt="xs">
@@ -1,8 +1,10 @@
 import { useEffect } from "react";
 import { useForm } from "@mantine/form";
-import { Accordion, Button, Checkbox, Group, NumberInput, Select } from "@mantine/core";
+import { Accordion, ActionIcon, Button, Checkbox, Group, MultiSelect, NumberInput, Paper, Select, Stack } from "@mantine/core";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import { faTrashCan } from "@fortawesome/free-solid-svg-icons";
 import { useTranslateModal } from "@hooks/index";
-import { ISearchKeyParameter, LiveScraperSettings, Wfm } from "$types/index";
+import { ISearchKeyParameter, LiveScraperSettings, RivenWatchSettings, Wfm } from "$types/index";
 import { MultiSelectListBox } from "../../multiSelectListBox";
 import { searchByPropertys } from "../../../utils/search.helper";
 import { MinMaxField } from "../../MinMaxField";
@@ -10,10 +12,12 @@ import { MinMaxField } from "../../MinMaxField";
 interface LiveScraperProps {
   settings: LiveScraperSettings | undefined;
   tradable_items: Wfm.ItemDto[];
+  riven_items: Wfm.RivenItemTypeDto[];
+  riven_attributes: Wfm.RivenAttributeInfoDto[];
   updateSettings: (user: Partial<LiveScraperSettings>) => void;
 }
 
-export function LiveScraperPanel({ settings, updateSettings, tradable_items }: LiveScraperProps) {
+export function LiveScraperPanel({ settings, updateSettings, tradable_items, riven_items, riven_attributes }: LiveScraperProps) {
   const roleForm = useForm({
     initialValues: {
       filter: {
@@ -41,6 +45,8 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
       live_trading: {
         webhook: "",
         stock_mode: "",
+        dry_run: false,
+        resume_on_start: false,
         stock_item: {
           volume_threshold: 200,
           range_threshold: 200,
@@ -53,9 +59,22 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
           report_to_wfm: true,
           auto_trade: false,
           order_mode: "both",
+          pricing_strategy: {
+            strategy: "default",
+            value: 0,
+          },
+          pricing_overrides: {},
+          price_history_days: 7,
         },
         stock_riven: {
           range_threshold: 25,
+          use_fair_value: true,
+          fair_value_confidence: 0.6,
+        },
+        riven_sniper: {
+          enabled: false,
+          interval: 5,
+          watchlist: [] as RivenWatchSettings[],
         },
       },
     },
@@ -115,6 +134,15 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
 
   const useTranslateSettingsModal = (key: string, context?: { [key: string]: any }, i18Key?: boolean) => useTranslateModal(`settings.panels.live_trading.${key}`, { ...context }, i18Key)
   const useTranslateFields = (key: string, context?: { [key: string]: any }, i18Key?: boolean) => useTranslateSettingsModal(`fields.${key}`, { ...context }, i18Key)
+  const useTranslateSniper = (key: string, context?: { [key: string]: any }, i18Key?: boolean) => useTranslateSettingsModal(`riven_sniper.${key}`, { ...context }, i18Key)
+
+  const weaponOptions = riven_items.map((riven) => ({ label: riven.item_name, value: riven.url_name }));
+  const attributeOptions = riven_attributes.filter((attribute) => !attribute.search_only).map((attribute) => ({ label: attribute.effect, value: attribute.url_name }));
+  const setWatch = (index: number, watch: Partial<RivenWatchSettings>) => {
+    const watchlist = [...roleForm.values.live_trading.riven_sniper.watchlist];
+    watchlist[index] = { ...watchlist[index], ...watch };
+    roleForm.setFieldValue('live_trading.riven_sniper.watchlist', watchlist);
+  }
   return (
     <form method="post" onSubmit={roleForm.onSubmit(async (data) => {
       updateSettings({
@@ -194,6 +222,7 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
                       { description: useTranslateFields(`stock_mode.all_description`), value: "all", label: useTranslateFields("stock_mode.options.all") },
                       { description: useTranslateFields(`stock_mode.item_description`), value: "item", label: useTranslateFields("stock_mode.options.item") },
                       { description: useTranslateFields(`stock_mode.riven_description`), value: "riven", label: useTranslateFields("stock_mode.options.riven") },
+                      { description: useTranslateFields(`stock_mode.sniper_description`), value: "sniper", label: useTranslateFields("stock_mode.options.sniper") },
                     ]}
                   />
                   <Select
@@ -237,10 +266,120 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
                     onChange={(value) => roleForm.setFieldValue('live_trading.stock_riven.range_threshold', Number(value))}
                     error={roleForm.errors.price_shift_threshold && 'Invalid Price Shift Threshold'}
                   />
+                  <NumberInput
+                    required
+                    min={0}
+                    max={1}
+                    step={0.05}
+                    precision={2}
+                    label={useTranslateSettingsModal('fair_value_confidence')}
+                    value={roleForm.values.live_trading.stock_riven.fair_value_confidence}
+                    description={useTranslateSettingsModal('fair_value_confidence_description')}
+                    onChange={(value) => roleForm.setFieldValue('live_trading.stock_riven.fair_value_confidence', Number(value))}
+                  />
+                  <Checkbox
+                    label={useTranslateSettingsModal('use_fair_value')}
+                    description={useTranslateSettingsModal('use_fair_value_description')}
+                    checked={roleForm.values.live_trading.stock_riven.use_fair_value}
+                    onChange={(event) => roleForm.setFieldValue('live_trading.stock_riven.use_fair_value', event.currentTarget.checked)}
+                  />
                 </Group>
               </Group>
             </Accordion.Panel>
           </Accordion.Item>
+          <Accordion.Item value="accordion_riven_sniper">
+            <Accordion.Control>{useTranslateSettingsModal('accordion_riven_sniper')}</Accordion.Control>
+            <Accordion.Panel>
+              {useTranslateSniper('description')}
+              <Group grow mt={10}>
+                <NumberInput
+                  required
+                  min={1}
+                  label={useTranslateSniper('interval')}
+                  value={roleForm.values.live_trading.riven_sniper.interval}
+                  description={useTranslateSniper('interval_description')}
+                  onChange={(value) => roleForm.setFieldValue('live_trading.riven_sniper.interval', Number(value))}
+                />
+                <Checkbox
+                  label={useTranslateSniper('enabled')}
+                  description={useTranslateSniper('enabled_description')}
+                  checked={roleForm.values.live_trading.riven_sniper.enabled}
+                  onChange={(event) => roleForm.setFieldValue('live_trading.riven_sniper.enabled', event.currentTarget.checked)}
+                />
+              </Group>
+              <Stack mt={10} spacing="xs">
+                {roleForm.values.live_trading.riven_sniper.watchlist.map((watch, index) => (
+                  <Paper key={index} withBorder p="xs">
+                    <Group grow align="flex-end">
+                      <Select
+                        searchable
+                        label={useTranslateSniper('weapon')}
+                        data={weaponOptions}
+                        value={watch.weapon_url}
+                        onChange={(value) => setWatch(index, { weapon_url: value || "" })}
+                      />
+                      <MultiSelect
+                        searchable
+                        maxSelectedValues={3}
+                        label={useTranslateSniper('positive_stats')}
+                        data={attributeOptions}
+                        value={watch.positive_stats}
+                        onChange={(value) => setWatch(index, { positive_stats: value })}
+                      />
+                      <Select
+                        searchable
+                        clearable
+                        label={useTranslateSniper('negative_stat')}
+                        data={attributeOptions}
+                        value={watch.negative_stat}
+                        onChange={(value) => setWatch(index, { negative_stat: value })}
+                      />
+                      <NumberInput
+                        min={0}
+                        max={16}
+                        label={useTranslateSniper('mastery_rank_max')}
+                        value={watch.mastery_rank_max ?? ""}
+                        onChange={(value) => setWatch(index, { mastery_rank_max: value === "" ? null : Number(value) })}
+                      />
+                      <NumberInput
+                        min={0}
+                        label={useTranslateSniper('re_rolls_max')}
+                        value={watch.re_rolls_max ?? ""}
+                        onChange={(value) => setWatch(index, { re_rolls_max: value === "" ? null : Number(value) })}
+                      />
+                      <Select
+                        label={useTranslateSniper('target')}
+                        data={[
+                          { value: "fixed", label: useTranslateSniper('targets.fixed') },
+                          { value: "relative", label: useTranslateSniper('targets.relative') },
+                        ]}
+                        value={watch.target}
+                        onChange={(value) => setWatch(index, { target: value == "relative" ? "relative" : "fixed" })}
+                      />
+                      <NumberInput
+                        required
+                        min={0}
+                        label={useTranslateSniper(`value_${watch.target}`)}
+                        value={watch.value}
+                        onChange={(value) => setWatch(index, { value: Number(value) })}
+                      />
+                      <ActionIcon sx={{ flexGrow: 0 }} color="red" variant="light" title={useTranslateSniper('remove')} onClick={() => roleForm.setFieldValue('live_trading.riven_sniper.watchlist', roleForm.values.live_trading.riven_sniper.watchlist.filter((_, i) => i != index))}>
+                        <FontAwesomeIcon icon={faTrashCan} />
+                      </ActionIcon>
+                    </Group>
+                  </Paper>
+                ))}
+                <Group>
+                  <Button variant="light" onClick={() => roleForm.setFieldValue('live_trading.riven_sniper.watchlist', [
+                    ...roleForm.values.live_trading.riven_sniper.watchlist,
+                    { weapon_url: "", positive_stats: [], negative_stat: null, mastery_rank_max: null, re_rolls_max: null, target: "relative", value: 70 },
+                  ])}>
+                    {useTranslateSniper('add')}
+                  </Button>
+                </Group>
+              </Stack>
+            </Accordion.Panel>
+          </Accordion.Item>
           <Accordion.Item value="accordion_whitelist">
             <Accordion.Control>{useTranslateSettingsModal('accordion_whitelist')}</Accordion.Control>
             <Accordion.Panel>
@@ -41,6 +41,14 @@ const Notification = ({ i18Key, notifi: conversation, onChange }: NotificationPr
         </Group>
       </Group>
       <Divider mb={5} />
+      <Group grow mb={5}>
+        <TextInput
+          label={useTranslateFields(`channels.label`)}
+          description={useTranslateFields(`channels.description`)}
+          value={conversation.channels.join(',')}
+          onChange={(event) => onChange('channels', event.currentTarget.value.split(',').map((name) => name.trim()))}
+        />
+      </Group>
       <Collapse in={conversation.discord_notify || conversation.system_notify}>
         <Group grow >
           <Group grow>
@@ -98,6 +106,7 @@ export function NotificationsPanel({ settings, updateSettings }: LiveScraperProp
           content: "From: <PLAYER_NAME>",
           webhook: "",
           user_ids: [] as string[],
+          channels: [] as string[],
         },
         on_wfm_chat_message: {
           system_notify: true,
@@ -106,6 +115,52 @@ export function NotificationsPanel({ settings, updateSettings }: LiveScraperProp
           content: "<WFM_MESSAGE>",
           webhook: "",
           user_ids: [] as string[],
+          channels: [] as string[],
+        },
+        on_trade_completed: {
+          system_notify: true,
+          discord_notify: false,
+          title: "Trade Accepted",
+          content: "Trade accepted from <PLAYER_NAME>",
+          webhook: "",
+          user_ids: [] as string[],
+          channels: [] as string[],
+        },
+        on_order_undercut: {
+          system_notify: false,
+          discord_notify: false,
+          title: "Order Undercut",
+          content: "<ITEM_NAME> was undercut, moved from <OLD_PRICE>p to <NEW_PRICE>p",
+          webhook: "",
+          user_ids: [] as string[],
+          channels: [] as string[],
+        },
+        on_riven_sold: {
+          system_notify: false,
+          discord_notify: false,
+          title: "Riven Sold",
+          content: "Sold <WEAPON_NAME> <MOD_NAME> for <PLATINUM>p",
+          webhook: "",
+          user_ids: [] as string[],
+          channels: [] as string[],
+        },
+        on_scraper_error: {
+          system_notify: true,
+          discord_notify: false,
+          title: "Live Scraper Paused",
+          content: "The live scraper paused for <MINUTES> minutes: <ERROR>",
+          webhook: "",
+          user_ids: [] as string[],
+          channels: [] as string[],
+        },
+        on_riven_snipe: {
+          system_notify: true,
+          discord_notify: false,
+          title: "Riven Deal",
+          content: "<WEAPON_NAME> <MOD_NAME> for <PRICE>p, target <TARGET>p\n<WHISPER>",
+          webhook: "",
+          user_ids: [] as string[],
+          channels: [] as string[],
         },
       },
     },
@@ -143,6 +198,31 @@ export function NotificationsPanel({ settings, updateSettings }: LiveScraperProp
             notifi={roleForm.values.notifications.on_wfm_chat_message}
             onChange={(key, event) => roleForm.setFieldValue(`notifications.on_wfm_chat_message.${key}`, event)}
           />
+          <Notification
+            i18Key="settings.panels.notifications.on_trade_completed"
+            notifi={roleForm.values.notifications.on_trade_completed}
+            onChange={(key, event) => roleForm.setFieldValue(`notifications.on_trade_completed.${key}`, event)}
+          />
+          <Notification
+            i18Key="settings.panels.notifications.on_order_undercut"
+            notifi={roleForm.values.notifications.on_order_undercut}
+            onChange={(key, event) => roleForm.setFieldValue(`notifications.on_order_undercut.${key}`, event)}
+          />
+          <Notification
+            i18Key="settings.panels.notifications.on_riven_sold"
+            notifi={roleForm.values.notifications.on_riven_sold}
+            onChange={(key, event) => roleForm.setFieldValue(`notifications.on_riven_sold.${key}`, event)}
+          />
+          <Notification
+            i18Key="settings.panels.notifications.on_scraper_error"
+            notifi={roleForm.values.notifications.on_scraper_error}
+            onChange={(key, event) => roleForm.setFieldValue(`notifications.on_scraper_error.${key}`, event)}
+          />
+          <Notification
+            i18Key="settings.panels.notifications.on_riven_snipe"
+            notifi={roleForm.values.notifications.on_riven_snipe}
+            onChange={(key, event) => roleForm.setFieldValue(`notifications.on_riven_snipe.${key}`, event)}
+          />
         </SimpleGrid>
       </Group>
       <Group position="right" mt={10} sx={{
@@ -35,7 +35,6 @@ export const LiveScraperContextProvider = ({ children }: LiveScraperContextProvi
       setIsRunning((is_running) => !is_running)
     });
     OnTauriEvent("LiveScraper:Error", (error: RustError) => {
-      setIsRunning(false)
       setError(error)
       SendNotificationToWindow(useTranslate("error_title"), useTranslate("error_message"));
     });
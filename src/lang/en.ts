@@ -11,6 +11,7 @@ export const en = {
         starting: "Starting Items",
         checking: "Checking: <blue>{{name}}</blue> <blue>{{count}}</blue>/<blue>{{total}}</blue>",
         deleting_orders: "Deleting Orders: <blue>{{count}}</blue>/<blue>{{total}}</blue>",
+        quarantined: "Skipping <blue>{{name}}</blue> for <blue>{{minutes}}</blue> minutes after an error",
         sell: {
           deleting: "Deleting Sell Order: <blue>{{name}}</blue>",
           updating: "Updating Sell Order: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
@@ -26,10 +27,17 @@ export const en = {
         starting: "Starting Rivens",
         deleting: "Deleting Riven: <blue>{{name}}</blue>",
         searching: "Searching Riven: <blue>{{name}}</blue>",
+        valuation: "Riven <blue>{{name}}</blue> lowest live <blue>{{lowest_price}}</plat></blue>, fair value <blue>{{fair_value}}</plat></blue> (<blue>{{confidence}}</blue>% confident)",
         no_offers: "No offers found for: <blue>{{name}}</blue>",
         updating: "Updating Riven: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
         creating: "Creating Riven: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
-      }
+        quarantined: "Skipping <blue>{{name}}</blue> for <blue>{{minutes}}</blue> minutes after an error",
+      },
+      riven_sniper: {
+        searching: "Searching Watchlist: <blue>{{name}}</blue> <blue>{{count}}</blue>/<blue>{{total}}</blue>",
+        found: "Found <blue>{{name}} {{mod_name}}</blue> for <blue>{{price}}</plat></blue>, target <blue>{{target}}</plat></blue>",
+      },
+      paused: "Paused after repeated errors, retrying in <blue>{{minutes}}</blue> minutes",
     },
   },
   general: {
@@ -171,10 +179,12 @@ export const en = {
                 all_description: "Will process all items",
                 item_description: "Will only process items",
                 riven_description: "Will only process rivens",
+                sniper_description: "Will not buy or sell, only searches the riven watchlist",
                 options: {
                   all: "All",
                   item: "Item",
                   riven: "Riven",
+                  sniper: "Sniper",
                 },
               },
             },
@@ -182,6 +192,7 @@ export const en = {
             accordion_general: "General",
             accordion_whitelist: "Whitelist",
             accordion_blacklist: "Blacklist",
+            accordion_riven_sniper: "Riven Sniper",
             volume_threshold: "Volume Threshold",
             volume_threshold_description: "Volume of items sold, set this to somewhere between 6-10, but default is 15",
             max_total_price_cap: "Max Total Price Cap",
@@ -190,6 +201,10 @@ export const en = {
             range_threshold_description: "Volume of plat profit per item flip the bot will look to buy/resell",
             riven_range_threshold: "Riven Range Threshold",
             riven_range_threshold_description: "Volume of profit for then riven to be sold.",
+            use_fair_value: "List At Fair Value",
+            use_fair_value_description: "List rivens at their estimated fair value when it is higher than the cheapest matching auction",
+            fair_value_confidence: "Fair Value Confidence",
+            fair_value_confidence_description: "0 to 1, fair values estimated with less confidence are only shown",
             avg_price_cap: "Average Price Cap",
             avg_price_cap_description: "Average price of the items it wants to buy",
             price_shift_threshold: "Price Shift Threshold",
@@ -215,6 +230,27 @@ export const en = {
               tax: "Tax Range",
               mr: "MR Range",
             },
+            riven_sniper: {
+              description: "Searches warframe.market for rivens on the watchlist and notifies you when one is listed at or below its target",
+              enabled: "Enable Riven Sniper",
+              enabled_description: "Search the watchlist while the live scraper is running",
+              interval: "Interval",
+              interval_description: "Minutes between two searches of the watchlist",
+              weapon: "Weapon",
+              positive_stats: "Positive Stats",
+              negative_stat: "Negative Stat",
+              mastery_rank_max: "Max Mastery Rank",
+              re_rolls_max: "Max Re-Rolls",
+              target: "Target",
+              targets: {
+                fixed: "Fixed price",
+                relative: "% of recent price",
+              },
+              value_fixed: "Price",
+              value_relative: "Percent",
+              add: "Add Riven",
+              remove: "Remove",
+            },
             save: "Save",
           },
           price_scraper: {},
@@ -244,6 +280,10 @@ export const en = {
                   label: "User IDs",
                   description: "The user ids to ping separated by comma",
                 },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
               }
             },
             on_wfm_chat_message: {
@@ -269,6 +309,155 @@ export const en = {
                   label: "User IDs",
                   description: "The user ids to ping separated by comma",
                 },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
+              }
+            },
+            on_trade_completed: {
+              title: "On Trade Completed",
+              tooltip: {
+                discord: "Send a notification to discord",
+                system: "Send a notification to the system",
+              },
+              fields: {
+                title: {
+                  label: "Title",
+                  description: "The title of the notification",
+                },
+                content: {
+                  label: "Content",
+                  description: "Use <PLAYER_NAME>, <TRADE_TYPE>, <PLATINUM>, <OFFERED> and <RECEIVED> as placeholders",
+                },
+                webhook: {
+                  label: "Discord Webhook",
+                  description: "The webhook to send notifications to",
+                },
+                user_ids: {
+                  label: "User IDs",
+                  description: "The user ids to ping separated by comma",
+                },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
+              }
+            },
+            on_order_undercut: {
+              title: "On Order Undercut",
+              tooltip: {
+                discord: "Send a notification to discord",
+                system: "Send a notification to the system",
+              },
+              fields: {
+                title: {
+                  label: "Title",
+                  description: "The title of the notification",
+                },
+                content: {
+                  label: "Content",
+                  description: "Use <ITEM_NAME>, <OLD_PRICE>, <LOWEST_PRICE> and <NEW_PRICE> as placeholders",
+                },
+                webhook: {
+                  label: "Discord Webhook",
+                  description: "The webhook to send notifications to",
+                },
+                user_ids: {
+                  label: "User IDs",
+                  description: "The user ids to ping separated by comma",
+                },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
+              }
+            },
+            on_riven_sold: {
+              title: "On Riven Sold",
+              tooltip: {
+                discord: "Send a notification to discord",
+                system: "Send a notification to the system",
+              },
+              fields: {
+                title: {
+                  label: "Title",
+                  description: "The title of the notification",
+                },
+                content: {
+                  label: "Content",
+                  description: "Use <WEAPON_NAME>, <MOD_NAME>, <PLATINUM> and <PLAYER_NAME> as placeholders",
+                },
+                webhook: {
+                  label: "Discord Webhook",
+                  description: "The webhook to send notifications to",
+                },
+                user_ids: {
+                  label: "User IDs",
+                  description: "The user ids to ping separated by comma",
+                },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
+              }
+            },
+            on_scraper_error: {
+              title: "On Live Scraper Error",
+              tooltip: {
+                discord: "Send a notification to discord",
+                system: "Send a notification to the system",
+              },
+              fields: {
+                title: {
+                  label: "Title",
+                  description: "The title of the notification",
+                },
+                content: {
+                  label: "Content",
+                  description: "Use <COMPONENT>, <ERROR> and <MINUTES> as placeholders",
+                },
+                webhook: {
+                  label: "Discord Webhook",
+                  description: "The webhook to send notifications to",
+                },
+                user_ids: {
+                  label: "User IDs",
+                  description: "The user ids to ping separated by comma",
+                },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
+              }
+            },
+            on_riven_snipe: {
+              title: "On Riven Deal",
+              tooltip: {
+                discord: "Send a notification to discord",
+                system: "Send a notification to the system",
+              },
+              fields: {
+                title: {
+                  label: "Title",
+                  description: "The title of the notification",
+                },
+                content: {
+                  label: "Content",
+                  description: "Use <WEAPON_NAME>, <MOD_NAME>, <PLAYER_NAME>, <PRICE>, <TARGET> and <WHISPER> as placeholders",
+                },
+                webhook: {
+                  label: "Discord Webhook",
+                  description: "The webhook to send notifications to",
+                },
+                user_ids: {
+                  label: "User IDs",
+                  description: "The user ids to ping separated by comma",
+                },
+                channels: {
+                  label: "Channels",
+                  description: "Names of the notification channels to also send to, separated by comma",
+                },
               }
             },
             save: "Save",
@@ -315,7 +504,7 @@ export const en = {
     },
     live_scraper: {
       error_title: "Live Scraper Error",
-      error_message: "There was an error with the live scraper. Please check the logs for more information.",
+      error_message: "The live scraper keeps failing and is paused, it will retry by itself. Please check the logs for more information.",
     },
     price_scraper: {
       error_title: "Price Scraper Error",
@@ -343,6 +532,14 @@ export const en = {
           ingame: "Ingame",
         }
       },
+      profiles: {
+        title: "Accounts",
+        create: "New Account",
+        prompt: {
+          title: "New Account",
+          name: "Name",
+        },
+      },
       notifications: {
         settings_updated: "Settings Updated",
         settings_updated_message: "Settings updated successfully",
@@ -500,6 +697,8 @@ export const en = {
         title: "Warframe Market - Login",
         email: "Email",
         password: "Password",
+        remember_me: "Keep me logged in",
+        remember_me_description: "Stores your credentials encrypted on this device to renew an expired session",
         submit: "Login",
       },
     },
@@ -569,6 +768,21 @@ export const en = {
               },
               price: "Price",
               listed_price: "Listed Price",
+              lowest_price: "Lowest Live",
+              fair_value: {
+                title: "Fair Value",
+                description: "Estimated from similar auctions seen on warframe.market, {{confidence}}% confident",
+              },
+              grade: {
+                title: "Grade",
+                stat: "{{name}}: better than {{percentile}}% of similar rolls",
+                stat_no_data: "{{name}}: not enough data, too few similar auctions seen",
+                advice: {
+                  keep: "Keep it or sell it on its own",
+                  roll: "Poor stats, worth rolling",
+                  sell_bulk: "Average, sell it in bulk",
+                },
+              },
               attributes: "Attributes",
               actions: {
                 title: "Actions",
@@ -768,6 +982,28 @@ export const en = {
         },
       },
     },
+    statistics: {
+      accounts: {
+        title: "Accounts",
+        from: "From",
+        to: "To",
+        total: "Total",
+        running: "Running",
+        stopped: "Stopped",
+        datatable: {
+          columns: {
+            name: "Account",
+            live_scraper: "Live Trading",
+            revenue: "Revenue",
+            cost: "Cost",
+            realised_profit: "Realised Profit",
+            unrealised_profit: "Unrealised Profit",
+            stock_items: "Stock Items",
+            stock_rivens: "Stock Rivens",
+          },
+        },
+      },
+    },
   },
   success: {
     auth: {
@@ -1,10 +1,10 @@
 import { Avatar, Group, Header, Menu, createStyles, rem, Container, ActionIcon, useMantineTheme, Indicator } from "@mantine/core";
 import { useEffect, useState } from "react";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
-import { faFolder, faRightFromBracket } from "@fortawesome/free-solid-svg-icons";
-import { useTranslateLayout } from "@hooks/index";
+import { faCheck, faFolder, faPlus, faRightFromBracket, faTrashCan, faUser } from "@fortawesome/free-solid-svg-icons";
+import { useTranslateLayout, useTranslateRustError } from "@hooks/index";
 import { SettingsModal } from "@components/modals/settings";
-import { DeepPartial, Settings, Wfm } from "$types/index";
+import { DeepPartial, ProfileInfoDto, RustError, Settings, Wfm } from "$types/index";
 import { faGear } from "@fortawesome/free-solid-svg-icons/faGear";
 import { modals } from "@mantine/modals";
 import { Logo } from "../components/logo";
@@ -12,7 +12,7 @@ import Clock from "../components/clock";
 import api, { wfmThumbnail } from "@api/index";
 import { useAppContext, useCacheContext, useSocketContextContext } from "../contexts";
 import { notifications } from "@mantine/notifications";
-import { getUserStatusColor } from "../utils";
+import { SendNotificationToWindow, getUserStatusColor } from "../utils";
 interface TopMenuProps {
   opened: boolean;
   user: Wfm.UserDto | undefined;
@@ -44,8 +44,9 @@ export default function Hedder({ user }: TopMenuProps) {
   const { socket } = useSocketContextContext();
   const [, setUserMenuOpened] = useState(false);
   const [avatar, setAvatar] = useState<string | undefined>(undefined);
+  const [profiles, setProfiles] = useState<ProfileInfoDto[]>([]);
   const { settings } = useAppContext();
-  const { items } = useCacheContext();
+  const { items, riven_items, riven_attributes } = useCacheContext();
   useEffect(() => {
     setAvatar(`${wfmThumbnail(user?.avatar || "")}`);
   }, [user?.avatar]);
@@ -65,6 +66,60 @@ export default function Hedder({ user }: TopMenuProps) {
     });
   }
 
+  const useTranslateProfiles = (key: string, context?: { [key: string]: any }) => useTranslateHedder(`profiles.${key}`, { ...context })
+
+  const onProfileError = (error: RustError) => {
+    SendNotificationToWindow(useTranslateRustError("title", { component: error.component }), useTranslateRustError("message", { loc: error.component }));
+  }
+
+  const loadProfiles = async () => {
+    try {
+      setProfiles(await api.profiles.list());
+    } catch (error) {
+      onProfileError(error as RustError);
+    }
+  }
+
+  // Init has to run again for the profile, so the window is reloaded
+  const SwitchProfile = async (profile: ProfileInfoDto) => {
+    if (profile.active) return;
+    try {
+      await api.profiles.switch(profile.id);
+      window.location.reload();
+    } catch (error) {
+      onProfileError(error as RustError);
+    }
+  }
+
+  const DeleteProfile = async (profile: ProfileInfoDto) => {
+    try {
+      await api.profiles.delete(profile.id);
+      await loadProfiles();
+    } catch (error) {
+      onProfileError(error as RustError);
+    }
+  }
+
+  const CreateProfile = () => {
+    modals.openContextModal({
+      modal: 'prompt',
+      title: useTranslateProfiles("prompt.title"),
+      innerProps: {
+        fields: [{ name: 'name', label: useTranslateProfiles("prompt.name"), type: 'text', required: true }],
+        onConfirm: async (data: { name: string }) => {
+          if (!data.name) return;
+          try {
+            await api.profiles.create(data.name);
+            await loadProfiles();
+          } catch (error) {
+            onProfileError(error as RustError);
+          }
+        },
+        onCancel: (id: string) => modals.close(id),
+      },
+    })
+  }
+
   const SetUserStatus = async (status: Wfm.UserStatus) => {
     socket?.send(JSON.stringify({
       type: "@WS/USER/SET_STATUS",
@@ -87,7 +142,10 @@ export default function Hedder({ user }: TopMenuProps) {
             position="bottom-end"
             transitionProps={{ transition: 'pop-top-right' }}
             onClose={() => setUserMenuOpened(false)}
-            onOpen={() => setUserMenuOpened(true)}
+            onOpen={() => {
+              setUserMenuOpened(true);
+              loadProfiles();
+            }}
           >
             <Menu.Target>
               <ActionIcon color="pink" size="xs">
@@ -120,7 +178,7 @@ export default function Hedder({ user }: TopMenuProps) {
                 modals.open({
                   size: "100%",
                   withCloseButton: false,
-                  children: <SettingsModal settings={settings} updateSettings={handleUpdateSettings} tradable_items={items} />,
+                  children: <SettingsModal settings={settings} updateSettings={handleUpdateSettings} tradable_items={items} riven_items={riven_items} riven_attributes={riven_attributes} />,
                 })
               }}>
                 {useTranslateHedder("profile.settings")}
@@ -141,6 +199,30 @@ export default function Hedder({ user }: TopMenuProps) {
                 </Menu.Item>
               )}
               <Menu.Divider />
+              <Menu.Label>{useTranslateProfiles("title")}</Menu.Label>
+              {profiles.map((profile) => (
+                <Menu.Item
+                  key={profile.id}
+                  closeMenuOnClick={!profile.active}
+                  icon={<FontAwesomeIcon icon={profile.active ? faCheck : faUser} />}
+                  color={profile.live_scraper_running ? "green" : undefined}
+                  onClick={() => SwitchProfile(profile)}
+                  rightSection={!profile.active && profile.id != "default" && (
+                    <ActionIcon size="xs" color="red.7" onClick={(e) => {
+                      e.stopPropagation();
+                      DeleteProfile(profile);
+                    }}>
+                      <FontAwesomeIcon icon={faTrashCan} />
+                    </ActionIcon>
+                  )}
+                >
+                  {profile.ingame_name ? `${profile.name} (${profile.ingame_name})` : profile.name}
+                </Menu.Item>
+              ))}
+              <Menu.Item icon={<FontAwesomeIcon icon={faPlus} />} onClick={CreateProfile}>
+                {useTranslateProfiles("create")}
+              </Menu.Item>
+              <Menu.Divider />
 
               {user && (<>
                 <Menu.Label>{useTranslateHedder("profile.status.title")}</Menu.Label>
@@ -1,4 +1,4 @@
-import { Box, Button, Center, Container, PasswordInput, TextInput, Title, Paper } from "@mantine/core";
+import { Box, Button, Center, Checkbox, Container, PasswordInput, TextInput, Title, Paper } from "@mantine/core";
 import { useForm } from "@mantine/form";
 import api from "@api/index";
 import { notifications } from "@mantine/notifications";
@@ -14,7 +14,7 @@ export default function LoginPage() {
   const useTraLogin = (key: string, context?: { [key: string]: any }) => useTranslatePage(`auth.${key}`, { ...context })
 
   const navigate = useNavigate();
-  const logInMutation = useMutation((data: { email: string, password: string }) => api.auth.login(data.email, data.password), {
+  const logInMutation = useMutation((data: { email: string, password: string, rememberMe: boolean }) => api.auth.login(data.email, data.password, data.rememberMe), {
     onSuccess: async (data: Wfm.UserDto) => {
       notifications.show({
         title: i18next.t('success.auth.login_title'),
@@ -87,6 +87,13 @@ export default function LoginPage() {
                 error={form.errors.password && i18next.t('error.auth.password_invalid')}
                 radius="md"
               />
+              <Checkbox
+                mt="md"
+                label={useTraLogin('login.remember_me')}
+                description={useTraLogin('login.remember_me_description')}
+                checked={form.values.rememberMe}
+                onChange={(event) => form.setFieldValue('rememberMe', event.currentTarget.checked)}
+              />
               <Button loading={logInMutation.isLoading} type="submit" fullWidth mt="xl">
                 {useTraLogin('login.submit')}
               </Button>
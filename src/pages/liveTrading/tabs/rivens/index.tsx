@@ -1,5 +1,5 @@
 import { Image, Group, Stack, Tooltip, ActionIcon, Text, Box, useMantineTheme, Grid } from "@mantine/core";
-import { useCacheContext, useLiveScraperContext, useStockContextContext } from "@contexts/index";
+import { useAppContext, useCacheContext, useLiveScraperContext, useStockContextContext } from "@contexts/index";
 import { DataTable, DataTableSortStatus } from "mantine-datatable";
 import { useTranslatePage, useTranslateRustError } from "@hooks/index";
 import api, { wfmThumbnail } from "@api/index";
@@ -8,7 +8,7 @@ import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
 import { faCheck, faComment, faEdit, faEye, faEyeSlash, faHammer, faMagnifyingGlass, faPen, faTrashCan } from "@fortawesome/free-solid-svg-icons";
 import { useMutation } from "@tanstack/react-query";
 import { RivenAttributes } from "@components/auction/rivenAttributes";
-import { StockRivenDto, RustError } from "$types/index";
+import { StockRivenDto, RustError, RivenGradeDto } from "$types/index";
 import { notifications } from "@mantine/notifications";
 import { modals } from "@mantine/modals";
 import { useNavigate } from "react-router-dom";
@@ -32,6 +32,7 @@ export const StockRivenPanel = ({ }: StockRivenPanelProps) => {
   const { rivens } = useStockContextContext();
   const { message } = useLiveScraperContext();
   const { riven_items } = useCacheContext();
+  const { settings } = useAppContext();
 
   // States For Total Price
   const [totalPurchasePrice, setTotalPurchasePrice] = useState<number>(0);
@@ -47,6 +48,13 @@ export const StockRivenPanel = ({ }: StockRivenPanelProps) => {
     setTotalListedPrice(totalListedPrice);
     setTotalProfit(totalListedPrice - totalPurchasePrice);
   }, [rivens])
+  // Grades are only recalculated when the stock changes
+  const [grades, setGrades] = useState<Record<number, RivenGradeDto>>({});
+  useEffect(() => {
+    if (!rivens) return;
+    api.stock.riven.get_grades().then(setGrades).catch(() => setGrades({}));
+  }, [rivens])
+
   // States For DataGrid
   const [page, setPage] = useState(1);
   const pageSizes = [5, 10, 15, 20, 25, 30, 50, 100];
@@ -368,6 +376,39 @@ export const StockRivenPanel = ({ }: StockRivenPanelProps) => {
               <Text>{(listed_price == 0) ? "" : listed_price}</Text>
             </Group>
           },
+          {
+            accessor: 'lowest_price',
+            title: useTranslateDataGridColumns('lowest_price'),
+            sortable: true,
+            render: ({ lowest_price }) => <Text>{lowest_price || ""}</Text>
+          },
+          {
+            accessor: 'fair_value',
+            title: useTranslateDataGridColumns('fair_value.title'),
+            sortable: true,
+            render: ({ fair_value, fair_value_confidence }) => fair_value ?
+              <Tooltip label={useTranslateDataGridColumns('fair_value.description', { confidence: Math.round((fair_value_confidence || 0) * 100) })}>
+                <Text color={(fair_value_confidence || 0) < (settings?.live_scraper.stock_riven.fair_value_confidence || 0) ? "dimmed" : undefined}>{fair_value}</Text>
+              </Tooltip> : <Text></Text>
+          },
+          {
+            accessor: 'grade',
+            title: useTranslateDataGridColumns('grade.title'),
+            render: ({ id }) => {
+              const grade = id ? grades[id] : undefined;
+              if (!grade) return <Text></Text>;
+              return <Tooltip multiline label={<Stack spacing={0}>
+                {grade.stats.map((stat) => <Text key={stat.url_name} size="sm">
+                  {stat.percentile == null
+                    ? useTranslateDataGridColumns('grade.stat_no_data', { name: stat.name })
+                    : useTranslateDataGridColumns('grade.stat', { name: stat.name, percentile: Math.round(stat.percentile * 100) })}
+                </Text>)}
+                <Text size="sm">{useTranslateDataGridColumns(`grade.advice.${grade.advice}`)}</Text>
+              </Stack>}>
+                <Text weight={700}>{grade.grade}</Text>
+              </Tooltip>
+            }
+          },
           {
             accessor: 'attributes',
             title: useTranslateDataGridColumns('attributes'),
@@ -1,9 +1,72 @@
-import { Grid } from "@mantine/core";
+import { Card, Grid, Group, Text, TextInput } from "@mantine/core";
+import { DataTable } from "mantine-datatable";
+import { useQuery } from "@tanstack/react-query";
+import { useState } from "react";
+import api from "@api/index";
+import { ProfileReportDto, RustError } from "$types/index";
+import { useTranslatePage, useTranslateRustError } from "@hooks/index";
+import { SendNotificationToWindow } from "@utils/index";
 
 export default function StatisticsPage() {
+  const useTranslateAccounts = (key: string, context?: { [key: string]: any }) => useTranslatePage(`statistics.accounts.${key}`, { ...context })
+  const [from, setFrom] = useState<string>("");
+  const [to, setTo] = useState<string>("");
+
+  // Profit and stock of every account, summed up in the last row
+  const { data, isFetching } = useQuery({
+    queryKey: ['profiles_report', from, to],
+    queryFn: () => api.profiles.report(from || undefined, to || undefined),
+    onError(error: RustError) {
+      SendNotificationToWindow(useTranslateRustError("title", { component: error.component }), useTranslateRustError("message", { loc: error.component }));
+    }
+  });
+
+  const records: ProfileReportDto[] = data ? [...data.profiles, {
+    profile: { id: "total", name: useTranslateAccounts("total"), created: "", active: false, live_scraper_running: false },
+    summary: data.total,
+  }] : [];
+
   return (
     <Grid>
       <Grid.Col md={12}>
+        <Card>
+          <Group position="apart" mb="xs">
+            <Text weight={500}>{useTranslateAccounts("title")}</Text>
+            <Group>
+              <TextInput size="xs" placeholder="YYYY-MM-DD" label={useTranslateAccounts("from")} value={from} onChange={(event) => setFrom(event.currentTarget.value)} />
+              <TextInput size="xs" placeholder="YYYY-MM-DD" label={useTranslateAccounts("to")} value={to} onChange={(event) => setTo(event.currentTarget.value)} />
+            </Group>
+          </Group>
+          <DataTable
+            striped
+            withColumnBorders
+            fetching={isFetching}
+            records={records}
+            idAccessor={(record) => record.profile.id}
+            columns={[
+              {
+                accessor: 'profile.name',
+                title: useTranslateAccounts("datatable.columns.name"),
+                render: ({ profile }) => profile.ingame_name ? `${profile.name} (${profile.ingame_name})` : profile.name,
+              },
+              {
+                accessor: 'profile.live_scraper_running',
+                title: useTranslateAccounts("datatable.columns.live_scraper"),
+                render: ({ profile }) => profile.id == "total" ? "" : useTranslateAccounts(profile.live_scraper_running ? "running" : "stopped"),
+              },
+              { accessor: 'summary.revenue', title: useTranslateAccounts("datatable.columns.revenue") },
+              { accessor: 'summary.cost', title: useTranslateAccounts("datatable.columns.cost") },
+              { accessor: 'summary.realised_profit', title: useTranslateAccounts("datatable.columns.realised_profit") },
+              {
+                accessor: 'summary.unrealised_profit',
+                title: useTranslateAccounts("datatable.columns.unrealised_profit"),
+                render: ({ summary }) => summary.unrealised_profit.toFixed(0),
+              },
+              { accessor: 'summary.stock_items', title: useTranslateAccounts("datatable.columns.stock_items") },
+              { accessor: 'summary.stock_rivens', title: useTranslateAccounts("datatable.columns.stock_rivens") },
+            ]}
+          />
+        </Card>
       </Grid.Col>
     </Grid>
   );
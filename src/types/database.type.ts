@@ -36,7 +36,28 @@ export interface StockRivenDto extends StockEntryDto {
   re_rolls: number;
   private: boolean;
   match_riven: MatchRivenDto;
+  lowest_price?: number | null;
+  fair_value?: number | null;
+  fair_value_confidence?: number | null;
 }
+export interface RivenStatGradeDto {
+  url_name: string;
+  name: string;
+  positive: boolean;
+  value: number;
+  desirability: number;
+  percentile?: number | null;
+}
+
+export interface RivenGradeDto {
+  stats: RivenStatGradeDto[];
+  stat_choice: number;
+  roll_quality?: number | null;
+  score: number;
+  grade: string;
+  advice: "keep" | "roll" | "sell_bulk";
+}
+
 export interface MatchRivenDto {
   rank?: MinMaxDto;
   mastery_rank?: MinMaxDto;
@@ -102,3 +123,63 @@ export interface TransactionRivenDto extends TransactionEntryDto {
   properties: StockRivenDto
 }
 
+export interface ItemProfitDto {
+  url: string,
+  name: string,
+  item_type: string,
+  rank: number,
+  quantity_sold: number,
+  revenue: number,
+  cost: number,
+  profit: number,
+  unmatched_quantity: number
+}
+
+export interface HoldingProfitDto {
+  url: string,
+  name: string,
+  rank: number,
+  owned: number,
+  cost: number,
+  market_price?: number,
+  value: number,
+  profit: number
+}
+
+export interface PeriodProfitDto {
+  period: string,
+  revenue: number,
+  cost: number,
+  profit: number,
+  quantity_sold: number,
+  expense: number
+}
+
+export interface ProfitReportDto {
+  revenue: number,
+  cost: number,
+  realised_profit: number,
+  unmatched_quantity: number,
+  unrealised_value: number,
+  unrealised_profit: number,
+  items: ItemProfitDto[],
+  holdings: HoldingProfitDto[],
+  daily: PeriodProfitDto[],
+  weekly: PeriodProfitDto[],
+  monthly: PeriodProfitDto[]
+}
+
+export type DataTransferTable = "stock_item" | "stock_riven" | "transaction";
+export type DataTransferFormat = "csv" | "json";
+
+export interface ImportConflictDto {
+  row: number,
+  key: string,
+  reason: string
+}
+
+export interface ImportReportDto {
+  table: DataTransferTable,
+  imported: number,
+  conflicts: ImportConflictDto[]
+}
@@ -0,0 +1,38 @@
+export type TradeClassification = "Sale" | "Purchase" | "Trade" | "Unknown";
+
+export interface TradeItemDto {
+  name: string,
+  wfm_id?: string,
+  wfm_url_name?: string,
+  display_name: string,
+  quantity: number,
+  rank: number
+}
+
+export interface PlayerTradeDto {
+  crated_at: string,
+  user_name: string,
+  trade_type: TradeClassification,
+  total_platinum: number,
+  offerings: TradeItemDto[],
+  receiving: TradeItemDto[]
+}
+
+export interface ReplayConversationDto {
+  file: string,
+  line: number,
+  user_name: string
+}
+
+export interface ReplayTradeDto {
+  file: string,
+  line: number,
+  trade: PlayerTradeDto
+}
+
+export interface ReplayReportDto {
+  files: string[],
+  lines: number,
+  conversations: ReplayConversationDto[],
+  trades: ReplayTradeDto[]
+}
@@ -6,6 +6,8 @@ export * from "./sorting.type";
 export * from "./search.type";
 export * from "./statistic.type";
 export * from "./progressReport.type";
+export * from "./eeLog.type";
+export * from "./profile.type";
 
 export type DeepPartial<T> = T extends object ? {
   [P in keyof T]?: DeepPartial<T[P]>;
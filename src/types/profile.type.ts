@@ -0,0 +1,31 @@
+export interface ProfileDto {
+  id: string,
+  name: string,
+  created: string,
+}
+
+export interface ProfileInfoDto extends ProfileDto {
+  ingame_name?: string,
+  active: boolean,
+  live_scraper_running: boolean,
+}
+
+export interface ProfileSummaryDto {
+  revenue: number,
+  cost: number,
+  realised_profit: number,
+  unrealised_value: number,
+  unrealised_profit: number,
+  stock_items: number,
+  stock_rivens: number,
+}
+
+export interface ProfileReportDto {
+  profile: ProfileInfoDto,
+  summary: ProfileSummaryDto,
+}
+
+export interface ProfilesReportDto {
+  profiles: ProfileReportDto[],
+  total: ProfileSummaryDto,
+}
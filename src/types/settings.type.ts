@@ -54,13 +54,32 @@ export interface Settings {
   dev_mode: boolean;
   live_scraper: LiveScraperSettings;
   notifications: Notifications;
+  notification_channels: NotificationChannel[];
+  ee_log: EELogSettings;
+  local_api: LocalApiSettings;
+  metrics: MetricsSettings;
+}
+
+export interface LocalApiSettings {
+  enabled: boolean;
+  port: number;
+  token: string;
+}
+
+export interface MetricsSettings {
+  enabled: boolean;
+  host: string;
+  port: number;
 }
 
 export interface LiveScraperSettings {
   webhook: string;
   stock_mode: string;
+  dry_run: boolean;
+  resume_on_start: boolean;
   stock_item: StockItemSettings;
   stock_riven: StockRivenSettings;
+  riven_sniper: RivenSniperSettings;
 }
 export interface StockItemSettings {
   volume_threshold: number;
@@ -74,9 +93,32 @@ export interface StockItemSettings {
   order_mode: string;
   blacklist: string[];
   whitelist: string[];
+  pricing_strategy: PricingStrategySettings;
+  pricing_overrides: Record<string, PricingStrategySettings>;
+  price_history_days: number;
+}
+export interface PricingStrategySettings {
+  strategy: "default" | "undercut" | "median_margin";
+  value: number;
 }
 export interface StockRivenSettings {
   range_threshold: number;
+  use_fair_value: boolean;
+  fair_value_confidence: number;
+}
+export interface RivenSniperSettings {
+  enabled: boolean;
+  interval: number;
+  watchlist: RivenWatchSettings[];
+}
+export interface RivenWatchSettings {
+  weapon_url: string;
+  positive_stats: string[];
+  negative_stat: string | null;
+  mastery_rank_max: number | null;
+  re_rolls_max: number | null;
+  target: "fixed" | "relative";
+  value: number;
 }
 
 export interface NotificationBase {
@@ -86,9 +128,35 @@ export interface NotificationBase {
   content: string;
   webhook: string;
   user_ids: string[];
+  channels: string[];
 }
 export interface Notifications {
   on_new_conversation: NotificationBase;
   on_wfm_chat_message: NotificationBase;
+  on_trade_completed: NotificationBase;
+  on_order_undercut: NotificationBase;
+  on_riven_sold: NotificationBase;
+  on_scraper_error: NotificationBase;
+  on_riven_snipe: NotificationBase;
+}
+
+export type NotificationChannelKind =
+  | { type: "webhook"; url: string; headers: Record<string, string> }
+  | { type: "telegram"; bot_token: string; chat_id: string }
+  | { type: "ntfy"; server: string; topic: string; token: string | null }
+  | { type: "gotify"; server: string; token: string; priority: number }
+  | { type: "email"; host: string; port: number; username: string; password: string; from: string; to: string[] };
+
+export type NotificationChannel = {
+  name: string;
+  enabled: boolean;
+} & NotificationChannelKind;
+
+export interface EELogEventSettings {
+  enabled: boolean;
+  priority: number;
+}
+export interface EELogSettings {
+  events: Record<string, EELogEventSettings>;
 }
 